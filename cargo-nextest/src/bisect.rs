@@ -0,0 +1,220 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Bisecting order-dependent test failures.
+//!
+//! Some tests only fail when run after other tests, e.g. because of state left behind on disk. To
+//! find out which tests are responsible, the target test is repeatedly run after subsets of the
+//! tests that precede it, narrowing down to a minimal set of tests that still cause a failure.
+
+use crate::ExpectedError;
+use nextest_metadata::RustBinaryId;
+use nextest_runner::{list::TestList, runner::TestRunnerBuilder};
+use owo_colors::{OwoColorize, Stream};
+
+/// Identifies a test within a test list: (binary ID, test name).
+pub(crate) type TestKey<'a> = (&'a RustBinaryId, &'a str);
+
+/// Finds a minimal set of tests that must run before a target test for it to fail.
+#[derive(Debug)]
+pub(crate) struct TestBisector<'a> {
+    target: TestKey<'a>,
+    preceding: Vec<TestKey<'a>>,
+}
+
+impl<'a> TestBisector<'a> {
+    /// Creates a new bisector for the test named `test_name`.
+    ///
+    /// Preceding tests are determined by the order in which a runner created from
+    /// `runner_builder` starts tests, so a shuffle seed is taken into account.
+    pub(crate) fn new(
+        test_list: &'a TestList<'a>,
+        runner_builder: &TestRunnerBuilder,
+        test_name: &str,
+    ) -> Result<Self, Box<ExpectedError>> {
+        let order: Vec<TestKey<'a>> = runner_builder
+            .run_order(test_list)
+            .into_iter()
+            .filter(|instance| instance.test_info.filter_match.is_match())
+            .map(|instance| (&instance.suite_info.binary_id, instance.name))
+            .collect();
+
+        let positions: Vec<usize> = order
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, name))| *name == test_name)
+            .map(|(position, _)| position)
+            .collect();
+        let position = match positions.as_slice() {
+            [] => {
+                return Err(Box::new(ExpectedError::BisectTestNotFound {
+                    test_name: test_name.to_owned(),
+                }))
+            }
+            [position] => *position,
+            _ => {
+                return Err(Box::new(ExpectedError::BisectTestAmbiguous {
+                    test_name: test_name.to_owned(),
+                    binary_ids: positions
+                        .iter()
+                        .map(|&position| order[position].0.to_string())
+                        .collect(),
+                }))
+            }
+        };
+
+        Ok(Self {
+            target: order[position],
+            preceding: order[..position].to_vec(),
+        })
+    }
+
+    /// Returns the test being bisected.
+    pub(crate) fn target(&self) -> TestKey<'a> {
+        self.target
+    }
+
+    /// Runs the bisection.
+    ///
+    /// `run_after` runs the target test serially after the given tests, in that order, and
+    /// returns true if the target test failed. Errors it returns are passed through.
+    pub(crate) fn run<E>(
+        self,
+        mut run_after: impl FnMut(&[TestKey<'a>]) -> Result<bool, E>,
+    ) -> Result<(), E> {
+        let target = display_test(self.target);
+        log::info!(
+            "bisecting {} tests that run before {target}",
+            self.preceding
+                .len()
+                .if_supports_color(Stream::Stderr, |x| x.bold()),
+        );
+
+        let mut step = 0;
+        let mut fails = |tests: &[TestKey<'a>]| -> Result<bool, E> {
+            step += 1;
+            let failed = run_after(tests)?;
+            log::info!(
+                "[step {step}] {target} {} after {} tests",
+                if failed { "failed" } else { "passed" },
+                tests.len(),
+            );
+            Ok(failed)
+        };
+
+        if fails(&[])? {
+            log::warn!("{target} fails when run on its own, so it doesn't depend on other tests");
+            return Ok(());
+        }
+        let preceding = self.preceding;
+        if !fails(&preceding)? {
+            log::warn!(
+                "{target} passes when run after all {} preceding tests: \
+                 unable to reproduce an order-dependent failure",
+                preceding.len(),
+            );
+            return Ok(());
+        }
+
+        let minimal = minimize(preceding, &mut fails)?;
+        log::info!(
+            "{target} fails when run after these {} tests, in order:",
+            minimal
+                .len()
+                .if_supports_color(Stream::Stderr, |x| x.bold()),
+        );
+        for test in minimal {
+            log::info!(
+                target: "cargo_nextest::no_heading",
+                "  - {}",
+                display_test(test),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn display_test((binary_id, name): TestKey<'_>) -> String {
+    format!(
+        "{} {}",
+        binary_id.if_supports_color(Stream::Stderr, |x| x.bold()),
+        name.if_supports_color(Stream::Stderr, |x| x.bold()),
+    )
+}
+
+/// Reduces `items` to a smaller list that still causes `fails` to return true, using delta
+/// debugging.
+///
+/// `fails(&items)` must be true initially. The result is 1-minimal: removing any single item from
+/// it makes `fails` return false. The relative order of items is preserved throughout.
+fn minimize<T: Clone, E>(
+    mut items: Vec<T>,
+    mut fails: impl FnMut(&[T]) -> Result<bool, E>,
+) -> Result<Vec<T>, E> {
+    let mut granularity = 2;
+    while items.len() >= 2 {
+        let chunk_size = (items.len() + granularity - 1) / granularity;
+        let chunks: Vec<&[T]> = items.chunks(chunk_size).collect();
+
+        let mut reduced = None;
+        // First, try each chunk on its own.
+        for chunk in &chunks {
+            if fails(chunk)? {
+                reduced = Some((chunk.to_vec(), 2));
+                break;
+            }
+        }
+        // Then, try removing each chunk. With two chunks, this is the same as the above.
+        if reduced.is_none() && chunks.len() > 2 {
+            for index in 0..chunks.len() {
+                let complement: Vec<T> = chunks
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != index)
+                    .flat_map(|(_, chunk)| chunk.iter().cloned())
+                    .collect();
+                if fails(&complement)? {
+                    reduced = Some((complement, (granularity - 1).max(2)));
+                    break;
+                }
+            }
+        }
+
+        match reduced {
+            Some((new_items, new_granularity)) => {
+                items = new_items;
+                granularity = new_granularity;
+            }
+            None if granularity >= items.len() => break,
+            None => granularity = (granularity * 2).min(items.len()),
+        }
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    #[test]
+    fn test_minimize() {
+        let items: Vec<usize> = (0..20).collect();
+
+        let minimal = minimize(items.clone(), |tests| {
+            Ok::<_, Infallible>(tests.contains(&3) && tests.contains(&17))
+        })
+        .unwrap();
+        assert_eq!(minimal, vec![3, 17], "two culprits");
+
+        let minimal = minimize(items.clone(), |tests| {
+            Ok::<_, Infallible>(tests.contains(&11))
+        })
+        .unwrap();
+        assert_eq!(minimal, vec![11], "one culprit");
+
+        let minimal = minimize(items, |tests| Ok::<_, Infallible>(tests.len() >= 20)).unwrap();
+        assert_eq!(minimal.len(), 20, "all items required");
+    }
+}
@@ -6,10 +6,11 @@
 use crate::output::OutputContext;
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::Args;
-use std::path::PathBuf;
+use nextest_metadata::BuildScriptFailure;
+use std::{num::NonZeroUsize, path::PathBuf};
 
 /// Options passed down to cargo.
-#[derive(Debug, Args)]
+#[derive(Clone, Debug, Args)]
 #[command(
     next_help_heading = "Cargo options",
     group = clap::ArgGroup::new("cargo-opts").multiple(true),
@@ -82,8 +83,15 @@ pub(crate) struct CargoOptions {
     #[arg(long, value_name = "NAME", group = "cargo-opts")]
     cargo_profile: Option<String>,
 
-    /// Number of build jobs to run
-    #[arg(long, value_name = "JOBS", group = "cargo-opts")]
+    /// Number of build jobs to run, or negative for the number of CPUs minus that many
+    /// [default: from profile, or Cargo's default]
+    #[arg(
+        long,
+        value_name = "JOBS",
+        value_parser = parse_build_jobs,
+        allow_negative_numbers = true,
+        group = "cargo-opts"
+    )]
     build_jobs: Option<String>,
 
     /// Space or comma separated list of features to activate
@@ -98,12 +106,36 @@ pub(crate) struct CargoOptions {
     #[arg(long, group = "cargo-opts")]
     no_default_features: bool,
 
+    /// Features to activate, or `all` for --all-features or `none` for --no-default-features
+    #[arg(
+        long,
+        value_name = "FEATURES",
+        group = "cargo-opts",
+        conflicts_with_all = &["features", "all_features", "no_default_features"],
+    )]
+    cargo_features: Option<String>,
+
+    /// Build and run tests for each combination of up to N features
+    #[arg(
+        long,
+        value_name = "N",
+        group = "cargo-opts",
+        visible_alias = "cargo-feature-combinations",
+        conflicts_with_all = &["features", "all_features", "no_default_features", "cargo_features"],
+    )]
+    pub(crate) feature_combinations: Option<usize>,
+
     /// Build for the target triple
     #[arg(long, value_name = "TRIPLE", group = "cargo-opts")]
     pub(crate) target: Option<String>,
 
     /// Directory for all generated artifacts
-    #[arg(long, value_name = "DIR", group = "cargo-opts")]
+    #[arg(
+        long,
+        value_name = "DIR",
+        group = "cargo-opts",
+        visible_alias = "cargo-target-dir"
+    )]
     pub(crate) target_dir: Option<Utf8PathBuf>,
 
     /// Ignore `rust-version` specification in packages
@@ -119,6 +151,18 @@ pub(crate) struct CargoOptions {
     future_incompat_report: bool,
 
     // --verbose is not currently supported
+    /// Suppress Cargo build output other than errors
+    #[arg(long, group = "cargo-opts")]
+    pub(crate) cargo_quiet: bool,
+
+    /// Cap the level of compiler lints while building tests (passed to rustc through RUSTFLAGS)
+    #[arg(long, value_name = "LEVEL", value_parser = ["allow", "warn", "deny", "forbid"], group = "cargo-opts")]
+    pub(crate) cap_lints: Option<String>,
+
+    /// Skip the tests of packages whose build scripts fail, rather than failing the build
+    #[arg(long, group = "cargo-opts")]
+    pub(crate) ignore_build_script_failures: bool,
+
     // --color is handled by runner
     /// Require Cargo.lock and cache are up to date
     #[arg(long, group = "cargo-opts")]
@@ -132,10 +176,15 @@ pub(crate) struct CargoOptions {
     #[arg(long, group = "cargo-opts")]
     offline: bool,
 
+    /// Pass --offline to every Cargo invocation, including `cargo metadata` and when reusing
+    /// builds
+    #[arg(long)]
+    pub(crate) cargo_offline: bool,
+
     // NOTE: this does not conflict with reuse build opts since we let target.runner be specified
     // this way
     /// Override a configuration value
-    #[arg(long, value_name = "KEY=VALUE")]
+    #[arg(long, value_name = "KEY=VALUE", visible_alias = "cargo-config")]
     pub(crate) config: Vec<String>,
 
     /// Unstable (nightly-only) flags to Cargo, see 'cargo -Z help' for details
@@ -143,6 +192,141 @@ pub(crate) struct CargoOptions {
     unstable_flags: Vec<String>,
 }
 
+impl CargoOptions {
+    /// Returns the packages selected with `-p`.
+    pub(crate) fn packages(&self) -> &[String] {
+        &self.packages
+    }
+
+    /// Returns environment variables to set while building tests, based on these options.
+    ///
+    /// `configured_rustflags` is called to get the rustflags from Cargo configuration, if they're
+    /// needed.
+    pub(crate) fn build_envs(
+        &self,
+        configured_rustflags: impl FnOnce() -> Vec<String>,
+    ) -> Vec<(&'static str, String)> {
+        let mut envs = Vec::new();
+        if let Some(level) = &self.cap_lints {
+            let cap_lints = format!("--cap-lints={level}");
+            // CARGO_ENCODED_RUSTFLAGS takes precedence over RUSTFLAGS, which takes precedence over
+            // rustflags in Cargo configuration. Append to whichever one Cargo is going to read.
+            match std::env::var("CARGO_ENCODED_RUSTFLAGS") {
+                Ok(flags) if !flags.is_empty() => {
+                    envs.push(("CARGO_ENCODED_RUSTFLAGS", format!("{flags}\x1f{cap_lints}")));
+                }
+                _ => match std::env::var("RUSTFLAGS") {
+                    Ok(flags) if !flags.trim().is_empty() => {
+                        envs.push(("RUSTFLAGS", format!("{flags} {cap_lints}")));
+                    }
+                    _ => {
+                        // Setting either env var makes Cargo ignore configured rustflags, so
+                        // pass those through as well. Use CARGO_ENCODED_RUSTFLAGS so that flags
+                        // containing spaces are preserved.
+                        let mut flags = configured_rustflags();
+                        flags.push(cap_lints);
+                        envs.push(("CARGO_ENCODED_RUSTFLAGS", flags.join("\x1f")));
+                    }
+                },
+            }
+        }
+        envs
+    }
+
+    /// Returns a copy of these options that builds with exactly the given features enabled.
+    pub(crate) fn with_exact_features(&self, features: &[String]) -> Self {
+        let mut options = self.clone();
+        options.features = if features.is_empty() {
+            Vec::new()
+        } else {
+            vec![features.join(",")]
+        };
+        options.all_features = false;
+        options.no_default_features = true;
+        options.cargo_features = None;
+        options.feature_combinations = None;
+        options
+    }
+
+    /// Sets the number of build jobs, unless `--build-jobs` was passed in.
+    pub(crate) fn set_default_build_jobs(&mut self, build_jobs: NonZeroUsize) {
+        if self.build_jobs.is_none() {
+            self.build_jobs = Some(build_jobs.to_string());
+        }
+    }
+
+    /// Excludes the given packages from the build.
+    ///
+    /// Packages passed in with `-p` are removed, and if the whole workspace is being built, the
+    /// packages are passed to Cargo with `--exclude`. Otherwise, `--exclude` can't be used, so
+    /// Cargo's default package selection is computed with `default_packages` and passed in with
+    /// `-p` instead.
+    ///
+    /// Returns false if no packages would be left to build.
+    pub(crate) fn exclude_packages(
+        &mut self,
+        packages: &[String],
+        default_packages: impl FnOnce() -> Vec<String>,
+    ) -> bool {
+        if !self.packages.is_empty() {
+            self.packages.retain(|package| !packages.contains(package));
+            !self.packages.is_empty()
+        } else if self.workspace || self.all {
+            self.exclude.extend(packages.iter().cloned());
+            true
+        } else {
+            self.packages = default_packages()
+                .into_iter()
+                .filter(|package| !packages.contains(package))
+                .collect();
+            !self.packages.is_empty()
+        }
+    }
+}
+
+fn parse_build_jobs(input: &str) -> Result<String, String> {
+    // Match what Cargo's --jobs accepts.
+    if input == "default" {
+        return Ok(input.to_owned());
+    }
+    match input.parse::<i32>() {
+        Ok(0) | Err(_) => Err("must be a non-zero integer or \"default\"".to_owned()),
+        Ok(_) => Ok(input.to_owned()),
+    }
+}
+
+/// Parses build script failures out of Cargo's stderr.
+pub(crate) fn parse_build_script_failures(stderr: &str) -> Vec<BuildScriptFailure> {
+    const PREFIX: &str = "error: failed to run custom build command for `";
+
+    let mut failures = Vec::new();
+    let mut lines = stderr.lines().peekable();
+    while let Some(line) = lines.next() {
+        let package = match line.strip_prefix(PREFIX) {
+            // This is of the form "name vX.Y.Z (source)".
+            Some(rest) => rest.split(' ').next().unwrap_or_default().to_owned(),
+            None => continue,
+        };
+
+        // The error is made up of the indented lines that follow, e.g. "Caused by:" and the
+        // build script's output.
+        let mut error_lines = Vec::new();
+        while let Some(line) = lines.peek() {
+            if line.starts_with(' ') {
+                error_lines.push(line.trim());
+            } else if !(line.is_empty() || *line == "Caused by:" || line.starts_with("note: ")) {
+                break;
+            }
+            lines.next();
+        }
+        failures.push(BuildScriptFailure {
+            package,
+            error: error_lines.join("\n"),
+        });
+    }
+    failures
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct CargoCli<'a> {
     cargo_path: Utf8PathBuf,
@@ -245,6 +429,12 @@ impl<'a> CargoCli<'a> {
         if options.no_default_features {
             self.args.push("--no-default-features");
         }
+        match options.cargo_features.as_deref() {
+            Some("all") => self.args.push("--all-features"),
+            Some("none") => self.args.push("--no-default-features"),
+            Some(features) => self.args.extend(["--features", features]),
+            None => {}
+        }
         if let Some(target) = &options.target {
             self.args.extend(["--target", target]);
         }
@@ -260,13 +450,16 @@ impl<'a> CargoCli<'a> {
         if options.future_incompat_report {
             self.args.push("--future-incompat-report");
         }
+        if options.cargo_quiet {
+            self.args.push("--quiet");
+        }
         if options.frozen {
             self.args.push("--frozen");
         }
         if options.locked {
             self.args.push("--locked");
         }
-        if options.offline {
+        if options.offline || options.cargo_offline {
             self.args.push("--offline");
         }
         self.args
@@ -290,17 +483,30 @@ impl<'a> CargoCli<'a> {
     }
 
     pub(crate) fn to_expression(&self) -> duct::Expression {
-        let mut initial_args = vec![self.output.color.to_arg(), self.command];
-        if let Some(path) = self.manifest_path {
-            initial_args.extend(["--manifest-path", path.as_str()]);
-        }
         duct::cmd(
             // Ensure that cargo gets picked up from PATH if necessary, by calling as_str
             // rather than as_std_path.
             self.cargo_path.as_str(),
-            initial_args.into_iter().chain(self.args.iter().copied()),
+            self.command_args(),
         )
     }
+
+    /// Returns a [`std::process::Command`] equivalent to [`Self::to_expression`], for when more
+    /// control over the child's output is required.
+    pub(crate) fn to_command(&self) -> std::process::Command {
+        let mut command = std::process::Command::new(self.cargo_path.as_str());
+        command.args(self.command_args());
+        command
+    }
+
+    fn command_args(&self) -> Vec<&str> {
+        let mut args = vec![self.output.color.to_arg(), self.command];
+        if let Some(path) = self.manifest_path {
+            args.extend(["--manifest-path", path.as_str()]);
+        }
+        args.extend_from_slice(&self.args);
+        args
+    }
 }
 
 fn cargo_path() -> Utf8PathBuf {
@@ -311,3 +517,111 @@ fn cargo_path() -> Utf8PathBuf {
         None => Utf8PathBuf::from("cargo"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_build_script_failures() {
+        let stderr = "   Compiling bad v0.1.0 (/tmp/ws/bad)
+error: failed to run custom build command for `bad v0.1.0 (/tmp/ws/bad)`
+note: To improve backtraces for build dependencies, set the CARGO_PROFILE_TEST_BUILD_OVERRIDE_DEBUG=true environment variable to enable debug information generation.
+
+Caused by:
+  process didn't exit successfully: `/tmp/ws/target/debug/build/bad-1234/build-script-build` (exit status: 1)
+  --- stderr
+  libfoo not found
+warning: build failed, waiting for other jobs to finish...
+error: could not compile `other` (lib test) due to 1 previous error
+";
+        assert_eq!(
+            parse_build_script_failures(stderr),
+            vec![BuildScriptFailure {
+                package: "bad".to_owned(),
+                error: "process didn't exit successfully: \
+                        `/tmp/ws/target/debug/build/bad-1234/build-script-build` (exit status: 1)\n\
+                        --- stderr\n\
+                        libfoo not found"
+                    .to_owned(),
+            }],
+        );
+
+        assert_eq!(
+            parse_build_script_failures("error: could not compile `other`"),
+            vec![],
+            "no build script failures"
+        );
+    }
+
+    #[derive(Debug, clap::Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        cargo_opts: CargoOptions,
+    }
+
+    fn parse_cargo_opts(args: &[&str]) -> CargoOptions {
+        <TestCli as clap::Parser>::try_parse_from(
+            std::iter::once("cargo-nextest").chain(args.iter().copied()),
+        )
+        .expect("arguments are valid")
+        .cargo_opts
+    }
+
+    #[test]
+    fn test_exclude_packages() {
+        let excluded = ["bad".to_owned()];
+        let default_packages = || vec!["good".to_owned(), "bad".to_owned()];
+
+        // Packages selected with -p are removed.
+        let mut opts = parse_cargo_opts(&["-p", "good", "-p", "bad"]);
+        assert!(opts.exclude_packages(&excluded, || panic!("not called")));
+        assert_eq!(opts.packages, vec!["good"]);
+        assert!(!opts.workspace);
+
+        let mut opts = parse_cargo_opts(&["-p", "bad"]);
+        assert!(
+            !opts.exclude_packages(&excluded, default_packages),
+            "no packages left"
+        );
+
+        // With --workspace, --exclude is used.
+        let mut opts = parse_cargo_opts(&["--workspace"]);
+        assert!(opts.exclude_packages(&excluded, || panic!("not called")));
+        assert_eq!(opts.exclude, vec!["bad"]);
+        assert!(opts.packages.is_empty());
+
+        // Otherwise, the default selection is narrowed rather than widened to the whole
+        // workspace.
+        let mut opts = parse_cargo_opts(&[]);
+        assert!(opts.exclude_packages(&excluded, default_packages));
+        assert_eq!(opts.packages, vec!["good"]);
+        assert!(!opts.workspace);
+        assert!(opts.exclude.is_empty());
+
+        let mut opts = parse_cargo_opts(&[]);
+        assert!(
+            !opts.exclude_packages(&excluded, || vec!["bad".to_owned()]),
+            "no packages left"
+        );
+    }
+
+    #[test]
+    fn test_cargo_features() {
+        let output = OutputContext {
+            verbose: false,
+            color: crate::output::Color::Never,
+        };
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["--cargo-features", "all"], &["--all-features"]),
+            (&["--cargo-features", "none"], &["--no-default-features"]),
+            (&["--cargo-features", "foo,bar"], &["--features", "foo,bar"]),
+        ];
+        for (args, expected) in cases {
+            let opts = parse_cargo_opts(args);
+            let mut cli = CargoCli::new("test", None, output);
+            cli.add_options(&opts);
+            assert_eq!(&cli.args, expected, "args for {args:?}");
+        }
+    }
+}
@@ -2,45 +2,75 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    cargo_cli::{CargoCli, CargoOptions},
-    output::{OutputContext, OutputOpts, OutputWriter},
+    bisect::TestBisector,
+    cargo_cli::{parse_build_script_failures, CargoCli, CargoOptions},
+    errors::BuildError,
+    feature_combinations::FeatureCombinationRunner,
+    flaky_rate::FlakyRateReport,
+    heartbeat::Heartbeat,
+    output::{Color, OutputContext, OutputOpts, OutputWriter},
+    pid_file::PidFile,
     reuse_build::{make_path_mapper, ArchiveFormatOpt, ReuseBuildOpts},
     ExpectedError, Result, ReuseBuildKind,
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
-use guppy::graph::PackageGraph;
+use guppy::{graph::PackageGraph, PackageId};
 use itertools::Itertools;
 use nextest_filtering::FilteringExpr;
-use nextest_metadata::{BinaryListSummary, BuildPlatform};
+use nextest_metadata::{
+    BinaryListSummary, BuildPlatform, BuildScriptFailure, MismatchReason, NextestExitCode,
+};
 use nextest_runner::{
     cargo_config::{CargoConfigs, EnvironmentMap, TargetTriple},
+    command_wrapper::CommandWrapper,
     config::{
-        get_num_cpus, NextestConfig, NextestProfile, PreBuildPlatform, RetryPolicy, TestGroup,
-        TestThreads, ToolConfigFile,
+        get_num_cpus, JunitClassNameStyle, JunitFailureFormat, JunitHostname, JunitPathRewrite,
+        JunitSuiteNameStyle, JunitTimestampFormat, NextestConfig, NextestJunitConfig,
+        NextestProfile, PreBuildPlatform, ProfileSelection, ProfileSelectionReason, RetryFilter,
+        RetryPolicy, TestGroup, TestThreads, ToolConfigFile,
     },
     double_spawn::DoubleSpawnInfo,
-    errors::WriteTestListError,
+    errors::{RunError, WriteTestListError},
     list::{
-        BinaryList, OutputFormat, RustTestArtifact, SerializableFormat, TestExecuteContext,
-        TestList,
+        check_extra_args, BinaryList, BinaryPathOverride, ExpectedTestCount, OutputFormat,
+        RustTestArtifact, RustTestSuiteStatus, SerializableFormat, TestCountBound,
+        TestExecuteContext, TestList,
     },
-    partition::PartitionerBuilder,
+    partition::{PartitionerBuilder, TimingData},
     platform::BuildPlatforms,
-    reporter::{FinalStatusLevel, StatusLevel, TestOutputDisplay, TestReporterBuilder},
-    reuse_build::{archive_to_file, ArchiveReporter, MetadataOrPath, PathMapper, ReuseBuildInfo},
-    runner::{configure_handle_inheritance, TestRunnerBuilder},
+    reporter::{
+        FinalStatusLevel, JunitProperty, ProgressBarStyle, RunHistory, RunSummary,
+        RunSummaryRecorder, StatusLevel, SummaryFormat, TestEvent, TestOutputDisplay,
+        TestReporterBuilder,
+    },
+    reuse_build::{
+        archive_to_file, inspect_archive, ArchiveReporter, ArchiveSourceOptions, MetadataOrPath,
+        PathMapper, ReuseBuildInfo, StripDebugInfoOptions,
+    },
+    runner::{
+        configure_handle_inheritance, EnvInheritance, ImmediateRetryCondition, RunStats,
+        ShuffleSeed, SimulatedFailures, TestIsolation, TestProcessPriority, TestRunnerBuilder,
+    },
     show_config::{ShowTestGroupSettings, ShowTestGroups, ShowTestGroupsMode},
     signal::SignalHandlerKind,
     target_runner::{PlatformRunner, TargetRunner},
-    test_filter::{RunIgnored, TestFilterBuilder},
+    test_filter::{
+        BinaryNameFilter, BinaryNameGlob, RunIgnored, TestFilterBuilder, WorkspaceFilter,
+        WorkspaceFilterExpr, WorkspaceIgnoreFilter, WorkspacePathGlob,
+    },
 };
 use once_cell::sync::OnceCell;
 use owo_colors::{OwoColorize, Stream, Style};
+use semver::Version;
 use std::{
+    borrow::Cow,
+    convert::Infallible,
     fmt::Write as _,
-    io::{Cursor, Write},
+    io::{Cursor, Read, Write},
+    num::NonZeroUsize,
     sync::Arc,
+    time::Duration,
 };
 
 /// A next-generation test runner for Rust.
@@ -108,6 +138,7 @@ impl AppOpts {
     /// Returns the exit code.
     fn exec(self, output_writer: &mut OutputWriter) -> Result<i32> {
         let output = self.output.init();
+        let bench = matches!(self.command, Command::Bench { .. });
 
         match self.command {
             Command::List {
@@ -131,6 +162,32 @@ impl AppOpts {
                 app.exec_list(message_format, list_type, output_writer)?;
                 Ok(0)
             }
+            Command::Check {
+                cargo_options,
+                build_filter,
+                reuse_build,
+            } => {
+                let base = BaseApp::new(
+                    output,
+                    reuse_build,
+                    cargo_options,
+                    self.config_opts,
+                    self.manifest_path,
+                    build_filter_needs_deps(&build_filter),
+                    output_writer,
+                )?;
+                let app = App::new(base, build_filter)?;
+                match app.exec_check() {
+                    Ok(()) => Ok(0),
+                    Err(err @ ExpectedError::CreateTestListError { .. }) => {
+                        // A test binary failed to list: report the error and exit with 1, as
+                        // documented.
+                        err.display_to_stderr();
+                        Ok(1)
+                    }
+                    Err(err) => Err(err),
+                }
+            }
             Command::Run {
                 profile,
                 no_capture,
@@ -140,8 +197,18 @@ impl AppOpts {
                 reporter_opts,
                 reuse_build,
                 ..
+            }
+            | Command::Bench {
+                profile,
+                no_capture,
+                cargo_options,
+                build_filter,
+                runner_opts,
+                reporter_opts,
+                reuse_build,
+                ..
             } => {
-                let base = BaseApp::new(
+                let mut base = BaseApp::new(
                     output,
                     reuse_build,
                     cargo_options,
@@ -150,10 +217,14 @@ impl AppOpts {
                     build_filter_needs_deps(&build_filter),
                     output_writer,
                 )?;
-                let app = App::new(base, build_filter)?;
+                if bench {
+                    base.set_cargo_command("bench");
+                }
+                let mut app = App::new(base, build_filter)?;
                 app.exec_run(
-                    profile.as_deref(),
+                    &profile,
                     no_capture,
+                    bench,
                     &runner_opts,
                     &reporter_opts,
                     output_writer,
@@ -161,11 +232,29 @@ impl AppOpts {
                 Ok(0)
             }
             Command::Archive {
+                command:
+                    Some(ArchiveCommand::Inspect {
+                        archive_file,
+                        archive_format,
+                    }),
+                ..
+            } => {
+                exec_archive_inspect(&archive_file, archive_format, output_writer)?;
+                Ok(0)
+            }
+            Command::Archive {
+                command: None,
                 cargo_options,
                 archive_file,
                 archive_format,
                 zstd_level,
+                include_source,
+                max_source_size_mb,
+                strip_debug_info,
+                strip_debug_info_tool,
             } => {
+                let archive_file =
+                    archive_file.expect("archive-file is required without a subcommand");
                 let app = BaseApp::new(
                     output,
                     ReuseBuildOpts::default(),
@@ -175,7 +264,31 @@ impl AppOpts {
                     true,
                     output_writer,
                 )?;
-                app.exec_archive(&archive_file, archive_format, zstd_level, output_writer)?;
+                // Limits too large to represent in bytes are effectively unlimited.
+                let max_source_size =
+                    include_source.then_some(max_source_size_mb.saturating_mul(1024 * 1024));
+                let strip_debug_info = strip_debug_info.then_some(StripDebugInfoOptions {
+                    tool: strip_debug_info_tool,
+                });
+                app.exec_archive(
+                    &archive_file,
+                    archive_format,
+                    zstd_level,
+                    max_source_size,
+                    strip_debug_info.as_ref(),
+                    output_writer,
+                )?;
+                Ok(0)
+            }
+            Command::Status { profile, format } => {
+                exec_status(
+                    self.manifest_path,
+                    self.output,
+                    self.config_opts,
+                    profile.as_deref(),
+                    format,
+                    output_writer,
+                )?;
                 Ok(0)
             }
             Command::ShowConfig { command } => command.exec(
@@ -220,6 +333,13 @@ impl ConfigOpts {
         workspace_root: &Utf8Path,
         graph: &PackageGraph,
     ) -> Result<NextestConfig> {
+        NextestConfig::check_min_version(
+            workspace_root,
+            self.config_file.as_deref(),
+            &self.tool_config_files,
+            &current_version(),
+        )
+        .map_err(ExpectedError::config_parse_error)?;
         NextestConfig::from_sources(
             workspace_root,
             graph,
@@ -230,6 +350,13 @@ impl ConfigOpts {
     }
 }
 
+/// Returns the version of cargo-nextest that's running.
+pub(crate) fn current_version() -> Version {
+    env!("CARGO_PKG_VERSION")
+        .parse()
+        .expect("cargo-nextest uses semantic versioning")
+}
+
 #[derive(Debug, Subcommand)]
 enum Command {
     /// List tests in workspace
@@ -273,6 +400,24 @@ enum Command {
         #[clap(flatten)]
         reuse_build: ReuseBuildOpts,
     },
+    /// Check that tests can be listed, without running them
+    ///
+    /// This command builds test binaries and queries them for the tests they contain, but exits
+    /// before running any tests. This catches compilation errors and broken test discovery without
+    /// the cost of a full run.
+    ///
+    /// Exits with code 0 if all test binaries were listed successfully, and 1 if any of them
+    /// failed to list.
+    Check {
+        #[clap(flatten)]
+        cargo_options: CargoOptions,
+
+        #[clap(flatten)]
+        build_filter: TestBuildFilter,
+
+        #[clap(flatten)]
+        reuse_build: ReuseBuildOpts,
+    },
     /// Build and run tests
     ///
     /// This command builds test binaries and queries them for the tests they contain,
@@ -281,9 +426,43 @@ enum Command {
     /// For more information, see <https://nexte.st/book/running>.
     #[command(visible_alias = "r")]
     Run {
-        /// Nextest profile to use
-        #[arg(long, short = 'P', env = "NEXTEST_PROFILE")]
-        profile: Option<String>,
+        #[clap(flatten)]
+        profile: ProfileOpts,
+
+        /// Run tests serially and do not capture output
+        #[arg(
+            long,
+            name = "no-capture",
+            alias = "nocapture",
+            help_heading = "Runner options",
+            display_order = 100
+        )]
+        no_capture: bool,
+
+        #[clap(flatten)]
+        cargo_options: CargoOptions,
+
+        #[clap(flatten)]
+        build_filter: TestBuildFilter,
+
+        #[clap(flatten)]
+        runner_opts: TestRunnerOpts,
+
+        #[clap(flatten)]
+        reporter_opts: TestReporterOpts,
+
+        #[clap(flatten)]
+        reuse_build: ReuseBuildOpts,
+    },
+    /// Build and run benchmarks
+    ///
+    /// This command builds test binaries with `cargo bench`, then runs each test and benchmark
+    /// through nextest's runner. Benchmarks are run serially unless --test-threads is passed in.
+    ///
+    /// For more information, see <https://nexte.st/book/benchmark-integration>.
+    Bench {
+        #[clap(flatten)]
+        profile: ProfileOpts,
 
         /// Run tests serially and do not capture output
         #[arg(
@@ -310,6 +489,21 @@ enum Command {
         #[clap(flatten)]
         reuse_build: ReuseBuildOpts,
     },
+    /// Show the summary of the last test run
+    ///
+    /// This command shows the results of the last `cargo nextest run` with the selected profile,
+    /// without building or running any tests.
+    ///
+    /// Exits with a setup error if no tests have been run with the profile yet.
+    Status {
+        /// Nextest profile to show the last run for
+        #[arg(long, short = 'P', env = "NEXTEST_PROFILE")]
+        profile: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t, value_name = "FORMAT")]
+        format: StatusFormatOpt,
+    },
     /// Build and archive tests
     ///
     /// This command builds test binaries and archives them to a file. The archive can then be
@@ -317,7 +511,11 @@ enum Command {
     /// --archive-file`.
     ///
     /// The archive is a tarball compressed with Zstandard (.tar.zst).
+    #[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
     Archive {
+        #[clap(subcommand)]
+        command: Option<ArchiveCommand>,
+
         #[clap(flatten)]
         cargo_options: CargoOptions,
 
@@ -326,9 +524,10 @@ enum Command {
             long,
             name = "archive-file",
             help_heading = "Archive options",
-            value_name = "PATH"
+            value_name = "PATH",
+            required = true
         )]
-        archive_file: Utf8PathBuf,
+        archive_file: Option<Utf8PathBuf>,
 
         /// Archive format
         ///
@@ -352,6 +551,40 @@ enum Command {
             allow_negative_numbers = true
         )]
         zstd_level: i32,
+
+        /// Include workspace source files tracked by git in the archive
+        ///
+        /// Source files are stored under `source/` within the archive, so that test runs can be
+        /// replayed against the source on machines without a checkout.
+        #[arg(long, help_heading = "Archive options")]
+        include_source: bool,
+
+        /// Maximum total size of source files to include, in megabytes
+        #[arg(
+            long,
+            help_heading = "Archive options",
+            value_name = "SIZE",
+            default_value_t = 100,
+            requires = "include_source"
+        )]
+        max_source_size_mb: u64,
+
+        /// Strip debug info from binaries before archiving them
+        ///
+        /// Debug info usually makes up most of the size of test binaries. Stripping it makes
+        /// archives smaller, at the cost of less detailed backtraces.
+        #[arg(long, help_heading = "Archive options")]
+        strip_debug_info: bool,
+
+        /// Tool to strip debug info with, invoked as `TOOL --strip-debug INPUT OUTPUT`
+        #[arg(
+            long,
+            help_heading = "Archive options",
+            value_name = "TOOL",
+            default_value = StripDebugInfoOptions::DEFAULT_TOOL,
+            requires = "strip_debug_info"
+        )]
+        strip_debug_info_tool: String,
         // ReuseBuildOpts, while it can theoretically work, is way too confusing so skip it.
     },
     /// Show information about nextest's configuration in this workspace.
@@ -395,6 +628,13 @@ impl From<PlatformFilterOpts> for Option<BuildPlatform> {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+enum StatusFormatOpt {
+    #[default]
+    Human,
+    Json,
+}
+
 #[derive(Copy, Clone, Debug, ValueEnum)]
 enum ListType {
     Full,
@@ -412,6 +652,7 @@ enum MessageFormatOpts {
     Human,
     Json,
     JsonPretty,
+    Dot,
 }
 
 impl MessageFormatOpts {
@@ -420,6 +661,7 @@ impl MessageFormatOpts {
             Self::Human => OutputFormat::Human { verbose },
             Self::Json => OutputFormat::Serializable(SerializableFormat::Json),
             Self::JsonPretty => OutputFormat::Serializable(SerializableFormat::JsonPretty),
+            Self::Dot => OutputFormat::Dot,
         }
     }
 }
@@ -441,6 +683,10 @@ struct TestBuildFilter {
     #[arg(long)]
     partition: Option<PartitionerBuilder>,
 
+    /// Fail if --partition would leave fewer than N tests per shard, so that no shard is empty
+    #[arg(long, value_name = "N", requires = "partition")]
+    min_shards_for_balance: Option<NonZeroUsize>,
+
     /// Filter test binaries by build platform (DEPRECATED)
     ///
     /// Instead, use -E with 'platform(host)' or 'platform(target)'.
@@ -453,6 +699,35 @@ struct TestBuildFilter {
     )]
     pub(crate) platform_filter: PlatformFilterOpts,
 
+    /// Only run tests in binaries whose names match this glob (may be specified multiple times)
+    #[arg(long, value_name = "GLOB", action(ArgAction::Append))]
+    test_binary_filter: Vec<BinaryNameGlob>,
+
+    /// Only run tests in packages whose [package.metadata] matches this expression (may be
+    /// specified multiple times)
+    ///
+    /// Expressions are of the form `metadata.key = "value"` or `metadata.key exists`.
+    #[arg(long, value_name = "EXPRESSION", action(ArgAction::Append))]
+    workspace_filter: Vec<WorkspaceFilterExpr>,
+
+    /// Skip building and running tests in workspace packages whose paths, relative to the
+    /// workspace root, match this glob (may be specified multiple times)
+    #[arg(long, value_name = "GLOB", action(ArgAction::Append))]
+    workspace_ignore_path: Vec<WorkspacePathGlob>,
+
+    /// Run the test binary with this ID from a different path, e.g. after rebuilding it manually
+    /// (may be specified multiple times)
+    #[arg(long, value_name = "BINARY_ID=PATH", action(ArgAction::Append))]
+    test_binary_path_override: Vec<BinaryPathOverride>,
+
+    /// Exclude doctests from the run (nextest doesn't run doctests, so this is always the case)
+    #[arg(long, conflicts_with = "only_doctest")]
+    ignore_doctest: bool,
+
+    /// Only run doctests (unsupported: run doctests with `cargo test --doc` instead)
+    #[arg(long)]
+    only_doctest: bool,
+
     /// Test filter expression (see {n}<https://nexte.st/book/filter-expressions>)
     #[arg(
         long,
@@ -467,6 +742,11 @@ struct TestBuildFilter {
     #[arg(name = "FILTERS", help_heading = None)]
     filter: Vec<String>,
 
+    /// Pass arguments after `--` to every test binary invocation, rather than interpreting them as
+    /// emulated cargo test binary arguments
+    #[arg(long)]
+    extra_args: bool,
+
     /// Emulated cargo test binary arguments (partially supported)
     #[arg(help_heading = None, value_name = "TEST-BINARY-ARGS", last = true)]
     test_binary_args: Vec<String>,
@@ -489,14 +769,26 @@ impl TestBuildFilter {
         )?;
 
         let rust_build_meta = binary_list.rust_build_meta.map_paths(&path_mapper);
-        let test_artifacts = RustTestArtifact::from_binary_list(
+        let mut test_artifacts = RustTestArtifact::from_binary_list(
             graph,
             binary_list,
             &rust_build_meta,
             &path_mapper,
             self.platform_filter.into(),
         )?;
-        TestList::new(
+        RustTestArtifact::apply_path_overrides(
+            &mut test_artifacts,
+            &self.test_binary_path_override,
+        )?;
+        let binary_name_filter = BinaryNameFilter::new(self.test_binary_filter.iter().cloned());
+        let workspace_filter = WorkspaceFilter::new(self.workspace_filter.iter().cloned());
+        let ignore_filter = WorkspaceIgnoreFilter::new(self.workspace_ignore_path.iter().cloned());
+        test_artifacts.retain(|artifact| {
+            binary_name_filter.is_match(artifact)
+                && workspace_filter.is_match(artifact)
+                && ignore_filter.is_match(artifact)
+        });
+        let test_list = TestList::new(
             ctx,
             test_artifacts,
             rust_build_meta,
@@ -505,7 +797,13 @@ impl TestBuildFilter {
             // TODO: do we need to allow customizing this?
             get_num_cpus(),
         )
-        .map_err(|err| ExpectedError::CreateTestListError { err })
+        .map_err(|err| ExpectedError::CreateTestListError { err })?;
+        if let (Some(partition), Some(min_tests_per_shard)) =
+            (&self.partition, self.min_shards_for_balance)
+        {
+            test_list.check_partition_balance(partition, min_tests_per_shard)?;
+        }
+        Ok(test_list)
     }
 
     fn make_test_filter_builder(
@@ -525,11 +823,28 @@ impl TestBuildFilter {
         )?)
     }
 
+    /// Returns the arguments to pass to every test binary invocation.
+    ///
+    /// These are validated in `merge_test_binary_args`.
+    fn extra_args(&self) -> &[String] {
+        if self.extra_args {
+            &self.test_binary_args
+        } else {
+            &[]
+        }
+    }
+
     fn merge_test_binary_args(
         &self,
         run_ignored: &mut Option<RunIgnored>,
         patterns: &mut Vec<String>,
     ) -> Result<()> {
+        if self.extra_args {
+            // The test binary args are forwarded as-is, so there's nothing to merge.
+            check_extra_args(&self.test_binary_args)?;
+            return Ok(());
+        }
+
         let mut ignore_filters = Vec::new();
         let mut read_trailing_filters = false;
 
@@ -615,37 +930,333 @@ impl From<RunIgnoredOpt> for RunIgnored {
 }
 
 impl CargoOptions {
+    #[allow(clippy::too_many_arguments)]
     fn compute_binary_list(
         &self,
-        graph: &PackageGraph,
+        cargo_command: &str,
+        graph_data: &(String, PackageGraph),
         manifest_path: Option<&Utf8Path>,
         output: OutputContext,
+        cargo_configs: &CargoConfigs,
         target_triple: Option<TargetTriple>,
+        default_build_jobs: Option<NonZeroUsize>,
     ) -> Result<BinaryList> {
+        let (graph_json, graph) = graph_data;
+        let mut options = Cow::Borrowed(self);
+        if let Some(build_jobs) = default_build_jobs {
+            options.to_mut().set_default_build_jobs(build_jobs);
+        }
+        let build_envs = self.build_envs(|| match &target_triple {
+            Some(triple) => cargo_configs.rustflags(&triple.platform),
+            None => match BuildPlatforms::new(None) {
+                Ok(build_platforms) => cargo_configs.rustflags(&build_platforms.host),
+                Err(error) => {
+                    log::warn!("ignoring rustflags in Cargo configuration: {error}");
+                    Vec::new()
+                }
+            },
+        });
+        let mut build_script_failures = Vec::new();
+        let stdout = loop {
+            match options.run_build(cargo_command, manifest_path, output, &build_envs)? {
+                Ok(stdout) => break stdout,
+                Err((stderr, error)) => {
+                    if !self.ignore_build_script_failures {
+                        return Err(error);
+                    }
+                    let failures = parse_build_script_failures(&stderr);
+
+                    // Only workspace packages can be excluded from the build.
+                    let mut in_dependencies = false;
+                    for failure in &failures {
+                        if graph.workspace().member_by_name(&failure.package).is_err() {
+                            log::warn!(
+                                "build script for dependency {} failed: only the tests of \
+                                 workspace packages can be skipped with \
+                                 --ignore-build-script-failures",
+                                failure
+                                    .package
+                                    .if_supports_color(Stream::Stderr, |x| x.bold()),
+                            );
+                            in_dependencies = true;
+                        }
+                    }
+
+                    let packages: Vec<_> = failures
+                        .iter()
+                        .map(|failure| failure.package.clone())
+                        .collect();
+                    // Every retry must exclude at least one new package, otherwise this would
+                    // loop forever.
+                    if in_dependencies
+                        || failures.is_empty()
+                        || build_script_failures
+                            .iter()
+                            .any(|failure: &BuildScriptFailure| packages.contains(&failure.package))
+                        || !options.to_mut().exclude_packages(&packages, || {
+                            default_packages(graph, graph_json, manifest_path)
+                        })
+                    {
+                        return Err(error);
+                    }
+
+                    for failure in &failures {
+                        log::warn!(
+                            "build script for {} failed, skipping its tests",
+                            failure
+                                .package
+                                .if_supports_color(Stream::Stderr, |x| x.bold()),
+                        );
+                    }
+                    build_script_failures.extend(failures);
+                }
+            }
+        };
+
+        let mut test_binaries =
+            BinaryList::from_messages(Cursor::new(stdout), graph, target_triple)?;
+        test_binaries.rust_build_meta.build_script_failures = build_script_failures;
+        Ok(test_binaries)
+    }
+
+    /// Runs the build once, returning Cargo's stdout on success, or its stderr (if captured) and
+    /// the error to report on failure.
+    fn run_build(
+        &self,
+        cargo_command: &str,
+        manifest_path: Option<&Utf8Path>,
+        output: OutputContext,
+        build_envs: &[(&str, String)],
+    ) -> Result<std::result::Result<Vec<u8>, (String, ExpectedError)>> {
+        // Errors are checked for build script failures, but should still be shown as Cargo writes
+        // them. Cargo can't tell that its stderr ends up on a terminal in that case, so decide
+        // whether to colorize it here.
+        let tee_stderr = self.ignore_build_script_failures && !self.cargo_quiet;
+        let mut output = output;
+        if tee_stderr {
+            output.color = if output.color.should_colorize(supports_color::Stream::Stderr) {
+                Color::Always
+            } else {
+                Color::Never
+            };
+        }
+
         // Don't use the manifest path from the graph to ensure that if the user cd's into a
         // particular crate and runs cargo nextest, then it behaves identically to cargo test.
-        let mut cargo_cli = CargoCli::new("test", manifest_path, output);
+        let mut cargo_cli = CargoCli::new(cargo_command, manifest_path, output);
 
         // Only build tests in the cargo test invocation, do not run them.
         cargo_cli.add_args(["--no-run", "--message-format", "json-render-diagnostics"]);
         cargo_cli.add_options(self);
 
-        let expression = cargo_cli.to_expression();
-        let output = expression
-            .stdout_capture()
-            .unchecked()
-            .run()
-            .map_err(|err| ExpectedError::build_exec_failed(cargo_cli.all_args(), err))?;
+        let output = if tee_stderr {
+            let mut command = cargo_cli.to_command();
+            command.envs(build_envs.iter().map(|(key, value)| (key, value)));
+            output_tee_stderr(command)
+        } else {
+            let mut expression = cargo_cli.to_expression().stdout_capture().unchecked();
+            for (key, value) in build_envs {
+                expression = expression.env(key, value);
+            }
+            if self.cargo_quiet || self.ignore_build_script_failures {
+                // Capture errors so that they can be reported as part of the build failure, or
+                // checked for build script failures.
+                expression = expression.stderr_capture();
+            }
+            expression.run()
+        }
+        .map_err(|err| ExpectedError::build_exec_failed(cargo_cli.all_args(), err))?;
+        let stderr = if tee_stderr {
+            // Remove any colors so that the output can be parsed.
+            let stripped = strip_ansi_escapes::strip(&output.stderr)
+                .expect("writing to a Vec<u8> is infallible");
+            String::from_utf8_lossy(&stripped).into_owned()
+        } else {
+            String::from_utf8_lossy(&output.stderr).into_owned()
+        };
         if !output.status.success() {
-            return Err(ExpectedError::build_failed(
-                cargo_cli.all_args(),
-                output.status.code(),
-            ));
+            let error = self.cargo_quiet.then(|| BuildError::new(&stderr));
+            let error =
+                ExpectedError::build_failed(cargo_cli.all_args(), output.status.code(), error);
+            return Ok(Err((stderr, error)));
         }
 
-        let test_binaries =
-            BinaryList::from_messages(Cursor::new(output.stdout), graph, target_triple)?;
-        Ok(test_binaries)
+        Ok(Ok(output.stdout))
+    }
+}
+
+/// Runs `command`, capturing its stdout and stderr while also copying its stderr to this process's
+/// stderr as it's written.
+fn output_tee_stderr(mut command: std::process::Command) -> std::io::Result<std::process::Output> {
+    let mut child = command
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+
+    // Read stdout on a separate thread so that the child doesn't block on either pipe.
+    let mut child_stdout = child.stdout.take().expect("stdout is piped");
+    let stdout_thread = std::thread::spawn(move || {
+        let mut stdout = Vec::new();
+        child_stdout.read_to_end(&mut stdout).map(|_| stdout)
+    });
+
+    let mut child_stderr = child.stderr.take().expect("stderr is piped");
+    let mut stderr = Vec::new();
+    let mut buf = [0; 8192];
+    loop {
+        let n = match child_stderr.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        // Failing to show Cargo's output isn't fatal, since it's still captured.
+        let _ = std::io::stderr().write_all(&buf[..n]);
+        stderr.extend_from_slice(&buf[..n]);
+    }
+
+    let stdout = stdout_thread
+        .join()
+        .expect("stdout reader thread doesn't panic")?;
+    let status = child.wait()?;
+    Ok(std::process::Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Returns the names of the packages that Cargo builds if no packages are selected.
+///
+/// This is the package whose manifest is passed in with `--manifest-path` or found in the current
+/// directory, or if that's the workspace root, the workspace's default members.
+fn default_packages(
+    graph: &PackageGraph,
+    graph_json: &str,
+    manifest_path: Option<&Utf8Path>,
+) -> Vec<String> {
+    let workspace = graph.workspace();
+    let manifest_dir = match manifest_path {
+        Some(path) => path.parent().map(|dir| dir.as_std_path().to_owned()),
+        None => std::env::current_dir().ok().and_then(|dir| {
+            dir.ancestors()
+                .find(|dir| dir.join("Cargo.toml").is_file())
+                .map(|dir| dir.to_owned())
+        }),
+    };
+    let rel_dir = manifest_dir
+        .and_then(|dir| dir.canonicalize().ok())
+        .and_then(|dir| Utf8PathBuf::try_from(dir).ok())
+        .and_then(|dir| {
+            dir.strip_prefix(workspace.root())
+                .ok()
+                .map(|rel_dir| rel_dir.to_owned())
+        });
+    if let Some(rel_dir) = rel_dir {
+        if rel_dir.as_str() != "" {
+            if let Ok(package) = workspace.member_by_path(&rel_dir) {
+                return vec![package.name().to_owned()];
+            }
+        }
+    }
+
+    // This is the workspace root. Cargo versions that support default members report them in
+    // the metadata.
+    let default_members = serde_json::from_str::<serde_json::Value>(graph_json)
+        .ok()
+        .and_then(|metadata| {
+            metadata
+                .get("workspace_default_members")?
+                .as_array()?
+                .iter()
+                .map(|id| {
+                    let id = PackageId::new(id.as_str()?);
+                    graph
+                        .metadata(&id)
+                        .ok()
+                        .map(|package| package.name().to_owned())
+                })
+                .collect::<Option<Vec<_>>>()
+        });
+    if let Some(default_members) = default_members {
+        return default_members;
+    }
+    match workspace.member_by_path("") {
+        Ok(package) => vec![package.name().to_owned()],
+        Err(_) => workspace
+            .iter()
+            .map(|package| package.name().to_owned())
+            .collect(),
+    }
+}
+
+/// Profile selection options.
+#[derive(Debug, Default, Args)]
+struct ProfileOpts {
+    /// Nextest profile to use
+    #[arg(long, short = 'P', env = "NEXTEST_PROFILE")]
+    profile: Option<String>,
+
+    /// How to select a profile if --profile isn't passed in [default: from config]
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        env = "NEXTEST_PROFILE_SELECTION"
+    )]
+    profile_selection: Option<ProfileSelectionOpt>,
+
+    /// Read the profile name from this environment variable, falling back to --profile if it
+    /// isn't set
+    #[arg(long, value_name = "VAR")]
+    profile_from_env: Option<String>,
+
+    /// Print the merged configuration for the profile as TOML before running tests
+    ///
+    /// The printed configuration includes settings inherited from the default profile, and
+    /// overrides that apply to the current build platforms.
+    #[arg(long)]
+    print_config_after_merge: bool,
+}
+
+impl ProfileOpts {
+    /// Returns the profile name that was passed in, along with the environment variable it was
+    /// read from, if any.
+    fn profile_name(&self) -> (Option<Cow<'_, str>>, Option<&str>) {
+        if let Some(env_var) = &self.profile_from_env {
+            match std::env::var(env_var) {
+                Ok(name) if !name.is_empty() => {
+                    log::info!(
+                        "using profile {} (from {env_var})",
+                        name.if_supports_color(Stream::Stderr, |x| x.bold()),
+                    );
+                    return (Some(Cow::Owned(name)), Some(env_var));
+                }
+                Ok(_) | Err(std::env::VarError::NotPresent) => {}
+                Err(std::env::VarError::NotUnicode(_)) => {
+                    log::warn!("ignoring --profile-from-env: `{env_var}` is not valid UTF-8");
+                }
+            }
+        }
+        (self.profile.as_deref().map(Cow::Borrowed), None)
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ProfileSelectionOpt {
+    /// Use the default profile
+    Manual,
+
+    /// Use the `ci` profile if it is defined and a CI environment is detected
+    Auto,
+}
+
+impl From<ProfileSelectionOpt> for ProfileSelection {
+    fn from(opt: ProfileSelectionOpt) -> Self {
+        match opt {
+            ProfileSelectionOpt::Manual => ProfileSelection::Manual,
+            ProfileSelectionOpt::Auto => ProfileSelection::Auto,
+        }
     }
 }
 
@@ -674,6 +1285,28 @@ pub struct TestRunnerOpts {
     #[arg(long, env = "NEXTEST_RETRIES", conflicts_with = "no-run")]
     retries: Option<usize>,
 
+    /// Stop retrying failing tests once this many retries have been performed across the run
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = &["no-run", "report_flaky_rate"]
+    )]
+    max_retries_global: Option<usize>,
+
+    /// Retry failing tests immediately, without a delay, if they match this condition, e.g.
+    /// "signal:SIGSEGV" (may be specified multiple times)
+    #[arg(long, value_name = "CONDITION", conflicts_with = "no-run")]
+    retry_immediately_on: Vec<ImmediateRetryCondition>,
+
+    /// Only retry failing tests that exit with this exit code (may be specified multiple times)
+    #[arg(
+        long,
+        value_name = "CODE",
+        conflicts_with = "no-run",
+        allow_negative_numbers = true
+    )]
+    retry_if_exit_code: Vec<i32>,
+
     /// Cancel test run on the first failure
     #[arg(long, name = "fail-fast", conflicts_with = "no-run")]
     fail_fast: bool,
@@ -681,32 +1314,505 @@ pub struct TestRunnerOpts {
     /// Run all tests regardless of failure
     #[arg(long, conflicts_with = "no-run", overrides_with = "fail-fast")]
     no_fail_fast: bool,
-}
 
-impl TestRunnerOpts {
-    fn to_builder(&self, no_capture: bool) -> Option<TestRunnerBuilder> {
-        if self.no_run {
-            return None;
-        }
+    /// Stop running tests from a test binary after this many of its tests fail [default: from
+    /// profile]
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = &["no-run", "test_isolation"]
+    )]
+    failure_limit_per_binary: Option<NonZeroUsize>,
 
-        let mut builder = TestRunnerBuilder::default();
-        builder.set_no_capture(no_capture);
-        if let Some(retries) = self.retries {
-            builder.set_retries(RetryPolicy::new_without_delay(retries));
-        }
+    /// Stop running tests from a test binary once this much time has passed since its first test
+    /// started [default: from profile]
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        conflicts_with_all = &["no-run", "test_isolation"]
+    )]
+    test_binary_timeout: Option<Duration>,
+
+    /// Mark tests as failed if they write anything to stderr [default: from profile]
+    #[arg(long, conflicts_with_all = &["no-run", "no-capture"])]
+    fail_on_stderr: bool,
+
+    /// Display test output as it's written, with each line prefixed by the test name
+    #[arg(long, conflicts_with_all = &["no-run", "no-capture"])]
+    stream_output: bool,
+
+    /// Connect the stdin of each test to nextest's stdin (implies --test-threads 1)
+    #[arg(long, conflicts_with = "no-run")]
+    stdin_passthrough: bool,
+
+    /// Kill tests that call connect, bind or sendto (Linux only)
+    #[arg(long, conflicts_with = "no-run", overrides_with = "network_access")]
+    no_network_access: bool,
+
+    /// Allow tests to access the network [default]
+    #[arg(long, conflicts_with = "no-run", overrides_with = "no_network_access")]
+    network_access: bool,
+
+    /// Fail tests that leave processes running after they exit (Linux and Windows only)
+    #[arg(long, conflicts_with = "no-run")]
+    fail_on_leaked_processes: bool,
+
+    /// Shared libraries to load into test processes through LD_PRELOAD, or DYLD_INSERT_LIBRARIES on
+    /// macOS (comma-separated, not supported on Windows)
+    #[arg(
+        long,
+        value_name = "LIBS",
+        value_delimiter = ',',
+        conflicts_with = "no-run"
+    )]
+    preload_libraries: Vec<Utf8PathBuf>,
+
+    /// How to isolate tests from each other [default: process]
+    ///
+    /// `process` runs each test in its own process. `thread` runs each binary's tests in one
+    /// process on separate threads, and `none` runs them in one process on a single thread. Retries
+    /// and timeouts don't apply to `thread` and `none`.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        conflicts_with_all = &["no-run", "no-capture", "stream_output"]
+    )]
+    test_isolation: Option<TestIsolationOpt>,
+
+    /// Prefix each line of captured output with the time since the test started, e.g. [+1.234s]
+    #[arg(long, conflicts_with_all = &["no-run", "no-capture", "test_isolation"])]
+    annotate_output_with_timestamps: bool,
+
+    /// Run each test process under this command, e.g. "taskset -c 0-3"
+    ///
+    /// The command is passed the test binary and its arguments (preceded by the target runner, if
+    /// any) as trailing arguments. It isn't used while listing tests.
+    #[arg(long, value_name = "COMMAND", conflicts_with = "no-run")]
+    test_wrapper: Option<CommandWrapper>,
+
+    /// CPU scheduling priority to run test processes at [default: normal]
+    #[arg(long, value_enum, value_name = "PRIORITY", conflicts_with = "no-run")]
+    test_process_priority: Option<TestProcessPriorityOpt>,
+
+    /// Whether tests can write to the workspace (read-only is Linux only) [default: read-write]
+    #[arg(long, value_enum, value_name = "ACCESS", conflicts_with = "no-run")]
+    filesystem_access: Option<FilesystemAccessOpt>,
+
+    /// Directory that tests can write to with --filesystem-access read-only (may be specified
+    /// multiple times)
+    #[arg(long, value_name = "PATH", requires = "filesystem_access")]
+    filesystem_access_exceptions: Vec<Utf8PathBuf>,
+
+    /// Report tests that create, remove or modify files in their working directory
+    #[arg(long, conflicts_with = "no-run")]
+    detect_leaky_tests: bool,
+
+    /// Additional path to check with --detect-leaky-tests (may be specified multiple times)
+    #[arg(long, value_name = "PATH", requires = "detect_leaky_tests")]
+    leak_detection_path: Vec<Utf8PathBuf>,
+
+    /// Run tests in a random order determined by this seed [possible values: integer or "random"]
+    #[arg(
+        long,
+        value_name = "SEED",
+        conflicts_with = "no-run",
+        env = "NEXTEST_SEED"
+    )]
+    seed: Option<ShuffleSeed>,
+
+    /// Run tests in a random order (alias for --seed random)
+    #[arg(long, conflicts_with_all = &["no-run", "seed"])]
+    shuffle: bool,
+
+    /// Warn if a newer version of nextest is available
+    ///
+    /// The latest version is fetched in the background while tests are built, and cached for 24
+    /// hours. The check never delays the test run.
+    #[arg(long)]
+    check_for_updates: bool,
+
+    /// Check that the target runner exists and is executable before building tests
+    ///
+    /// A misconfigured target runner is reported immediately rather than after the build.
+    #[arg(long, conflicts_with = "no-run")]
+    check_target_runner: bool,
+
+    /// Write the process ID of nextest to this file, and remove it when nextest exits
+    ///
+    /// Fails if the file already exists. The file is left behind if nextest exits abnormally.
+    #[arg(long, value_name = "PATH", conflicts_with = "no-run")]
+    test_pid_file: Option<Utf8PathBuf>,
+
+    /// Write a "[nextest heartbeat]" line to stderr at this interval, e.g. "60s"
+    ///
+    /// Heartbeats are written for the whole invocation, including while tests are being built, so
+    /// that CI systems don't kill jobs that are quiet for a long time.
+    #[arg(long, value_name = "DURATION", value_parser = parse_heartbeat_interval)]
+    heartbeat: Option<Duration>,
+
+    /// Warn about passing tests that finish faster than this [default: from profile]
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, conflicts_with = "no-run")]
+    min_test_run_time: Option<Duration>,
+
+    /// Time to wait after sending SIGTERM to a timed-out test before sending SIGKILL
+    /// [default: from profile]
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, conflicts_with = "no-run")]
+    timeout_grace_period: Option<Duration>,
+
+    /// Kill any test attempt that runs for longer than this, regardless of its slow-timeout
+    /// configuration
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, conflicts_with = "no-run")]
+    max_test_time: Option<Duration>,
+
+    /// Environment variables that tests inherit, in addition to PATH (and SystemRoot on Windows)
+    /// [possible values: "all", "none", or a comma-separated list of variables] [default: all]
+    #[arg(long, value_name = "VARS", conflicts_with = "no-run")]
+    test_env_inherit: Option<EnvInheritance>,
+
+    /// Environment variable to inherit in addition to --test-env-inherit (may be specified
+    /// multiple times)
+    #[arg(long, value_name = "KEY", requires = "test_env_inherit")]
+    test_env_inherit_add: Vec<String>,
+
+    /// Stack size in bytes for threads that run tests, set via RUST_MIN_STACK [default: from profile]
+    #[arg(long, value_name = "BYTES", conflicts_with = "no-run")]
+    test_thread_stack_size: Option<u64>,
+
+    /// Fail the run if any test binary contains no tests at all
+    #[arg(long, conflicts_with = "no-run")]
+    forbid_only: bool,
+
+    /// Fail the run unless exactly N tests will be run, or N tests in a package with PACKAGE:N
+    /// (may be specified multiple times)
+    #[arg(
+        long,
+        value_name = "[PACKAGE:]N",
+        conflicts_with = "no-run",
+        action(ArgAction::Append)
+    )]
+    assert_test_count: Vec<ExpectedTestCount>,
+
+    /// Fail the run unless at least N tests will be run (may be specified multiple times)
+    #[arg(
+        long,
+        value_name = "[PACKAGE:]N",
+        conflicts_with = "no-run",
+        action(ArgAction::Append)
+    )]
+    assert_test_count_min: Vec<ExpectedTestCount>,
+
+    /// Fail the run unless at most N tests will be run (may be specified multiple times)
+    #[arg(
+        long,
+        value_name = "[PACKAGE:]N",
+        conflicts_with = "no-run",
+        action(ArgAction::Append)
+    )]
+    assert_test_count_max: Vec<ExpectedTestCount>,
+
+    /// Find a minimal set of tests that cause this test to fail when run before it
+    #[arg(long, value_name = "TEST_NAME", conflicts_with_all = &["no-run", "shuffle"])]
+    bisect_test: Option<String>,
+
+    /// Run each test N times in a row and report how often each test fails
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = &["no-run", "retries", "bisect_test"]
+    )]
+    report_flaky_rate: Option<NonZeroUsize>,
+
+    /// Write the flaky rate report to this file as JSON
+    #[arg(long, value_name = "FILE", requires = "report_flaky_rate")]
+    flaky_report: Option<Utf8PathBuf>,
+
+    /// Compare test times against the baseline timings in this JSON file, and fail the run if any
+    /// passing test is slower than its baseline by more than --regression-threshold
+    #[arg(
+        long,
+        value_name = "BASELINE",
+        conflicts_with_all = &["no-run", "test_isolation"]
+    )]
+    report_timing_regression: Option<Utf8PathBuf>,
+
+    /// Percentage by which a test must be slower than its baseline to count as a regression
+    #[arg(
+        long,
+        value_name = "PCT",
+        value_parser = parse_regression_threshold,
+        default_value = "20",
+        requires = "report_timing_regression"
+    )]
+    regression_threshold: f64,
+
+    /// Write the time taken by each passing test to this JSON file, for use as a baseline with
+    /// --report-timing-regression
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = &["no-run", "test_isolation"]
+    )]
+    write_timing_baseline: Option<Utf8PathBuf>,
+
+    /// Run this script after the test run, passing in the exit code as an argument and the run
+    /// summary as JSON on stdin
+    #[arg(long, value_name = "SCRIPT", conflicts_with_all = &["no-run", "bisect_test"])]
+    post_run_script: Option<Utf8PathBuf>,
+
+    /// Post the result of each test to this URL as JSON while the run is in progress
+    #[cfg(feature = "remote-reporting")]
+    #[arg(long, value_name = "URL", conflicts_with_all = &["no-run", "bisect_test"])]
+    report_artifacts_to: Option<String>,
+
+    /// Bearer token to authenticate with when posting results to --report-artifacts-to
+    #[cfg(feature = "remote-reporting")]
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "NEXTEST_REPORT_TOKEN",
+        hide_env_values = true,
+        requires = "report_artifacts_to"
+    )]
+    report_artifacts_token: Option<String>,
+
+    /// Enable features that are still being developed and may change or be removed
+    #[arg(long, hide = true)]
+    unstable_features: bool,
+
+    /// Randomly mark this fraction (0.0 to 1.0) of passing tests as failed, to test nextest itself
+    /// (requires --unstable-features)
+    #[arg(
+        long,
+        value_name = "RATE",
+        value_parser = parse_simulated_failure_rate,
+        requires = "unstable_features",
+        conflicts_with = "no-run",
+        hide = true
+    )]
+    simulate_test_failures: Option<f64>,
+
+    /// Seed for choosing tests to fail with --simulate-test-failures [default: 0]
+    #[arg(
+        long,
+        value_name = "SEED",
+        requires = "simulate_test_failures",
+        hide = true
+    )]
+    simulate_test_failures_seed: Option<u64>,
+}
+
+fn parse_simulated_failure_rate(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err("must be a number between 0.0 and 1.0".to_owned()),
+    }
+}
+
+fn parse_heartbeat_interval(input: &str) -> Result<Duration, String> {
+    match humantime::parse_duration(input) {
+        Ok(interval) if !interval.is_zero() => Ok(interval),
+        Ok(_) => Err("must be greater than zero".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn parse_junit_elapsed_time_precision(input: &str) -> Result<usize, String> {
+    let max = NextestJunitConfig::MAX_ELAPSED_TIME_PRECISION;
+    match input.parse::<usize>() {
+        Ok(precision) if precision <= max => Ok(precision),
+        _ => Err(format!("must be an integer from 0 to {max}")),
+    }
+}
+
+fn parse_junit_truncate_name_at(input: &str) -> Result<usize, String> {
+    let min = NextestJunitConfig::MIN_TRUNCATE_NAME_AT;
+    match input.parse::<usize>() {
+        Ok(truncate_name_at) if truncate_name_at >= min => Ok(truncate_name_at),
+        _ => Err(format!("must be an integer that's at least {min}")),
+    }
+}
+
+fn parse_regression_threshold(input: &str) -> Result<f64, String> {
+    // Accept both "20" and "20%".
+    let input = input.strip_suffix('%').unwrap_or(input);
+    match input.parse::<f64>() {
+        Ok(threshold) if threshold.is_finite() && threshold >= 0.0 => Ok(threshold),
+        _ => Err("must be a non-negative percentage, e.g. 20 or 20%".to_owned()),
+    }
+}
+
+impl TestRunnerOpts {
+    fn test_count_assertions(
+        &self,
+    ) -> impl Iterator<Item = (TestCountBound, &ExpectedTestCount)> + '_ {
+        let with_bound = |bound| move |expected| (bound, expected);
+        self.assert_test_count
+            .iter()
+            .map(with_bound(TestCountBound::Exact))
+            .chain(
+                self.assert_test_count_min
+                    .iter()
+                    .map(with_bound(TestCountBound::Min)),
+            )
+            .chain(
+                self.assert_test_count_max
+                    .iter()
+                    .map(with_bound(TestCountBound::Max)),
+            )
+    }
+
+    fn to_builder(&self, no_capture: bool) -> Option<TestRunnerBuilder> {
+        if self.no_run {
+            return None;
+        }
+
+        let mut builder = TestRunnerBuilder::default();
+        builder
+            .set_no_capture(no_capture)
+            .set_stream_output(self.stream_output)
+            .set_stdin_passthrough(self.stdin_passthrough);
+        if let Some(retries) = self.retries {
+            builder.set_retries(RetryPolicy::new_without_delay(retries));
+        }
+        if let Some(max_retries) = self.max_retries_global {
+            builder.set_max_retries_global(max_retries);
+        }
+        builder.set_retry_immediately_on(self.retry_immediately_on.iter().copied());
+        if !self.retry_if_exit_code.is_empty() {
+            builder.set_retry_filter(RetryFilter::ExitCodes(self.retry_if_exit_code.clone()));
+        }
         if self.no_fail_fast {
             builder.set_fail_fast(false);
         } else if self.fail_fast {
             builder.set_fail_fast(true);
         }
+        if let Some(limit) = self.failure_limit_per_binary {
+            builder.set_failure_limit_per_binary(limit);
+        }
+        if let Some(timeout) = self.test_binary_timeout {
+            builder.set_test_binary_timeout(timeout);
+        }
+        if self.fail_on_stderr {
+            builder.set_fail_on_stderr(true);
+        }
+        if self.no_network_access {
+            builder.set_network_access(false);
+        }
+        if self.fail_on_leaked_processes {
+            builder.set_fail_on_leaked_processes(true);
+        }
+        if !self.preload_libraries.is_empty() {
+            builder.set_preload_libraries(self.preload_libraries.iter().cloned());
+        }
+        if let Some(test_isolation) = self.test_isolation {
+            builder.set_test_isolation(test_isolation.into());
+        }
+        builder.set_annotate_output_with_timestamps(self.annotate_output_with_timestamps);
+        if let Some(wrapper) = &self.test_wrapper {
+            builder.set_test_wrapper(wrapper.clone());
+        }
+        if let Some(priority) = self.test_process_priority {
+            if let TestProcessPriorityOpt::High = priority {
+                log::warn!(
+                    "running tests at high priority: this can starve other processes of CPU time"
+                );
+            }
+            builder.set_process_priority(priority.into());
+        }
+        if let Some(FilesystemAccessOpt::ReadOnly) = self.filesystem_access {
+            builder.set_read_only_workspace(self.filesystem_access_exceptions.clone());
+        }
         if let Some(test_threads) = self.test_threads {
             builder.set_test_threads(test_threads);
         }
+        if self.detect_leaky_tests {
+            builder.set_detect_leaky_tests(self.leak_detection_path.clone());
+        }
+        if let Some(min_run_time) = self.min_test_run_time {
+            builder.set_min_run_time(min_run_time);
+        }
+        if let Some(grace_period) = self.timeout_grace_period {
+            builder.set_timeout_grace_period(grace_period);
+        }
+        if let Some(max_test_time) = self.max_test_time {
+            builder.set_max_test_time(max_test_time);
+        }
+        if let Some(env_inheritance) = &self.test_env_inherit {
+            let mut env_inheritance = env_inheritance.clone();
+            for name in &self.test_env_inherit_add {
+                env_inheritance.add(name);
+            }
+            builder.set_env_inheritance(env_inheritance);
+        }
+        if let Some(stack_size) = self.test_thread_stack_size {
+            builder.set_test_thread_stack_size(stack_size);
+        }
+        if let Some(runs) = self.report_flaky_rate {
+            builder.set_runs_per_test(runs.get());
+        }
+        if let Some(rate) = self.simulate_test_failures {
+            let seed = self.simulate_test_failures_seed.unwrap_or(0);
+            log::warn!("simulating test failures at rate {rate} with seed {seed}");
+            builder.set_simulated_failures(
+                SimulatedFailures::new(rate, seed).expect("rate was validated while parsing"),
+            );
+        }
+        let shuffle_seed = match (self.shuffle, self.seed) {
+            (true, _) => Some(ShuffleSeed::Random),
+            (false, seed) => seed,
+        };
+        if let Some(shuffle_seed) = shuffle_seed {
+            let seed = shuffle_seed.resolve();
+            log::info!("shuffling tests with seed {seed} (rerun with --seed {seed} to reproduce)");
+            builder.set_shuffle_seed(seed);
+        }
 
         Some(builder)
     }
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum FilesystemAccessOpt {
+    ReadWrite,
+    ReadOnly,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum TestIsolationOpt {
+    Process,
+    Thread,
+    None,
+}
+
+impl From<TestIsolationOpt> for TestIsolation {
+    fn from(opt: TestIsolationOpt) -> Self {
+        match opt {
+            TestIsolationOpt::Process => TestIsolation::Process,
+            TestIsolationOpt::Thread => TestIsolation::Thread,
+            TestIsolationOpt::None => TestIsolation::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum TestProcessPriorityOpt {
+    Nice,
+    Normal,
+    High,
+}
+
+impl From<TestProcessPriorityOpt> for TestProcessPriority {
+    fn from(opt: TestProcessPriorityOpt) -> Self {
+        match opt {
+            TestProcessPriorityOpt::Nice => TestProcessPriority::Nice,
+            TestProcessPriorityOpt::Normal => TestProcessPriority::Normal,
+            TestProcessPriorityOpt::High => TestProcessPriority::High,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum IgnoreOverridesOpt {
     Retries,
@@ -736,6 +1842,10 @@ struct TestReporterOpts {
     )]
     success_output: Option<TestOutputDisplayOpt>,
 
+    /// Hide output for passing tests that produce fewer than this many bytes of output
+    #[arg(long, conflicts_with_all = &["no-capture", "no-run"], value_name = "BYTES")]
+    suppress_pass_output_threshold: Option<u64>,
+
     // status_level does not conflict with --no-capture because pass vs skip still makes sense.
     /// Test statuses to output
     #[arg(
@@ -760,6 +1870,133 @@ struct TestReporterOpts {
     /// Do not display the progress bar
     #[arg(long, env = "NEXTEST_HIDE_PROGRESS_BAR")]
     hide_progress_bar: bool,
+
+    /// How to display progress while tests are running [default: spinner if TERM is "dumb",
+    /// otherwise bar]
+    #[arg(long, value_enum, conflicts_with = "no-run", value_name = "STYLE")]
+    progress_bar: Option<ProgressBarStyleOpt>,
+
+    /// Defer output for failing tests until the end of the run, quickest failures first
+    #[arg(long, conflicts_with_all = &["no-capture", "no-run"])]
+    aggregate_failures: bool,
+
+    /// Only display results for packages with at least one failing test
+    #[arg(long, conflicts_with_all = &["no-capture", "no-run"])]
+    display_only_failing_packages: bool,
+
+    /// How to display the summary at the end of the run [default: table if the terminal is at
+    /// least 80 columns wide, otherwise compact]
+    #[arg(long, value_enum, conflicts_with = "no-run", value_name = "FORMAT")]
+    summary_format: Option<SummaryFormatOpt>,
+
+    /// How to name test suites in the JUnit report [default: from profile]
+    #[arg(long, value_enum, conflicts_with = "no-run", value_name = "STYLE")]
+    junit_suitename_style: Option<JunitSuiteNameStyleOpt>,
+
+    /// How to populate the classname of test cases in the JUnit report [default: from profile]
+    #[arg(long, value_enum, conflicts_with = "no-run", value_name = "STYLE")]
+    junit_classname_style: Option<JunitClassNameStyleOpt>,
+
+    /// How much output to write to the JUnit report for failing tests [default: from profile]
+    #[arg(long, value_enum, conflicts_with = "no-run", value_name = "FORMAT")]
+    junit_failure_format: Option<JunitFailureFormatOpt>,
+
+    /// Nest each test suite in the JUnit report within a parent suite for its package
+    #[arg(long, conflicts_with = "no-run")]
+    junit_nest_packages: bool,
+
+    /// Remove ANSI escape sequences from test output in the JUnit report [default: from profile]
+    #[arg(long, conflicts_with = "no-run")]
+    junit_strip_ansi: bool,
+
+    /// Omit tests that passed on their first attempt from the JUnit report, while still counting
+    /// them [default: from profile]
+    #[arg(long, conflicts_with = "no-run")]
+    no_junit_passing_tests: bool,
+
+    /// Add a property to the root of the JUnit report, reading the value from the environment if
+    /// only KEY is given (may be specified multiple times)
+    #[arg(long, value_name = "KEY[=VALUE]", conflicts_with = "no-run")]
+    junit_add_system_property: Vec<JunitProperty>,
+
+    /// Format of timestamps in the JUnit report [default: from profile]
+    #[arg(long, value_enum, conflicts_with = "no-run", value_name = "FORMAT")]
+    junit_timestamp_format: Option<JunitTimestampFormatOpt>,
+
+    /// Number of digits after the decimal point in JUnit elapsed times [default: from profile]
+    #[arg(
+        long,
+        conflicts_with = "no-run",
+        value_name = "DIGITS",
+        value_parser = parse_junit_elapsed_time_precision
+    )]
+    junit_elapsed_time_precision: Option<usize>,
+
+    /// Hostname of test suites in the JUnit report: a hostname, "none", or "env:VAR" [default: from
+    /// profile]
+    #[arg(long, conflicts_with = "no-run", value_name = "HOSTNAME")]
+    junit_hostname: Option<JunitHostname>,
+
+    /// Name of the JUnit report, which can contain {workspace}, {profile} and {timestamp}
+    /// [default: from profile]
+    #[arg(long, conflicts_with = "no-run", value_name = "NAME")]
+    junit_name: Option<String>,
+
+    /// Rewrite path-like strings in the JUnit report, e.g. "^/home/[^/]+/:" (may be specified
+    /// multiple times) [default: from profile]
+    #[arg(long, conflicts_with = "no-run", value_name = "REGEX:REPLACEMENT")]
+    junit_path_rewrite: Vec<JunitPathRewrite>,
+
+    /// Truncate test case names in the JUnit report to N characters [default: from profile]
+    #[arg(
+        long,
+        conflicts_with = "no-run",
+        value_name = "N",
+        value_parser = parse_junit_truncate_name_at
+    )]
+    junit_truncate_name_at: Option<usize>,
+
+    /// Report tests that failed due to a panic, rather than a failed assertion, as errors in the
+    /// JUnit report [default: from profile]
+    #[arg(
+        long,
+        conflicts_with = "no-run",
+        visible_alias = "junit-errored-vs-failed"
+    )]
+    junit_use_error_for_panics: bool,
+
+    /// Mark tests that failed in more than N of the last 10 runs as skipped in the JUnit report
+    #[arg(long, conflicts_with = "no-run", value_name = "N")]
+    junit_rerun_threshold: Option<usize>,
+
+    /// Write the output of every test to --output-dir, including passing tests
+    #[arg(
+        long,
+        conflicts_with_all = &["no-capture", "no-run"],
+        requires = "output_dir"
+    )]
+    capture_all: bool,
+
+    /// Directory to write test output to with --capture-all
+    #[arg(long, value_name = "DIR", requires = "capture_all")]
+    output_dir: Option<Utf8PathBuf>,
+
+    /// Write to --output-dir even if it isn't empty
+    #[arg(long, requires = "output_dir", conflicts_with = "clean_output_dir")]
+    ignore_output_dir_exists: bool,
+
+    /// Delete the contents of --output-dir before running tests
+    #[arg(long, requires = "output_dir")]
+    clean_output_dir: bool,
+
+    /// Maximum total number of bytes of output to write with --capture-all
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = 1024 * 1024 * 1024,
+        requires = "capture_all"
+    )]
+    capture_all_max_bytes: u64,
 }
 
 impl TestReporterOpts {
@@ -772,6 +2009,9 @@ impl TestReporterOpts {
         if let Some(success_output) = self.success_output {
             builder.set_success_output(success_output.into());
         }
+        if let Some(threshold) = self.suppress_pass_output_threshold {
+            builder.set_suppress_pass_output_threshold(threshold);
+        }
         if let Some(status_level) = self.status_level {
             builder.set_status_level(status_level.into());
         }
@@ -779,8 +2019,160 @@ impl TestReporterOpts {
             builder.set_final_status_level(final_status_level.into());
         }
         builder.set_hide_progress_bar(self.hide_progress_bar);
+        if let Some(progress_bar) = self.progress_bar {
+            builder.set_progress_bar_style(progress_bar.into());
+        }
+        builder.set_aggregate_failures(self.aggregate_failures);
+        builder.set_display_only_failing_packages(self.display_only_failing_packages);
+        if let Some(summary_format) = self.summary_format {
+            builder.set_summary_format(summary_format.into());
+        }
+        if let Some(suitename_style) = self.junit_suitename_style {
+            builder.set_junit_suitename_style(suitename_style.into());
+        }
+        if let Some(classname_style) = self.junit_classname_style {
+            builder.set_junit_classname_style(classname_style.into());
+        }
+        if let Some(failure_format) = self.junit_failure_format {
+            builder.set_junit_failure_format(failure_format.into());
+        }
+        builder.set_junit_nest_packages(self.junit_nest_packages);
+        builder.set_junit_strip_ansi(self.junit_strip_ansi);
+        if self.no_junit_passing_tests {
+            builder.set_junit_include_passing(false);
+        }
+        for property in &self.junit_add_system_property {
+            builder.add_junit_property(property.clone());
+        }
+        if let Some(timestamp_format) = self.junit_timestamp_format {
+            builder.set_junit_timestamp_format(timestamp_format.into());
+        }
+        if let Some(precision) = self.junit_elapsed_time_precision {
+            builder.set_junit_elapsed_time_precision(precision);
+        }
+        if let Some(hostname) = &self.junit_hostname {
+            builder.set_junit_hostname(hostname.clone());
+        }
+        if let Some(name) = &self.junit_name {
+            builder.set_junit_name(name);
+        }
+        if !self.junit_path_rewrite.is_empty() {
+            builder.set_junit_path_rewrites(self.junit_path_rewrite.clone());
+        }
+        if let Some(truncate_name_at) = self.junit_truncate_name_at {
+            builder.set_junit_truncate_name_at(truncate_name_at);
+        }
+        builder.set_junit_use_error_for_panics(self.junit_use_error_for_panics);
+        if let Some(output_dir) = &self.output_dir {
+            builder.set_capture_all(output_dir, self.capture_all_max_bytes);
+        }
         builder
     }
+
+    /// Checks that --output-dir is empty, or cleans it if requested.
+    fn prepare_output_dir(&self) -> Result<()> {
+        let output_dir = match &self.output_dir {
+            Some(output_dir) => output_dir,
+            None => return Ok(()),
+        };
+        let clean_error = |err| ExpectedError::OutputDirCleanError {
+            output_dir: output_dir.clone(),
+            err,
+        };
+
+        let mut entries = match output_dir.read_dir_utf8() {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(clean_error(err)),
+        };
+        if self.clean_output_dir {
+            for entry in entries {
+                let entry = entry.map_err(clean_error)?;
+                let path = entry.path();
+                let res = if entry.file_type().map_err(clean_error)?.is_dir() {
+                    std::fs::remove_dir_all(path)
+                } else {
+                    std::fs::remove_file(path)
+                };
+                res.map_err(clean_error)?;
+            }
+        } else if !self.ignore_output_dir_exists && entries.next().is_some() {
+            return Err(ExpectedError::OutputDirNotEmpty {
+                output_dir: output_dir.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum JunitSuiteNameStyleOpt {
+    Full,
+    Package,
+    Binary,
+}
+
+impl From<JunitSuiteNameStyleOpt> for JunitSuiteNameStyle {
+    fn from(opt: JunitSuiteNameStyleOpt) -> Self {
+        match opt {
+            JunitSuiteNameStyleOpt::Full => JunitSuiteNameStyle::Full,
+            JunitSuiteNameStyleOpt::Package => JunitSuiteNameStyle::Package,
+            JunitSuiteNameStyleOpt::Binary => JunitSuiteNameStyle::Binary,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum JunitClassNameStyleOpt {
+    Full,
+    Package,
+    Binary,
+    ModulePath,
+    None,
+}
+
+impl From<JunitClassNameStyleOpt> for JunitClassNameStyle {
+    fn from(opt: JunitClassNameStyleOpt) -> Self {
+        match opt {
+            JunitClassNameStyleOpt::Full => JunitClassNameStyle::Full,
+            JunitClassNameStyleOpt::Package => JunitClassNameStyle::Package,
+            JunitClassNameStyleOpt::Binary => JunitClassNameStyle::Binary,
+            JunitClassNameStyleOpt::ModulePath => JunitClassNameStyle::ModulePath,
+            JunitClassNameStyleOpt::None => JunitClassNameStyle::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum JunitFailureFormatOpt {
+    Detailed,
+    Brief,
+}
+
+impl From<JunitFailureFormatOpt> for JunitFailureFormat {
+    fn from(opt: JunitFailureFormatOpt) -> Self {
+        match opt {
+            JunitFailureFormatOpt::Detailed => JunitFailureFormat::Detailed,
+            JunitFailureFormatOpt::Brief => JunitFailureFormat::Brief,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum JunitTimestampFormatOpt {
+    Rfc3339,
+    Simple,
+    Epoch,
+}
+
+impl From<JunitTimestampFormatOpt> for JunitTimestampFormat {
+    fn from(opt: JunitTimestampFormatOpt) -> Self {
+        match opt {
+            JunitTimestampFormatOpt::Rfc3339 => JunitTimestampFormat::Rfc3339,
+            JunitTimestampFormatOpt::Simple => JunitTimestampFormat::Simple,
+            JunitTimestampFormatOpt::Epoch => JunitTimestampFormat::Epoch,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -802,6 +2194,42 @@ impl From<TestOutputDisplayOpt> for TestOutputDisplay {
     }
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ProgressBarStyleOpt {
+    None,
+    Bar,
+    Fraction,
+    Spinner,
+}
+
+impl From<ProgressBarStyleOpt> for ProgressBarStyle {
+    fn from(opt: ProgressBarStyleOpt) -> Self {
+        match opt {
+            ProgressBarStyleOpt::None => ProgressBarStyle::None,
+            ProgressBarStyleOpt::Bar => ProgressBarStyle::Bar,
+            ProgressBarStyleOpt::Fraction => ProgressBarStyle::Fraction,
+            ProgressBarStyleOpt::Spinner => ProgressBarStyle::Spinner,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SummaryFormatOpt {
+    Compact,
+    Verbose,
+    Table,
+}
+
+impl From<SummaryFormatOpt> for SummaryFormat {
+    fn from(opt: SummaryFormatOpt) -> Self {
+        match opt {
+            SummaryFormatOpt::Compact => SummaryFormat::Compact,
+            SummaryFormatOpt::Verbose => SummaryFormat::Verbose,
+            SummaryFormatOpt::Table => SummaryFormat::Table,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum StatusLevelOpt {
     None,
@@ -864,8 +2292,13 @@ struct BaseApp {
     workspace_root: Utf8PathBuf,
     manifest_path: Option<Utf8PathBuf>,
     reuse_build: ReuseBuildInfo,
+    // Whether a target runner built for a different architecture is a warning rather than an
+    // error.
+    allow_runner_arch_mismatch: bool,
     cargo_opts: CargoOptions,
     config_opts: ConfigOpts,
+    // The Cargo subcommand used to build test binaries: "test", or "bench" for benchmarks.
+    cargo_command: &'static str,
 
     cargo_configs: CargoConfigs,
     double_spawn: OnceCell<DoubleSpawnInfo>,
@@ -884,7 +2317,14 @@ impl BaseApp {
     ) -> Result<Self> {
         reuse_build.check_experimental(output);
 
+        let allow_runner_arch_mismatch = reuse_build.allow_runner_arch_mismatch;
         let reuse_build = reuse_build.process(output, writer)?;
+        if cargo_opts.cargo_offline
+            && reuse_build.binaries_metadata().is_some()
+            && reuse_build.cargo_metadata().is_none()
+        {
+            return Err(ExpectedError::ArchiveOfflineIncompatibleError);
+        }
 
         let graph_data = match reuse_build.cargo_metadata() {
             Some(MetadataOrPath::Metadata(graph_data)) => graph_data.clone(),
@@ -903,6 +2343,7 @@ impl BaseApp {
                     cargo_opts.target_dir.as_deref(),
                     output,
                     graph_with_deps,
+                    cargo_opts.cargo_offline,
                 )?;
                 let graph = PackageGraph::from_json(&json)
                     .map_err(|err| ExpectedError::cargo_metadata_parse_error(None, err))?;
@@ -916,7 +2357,10 @@ impl BaseApp {
             manifest_path
         };
 
-        let workspace_root = match reuse_build.workspace_remap() {
+        let workspace_root = match reuse_build
+            .workspace_root_override()
+            .or_else(|| reuse_build.workspace_remap())
+        {
             Some(path) => path.to_owned(),
             _ => graph_data.1.workspace().root().to_owned(),
         };
@@ -925,7 +2369,9 @@ impl BaseApp {
         if !root_manifest_path.exists() {
             // This doesn't happen in normal use, but is a common situation if the build is being
             // reused.
-            let reuse_build_kind = if reuse_build.workspace_remap().is_some() {
+            let reuse_build_kind = if reuse_build.workspace_root_override().is_some() {
+                ReuseBuildKind::WorkspaceRootOverride { workspace_root }
+            } else if reuse_build.workspace_remap().is_some() {
                 ReuseBuildKind::ReuseWithWorkspaceRemap { workspace_root }
             } else if reuse_build.is_active() {
                 ReuseBuildKind::Reuse
@@ -939,6 +2385,12 @@ impl BaseApp {
             });
         }
 
+        if let Some(target_dir) = &cargo_opts.target_dir {
+            if !reuse_build.is_active() {
+                warn_if_different_filesystem(target_dir, &workspace_root);
+            }
+        }
+
         let cargo_configs = CargoConfigs::new(&cargo_opts.config).map_err(Box::new)?;
 
         Ok(Self {
@@ -946,9 +2398,11 @@ impl BaseApp {
             graph_data,
             workspace_root,
             reuse_build,
+            allow_runner_arch_mismatch,
             manifest_path,
             cargo_opts,
             config_opts,
+            cargo_command: "test",
             cargo_configs,
 
             double_spawn: OnceCell::new(),
@@ -956,6 +2410,10 @@ impl BaseApp {
         })
     }
 
+    fn set_cargo_command(&mut self, cargo_command: &'static str) {
+        self.cargo_command = cargo_command;
+    }
+
     fn load_double_spawn(&self) -> &DoubleSpawnInfo {
         self.double_spawn.get_or_init(|| {
             if std::env::var("NEXTEST_EXPERIMENTAL_DOUBLE_SPAWN").is_ok() {
@@ -973,9 +2431,28 @@ impl BaseApp {
         })
     }
 
-    fn load_runner(&self, build_platforms: &BuildPlatforms) -> &TargetRunner {
-        self.target_runner
-            .get_or_init(|| runner_for_target(&self.cargo_configs, build_platforms))
+    fn load_runner(&self, build_platforms: &BuildPlatforms) -> Result<&TargetRunner> {
+        self.target_runner.get_or_try_init(|| {
+            let runner = runner_for_target(&self.cargo_configs, build_platforms);
+            match runner.check_arch(build_platforms) {
+                Ok(()) => Ok(runner),
+                Err(err) if self.allow_runner_arch_mismatch => {
+                    warn_on_err("target runner", &err).expect("writing to a string is infallible");
+                    Ok(runner)
+                }
+                Err(err) => Err(ExpectedError::TargetRunnerError { err }),
+            }
+        })
+    }
+
+    /// Resolves the target runner for these platforms, and checks that its binaries can be run.
+    fn check_target_runner(&self, build_platforms: &BuildPlatforms) -> Result<()> {
+        let runner = TargetRunner::new(&self.cargo_configs, build_platforms)?;
+        runner.check_binaries()?;
+        if !self.allow_runner_arch_mismatch {
+            runner.check_arch(build_platforms)?;
+        }
+        Ok(())
     }
 
     fn exec_archive(
@@ -983,12 +2460,18 @@ impl BaseApp {
         output_file: &Utf8Path,
         format: ArchiveFormatOpt,
         zstd_level: i32,
+        max_source_size: Option<u64>,
+        strip_debug_info: Option<&StripDebugInfoOptions>,
         output_writer: &mut OutputWriter,
     ) -> Result<()> {
         // Do format detection first so we fail immediately.
         let format = format.to_archive_format(output_file)?;
-        let binary_list = self.build_binary_list()?;
+        let binary_list = self.build_binary_list(None, &[])?;
         let path_mapper = PathMapper::noop();
+        let source = max_source_size.map(|max_size| ArchiveSourceOptions {
+            workspace_root: self.workspace_root.clone(),
+            max_size,
+        });
 
         let mut reporter = ArchiveReporter::new(self.output.verbose);
         if self
@@ -1006,6 +2489,8 @@ impl BaseApp {
             // Note that path_mapper is currently a no-op -- we don't support reusing builds for
             // archive creation because it's too confusing.
             &path_mapper,
+            source.as_ref(),
+            strip_debug_info,
             format,
             zstd_level,
             output_file,
@@ -1022,7 +2507,15 @@ impl BaseApp {
         Ok(())
     }
 
-    fn build_binary_list(&self) -> Result<Arc<BinaryList>> {
+    /// Builds tests or reads the list of binaries from `--binaries-metadata`.
+    ///
+    /// `default_build_jobs` is used if `--build-jobs` isn't passed in, and `ignored_packages` are
+    /// excluded from the build as described in [`CargoOptions::exclude_packages`].
+    fn build_binary_list(
+        &self,
+        default_build_jobs: Option<NonZeroUsize>,
+        ignored_packages: &[String],
+    ) -> Result<Arc<BinaryList>> {
         let binary_list = match self.reuse_build.binaries_metadata() {
             Some(MetadataOrPath::Metadata(binary_list)) => binary_list.clone(),
             Some(MetadataOrPath::Path(path)) => {
@@ -1038,11 +2531,25 @@ impl BaseApp {
             None => {
                 let target_triple =
                     discover_target_triple(&self.cargo_configs, self.cargo_opts.target.as_deref());
-                Arc::new(self.cargo_opts.compute_binary_list(
-                    self.graph(),
+                let mut cargo_opts = Cow::Borrowed(&self.cargo_opts);
+                if !ignored_packages.is_empty()
+                    && !cargo_opts.to_mut().exclude_packages(ignored_packages, || {
+                        let (graph_json, graph) = &*self.graph_data;
+                        default_packages(graph, graph_json, self.manifest_path.as_deref())
+                    })
+                {
+                    return Err(ExpectedError::AllPackagesIgnored {
+                        packages: ignored_packages.to_vec(),
+                    });
+                }
+                Arc::new(cargo_opts.compute_binary_list(
+                    self.cargo_command,
+                    &self.graph_data,
                     self.manifest_path.as_deref(),
                     self.output,
+                    &self.cargo_configs,
                     target_triple,
+                    default_build_jobs,
                 )?)
             }
         };
@@ -1073,10 +2580,19 @@ fn check_experimental_filtering(_output: OutputContext) {
 impl App {
     fn new(base: BaseApp, build_filter: TestBuildFilter) -> Result<Self> {
         check_experimental_filtering(base.output);
+        if build_filter.only_doctest {
+            return Err(ExpectedError::DoctestsNotSupported);
+        }
 
         Ok(Self { base, build_filter })
     }
 
+    /// Returns the names of workspace packages matched by --workspace-ignore-path.
+    fn ignored_packages(&self) -> Vec<String> {
+        WorkspaceIgnoreFilter::new(self.build_filter.workspace_ignore_path.iter().cloned())
+            .ignored_packages(self.base.graph())
+    }
+
     fn build_filtering_expressions(&self) -> Result<Vec<FilteringExpr>> {
         let (exprs, all_errors): (Vec<_>, Vec<_>) = self
             .build_filter
@@ -1112,20 +2628,29 @@ impl App {
     fn load_profile<'cfg>(
         &self,
         profile_name: Option<&str>,
+        profile_env_var: Option<&str>,
+        profile_selection: Option<ProfileSelection>,
         config: &'cfg NextestConfig,
     ) -> Result<NextestProfile<'cfg, PreBuildPlatform>> {
         let profile_name = profile_name.unwrap_or_else(|| {
-            // The "official" way to detect a miri environment is with MIRI_SYSROOT.
-            // https://github.com/rust-lang/miri/pull/2398#issuecomment-1190747685
-            if std::env::var_os("MIRI_SYSROOT").is_some() {
-                NextestConfig::DEFAULT_MIRI_PROFILE
-            } else {
-                NextestConfig::DEFAULT_PROFILE
+            let selected = config.select_profile(profile_selection);
+            if let ProfileSelectionReason::Ci { .. } = selected.reason {
+                log::info!(
+                    "using profile {} ({})",
+                    selected
+                        .name
+                        .if_supports_color(Stream::Stderr, |x| x.bold()),
+                    selected.reason,
+                );
             }
+            selected.name
         });
         let profile = config
             .profile(profile_name)
-            .map_err(ExpectedError::profile_not_found)?;
+            .map_err(|err| match profile_env_var {
+                Some(env_var) => ExpectedError::profile_from_env_not_found(err, env_var),
+                None => ExpectedError::profile_not_found(err),
+            })?;
         let store_dir = profile.store_dir();
         std::fs::create_dir_all(store_dir).map_err(|err| ExpectedError::StoreDirCreateError {
             store_dir: store_dir.to_owned(),
@@ -1143,7 +2668,9 @@ impl App {
         let filter_exprs = self.build_filtering_expressions()?;
         let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
 
-        let binary_list = self.base.build_binary_list()?;
+        let binary_list = self
+            .base
+            .build_binary_list(None, &self.ignored_packages())?;
 
         match list_type {
             ListType::BinariesOnly => {
@@ -1162,10 +2689,11 @@ impl App {
                 let double_spawn = self.base.load_double_spawn();
                 let target_runner = self
                     .base
-                    .load_runner(&binary_list.rust_build_meta.build_platforms()?);
+                    .load_runner(&binary_list.rust_build_meta.build_platforms()?)?;
                 let ctx = TestExecuteContext {
                     double_spawn,
                     target_runner,
+                    extra_args: self.build_filter.extra_args(),
                 };
 
                 let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder)?;
@@ -1185,6 +2713,37 @@ impl App {
         Ok(())
     }
 
+    fn exec_check(&self) -> Result<()> {
+        let filter_exprs = self.build_filtering_expressions()?;
+        let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+
+        let binary_list = self
+            .base
+            .build_binary_list(None, &self.ignored_packages())?;
+        let build_platforms = binary_list.rust_build_meta.build_platforms()?;
+        let double_spawn = self.base.load_double_spawn();
+        let target_runner = self.base.load_runner(&build_platforms)?;
+        let ctx = TestExecuteContext {
+            double_spawn,
+            target_runner,
+            extra_args: self.build_filter.extra_args(),
+        };
+
+        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder)?;
+
+        log::info!(
+            "checked {} tests across {} binaries",
+            test_list
+                .test_count()
+                .if_supports_color(Stream::Stderr, |s| s.bold()),
+            test_list
+                .binary_count()
+                .if_supports_color(Stream::Stderr, |s| s.bold()),
+        );
+
+        Ok(())
+    }
+
     fn exec_show_test_groups(
         &self,
         profile_name: Option<&str>,
@@ -1196,7 +2755,7 @@ impl App {
             .base
             .config_opts
             .make_config(&self.base.workspace_root, self.base.graph())?;
-        let profile = self.load_profile(profile_name, &config)?;
+        let profile = self.load_profile(profile_name, None, None, &config)?;
 
         // Validate test groups before doing any other work.
         let mode = if groups.is_empty() {
@@ -1210,16 +2769,19 @@ impl App {
         let filter_exprs = self.build_filtering_expressions()?;
         let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
 
-        let binary_list = self.base.build_binary_list()?;
+        let binary_list = self
+            .base
+            .build_binary_list(None, &self.ignored_packages())?;
         let build_platforms = binary_list.rust_build_meta.build_platforms()?;
 
         let double_spawn = self.base.load_double_spawn();
         let target_runner = self
             .base
-            .load_runner(&binary_list.rust_build_meta.build_platforms()?);
+            .load_runner(&binary_list.rust_build_meta.build_platforms()?)?;
         let ctx = TestExecuteContext {
             double_spawn,
             target_runner,
+            extra_args: self.build_filter.extra_args(),
         };
 
         let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder)?;
@@ -1244,40 +2806,176 @@ impl App {
     }
 
     fn exec_run(
-        &self,
-        profile_name: Option<&str>,
+        &mut self,
+        profile_opts: &ProfileOpts,
         no_capture: bool,
+        bench: bool,
         runner_opts: &TestRunnerOpts,
         reporter_opts: &TestReporterOpts,
         output_writer: &mut OutputWriter,
     ) -> Result<()> {
+        // Held until the run finishes, and removed on the way out.
+        let _pid_file = runner_opts
+            .test_pid_file
+            .as_deref()
+            .map(PidFile::create)
+            .transpose()?;
+        let heartbeat = runner_opts.heartbeat.map(Heartbeat::start);
+
+        // Do this once up front, since each feature combination writes to the same directory.
+        reporter_opts.prepare_output_dir()?;
+
+        match self.base.cargo_opts.feature_combinations {
+            Some(depth) => {
+                let mut combination_runner = FeatureCombinationRunner::new(
+                    self.base.graph(),
+                    self.base.cargo_opts.packages(),
+                    depth,
+                )?;
+                let cargo_opts = self.base.cargo_opts.clone();
+                combination_runner.run(|features| {
+                    self.base.cargo_opts = cargo_opts.with_exact_features(features);
+                    self.run_once(
+                        profile_opts,
+                        no_capture,
+                        bench,
+                        runner_opts,
+                        reporter_opts,
+                        heartbeat.as_ref(),
+                        output_writer,
+                    )
+                })
+            }
+            None => {
+                let run_stats = self.run_once(
+                    profile_opts,
+                    no_capture,
+                    bench,
+                    runner_opts,
+                    reporter_opts,
+                    heartbeat.as_ref(),
+                    output_writer,
+                )?;
+                match run_stats {
+                    Some(run_stats) if !run_stats.is_success() => {
+                        Err(ExpectedError::test_run_failed())
+                    }
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Builds and runs tests once, returning `None` if `--no-run` was passed in.
+    #[allow(clippy::too_many_arguments)]
+    fn run_once(
+        &self,
+        profile_opts: &ProfileOpts,
+        no_capture: bool,
+        bench: bool,
+        runner_opts: &TestRunnerOpts,
+        reporter_opts: &TestReporterOpts,
+        heartbeat: Option<&Heartbeat>,
+        output_writer: &mut OutputWriter,
+    ) -> Result<Option<RunStats>> {
         let config = self
             .base
             .config_opts
             .make_config(&self.base.workspace_root, self.base.graph())?;
-        let profile = self.load_profile(profile_name, &config)?;
+        let (profile_name, profile_env_var) = profile_opts.profile_name();
+        let profile = self.load_profile(
+            profile_name.as_deref(),
+            profile_env_var,
+            profile_opts.profile_selection.map(Into::into),
+            &config,
+        )?;
 
         let filter_exprs = self.build_filtering_expressions()?;
         let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
 
-        let binary_list = self.base.build_binary_list()?;
+        #[cfg(feature = "self-update")]
+        let update_check = runner_opts
+            .check_for_updates
+            .then(crate::update::BackgroundUpdateCheck::start);
+        #[cfg(not(feature = "self-update"))]
+        if runner_opts.check_for_updates {
+            log::info!(
+                "this version of cargo-nextest cannot check for updates\n\
+                 (hint: this usually means nextest was installed by a package manager)"
+            );
+        }
+
+        // Reused builds record the platforms they were built for, so check those once they've
+        // been loaded. Otherwise, check before building so that a misconfigured runner is
+        // reported without waiting for the build.
+        let reuses_build = self.base.reuse_build.binaries_metadata().is_some();
+        let timing_baseline = runner_opts
+            .report_timing_regression
+            .as_deref()
+            .map(TimingData::read_json)
+            .transpose()?;
+        if runner_opts.check_target_runner && !reuses_build {
+            let target_triple = discover_target_triple(
+                &self.base.cargo_configs,
+                self.base.cargo_opts.target.as_deref(),
+            );
+            self.base
+                .check_target_runner(&BuildPlatforms::new(target_triple)?)?;
+        }
+
+        let binary_list = self
+            .base
+            .build_binary_list(profile.build_jobs(), &self.ignored_packages())?;
         let build_platforms = binary_list.rust_build_meta.build_platforms()?;
+        if runner_opts.check_target_runner && reuses_build {
+            self.base.check_target_runner(&build_platforms)?;
+        }
         let double_spawn = self.base.load_double_spawn();
-        let target_runner = self.base.load_runner(&build_platforms);
+        let target_runner = self.base.load_runner(&build_platforms)?;
         let ctx = TestExecuteContext {
             double_spawn,
             target_runner,
+            extra_args: self.build_filter.extra_args(),
         };
 
         let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder)?;
+        if runner_opts.forbid_only {
+            check_forbid_only(&test_list)?;
+        }
+        for (bound, expected) in runner_opts.test_count_assertions() {
+            test_list.check_test_count(bound, expected)?;
+        }
+        #[cfg(feature = "self-update")]
+        if let Some(update_check) = update_check {
+            update_check.report();
+        }
 
         let output = output_writer.reporter_output();
         let profile = profile.apply_build_platforms(&build_platforms);
+        if profile_opts.print_config_after_merge {
+            log::info!(
+                "merged config for profile {}:",
+                profile
+                    .name()
+                    .if_supports_color(Stream::Stderr, |x| x.bold()),
+            );
+            log::info!(
+                target: "cargo_nextest::no_heading",
+                "{}",
+                profile.to_resolved_toml(),
+            );
+        }
 
-        let mut reporter = reporter_opts
-            .to_builder(no_capture)
-            .set_verbose(self.base.output.verbose)
-            .build(&test_list, &profile, output);
+        let mut reporter_builder = reporter_opts.to_builder(no_capture);
+        reporter_builder
+            .set_stream_output(runner_opts.stream_output)
+            .set_bench(bench)
+            .set_verbose(self.base.output.verbose);
+        if let Some(threshold) = reporter_opts.junit_rerun_threshold {
+            let history = read_run_history(profile.store_dir());
+            reporter_builder.set_junit_rerun_threshold(threshold, history);
+        }
+        let mut reporter = reporter_builder.build(&test_list, &profile, output);
         if self
             .base
             .output
@@ -1286,16 +2984,94 @@ impl App {
         {
             reporter.colorize();
         }
+        // Declared after the reporter so that it's dropped first, releasing the progress bar.
+        let _heartbeat_guard =
+            heartbeat.map(|heartbeat| heartbeat.set_line_writer(reporter.stderr_line_writer()));
 
         let handler = SignalHandlerKind::Standard;
-        let runner_builder = match runner_opts.to_builder(no_capture) {
+        let mut runner_builder = match runner_opts.to_builder(no_capture) {
             Some(runner_builder) => runner_builder,
             None => {
                 // This means --no-run was passed in. Exit.
-                return Ok(());
+                return Ok(None);
             }
         };
+        if bench {
+            match runner_opts.test_threads {
+                Some(test_threads) => {
+                    let test_threads = test_threads.compute();
+                    if test_threads > 1 && !no_capture {
+                        log::warn!(
+                            "running benchmarks with {test_threads} test threads: \
+                             benchmarks running in parallel may produce unreliable results"
+                        );
+                    }
+                }
+                None => {
+                    // Benchmarks are rarely designed to run in parallel.
+                    runner_builder.set_test_threads(TestThreads::Count(1));
+                }
+            }
+            runner_builder.set_bench(true);
+        }
+        if let Some(timing_baseline) = timing_baseline {
+            runner_builder.set_timing_baseline(timing_baseline, runner_opts.regression_threshold);
+        }
 
+        if let Some(test_name) = &runner_opts.bisect_test {
+            let bisector =
+                TestBisector::new(&test_list, &runner_builder, test_name).map_err(|err| *err)?;
+            let target = bisector.target();
+            // Run tests serially so that they start in the bisect order, and don't retry them.
+            runner_builder
+                .set_test_threads(TestThreads::Count(1))
+                .set_fail_fast(false)
+                .set_retries(RetryPolicy::new_without_delay(0));
+            configure_handle_inheritance(no_capture)?;
+            // Errors are boxed to keep the closure's return type small.
+            let result = bisector.run(|preceding| {
+                let test_list = test_list.retain_tests(
+                    |instance| {
+                        let key = (&instance.suite_info.binary_id, instance.name);
+                        key == target || preceding.contains(&key)
+                    },
+                    MismatchReason::Bisect,
+                );
+                let runner = runner_builder
+                    .clone()
+                    .build(
+                        &test_list,
+                        profile.clone(),
+                        SignalHandlerKind::Standard,
+                        double_spawn.clone(),
+                        target_runner.clone(),
+                    )
+                    .map_err(|err| Box::new(err.into()))?;
+                let mut target_failed = false;
+                let res = runner.try_execute(|event| {
+                    if let TestEvent::TestFinished {
+                        test_instance,
+                        run_statuses,
+                        ..
+                    } = event
+                    {
+                        if (&test_instance.suite_info.binary_id, test_instance.name) == target {
+                            target_failed = !run_statuses.last_status().result.is_success();
+                        }
+                    }
+                    Ok::<_, Infallible>(())
+                });
+                match res {
+                    Ok(_) => Ok(target_failed),
+                    Err(_) => Err(Box::new(ExpectedError::test_run_failed())),
+                }
+            });
+            result.map_err(|err| *err)?;
+            return Ok(None);
+        }
+
+        let store_dir = profile.store_dir().to_owned();
+        let mut summary_recorder = RunSummaryRecorder::new(profile.name());
         let runner = runner_builder.build(
             &test_list,
             profile,
@@ -1305,17 +3081,184 @@ impl App {
         )?;
 
         configure_handle_inheritance(no_capture)?;
-        let run_stats = runner.try_execute(|event| {
+        let mut flaky_rate_report = runner_opts
+            .report_flaky_rate
+            .map(|runs| FlakyRateReport::new(runs.get()));
+        #[cfg(feature = "remote-reporting")]
+        let mut remote_reporter = runner_opts
+            .report_artifacts_to
+            .as_deref()
+            .map(|url| {
+                nextest_runner::reporter::RemoteReporter::new(
+                    url,
+                    runner_opts.report_artifacts_token.clone(),
+                )
+            })
+            .transpose()?;
+        let mut run_summary = None;
+        let mut timing_regressions = 0;
+        let mut timings = runner_opts
+            .write_timing_baseline
+            .as_ref()
+            .map(|_| TimingData::new());
+        let res = runner.try_execute(|event| {
+            if let TestEvent::TimingRegression { .. } = event {
+                timing_regressions += 1;
+            }
+            if let (
+                Some(timings),
+                TestEvent::TestFinished {
+                    test_instance,
+                    run_statuses,
+                    ..
+                },
+            ) = (&mut timings, &event)
+            {
+                let last_status = run_statuses.last_status();
+                if last_status.result.is_success() && last_status.time_taken_known {
+                    timings.insert(
+                        test_instance.suite_info.binary_id.clone(),
+                        test_instance.name,
+                        last_status.time_taken,
+                    );
+                }
+            }
+            if let Some(flaky_rate_report) = &mut flaky_rate_report {
+                flaky_rate_report.record_event(&event);
+            }
+            #[cfg(feature = "remote-reporting")]
+            if let Some(remote_reporter) = &mut remote_reporter {
+                remote_reporter.report_event(&event);
+            }
+            if let Some(summary) = summary_recorder.record_event(&event) {
+                run_summary = Some(summary);
+            }
             // Write and flush the event.
             reporter.report_event(event)
-        })?;
-        if !run_stats.is_success() {
-            return Err(ExpectedError::test_run_failed());
+        });
+        let run_stats = match res {
+            Ok(run_stats) => run_stats,
+            Err(RunError::Callback(err)) => return Err(err.into()),
+            // cargo-nextest doesn't set a cancellation token, so the run can't be cancelled that
+            // way. Treat any other error as a failed run.
+            Err(_) => return Err(ExpectedError::test_run_failed()),
+        };
+        if let Some(flaky_rate_report) = flaky_rate_report {
+            flaky_rate_report.finish(runner_opts.flaky_report.as_deref())?;
+        }
+        if let (Some(timings), Some(path)) = (&timings, &runner_opts.write_timing_baseline) {
+            timings.write_json(path)?;
         }
+        #[cfg(feature = "remote-reporting")]
+        if let Some(remote_reporter) = remote_reporter {
+            let failed = remote_reporter.finish();
+            if failed > 0 {
+                log::warn!("{failed} test results couldn't be posted to --report-artifacts-to");
+            }
+        }
+        if let Some(run_summary) = &run_summary {
+            run_summary.write_to_store_dir(&store_dir)?;
+            let mut history = read_run_history(&store_dir);
+            history.record(run_summary);
+            history.write_to_store_dir(&store_dir)?;
+        }
+        if let Some(script) = &runner_opts.post_run_script {
+            let exit_code = if run_stats.is_success() && timing_regressions == 0 {
+                0
+            } else {
+                NextestExitCode::TEST_RUN_FAILED
+            };
+            if let Err(err) = run_post_run_script(script, exit_code, run_summary.as_ref()) {
+                if exit_code == 0 {
+                    return Err(err);
+                }
+                // The test run failed, which takes precedence over the script failing.
+                err.display_to_stderr();
+            }
+        }
+        if run_stats.is_success() && timing_regressions > 0 {
+            return Err(ExpectedError::TimingRegressionsFound {
+                count: timing_regressions,
+            });
+        }
+        Ok(Some(run_stats))
+    }
+}
+
+/// Runs the script passed in with `--post-run-script`, passing in the exit code of the test run as
+/// an argument and the run summary as JSON on standard input.
+fn run_post_run_script(
+    script: &Utf8Path,
+    exit_code: i32,
+    run_summary: Option<&RunSummary>,
+) -> Result<()> {
+    let exec_failed = |err| ExpectedError::PostRunScriptExecFailed {
+        script: script.to_owned(),
+        err,
+    };
+    let mut child = std::process::Command::new(script)
+        .arg(exit_code.to_string())
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(exec_failed)?;
+
+    let summary_json = run_summary
+        .map(|summary| serde_json::to_vec_pretty(summary).expect("serializing JSON succeeds"))
+        .unwrap_or_default();
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    match stdin.write_all(&summary_json) {
+        // The script doesn't have to read the summary.
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+        Err(err) => return Err(exec_failed(err)),
+    }
+    drop(stdin);
+
+    let exit_status = child.wait().map_err(exec_failed)?;
+    if exit_status.success() {
+        Ok(())
+    } else {
+        Err(ExpectedError::PostRunScriptFailed {
+            script: script.to_owned(),
+            exit_status,
+        })
+    }
+}
+
+/// Checks that every listed test binary contains at least one test.
+///
+/// A binary that lists no tests at all may have had its tests excluded by a focus mechanism
+/// within the binary itself, which would otherwise let a run pass without running anything.
+fn check_forbid_only(test_list: &TestList<'_>) -> Result<()> {
+    let binary_ids: Vec<_> = test_list
+        .iter()
+        .filter(|test_suite| {
+            matches!(test_suite.status, RustTestSuiteStatus::Listed { .. })
+                && test_suite.status.test_count() == 0
+        })
+        .map(|test_suite| test_suite.binary_id.to_string())
+        .collect();
+    if binary_ids.is_empty() {
         Ok(())
+    } else {
+        Err(ExpectedError::ForbidOnlyViolation { binary_ids })
     }
 }
 
+#[derive(Debug, Subcommand)]
+enum ArchiveCommand {
+    /// Show information about an archive without extracting it.
+    Inspect {
+        /// Archive file to inspect
+        #[arg(long, name = "archive-file", value_name = "PATH")]
+        archive_file: Utf8PathBuf,
+
+        /// Archive format
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t)]
+        archive_format: ArchiveFormatOpt,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 enum ShowConfigCommand {
     /// Show defined test groups and their associated tests.
@@ -1456,11 +3399,108 @@ impl SelfCommand {
     }
 }
 
+fn exec_archive_inspect(
+    archive_file: &Utf8Path,
+    format: ArchiveFormatOpt,
+    output_writer: &mut OutputWriter,
+) -> Result<()> {
+    let format = format.to_archive_format(archive_file)?;
+    let info = inspect_archive(archive_file, format).map_err(|err| {
+        ExpectedError::ArchiveInspectError {
+            archive_file: archive_file.to_owned(),
+            err: Box::new(err),
+        }
+    })?;
+    let binary_list = &info.binary_list;
+    let build_meta = &binary_list.rust_build_meta;
+    let non_test_binary_count: usize = build_meta
+        .non_test_binaries
+        .values()
+        .map(|binaries| binaries.len())
+        .sum();
+    let target_platform = build_meta
+        .target_triple
+        .as_ref()
+        .map_or("host", |triple| triple.platform.triple_str());
+    let debug_info = if build_meta.debug_info_stripped {
+        "stripped"
+    } else {
+        "included"
+    };
+    let sources = if info.source_file_count > 0 {
+        format!("included ({} files)", info.source_file_count)
+    } else {
+        "not included".to_owned()
+    };
+
+    let mut writer = output_writer.stdout_writer();
+    writeln!(
+        writer,
+        "test binaries: {}\n\
+         non-test binaries: {non_test_binary_count}\n\
+         linked paths: {}\n\
+         target platform: {target_platform}\n\
+         debug info: {debug_info}\n\
+         sources: {sources}",
+        binary_list.rust_binaries.len(),
+        build_meta.linked_paths.len(),
+    )
+    .map_err(WriteTestListError::Io)?;
+    writer.flush().map_err(WriteTestListError::Io)?;
+    Ok(())
+}
+
+fn exec_status(
+    manifest_path: Option<Utf8PathBuf>,
+    output: OutputOpts,
+    config_opts: ConfigOpts,
+    profile_name: Option<&str>,
+    format: StatusFormatOpt,
+    output_writer: &mut OutputWriter,
+) -> Result<()> {
+    let output = output.init();
+    let json = acquire_graph_data(manifest_path.as_deref(), None, output, false, false)?;
+    let graph = PackageGraph::from_json(json)
+        .map_err(|err| ExpectedError::cargo_metadata_parse_error(None, err))?;
+    let config = config_opts.make_config(graph.workspace().root(), &graph)?;
+    let profile_name = profile_name.unwrap_or_else(|| config.select_profile(None).name);
+    let profile = config
+        .profile(profile_name)
+        .map_err(ExpectedError::profile_not_found)?;
+
+    let summary = match RunSummary::read_from_store_dir(profile.store_dir())? {
+        Some(summary) => summary,
+        None => {
+            return Err(ExpectedError::NoPreviousRun {
+                profile: profile_name.to_owned(),
+                store_dir: profile.store_dir().to_owned(),
+            })
+        }
+    };
+
+    let mut writer = output_writer.stdout_writer();
+    match format {
+        StatusFormatOpt::Human => summary
+            .write_human(
+                output.color.should_colorize(supports_color::Stream::Stdout),
+                &mut writer,
+            )
+            .map_err(WriteTestListError::Io)?,
+        StatusFormatOpt::Json => {
+            let json = serde_json::to_string_pretty(&summary).expect("serializing JSON succeeds");
+            writeln!(writer, "{json}").map_err(WriteTestListError::Io)?;
+        }
+    }
+    writer.flush().map_err(WriteTestListError::Io)?;
+    Ok(())
+}
+
 fn acquire_graph_data(
     manifest_path: Option<&Utf8Path>,
     target_dir: Option<&Utf8Path>,
     output: OutputContext,
     with_deps: bool,
+    offline: bool,
 ) -> Result<String> {
     let mut cargo_cli = CargoCli::new("metadata", manifest_path, output);
     cargo_cli.add_args(["--format-version=1", "--all-features"]);
@@ -1468,6 +3508,9 @@ fn acquire_graph_data(
     if !with_deps {
         cargo_cli.add_arg("--no-deps");
     }
+    if offline {
+        cargo_cli.add_arg("--offline");
+    }
 
     let mut expression = cargo_cli.to_expression().stdout_capture().unchecked();
     // cargo metadata doesn't support "--target-dir" but setting the environment
@@ -1490,6 +3533,54 @@ fn acquire_graph_data(
     Ok(json)
 }
 
+/// Warns if `target_dir` is on a different filesystem from `workspace_root`.
+///
+/// Builds can be slower in this case, since some artifacts are copied rather than hard-linked. The
+/// target directory may not exist yet, so its closest existing ancestor is checked instead.
+#[cfg(unix)]
+fn warn_if_different_filesystem(target_dir: &Utf8Path, workspace_root: &Utf8Path) {
+    use std::os::unix::fs::MetadataExt;
+
+    let existing = target_dir
+        .ancestors()
+        .map(|path| {
+            if path.as_str().is_empty() {
+                Utf8Path::new(".")
+            } else {
+                path
+            }
+        })
+        .find_map(|path| path.metadata().ok());
+    if let (Some(target_metadata), Ok(workspace_metadata)) = (existing, workspace_root.metadata()) {
+        if target_metadata.dev() != workspace_metadata.dev() {
+            log::warn!(
+                "target directory {target_dir} is on a different filesystem from \
+                 the workspace at {workspace_root}: builds may be slower since \
+                 artifacts can't be hard-linked across filesystems"
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_different_filesystem(_target_dir: &Utf8Path, _workspace_root: &Utf8Path) {}
+
+/// Reads the run history from `store_dir`, starting a fresh one if the existing file can't be read
+/// (for example, because it's corrupt or was written by an incompatible version of nextest).
+fn read_run_history(store_dir: &Utf8Path) -> RunHistory {
+    match RunHistory::read_from_store_dir(store_dir) {
+        Ok(history) => history,
+        Err(err) => {
+            let mut message = err.to_string();
+            if let Some(source) = std::error::Error::source(&err) {
+                write!(message, ": {source}").expect("writing to a String succeeds");
+            }
+            log::warn!("{message}; starting a new run history");
+            RunHistory::default()
+        }
+    }
+}
+
 fn discover_target_triple(
     cargo_configs: &CargoConfigs,
     target_cli_option: Option<&str>,
@@ -1585,6 +3676,7 @@ mod tests {
             // ---
             "cargo nextest list",
             "cargo nextest run",
+            "cargo nextest check",
             // ---
             // Commands with arguments
             // ---
@@ -1593,10 +3685,109 @@ mod tests {
             "cargo nextest list --message-format json-pretty",
             "cargo nextest run --failure-output never",
             "cargo nextest run --success-output=immediate",
+            "cargo nextest run --success-output immediate --suppress-pass-output-threshold 1024",
             "cargo nextest run --status-level=all",
             "cargo nextest run --no-capture",
             "cargo nextest run --nocapture",
             "cargo nextest run --no-run",
+            "cargo nextest run --stdin-passthrough",
+            "cargo nextest run --stream-output",
+            "cargo nextest run --test-process-priority nice",
+            "cargo nextest run --test-isolation thread",
+            "cargo nextest run --test-isolation none",
+            "cargo nextest run --test-process-priority high",
+            "cargo nextest run --stream-output --test-threads 4",
+            "cargo nextest run --stdin-passthrough --test-threads 1",
+            "cargo nextest run --fail-on-stderr",
+            "cargo nextest run --no-network-access",
+            "cargo nextest run --fail-on-leaked-processes",
+            "cargo nextest run --preload-libraries libfoo.so",
+            "cargo nextest run --preload-libraries libfoo.so,/opt/libbar.so",
+            "cargo nextest run --no-network-access --network-access",
+            "cargo nextest run --network-access --no-network-access",
+            "cargo nextest run --filesystem-access read-only",
+            "cargo nextest run --filesystem-access read-only --filesystem-access-exceptions /tmp",
+            "cargo nextest run --extra-args -- --test-data-dir foo",
+            "cargo nextest run --aggregate-failures",
+            "cargo nextest run --detect-leaky-tests",
+            "cargo nextest run --test-binary-filter '*_integration'",
+            "cargo nextest list --test-binary-filter foo --test-binary-filter 'bar*'",
+            "cargo nextest run --detect-leaky-tests --leak-detection-path /tmp/foo",
+            "cargo nextest run --shuffle",
+            "cargo nextest run --seed 42",
+            "cargo nextest run --seed random",
+            "cargo nextest bench",
+            "cargo nextest bench --test-threads 4 --benches",
+            "cargo nextest run --cargo-quiet",
+            "cargo nextest run --min-test-run-time 1us",
+            "cargo nextest run --no-fail-fast --failure-limit-per-binary 3",
+            "cargo nextest run --min-test-run-time 10ms",
+            "cargo nextest run --timeout-grace-period 30s",
+            "cargo nextest run --timeout-grace-period 0s",
+            "cargo nextest run --max-test-time 10m",
+            "cargo nextest run --test-env-inherit none --test-env-inherit-add PATH",
+            "cargo nextest run --test-env-inherit PATH,HOME",
+            "cargo nextest run --test-thread-stack-size 16777216",
+            "cargo nextest run --check-for-updates",
+            "cargo nextest run --profile-selection auto",
+            "cargo nextest run --capture-all --output-dir out",
+            "cargo nextest run --report-flaky-rate 10",
+            "cargo nextest run --report-flaky-rate 10 --flaky-report flaky.json",
+            "cargo nextest run --unstable-features --simulate-test-failures 0.5",
+            "cargo nextest run --unstable-features --simulate-test-failures 0.5 --simulate-test-failures-seed 42",
+            "cargo nextest run --workspace-filter 'metadata.category = \"backend\"'",
+            "cargo nextest run --workspace-filter 'metadata.owner exists' --workspace-filter 'metadata.team = \"a\"'",
+            "cargo nextest run --workspace --workspace-ignore-path 'vendor/**' --workspace-ignore-path gen/*",
+            "cargo nextest run --capture-all --output-dir out --capture-all-max-bytes 1024",
+            "cargo nextest run --profile ci --profile-selection manual",
+            "cargo nextest run --feature-combinations 2",
+            "cargo nextest run --junit-suitename-style package",
+            "cargo nextest run --junit-timestamp-format simple",
+            "cargo nextest run --junit-elapsed-time-precision 1",
+            "cargo nextest run --junit-hostname none",
+            "cargo nextest run --junit-rerun-threshold 3",
+            "cargo nextest run --progress-bar fraction",
+            "cargo nextest run --post-run-script scripts/upload.sh",
+            "cargo nextest run --partition count:1/4 --min-shards-for-balance 1",
+            "cargo nextest run --annotate-output-with-timestamps --stream-output",
+            "cargo nextest run --cargo-features foo,bar",
+            "cargo nextest run --cargo-features all",
+            "cargo nextest run --test-binary-timeout 30m",
+            "cargo nextest run --progress-bar spinner",
+            "cargo nextest run --retries 2 --retry-if-exit-code 2 --retry-if-exit-code -1",
+            "cargo nextest run --junit-hostname env:RUNNER_NAME",
+            "cargo nextest run --junit-classname-style module-path",
+            "cargo nextest run --junit-classname-style none",
+            "cargo nextest run --junit-failure-format brief",
+            "cargo nextest run --junit-nest-packages",
+            "cargo nextest run --junit-strip-ansi",
+            "cargo nextest run --no-junit-passing-tests",
+            "cargo nextest run --check-target-runner",
+            "cargo nextest run --retries 3 --max-retries-global 10",
+            "cargo nextest run --junit-name {workspace}-{profile}",
+            "cargo nextest run --test-pid-file nextest.pid",
+            "cargo nextest run --heartbeat 60s",
+            "cargo nextest run --test-wrapper 'taskset -c 0-3'",
+            "cargo nextest run --cargo-config build.jobs=2 --cargo-config target.x.runner=\"foo\"",
+            "cargo nextest run --report-timing-regression baseline.json",
+            "cargo nextest run --report-timing-regression baseline.json --regression-threshold 20%",
+            "cargo nextest run --write-timing-baseline baseline.json",
+            "cargo nextest run --junit-path-rewrite ^/home/[^/]+/:~/ --junit-path-rewrite foo:bar",
+            "cargo nextest run --junit-truncate-name-at 200",
+            "cargo nextest run --junit-use-error-for-panics",
+            "cargo nextest run --junit-errored-vs-failed",
+            "cargo nextest run --test-binary-path-override my-crate::bin/foo=/tmp/foo",
+            "cargo nextest run --capture-all --output-dir out --clean-output-dir",
+            "cargo nextest run --capture-all --output-dir out --ignore-output-dir-exists",
+            "cargo nextest run --junit-add-system-property build=42 --junit-add-system-property CI",
+            "cargo nextest run --display-only-failing-packages",
+            "cargo nextest run --summary-format table",
+            "cargo nextest run --ignore-doctest",
+            "cargo nextest run --print-config-after-merge",
+            "cargo nextest run --forbid-only",
+            "cargo nextest run --cap-lints allow",
+            "cargo nextest run --cargo-feature-combinations 1 -p foo",
+            "cargo nextest run --aggregate-failures --failure-output immediate-final",
             "cargo nextest run --final-status-level flaky",
             // retry is an alias for flaky -- ensure that it parses
             "cargo nextest run --final-status-level retry",
@@ -1605,16 +3796,42 @@ mod tests {
             // ---
             "cargo nextest list --lib --bins",
             "cargo nextest run --ignore-rust-version --unit-graph",
+            "cargo nextest run --cargo-target-dir /tmp/target",
             // ---
             // Reuse build options
             // ---
             "cargo nextest list --binaries-metadata=foo",
+            "cargo nextest run --cargo-offline",
+            "cargo nextest run --archive-file my-archive.tar.zst --cargo-offline",
             "cargo nextest run --binaries-metadata=foo --target-dir-remap=bar",
             "cargo nextest list --cargo-metadata path",
             "cargo nextest run --cargo-metadata=path --workspace-remap remapped-path",
+            "cargo nextest run --workspace-root-override relocated-path",
+            "cargo nextest list --archive-file my-archive.tar.zst --workspace-root-override foo",
             "cargo nextest archive --archive-file my-archive.tar.zst --zstd-level -1",
             "cargo nextest archive --archive-file my-archive.foo --archive-format tar-zst",
             "cargo nextest archive --archive-file my-archive.foo --archive-format tar-zstd",
+            "cargo nextest archive --archive-file my-archive.tar.zst --include-source",
+            "cargo nextest archive --archive-file my-archive.tar.zst --include-source --max-source-size-mb 10",
+            "cargo nextest archive --archive-file my-archive.tar.zst --strip-debug-info",
+            "cargo nextest archive --archive-file my-archive.tar.zst --strip-debug-info --strip-debug-info-tool llvm-objcopy",
+            "cargo nextest archive inspect --archive-file my-archive.tar.zst",
+            "cargo nextest run --allow-runner-arch-mismatch",
+            "cargo nextest run --ignore-build-script-failures",
+            "cargo nextest run --build-jobs 4",
+            "cargo nextest run --build-jobs -1",
+            "cargo nextest run --build-jobs default",
+            "cargo nextest run --assert-test-count 10",
+            "cargo nextest run --assert-test-count my-crate:3 --assert-test-count other-crate:4",
+            "cargo nextest run --assert-test-count-min 5 --assert-test-count-max my-crate:20",
+            "cargo nextest run --profile-from-env MY_PROFILE",
+            "cargo nextest run --profile-from-env MY_PROFILE -P ci",
+            "cargo nextest status",
+            "cargo nextest status --format json",
+            "cargo nextest status -P ci",
+            "cargo nextest run --bisect-test my_test",
+            "cargo nextest run --bisect-test my_test --seed 42",
+            "cargo nextest list --archive-file my-archive.tar.zst --allow-runner-arch-mismatch",
             "cargo nextest list --archive-file my-archive.tar.zst",
             "cargo nextest list --archive-file my-archive.tar.zst --archive-format tar-zst",
             "cargo nextest list --archive-file my-archive.tar.zst --extract-to my-path",
@@ -1652,12 +3869,259 @@ mod tests {
                 "cargo nextest run --no-capture --success-output=final",
                 ArgumentConflict,
             ),
+            (
+                "cargo nextest run --no-capture --suppress-pass-output-threshold 100",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-capture --fail-on-stderr",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-capture --aggregate-failures",
+                ArgumentConflict,
+            ),
             // ---
             // --no-run and these options conflict
             // ---
             ("cargo nextest run --no-run -j8", ArgumentConflict),
             ("cargo nextest run --no-run --retries 3", ArgumentConflict),
+            (
+                "cargo nextest run --retry-immediately-on segfault",
+                ValueValidation,
+            ),
             ("cargo nextest run --no-run --fail-fast", ArgumentConflict),
+            (
+                "cargo nextest run --leak-detection-path /tmp/foo",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --test-binary-filter 'foo['",
+                ValueValidation,
+            ),
+            ("cargo nextest run --shuffle --seed 42", ArgumentConflict),
+            ("cargo nextest run --seed foo", ValueValidation),
+            ("cargo nextest run --min-test-run-time foo", ValueValidation),
+            (
+                "cargo nextest run --failure-limit-per-binary 0",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --timeout-grace-period foo",
+                ValueValidation,
+            ),
+            ("cargo nextest run --max-test-time 10", ValueValidation),
+            (
+                "cargo nextest run --test-env-inherit PATH,",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --test-env-inherit-add PATH",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --no-run --timeout-grace-period 5s",
+                ArgumentConflict,
+            ),
+            ("cargo nextest run --profile-selection ci", InvalidValue),
+            ("cargo nextest run --report-flaky-rate 0", ValueValidation),
+            (
+                "cargo nextest run --simulate-test-failures 0.5",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --unstable-features --simulate-test-failures 1.5",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --simulate-test-failures-seed 42",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --flaky-report flaky.json",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --report-flaky-rate 10 --retries 2",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --workspace-filter category=backend",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --workspace-ignore-path vendor/[",
+                ValueValidation,
+            ),
+            ("cargo nextest run --capture-all", MissingRequiredArgument),
+            (
+                "cargo nextest run --filesystem-access-exceptions /tmp",
+                MissingRequiredArgument,
+            ),
+            ("cargo nextest run --filesystem-access none", InvalidValue),
+            ("cargo nextest run --test-process-priority low", InvalidValue),
+            ("cargo nextest run --test-isolation threads", InvalidValue),
+            (
+                "cargo nextest run --test-isolation thread --no-capture",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --test-isolation none --stream-output",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --test-isolation thread --failure-limit-per-binary 2",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --test-isolation none --report-timing-regression baseline.json",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --test-process-priority nice",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --output-dir out",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --capture-all-max-bytes 1024",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --no-capture --capture-all --output-dir out",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --test-thread-stack-size 8M",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --no-run --test-thread-stack-size 16777216",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --junit-suitename-style foo",
+                InvalidValue,
+            ),
+            (
+                "cargo nextest run --junit-timestamp-format iso8601",
+                InvalidValue,
+            ),
+            (
+                "cargo nextest run --junit-elapsed-time-precision abc",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --junit-elapsed-time-precision 10",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --junit-truncate-name-at 3",
+                ValueValidation,
+            ),
+            ("cargo nextest run --junit-hostname env:", ValueValidation),
+            ("cargo nextest run --junit-rerun-threshold -1", UnknownArgument),
+            ("cargo nextest run --progress-bar dots", InvalidValue),
+            (
+                "cargo nextest run --no-run --post-run-script scripts/upload.sh",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --min-shards-for-balance 1",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --annotate-output-with-timestamps --no-capture",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --cargo-features all --features foo",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --test-binary-timeout forever",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --cargo-features none --feature-combinations 2",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --annotate-output-with-timestamps --test-isolation thread",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --partition count:1/4 --min-shards-for-balance 0",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --retry-if-exit-code two",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --junit-classname-style module",
+                InvalidValue,
+            ),
+            (
+                "cargo nextest run --junit-failure-format full",
+                InvalidValue,
+            ),
+            (
+                "cargo nextest run --junit-add-system-property =foo",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --capture-all --output-dir out --clean-output-dir \
+                 --ignore-output-dir-exists",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --clean-output-dir",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --test-binary-path-override my-crate",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --display-only-failing-packages --no-capture",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --ignore-doctest --only-doctest",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --summary-format table",
+                ArgumentConflict,
+            ),
+            ("cargo nextest run --cap-lints foo", InvalidValue),
+            (
+                "cargo nextest run --feature-combinations 2 --all-features",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --stdin-passthrough",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --stream-output",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --fail-on-leaked-processes",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --preload-libraries libfoo.so",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-capture --stream-output",
+                ArgumentConflict,
+            ),
             (
                 "cargo nextest run --no-run --no-fail-fast",
                 ArgumentConflict,
@@ -1670,6 +4134,10 @@ mod tests {
                 "cargo nextest run --no-run --success-output never",
                 ArgumentConflict,
             ),
+            (
+                "cargo nextest run --suppress-pass-output-threshold 1k",
+                ValueValidation,
+            ),
             (
                 "cargo nextest run --no-run --status-level pass",
                 ArgumentConflict,
@@ -1698,6 +4166,10 @@ mod tests {
                 "cargo nextest run --workspace-remap foo",
                 MissingRequiredArgument,
             ),
+            (
+                "cargo nextest run --cargo-metadata bar --workspace-remap foo --workspace-root-override foo",
+                ArgumentConflict,
+            ),
             // ---
             // target-dir-remap requires binaries-metadata
             // ---
@@ -1736,6 +4208,60 @@ mod tests {
                 "cargo nextest run --archive-file foo --extract-to bar --persist-extract-tempdir",
                 ArgumentConflict,
             ),
+            (
+                "cargo nextest archive --archive-file foo.tar.zst --max-source-size-mb 10",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest archive --archive-file foo.tar.zst --strip-debug-info-tool strip",
+                MissingRequiredArgument,
+            ),
+            ("cargo nextest archive --strip-debug-info", MissingRequiredArgument),
+            ("cargo nextest archive inspect", MissingRequiredArgument),
+            (
+                "cargo nextest archive --archive-file foo.tar.zst inspect --archive-file foo.tar.zst",
+                UnknownArgument,
+            ),
+            (
+                "cargo nextest run --bisect-test my_test --shuffle",
+                ArgumentConflict,
+            ),
+            ("cargo nextest status --format xml", InvalidValue),
+            (
+                "cargo nextest run --assert-test-count many",
+                ValueValidation,
+            ),
+            ("cargo nextest run --assert-test-count :3", ValueValidation),
+            (
+                "cargo nextest run --no-run --assert-test-count 3",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --check-target-runner",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --report-flaky-rate 10 --max-retries-global 5",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --junit-path-rewrite no-separator",
+                ValueValidation,
+            ),
+            ("cargo nextest run --heartbeat 0s", ValueValidation),
+            ("cargo nextest run --test-wrapper ''", ValueValidation),
+            (
+                "cargo nextest run --regression-threshold 20",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --report-timing-regression baseline.json --regression-threshold x%",
+                ValueValidation,
+            ),
+            (
+                "cargo nextest run --archive-file foo --ignore-build-script-failures",
+                ArgumentConflict,
+            ),
             (
                 "cargo nextest run --archive-file foo --cargo-metadata bar",
                 ArgumentConflict,
@@ -1750,6 +4276,8 @@ mod tests {
             ),
             // Invalid test threads: 0
             ("cargo nextest run --jobs 0", ValueValidation),
+            ("cargo nextest run --build-jobs 0", ValueValidation),
+            ("cargo nextest run --build-jobs many", ValueValidation),
         ];
 
         // Unset all NEXTEST_ env vars because they can conflict with the try_parse_from below.
@@ -1786,6 +4314,45 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "remote-reporting")]
+    #[test]
+    fn test_report_artifacts_argument_parsing() {
+        use clap::error::ErrorKind::*;
+
+        let valid = [
+            "cargo nextest run --report-artifacts-to https://ci.example.com/results",
+            "cargo nextest run --report-artifacts-to https://ci.example.com/results --report-artifacts-token abc",
+        ];
+        for valid_args in valid {
+            if let Err(error) = CargoNextestApp::try_parse_from(
+                shell_words::split(valid_args).expect("valid command line"),
+            ) {
+                panic!("{valid_args} should have successfully parsed, but didn't: {error}");
+            }
+        }
+
+        let invalid = [
+            (
+                "cargo nextest run --no-run --report-artifacts-to https://ci.example.com/results",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --report-artifacts-token abc",
+                MissingRequiredArgument,
+            ),
+        ];
+        for (invalid_args, kind) in invalid {
+            match CargoNextestApp::try_parse_from(
+                shell_words::split(invalid_args).expect("valid command"),
+            ) {
+                Ok(_) => {
+                    panic!("{invalid_args} should have errored out but successfully parsed");
+                }
+                Err(error) => assert_eq!(error.kind(), kind, "{invalid_args} errored with"),
+            }
+        }
+    }
+
     #[derive(Debug, Parser)]
     struct TestCli {
         #[structopt(flatten)]
@@ -1819,6 +4386,11 @@ mod tests {
                 "foo --run-ignored ignored-only str -- -- --- --ignored",
             ),
             ("foo -- -- str1 str2 --", "foo str1 str2 -- -- --"),
+            // ---
+            // extra args are not used as filters
+            // ---
+            ("foo --extra-args -- --test-data-dir str1", "foo"),
+            ("foo str1 --extra-args -- --ignored-flag", "foo str1"),
         ];
         let invalid = &[
             // ---
@@ -1861,5 +4433,19 @@ mod tests {
                 );
             }
         }
+
+        let extra_args_conflicts = &[
+            ("foo --extra-args -- --exact", "--exact"),
+            ("foo --extra-args -- --format=json", "--format=json"),
+            ("foo --extra-args -- --test-data-dir --ignored", "--ignored"),
+        ];
+        for (s, arg) in extra_args_conflicts {
+            let res = get_test_filter_builder(s);
+            if let Err(ExpectedError::ExtraArgConflictError { err }) = &res {
+                assert_eq!(err.arg(), *arg);
+            } else {
+                panic!("{s} should have errored out with ExtraArgConflictError, actual: {res:?}");
+            }
+        }
     }
 }
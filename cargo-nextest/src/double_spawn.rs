@@ -4,7 +4,7 @@
 use crate::{output::Color, ExpectedError, Result};
 use camino::Utf8PathBuf;
 use clap::Args;
-use nextest_runner::double_spawn::double_spawn_child_init;
+use nextest_runner::double_spawn::{double_spawn_child_init, double_spawn_child_prepare};
 use std::os::unix::process::CommandExt;
 
 #[derive(Debug, Args)]
@@ -28,6 +28,7 @@ impl DoubleSpawnOpts {
             }
         })?;
         let mut command = std::process::Command::new(&self.program);
+        double_spawn_child_prepare(&mut command);
         // Note: exec only returns an error -- in the success case it never returns.
         let err = command.args(args).exec();
         Err(ExpectedError::DoubleSpawnExecError { command, err })
@@ -17,9 +17,28 @@ pub(crate) type Result<T, E = ExpectedError> = std::result::Result<T, E>;
 pub enum ReuseBuildKind {
     Normal,
     ReuseWithWorkspaceRemap { workspace_root: Utf8PathBuf },
+    WorkspaceRootOverride { workspace_root: Utf8PathBuf },
     Reuse,
 }
 
+/// Errors reported by Cargo while building test binaries.
+///
+/// This is only captured with `--cargo-quiet`: otherwise, Cargo's output is passed through to
+/// stderr as-is.
+#[derive(Debug, Error)]
+#[error("{}", .stderr.trim_end())]
+pub struct BuildError {
+    stderr: String,
+}
+
+impl BuildError {
+    pub(crate) fn new(stderr: impl Into<String>) -> Self {
+        Self {
+            stderr: stderr.into(),
+        }
+    }
+}
+
 // Note that the #[error()] strings are mostly placeholder messages -- the expected way to print out
 // errors is with the display_to_stderr method, which colorizes errors.
 
@@ -43,8 +62,9 @@ pub enum ExpectedError {
     },
     #[error("profile not found")]
     ProfileNotFound {
-        #[from]
+        #[source]
         err: ProfileNotFound,
+        env_var: Option<String>,
     },
     #[error("failed to create store directory")]
     StoreDirCreateError {
@@ -72,6 +92,11 @@ pub enum ExpectedError {
         #[from]
         err: UnknownHostPlatform,
     },
+    #[error("target runner error")]
+    TargetRunnerError {
+        #[from]
+        err: TargetRunnerError,
+    },
     #[error("argument file read error")]
     ArgumentFileReadError {
         arg_name: &'static str,
@@ -97,6 +122,12 @@ pub enum ExpectedError {
         #[source]
         err: Box<ArchiveExtractError>,
     },
+    #[error("archive inspect error")]
+    ArchiveInspectError {
+        archive_file: Utf8PathBuf,
+        #[source]
+        err: Box<ArchiveExtractError>,
+    },
     #[error("path mapper construct error")]
     PathMapperConstructError {
         arg_name: &'static str,
@@ -141,6 +172,8 @@ pub enum ExpectedError {
     BuildFailed {
         command: String,
         exit_code: Option<i32>,
+        #[source]
+        error: Option<BuildError>,
     },
     #[error("building test runner failed")]
     TestRunnerBuildError {
@@ -162,13 +195,102 @@ pub enum ExpectedError {
         #[from]
         err: ConfigureHandleInheritanceError,
     },
+    #[error(transparent)]
+    ExtraArgConflictError {
+        #[from]
+        err: ExtraArgConflictError,
+    },
+    #[error("test binaries contain no tests")]
+    ForbidOnlyViolation { binary_ids: Vec<String> },
+    #[error(transparent)]
+    TestCountAssertionError {
+        #[from]
+        err: TestCountAssertionError,
+    },
+    #[error("too many feature combinations")]
+    TooManyFeatureCombinations {
+        feature_count: usize,
+        depth: usize,
+        limit: usize,
+    },
+    #[error("bisect test not found")]
+    BisectTestNotFound { test_name: String },
+    #[error("test binary path override error")]
+    BinaryPathOverrideError {
+        #[from]
+        err: BinaryPathOverrideError,
+    },
+    #[error("reused build is incompatible with --cargo-offline")]
+    ArchiveOfflineIncompatibleError,
+    #[error("all selected packages are ignored")]
+    AllPackagesIgnored { packages: Vec<String> },
+    #[error("doctests are not supported")]
+    DoctestsNotSupported,
+    #[error("output dir is not empty")]
+    OutputDirNotEmpty { output_dir: Utf8PathBuf },
+    #[error("error cleaning output dir")]
+    OutputDirCleanError {
+        output_dir: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("PID file error")]
+    PidFileError {
+        path: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("bisect test is ambiguous")]
+    BisectTestAmbiguous {
+        test_name: String,
+        binary_ids: Vec<String>,
+    },
+    #[error("error reading run summary")]
+    RunSummaryReadError {
+        #[from]
+        err: RunSummaryReadError,
+    },
+    #[error("error reading timing baseline")]
+    TimingDataReadError {
+        #[from]
+        err: TimingDataReadError,
+    },
+    #[error("timing regressions found")]
+    TimingRegressionsFound { count: usize },
+    #[error("no previous run found")]
+    NoPreviousRun {
+        profile: String,
+        store_dir: Utf8PathBuf,
+    },
     #[error("show test groups error")]
     ShowTestGroupsError {
         #[from]
         err: ShowTestGroupsError,
     },
+    #[error("partition error")]
+    PartitionError {
+        #[from]
+        err: PartitionError,
+    },
+    #[cfg(feature = "remote-reporting")]
+    #[error("remote reporter error")]
+    RemoteReporterError {
+        #[from]
+        err: RemoteReporterError,
+    },
     #[error("test run failed")]
     TestRunFailed,
+    #[error("post-run script exec failed")]
+    PostRunScriptExecFailed {
+        script: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("post-run script failed")]
+    PostRunScriptFailed {
+        script: Utf8PathBuf,
+        exit_status: std::process::ExitStatus,
+    },
     #[cfg(feature = "self-update")]
     #[error("failed to parse --version")]
     UpdateVersionParseError {
@@ -238,7 +360,14 @@ impl ExpectedError {
     }
 
     pub(crate) fn profile_not_found(err: ProfileNotFound) -> Self {
-        Self::ProfileNotFound { err }
+        Self::ProfileNotFound { err, env_var: None }
+    }
+
+    pub(crate) fn profile_from_env_not_found(err: ProfileNotFound, env_var: &str) -> Self {
+        Self::ProfileNotFound {
+            err,
+            env_var: Some(env_var.to_owned()),
+        }
     }
 
     pub(crate) fn config_parse_error(err: ConfigParseError) -> Self {
@@ -297,10 +426,12 @@ impl ExpectedError {
     pub(crate) fn build_failed(
         command: impl IntoIterator<Item = impl AsRef<str>>,
         exit_code: Option<i32>,
+        error: Option<BuildError>,
     ) -> Self {
         Self::BuildFailed {
             command: shell_words::join(command),
             exit_code,
+            error,
         }
     }
 
@@ -332,9 +463,11 @@ impl ExpectedError {
             | Self::ConfigParseError { .. }
             | Self::TestFilterBuilderError { .. }
             | Self::UnknownHostPlatform { .. }
+            | Self::TargetRunnerError { .. }
             | Self::ArgumentFileReadError { .. }
             | Self::UnknownArchiveFormat { .. }
             | Self::ArchiveExtractError { .. }
+            | Self::ArchiveInspectError { .. }
             | Self::RustBuildMetaParseError { .. }
             | Self::PathMapperConstructError { .. }
             | Self::ArgumentJsonParseError { .. }
@@ -344,7 +477,24 @@ impl ExpectedError {
             | Self::TestBinaryArgsParseError { .. }
             | Self::DialoguerError { .. }
             | Self::SignalHandlerSetupError { .. }
+            | Self::ExtraArgConflictError { .. }
+            | Self::TooManyFeatureCombinations { .. }
+            | Self::BisectTestNotFound { .. }
+            | Self::BisectTestAmbiguous { .. }
+            | Self::BinaryPathOverrideError { .. }
+            | Self::ArchiveOfflineIncompatibleError
+            | Self::AllPackagesIgnored { .. }
+            | Self::DoctestsNotSupported
+            | Self::OutputDirNotEmpty { .. }
+            | Self::OutputDirCleanError { .. }
+            | Self::PidFileError { .. }
+            | Self::RunSummaryReadError { .. }
+            | Self::TimingDataReadError { .. }
+            | Self::NoPreviousRun { .. }
             | Self::ShowTestGroupsError { .. } => NextestExitCode::SETUP_ERROR,
+            #[cfg(feature = "remote-reporting")]
+            Self::RemoteReporterError { .. } => NextestExitCode::SETUP_ERROR,
+            Self::PartitionError { .. } => NextestExitCode::TEST_LIST_CREATION_FAILED,
             #[cfg(feature = "self-update")]
             Self::UpdateVersionParseError { .. } => NextestExitCode::SETUP_ERROR,
             Self::DoubleSpawnParseArgsError { .. } | Self::DoubleSpawnExecError { .. } => {
@@ -356,7 +506,13 @@ impl ExpectedError {
             Self::BuildExecFailed { .. } | Self::BuildFailed { .. } => {
                 NextestExitCode::BUILD_FAILED
             }
-            Self::TestRunFailed => NextestExitCode::TEST_RUN_FAILED,
+            Self::TestRunFailed
+            | Self::ForbidOnlyViolation { .. }
+            | Self::TestCountAssertionError { .. }
+            | Self::TimingRegressionsFound { .. } => NextestExitCode::TEST_RUN_FAILED,
+            Self::PostRunScriptExecFailed { .. } | Self::PostRunScriptFailed { .. } => {
+                NextestExitCode::POST_RUN_SCRIPT_FAILED
+            }
             Self::ArchiveCreateError { .. } => NextestExitCode::ARCHIVE_CREATION_FAILED,
             Self::WriteTestListError { .. } | Self::WriteEventError { .. } => {
                 NextestExitCode::WRITE_OUTPUT_ERROR
@@ -388,8 +544,15 @@ impl ExpectedError {
                 // The error produced by `cargo metadata` is enough.
                 None
             }
-            Self::ProfileNotFound { err } => {
+            Self::ProfileNotFound { err, env_var } => {
                 log::error!("{}", err);
+                if let Some(env_var) = env_var {
+                    log::error!(
+                        target: "cargo_nextest::no_heading",
+                        "(the profile name was read from `{}`, passed in with --profile-from-env)",
+                        env_var.if_supports_color(Stream::Stderr, |x| x.bold()),
+                    );
+                }
                 err.source()
             }
             Self::RootManifestNotFound {
@@ -403,6 +566,13 @@ impl ExpectedError {
                             workspace_root.if_supports_color(Stream::Stderr, |x| x.bold())
                         )
                     }
+                    ReuseBuildKind::WorkspaceRootOverride { workspace_root } => {
+                        format!(
+                            "\n(hint: ensure that --workspace-root-override points to the \
+                              workspace root, {})",
+                            workspace_root.if_supports_color(Stream::Stderr, |x| x.bold())
+                        )
+                    }
                     ReuseBuildKind::Reuse => {
                         "\n(hint: ensure that project source is available for reused build, \
                           using --workspace-remap if necessary)"
@@ -490,6 +660,16 @@ impl ExpectedError {
                 log::error!("the host platform was unknown to nextest");
                 Some(err as &dyn Error)
             }
+            Self::TargetRunnerError { err } => {
+                if matches!(err, TargetRunnerError::IncompatibleRunnerArch { .. }) {
+                    log::error!(
+                        "{err}\n(hint: pass in --allow-runner-arch-mismatch to run tests anyway)"
+                    );
+                } else {
+                    log::error!("{err}");
+                }
+                err.source()
+            }
             Self::ArgumentFileReadError {
                 arg_name,
                 file_name,
@@ -523,6 +703,13 @@ impl ExpectedError {
                 );
                 Some(err as &dyn Error)
             }
+            Self::ArchiveInspectError { archive_file, err } => {
+                log::error!(
+                    "error reading archive `{}`",
+                    archive_file.if_supports_color(Stream::Stderr, |x| x.bold())
+                );
+                Some(err as &dyn Error)
+            }
             Self::RustBuildMetaParseError { err } => {
                 log::error!("error parsing Rust build metadata");
                 Some(err as &dyn Error)
@@ -573,7 +760,11 @@ impl ExpectedError {
                 );
                 Some(err as &dyn Error)
             }
-            Self::BuildFailed { command, exit_code } => {
+            Self::BuildFailed {
+                command,
+                exit_code,
+                error,
+            } => {
                 let with_code_str = match exit_code {
                     Some(code) => {
                         format!(
@@ -590,7 +781,7 @@ impl ExpectedError {
                     with_code_str,
                 );
 
-                None
+                error.as_ref().map(|error| error as &dyn Error)
             }
             Self::TestRunnerBuildError { err } => {
                 log::error!("failed to build test runner");
@@ -612,10 +803,193 @@ impl ExpectedError {
                 log::error!("test run failed");
                 None
             }
+            Self::PostRunScriptExecFailed { script, err } => {
+                log::error!(
+                    "failed to execute post-run script `{}`",
+                    script.if_supports_color(Stream::Stderr, |x| x.bold())
+                );
+                Some(err as &dyn Error)
+            }
+            Self::PostRunScriptFailed {
+                script,
+                exit_status,
+            } => {
+                log::error!(
+                    "post-run script `{}` failed with {}",
+                    script.if_supports_color(Stream::Stderr, |x| x.bold()),
+                    exit_status,
+                );
+                None
+            }
+            Self::ExtraArgConflictError { err } => {
+                log::error!("{err}");
+                None
+            }
+            Self::TestCountAssertionError { err } => {
+                log::error!("{err}");
+                None
+            }
+            Self::ForbidOnlyViolation { binary_ids } => {
+                log::error!(
+                    "--forbid-only: {} test {} listed no tests (is a focused test committed?):",
+                    binary_ids.len(),
+                    if binary_ids.len() == 1 {
+                        "binary"
+                    } else {
+                        "binaries"
+                    },
+                );
+                for binary_id in binary_ids {
+                    log::error!(
+                        target: "cargo_nextest::no_heading",
+                        "  - {}",
+                        binary_id.if_supports_color(Stream::Stderr, |x| x.bold())
+                    );
+                }
+                None
+            }
+            Self::TooManyFeatureCombinations {
+                feature_count,
+                depth,
+                limit,
+            } => {
+                log::error!(
+                    "combinations of up to {} out of {} features exceed the limit of {} \
+                     (hint: pass in a smaller value to --feature-combinations, or select fewer \
+                     packages with -p)",
+                    depth.if_supports_color(Stream::Stderr, |x| x.bold()),
+                    feature_count.if_supports_color(Stream::Stderr, |x| x.bold()),
+                    limit.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                None
+            }
+            Self::BisectTestNotFound { test_name } => {
+                log::error!(
+                    "--bisect-test: no test named {} is run (hint: check the filters passed in)",
+                    test_name.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                None
+            }
+            Self::BinaryPathOverrideError { err } => {
+                log::error!("{err}");
+                err.source()
+            }
+            Self::ArchiveOfflineIncompatibleError => {
+                log::error!(
+                    "--cargo-offline was passed in, but the reused build doesn't include Cargo \
+                     metadata, so it would have to be computed by running `cargo metadata` on \
+                     the current workspace\n\
+                     (hint: pass in --cargo-metadata, or use --archive-file instead)"
+                );
+                None
+            }
+            Self::AllPackagesIgnored { packages } => {
+                log::error!(
+                    "every package passed in with -p matches --workspace-ignore-path \
+                     (ignored: {})\n\
+                     (hint: check the paths passed in to --workspace-ignore-path)",
+                    packages.join(", "),
+                );
+                None
+            }
+            Self::DoctestsNotSupported => {
+                log::error!(
+                    "--only-doctest was passed in, but nextest doesn't run doctests\n\
+                     (hint: run doctests with `cargo test --doc` instead)"
+                );
+                None
+            }
+            Self::RunSummaryReadError { err } => {
+                log::error!("{}", err);
+                err.source()
+            }
+            Self::TimingDataReadError { err } => {
+                log::error!("{}", err);
+                err.source()
+            }
+            Self::TimingRegressionsFound { count } => {
+                log::error!(
+                    "--report-timing-regression: {} {} slower than the baseline by more than \
+                     --regression-threshold",
+                    count,
+                    if *count == 1 {
+                        "test was"
+                    } else {
+                        "tests were"
+                    },
+                );
+                None
+            }
+            Self::NoPreviousRun { profile, store_dir } => {
+                log::error!(
+                    "no previous run found for profile `{}` in `{}`\n\
+                     (hint: run `cargo nextest run` first)",
+                    profile.if_supports_color(Stream::Stderr, |x| x.bold()),
+                    store_dir.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                None
+            }
+            Self::OutputDirNotEmpty { output_dir } => {
+                log::error!(
+                    "output dir `{}` is not empty\n\
+                     (hint: pass in --clean-output-dir to delete its contents, or \
+                     --ignore-output-dir-exists to write to it anyway)",
+                    output_dir.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                None
+            }
+            Self::OutputDirCleanError { output_dir, err } => {
+                log::error!(
+                    "failed to clean output dir `{}`",
+                    output_dir.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                Some(err as &dyn Error)
+            }
+            Self::PidFileError { path, err } => {
+                let path = path.if_supports_color(Stream::Stderr, |x| x.bold());
+                if err.kind() == std::io::ErrorKind::AlreadyExists {
+                    log::error!(
+                        "PID file `{path}` already exists\n\
+                         (hint: another nextest run may be using it; if not, remove the file)"
+                    );
+                    None
+                } else {
+                    log::error!("failed to write PID file `{path}`");
+                    Some(err as &dyn Error)
+                }
+            }
+            Self::BisectTestAmbiguous {
+                test_name,
+                binary_ids,
+            } => {
+                log::error!(
+                    "--bisect-test: {} tests named {} are run (hint: select one binary with -E \
+                     'binary_id(..)'):",
+                    binary_ids.len(),
+                    test_name.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                for binary_id in binary_ids {
+                    log::error!(
+                        target: "cargo_nextest::no_heading",
+                        "  - {}",
+                        binary_id.if_supports_color(Stream::Stderr, |x| x.bold())
+                    );
+                }
+                None
+            }
             Self::ShowTestGroupsError { err } => {
                 log::error!("{err}");
                 err.source()
             }
+            #[cfg(feature = "remote-reporting")]
+            Self::RemoteReporterError { err } => {
+                log::error!("{err}");
+                err.source()
+            }
+            Self::PartitionError { err } => {
+                log::error!("{err}");
+                None
+            }
             #[cfg(feature = "self-update")]
             Self::UpdateVersionParseError { err } => {
                 log::error!("failed to parse --version");
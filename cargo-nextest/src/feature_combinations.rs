@@ -0,0 +1,188 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Running tests across combinations of Cargo features.
+//!
+//! This is similar to `cargo hack --feature-powerset --depth N`: for each combination of up to N
+//! features, test binaries are built with `--no-default-features --features <combination>` and
+//! tests are run against them.
+
+use crate::{ExpectedError, Result};
+use guppy::graph::{DependencyDirection, PackageGraph, PackageMetadata};
+use itertools::Itertools;
+use nextest_runner::runner::RunStats;
+use owo_colors::{OwoColorize, Stream};
+
+/// The maximum number of feature combinations that can be run at once.
+///
+/// The size of a powerset grows exponentially, so this guards against accidentally kicking off
+/// thousands of builds.
+pub(crate) const MAX_FEATURE_COMBINATIONS: usize = 256;
+
+/// Runs tests for each combination of features, and aggregates the results.
+#[derive(Debug)]
+pub(crate) struct FeatureCombinationRunner {
+    combinations: Vec<Vec<String>>,
+    results: Vec<(Vec<String>, RunStats)>,
+}
+
+impl FeatureCombinationRunner {
+    /// Creates a new runner for combinations of up to `depth` features, across the features of
+    /// `packages`.
+    ///
+    /// If `packages` is empty, all workspace members are used.
+    pub(crate) fn new(graph: &PackageGraph, packages: &[String], depth: usize) -> Result<Self> {
+        let members: Vec<PackageMetadata<'_>> = graph
+            .resolve_workspace()
+            .packages(DependencyDirection::Forward)
+            .filter(|package| packages.is_empty() || packages.iter().any(|p| p == package.name()))
+            .collect();
+
+        // Features of multiple packages must be qualified with the package name.
+        let qualify = members.len() > 1;
+        let features: Vec<String> = members
+            .iter()
+            .flat_map(|package| {
+                package
+                    .named_features()
+                    .filter(|feature| *feature != "default")
+                    .map(move |feature| {
+                        if qualify {
+                            format!("{}/{}", package.name(), feature)
+                        } else {
+                            feature.to_owned()
+                        }
+                    })
+            })
+            .sorted()
+            .dedup()
+            .collect();
+
+        let combinations = feature_powerset(&features, depth, MAX_FEATURE_COMBINATIONS)
+            .ok_or_else(|| ExpectedError::TooManyFeatureCombinations {
+                feature_count: features.len(),
+                depth,
+                limit: MAX_FEATURE_COMBINATIONS,
+            })?;
+
+        Ok(Self {
+            combinations,
+            results: Vec::new(),
+        })
+    }
+
+    /// Runs `run_one` for each feature combination in turn.
+    ///
+    /// `run_one` returns `None` if nothing was run (e.g. with `--no-run`). If a run is cancelled
+    /// partway through, the remaining combinations are skipped.
+    pub(crate) fn run(
+        &mut self,
+        mut run_one: impl FnMut(&[String]) -> Result<Option<RunStats>>,
+    ) -> Result<()> {
+        let total = self.combinations.len();
+        for (index, features) in self.combinations.iter().enumerate() {
+            log::info!(
+                "[{}/{}] running tests with features: {}",
+                index + 1,
+                total,
+                display_features(features).if_supports_color(Stream::Stderr, |x| x.bold()),
+            );
+            let run_stats = match run_one(features)? {
+                Some(run_stats) => run_stats,
+                None => continue,
+            };
+            let cancelled = run_stats.finished_count < run_stats.initial_run_count;
+            self.results.push((features.clone(), run_stats));
+            if cancelled {
+                log::warn!(
+                    "test run cancelled, skipping {} remaining feature combinations",
+                    total - index - 1
+                );
+                break;
+            }
+        }
+
+        self.finish()
+    }
+
+    fn finish(&self) -> Result<()> {
+        if self.results.is_empty() {
+            return Ok(());
+        }
+
+        let failed: Vec<_> = self
+            .results
+            .iter()
+            .filter(|(_, run_stats)| !run_stats.is_success())
+            .collect();
+        log::info!(
+            "{}/{} feature combinations passed",
+            self.results.len() - failed.len(),
+            self.combinations.len(),
+        );
+        for (features, _) in &failed {
+            log::error!(
+                "tests failed with features: {}",
+                display_features(features).if_supports_color(Stream::Stderr, |x| x.bold()),
+            );
+        }
+
+        if failed.is_empty() && self.results.len() == self.combinations.len() {
+            Ok(())
+        } else {
+            Err(ExpectedError::test_run_failed())
+        }
+    }
+}
+
+fn display_features(features: &[String]) -> String {
+    if features.is_empty() {
+        "(none)".to_owned()
+    } else {
+        features.join(",")
+    }
+}
+
+/// Returns all combinations of up to `depth` features, smallest first.
+///
+/// Returns `None` if there are more than `limit` combinations.
+fn feature_powerset(features: &[String], depth: usize, limit: usize) -> Option<Vec<Vec<String>>> {
+    let mut combinations = Vec::new();
+    for size in 0..=depth.min(features.len()) {
+        for combination in features.iter().cloned().combinations(size) {
+            if combinations.len() == limit {
+                return None;
+            }
+            combinations.push(combination);
+        }
+    }
+    Some(combinations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_powerset() {
+        let features: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+
+        let combinations = feature_powerset(&features, 1, 256).unwrap();
+        assert_eq!(
+            combinations,
+            vec![vec![], vec!["a"], vec!["b"], vec!["c"]],
+            "depth 1"
+        );
+
+        let combinations = feature_powerset(&features, 10, 256).unwrap();
+        assert_eq!(combinations.len(), 8, "depth larger than the feature count");
+        assert_eq!(combinations[7], vec!["a", "b", "c"]);
+
+        assert_eq!(feature_powerset(&features, 3, 7), None, "limit exceeded");
+        assert_eq!(
+            feature_powerset(&[], 2, 256),
+            Some(vec![vec![]]),
+            "no features"
+        );
+    }
+}
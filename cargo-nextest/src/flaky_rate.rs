@@ -0,0 +1,194 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Measuring how flaky tests are.
+//!
+//! With `--report-flaky-rate N`, each test is run `N` times in a row, and pass/fail counts are
+//! aggregated into per-test and overall flakiness rates.
+
+use crate::Result;
+use camino::Utf8Path;
+use nextest_runner::{errors::WriteEventError, reporter::TestEvent};
+use owo_colors::{OwoColorize, Stream};
+
+/// Aggregates attempt results into flakiness rates.
+#[derive(Debug)]
+pub(crate) struct FlakyRateReport {
+    runs_per_test: usize,
+    tests: Vec<TestFlakiness>,
+}
+
+#[derive(Debug)]
+struct TestFlakiness {
+    binary_id: String,
+    test_name: String,
+    attempts: usize,
+    failed_attempts: usize,
+}
+
+impl TestFlakiness {
+    fn flaky_rate(&self) -> f64 {
+        flaky_rate(self.failed_attempts, self.attempts)
+    }
+
+    fn classification(&self) -> Classification {
+        if self.failed_attempts == 0 {
+            Classification::Passing
+        } else if self.failed_attempts < self.attempts {
+            Classification::Flaky
+        } else {
+            Classification::Failing
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Classification {
+    /// `flaky_rate = 0`.
+    Passing,
+    /// `0 < flaky_rate < 1`.
+    Flaky,
+    /// `flaky_rate = 1`.
+    Failing,
+}
+
+impl Classification {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Passing => "passing",
+            Self::Flaky => "flaky",
+            Self::Failing => "failing",
+        }
+    }
+}
+
+impl FlakyRateReport {
+    /// Creates a new report for tests that are each run `runs_per_test` times.
+    pub(crate) fn new(runs_per_test: usize) -> Self {
+        Self {
+            runs_per_test,
+            tests: Vec::new(),
+        }
+    }
+
+    /// Records the attempts of a finished test.
+    pub(crate) fn record_event(&mut self, event: &TestEvent<'_>) {
+        if let TestEvent::TestFinished {
+            test_instance,
+            run_statuses,
+            ..
+        } = event
+        {
+            let attempts = run_statuses.iter().count();
+            let failed_attempts = run_statuses
+                .iter()
+                .filter(|status| !status.result.is_success())
+                .count();
+            self.tests.push(TestFlakiness {
+                binary_id: test_instance.suite_info.binary_id.to_string(),
+                test_name: test_instance.name.to_owned(),
+                attempts,
+                failed_attempts,
+            });
+        }
+    }
+
+    /// Prints a summary of flaky and failing tests, and writes the report to `output` as JSON.
+    pub(crate) fn finish(mut self, output: Option<&Utf8Path>) -> Result<()> {
+        self.tests
+            .sort_by(|a, b| (&a.binary_id, &a.test_name).cmp(&(&b.binary_id, &b.test_name)));
+
+        let attempts: usize = self.tests.iter().map(|test| test.attempts).sum();
+        let failed_attempts: usize = self.tests.iter().map(|test| test.failed_attempts).sum();
+        let count = |classification| {
+            self.tests
+                .iter()
+                .filter(|test| test.classification() == classification)
+                .count()
+        };
+
+        log::info!(
+            "flaky rate: {:.4} ({failed_attempts}/{attempts} attempts failed across {} tests, \
+             {} runs each): {} flaky, {} failing",
+            flaky_rate(failed_attempts, attempts),
+            self.tests.len(),
+            self.runs_per_test,
+            count(Classification::Flaky).if_supports_color(Stream::Stderr, |x| x.bold()),
+            count(Classification::Failing).if_supports_color(Stream::Stderr, |x| x.bold()),
+        );
+        for test in &self.tests {
+            let classification = test.classification();
+            if classification != Classification::Passing {
+                log::info!(
+                    target: "cargo_nextest::no_heading",
+                    "  {:>7} {}/{} {} {}",
+                    classification.as_str(),
+                    test.failed_attempts,
+                    test.attempts,
+                    test.binary_id.if_supports_color(Stream::Stderr, |x| x.bold()),
+                    test.test_name.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+            }
+        }
+
+        if let Some(output) = output {
+            let json = serde_json::json!({
+                "runs-per-test": self.runs_per_test,
+                "total-tests": self.tests.len(),
+                "attempts": attempts,
+                "failed-attempts": failed_attempts,
+                "flaky-rate": flaky_rate(failed_attempts, attempts),
+                "tests": self.tests.iter().map(|test| serde_json::json!({
+                    "binary-id": test.binary_id,
+                    "test-name": test.test_name,
+                    "attempts": test.attempts,
+                    "failed-attempts": test.failed_attempts,
+                    "flaky-rate": test.flaky_rate(),
+                    "status": test.classification().as_str(),
+                })).collect::<Vec<_>>(),
+            });
+            let contents = serde_json::to_vec_pretty(&json).expect("serializing JSON succeeds");
+            std::fs::write(output, contents).map_err(|error| WriteEventError::Fs {
+                file: output.to_owned(),
+                error,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `failed_attempts / attempts`, or 0 if there were no attempts.
+///
+/// If all tests ran to completion, `attempts` is `runs_per_test * total_tests`.
+fn flaky_rate(failed_attempts: usize, attempts: usize) -> f64 {
+    if attempts == 0 {
+        0.0
+    } else {
+        failed_attempts as f64 / attempts as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        let test = |failed_attempts| TestFlakiness {
+            binary_id: "crate".to_owned(),
+            test_name: "test".to_owned(),
+            attempts: 4,
+            failed_attempts,
+        };
+
+        assert_eq!(test(0).classification(), Classification::Passing);
+        assert_eq!(test(0).flaky_rate(), 0.0);
+        assert_eq!(test(1).classification(), Classification::Flaky);
+        assert_eq!(test(1).flaky_rate(), 0.25);
+        assert_eq!(test(3).classification(), Classification::Flaky);
+        assert_eq!(test(4).classification(), Classification::Failing);
+        assert_eq!(test(4).flaky_rate(), 1.0);
+        assert_eq!(flaky_rate(0, 0), 0.0, "no attempts");
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Periodic liveness lines, written with `--heartbeat`.
+//!
+//! CI systems often kill jobs that haven't produced output for a while. Heartbeats are written to
+//! standard error at a fixed interval for as long as nextest runs, including while tests are being
+//! built, so that long quiet phases aren't mistaken for hangs.
+
+use nextest_runner::reporter::StderrLineWriter;
+use std::{
+    io::Write,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// The prefix for heartbeat lines, so that they can be told apart from test output.
+const HEARTBEAT_PREFIX: &str = "[nextest heartbeat]";
+
+/// A background thread that writes heartbeat lines. The thread is stopped when this is dropped.
+#[derive(Debug)]
+pub(crate) struct Heartbeat {
+    stop_sender: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+    // While tests are running, heartbeats are written through the reporter so that they don't
+    // corrupt its progress bar.
+    line_writer: Arc<Mutex<Option<StderrLineWriter>>>,
+}
+
+impl Heartbeat {
+    /// Starts writing a heartbeat line to standard error every `interval`.
+    pub(crate) fn start(interval: Duration) -> Self {
+        Self::start_with_writer(interval, std::io::stderr)
+    }
+
+    fn start_with_writer<W: Write>(
+        interval: Duration,
+        make_writer: impl Fn() -> W + Send + 'static,
+    ) -> Self {
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+        let line_writer = Arc::new(Mutex::new(None::<StderrLineWriter>));
+        let thread_line_writer = line_writer.clone();
+        let start = Instant::now();
+        let handle = std::thread::spawn(move || {
+            // Sending a message or dropping the sender both stop the thread.
+            while let Err(RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(interval) {
+                let elapsed = Duration::from_secs(start.elapsed().as_secs());
+                let line = format!(
+                    "{HEARTBEAT_PREFIX} {} elapsed",
+                    humantime::format_duration(elapsed)
+                );
+                match &*thread_line_writer.lock().unwrap() {
+                    Some(line_writer) => line_writer.write_line(&line),
+                    // Heartbeats are best-effort, so write errors are ignored.
+                    None => _ = writeln!(make_writer(), "{line}"),
+                }
+            }
+        });
+
+        Self {
+            stop_sender: Some(stop_sender),
+            handle: Some(handle),
+            line_writer,
+        }
+    }
+
+    /// Writes heartbeats through `line_writer` until the returned guard is dropped.
+    pub(crate) fn set_line_writer(&self, line_writer: StderrLineWriter) -> LineWriterGuard<'_> {
+        *self.line_writer.lock().unwrap() = Some(line_writer);
+        LineWriterGuard { heartbeat: self }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        // Drop the sender first to wake the thread up, then wait for it so that no heartbeats are
+        // written after this point.
+        self.stop_sender.take();
+        if let Some(handle) = self.handle.take() {
+            _ = handle.join();
+        }
+    }
+}
+
+/// Returned by [`Heartbeat::set_line_writer`]. Heartbeats are written directly to standard error
+/// again once this is dropped.
+#[derive(Debug)]
+pub(crate) struct LineWriterGuard<'a> {
+    heartbeat: &'a Heartbeat,
+}
+
+impl Drop for LineWriterGuard<'_> {
+    fn drop(&mut self) {
+        // This releases the reporter's progress bar, which would otherwise be redrawn by later
+        // heartbeats.
+        self.heartbeat.line_writer.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let buf = SharedBuf::default();
+        let writer = buf.clone();
+        let heartbeat =
+            Heartbeat::start_with_writer(Duration::from_millis(10), move || writer.clone());
+        std::thread::sleep(Duration::from_millis(100));
+        drop(heartbeat);
+
+        let output = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert!(!lines.is_empty(), "at least one heartbeat was written");
+        for line in &lines {
+            assert!(
+                line.starts_with(HEARTBEAT_PREFIX) && line.ends_with(" elapsed"),
+                "heartbeat line is marked: {line}"
+            );
+        }
+
+        // No more heartbeats are written once the heartbeat is dropped.
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(buf.0.lock().unwrap().len(), output.len());
+    }
+}
@@ -13,12 +13,17 @@
 
 #![warn(missing_docs)]
 
+mod bisect;
 mod cargo_cli;
 mod dispatch;
 #[cfg(unix)]
 mod double_spawn;
 mod errors;
+mod feature_combinations;
+mod flaky_rate;
+mod heartbeat;
 mod output;
+mod pid_file;
 mod reuse_build;
 #[cfg(feature = "self-update")]
 mod update;
@@ -0,0 +1,76 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! PID files, written with `--test-pid-file`.
+//!
+//! The file contains the ID of the nextest process, and is removed when nextest exits normally. If
+//! nextest crashes or is killed, the file is left behind.
+
+use crate::{ExpectedError, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::{fs::OpenOptions, io::Write};
+
+/// A PID file containing the ID of this process. The file is removed when this is dropped.
+#[derive(Debug)]
+pub(crate) struct PidFile {
+    path: Utf8PathBuf,
+}
+
+impl PidFile {
+    /// Creates a PID file at `path`, failing if the file already exists.
+    pub(crate) fn create(path: &Utf8Path) -> Result<Self> {
+        let pid_file_error = |err| ExpectedError::PidFileError {
+            path: path.to_owned(),
+            err,
+        };
+
+        // create_new opens the file with O_CREAT | O_EXCL, so concurrent runs can't both claim it.
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(pid_file_error)?;
+        // Construct this before writing, so that the file is removed if the write fails.
+        let pid_file = Self {
+            path: path.to_owned(),
+        };
+        writeln!(file, "{}", std::process::id()).map_err(pid_file_error)?;
+        Ok(pid_file)
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_file(&self.path) {
+            log::warn!("failed to remove PID file `{}`: {error}", self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Utf8PathBuf::try_from(dir.path().join("nextest.pid")).unwrap();
+
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", std::process::id())
+        );
+
+        match PidFile::create(&path) {
+            Err(ExpectedError::PidFileError { err, .. }) => {
+                assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists)
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(path.exists(), "existing PID file is left alone");
+
+        drop(pid_file);
+        assert!(!path.exists(), "PID file is removed on drop");
+    }
+}
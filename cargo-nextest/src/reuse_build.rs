@@ -72,6 +72,11 @@ pub(crate) struct ReuseBuildOpts {
     #[arg(long, requires = "cargo-metadata-sources", value_name = "PATH")]
     pub(crate) workspace_remap: Option<Utf8PathBuf>,
 
+    /// Override the workspace root used for config lookup, test binary paths and working
+    /// directories
+    #[arg(long, conflicts_with = "workspace_remap", value_name = "PATH")]
+    pub(crate) workspace_root_override: Option<Utf8PathBuf>,
+
     /// Path to binaries-metadata JSON
     #[arg(
         long,
@@ -91,6 +96,10 @@ pub(crate) struct ReuseBuildOpts {
         value_name = "PATH"
     )]
     pub(crate) target_dir_remap: Option<Utf8PathBuf>,
+
+    /// Warn rather than fail if a target runner is built for a different architecture than tests
+    #[arg(long)]
+    pub(crate) allow_runner_arch_mismatch: bool,
 }
 
 impl ReuseBuildOpts {
@@ -128,7 +137,7 @@ impl ReuseBuildOpts {
             }
 
             let mut writer = output_writer.stderr_writer();
-            return ReuseBuildInfo::extract_archive(
+            let mut info = ReuseBuildInfo::extract_archive(
                 archive_file,
                 format,
                 dest,
@@ -141,7 +150,11 @@ impl ReuseBuildOpts {
             .map_err(|err| ExpectedError::ArchiveExtractError {
                 archive_file: archive_file.clone(),
                 err: Box::new(err),
-            });
+            })?;
+            if let Some(workspace_root) = &self.workspace_root_override {
+                info.set_workspace_root_override(workspace_root.clone());
+            }
+            return Ok(info);
         }
 
         let cargo_metadata = self.cargo_metadata.as_ref().map(|path| MetadataWithRemap {
@@ -157,7 +170,11 @@ impl ReuseBuildOpts {
                 remap: self.target_dir_remap.clone(),
             });
 
-        Ok(ReuseBuildInfo::new(cargo_metadata, binaries_metadata))
+        let mut info = ReuseBuildInfo::new(cargo_metadata, binaries_metadata);
+        if let Some(workspace_root) = &self.workspace_root_override {
+            info.set_workspace_root_override(workspace_root.clone());
+        }
+        Ok(info)
     }
 }
 
@@ -193,15 +210,19 @@ pub(crate) fn make_path_mapper(
     graph: &PackageGraph,
     orig_target_dir: &Utf8Path,
 ) -> Result<PathMapper> {
+    let (workspace_remap, workspace_arg_name) = match info.workspace_root_override() {
+        Some(workspace_root) => (Some(workspace_root), "workspace-root-override"),
+        None => (info.workspace_remap(), "workspace-remap"),
+    };
     PathMapper::new(
         graph.workspace().root(),
-        info.workspace_remap(),
+        workspace_remap,
         orig_target_dir,
         info.target_dir_remap(),
     )
     .map_err(|err| {
         let arg_name = match err.kind() {
-            PathMapperConstructKind::WorkspaceRoot => "workspace-remap",
+            PathMapperConstructKind::WorkspaceRoot => workspace_arg_name,
             PathMapperConstructKind::TargetDir => "target-dir-remap",
         };
         ExpectedError::PathMapperConstructError { arg_name, err }
@@ -1,13 +1,16 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{output::OutputContext, ExpectedError, Result};
+use crate::{dispatch::current_version, output::OutputContext, ExpectedError, Result};
 use camino::Utf8PathBuf;
 use nextest_metadata::NextestExitCode;
-use nextest_runner::update::{CheckStatus, MuktiBackend, UpdateVersion};
+use nextest_runner::update::{CheckStatus, MuktiBackend, UpdateVersion, VersionCheckCache};
 use owo_colors::{OwoColorize, Stream};
 use semver::Version;
-use std::cmp::Ordering;
+use std::{cmp::Ordering, thread::JoinHandle, time::SystemTime};
+
+/// The default URL to download release metadata from.
+const RELEASES_URL: &str = "https://get.nexte.st/releases.json";
 
 /// Perform an update.
 pub(crate) fn perform_update(
@@ -21,8 +24,7 @@ pub(crate) fn perform_update(
     let version = version
         .parse::<UpdateVersion>()
         .map_err(|err| ExpectedError::UpdateVersionParseError { err })?;
-    let releases_url =
-        releases_url.unwrap_or_else(|| "https://get.nexte.st/releases.json".to_owned());
+    let releases_url = releases_url.unwrap_or_else(|| RELEASES_URL.to_owned());
 
     // Configure the backend.
     let backend = MuktiBackend {
@@ -30,9 +32,7 @@ pub(crate) fn perform_update(
         package_name: "cargo-nextest".to_owned(),
     };
 
-    let current_version: Version = env!("CARGO_PKG_VERSION")
-        .parse()
-        .expect("cargo-nextest uses semantic versioning");
+    let current_version = current_version();
 
     let releases = backend.fetch_releases(current_version.clone())?;
 
@@ -110,3 +110,83 @@ pub(crate) fn perform_update(
         }
     }
 }
+
+/// A check for newer versions of nextest that runs in the background.
+///
+/// Used by `cargo nextest run --check-for-updates`. The check never delays a test run: if the
+/// latest version isn't known by the time [`Self::report`] is called, nothing is reported.
+#[derive(Debug)]
+pub(crate) struct BackgroundUpdateCheck {
+    current_version: Version,
+    cached: Option<Version>,
+    handle: Option<JoinHandle<Option<Version>>>,
+}
+
+impl BackgroundUpdateCheck {
+    /// Starts checking for updates.
+    ///
+    /// If a check was performed within the last 24 hours, its result is reused. Otherwise, the
+    /// latest version is fetched on a separate thread and cached for later runs.
+    pub(crate) fn start() -> Self {
+        let cache = VersionCheckCache::in_data_dir();
+        let cached = cache
+            .as_ref()
+            .and_then(|cache| cache.read(SystemTime::now()));
+        let handle = match (&cached, cache) {
+            (None, Some(cache)) => Some(std::thread::spawn(move || fetch_and_cache(&cache))),
+            // Either the cache is fresh, or there's nowhere to cache results.
+            (Some(_), _) | (None, None) => None,
+        };
+
+        Self {
+            current_version: current_version(),
+            cached,
+            handle,
+        }
+    }
+
+    /// Prints a warning if a newer version of nextest is known to be available.
+    pub(crate) fn report(self) {
+        let latest = match (self.cached, self.handle) {
+            (Some(latest), _) => Some(latest),
+            (None, Some(handle)) if handle.is_finished() => handle.join().ok().flatten(),
+            // The check is still in progress: don't wait for it. The thread is detached, so it
+            // may still finish and cache its result while tests are running.
+            (None, _) => None,
+        };
+
+        match latest {
+            Some(latest) if latest > self.current_version => {
+                log::warn!(
+                    "nextest {} is available, you have {} (run `cargo nextest self update` to update)",
+                    latest.if_supports_color(Stream::Stderr, |s| s.bold()),
+                    self.current_version
+                        .if_supports_color(Stream::Stderr, |s| s.bold()),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+fn fetch_and_cache(cache: &VersionCheckCache) -> Option<Version> {
+    let backend = MuktiBackend {
+        url: RELEASES_URL.to_owned(),
+        package_name: "cargo-nextest".to_owned(),
+    };
+    match backend.fetch_latest_version() {
+        Ok(latest) => {
+            if let Err(error) = cache.write(&latest, SystemTime::now()) {
+                log::debug!(
+                    "failed to write version check cache to `{}`: {error}",
+                    cache.path()
+                );
+            }
+            Some(latest)
+        }
+        Err(error) => {
+            log::debug!("failed to check for updates: {error}");
+            None
+        }
+    }
+}
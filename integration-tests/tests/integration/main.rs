@@ -367,6 +367,20 @@ fn test_run_from_archive() {
         ])
         .output();
 
+    let output = CargoNextestCli::new()
+        .args([
+            "archive",
+            "inspect",
+            "--archive-file",
+            archive_file.as_str(),
+        ])
+        .output();
+    let stdout = output.stdout_as_str();
+    assert!(
+        stdout.contains("debug info: included\nsources: not included\n"),
+        "archive inspect reports debug info and sources\n{output}"
+    );
+
     // Remove the old source and target directories to ensure that any tests that refer to files within
     // it fail.
     std::fs::remove_dir_all(p.workspace_root()).unwrap();
@@ -135,6 +135,10 @@ pub struct TestListSummary {
     /// A map of Rust test suites to the test binaries within them, keyed by a unique identifier
     /// for each test suite.
     pub rust_suites: BTreeMap<RustBinaryId, RustTestSuiteSummary>,
+
+    /// Packages whose tests weren't built, along with the reason why.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_packages: Vec<SkippedPackageSummary>,
 }
 
 impl TestListSummary {
@@ -144,6 +148,7 @@ impl TestListSummary {
             rust_build_meta,
             test_count: 0,
             rust_suites: BTreeMap::new(),
+            skipped_packages: Vec::new(),
         }
     }
     /// Parse JSON output from `cargo nextest list --message-format json`.
@@ -257,6 +262,10 @@ pub struct BinaryListSummary {
 
     /// The list of Rust test binaries (indexed by binary-id).
     pub rust_binaries: BTreeMap<RustBinaryId, RustTestBinarySummary>,
+
+    /// Packages whose tests weren't built, along with the reason why.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_packages: Vec<SkippedPackageSummary>,
 }
 
 // IMPLEMENTATION NOTE: SmolStr is *not* part of the public API.
@@ -423,6 +432,37 @@ pub struct RustBuildMetaSummary {
     /// which used to generate this.
     #[serde(default)]
     pub target_platform: Option<String>,
+
+    /// Whether debug info was stripped from binaries while archiving them.
+    ///
+    /// This is only true for binaries metadata within archives created with
+    /// `--strip-debug-info`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub debug_info_stripped: bool,
+}
+
+/// A package whose tests weren't built.
+///
+/// Part of a [`TestListSummary`] or [`BinaryListSummary`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum SkippedPackageSummary {
+    /// The package's build script failed, and `--ignore-build-script-failures` was passed in.
+    BuildScriptFailed(BuildScriptFailure),
+}
+
+/// A package whose build script failed.
+///
+/// Part of a [`SkippedPackageSummary`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildScriptFailure {
+    /// The name of the package.
+    pub package: String,
+
+    /// The error reported by Cargo for the build script.
+    pub error: String,
 }
 
 /// A non-test Rust binary. Used to set the correct environment
@@ -602,6 +642,15 @@ pub enum MismatchReason {
 
     /// This test is in a different partition.
     Partition,
+
+    /// This test is not part of the current bisect step.
+    Bisect,
+
+    /// This test wasn't run because its test binary exceeded its timeout.
+    BinaryTimeout,
+
+    /// This test wasn't run because its test binary reached its failure limit.
+    BinaryFailureLimit,
 }
 
 impl fmt::Display for MismatchReason {
@@ -613,6 +662,16 @@ impl fmt::Display for MismatchReason {
                 write!(f, "does not match the provided expression filters")
             }
             MismatchReason::Partition => write!(f, "is in a different partition"),
+            MismatchReason::Bisect => write!(f, "is not part of the current bisect step"),
+            MismatchReason::BinaryTimeout => {
+                write!(f, "was not run because its test binary timed out")
+            }
+            MismatchReason::BinaryFailureLimit => {
+                write!(
+                    f,
+                    "was not run because its test binary reached its failure limit"
+                )
+            }
         }
     }
 }
@@ -634,6 +693,7 @@ mod tests {
         linked_paths: BTreeSet::new(),
         target_platform: None,
         target_platforms: vec![],
+        debug_info_stripped: false,
     }; "no target platform")]
     #[test_case(r#"{
         "target-directory": "/foo",
@@ -648,6 +708,7 @@ mod tests {
         linked_paths: BTreeSet::new(),
         target_platform: Some("x86_64-unknown-linux-gnu".to_owned()),
         target_platforms: vec![],
+        debug_info_stripped: false,
     }; "single target platform specified")]
     fn test_deserialize_old_rust_build_meta(input: &str, expected: RustBuildMetaSummary) {
         let build_meta: RustBuildMetaSummary =
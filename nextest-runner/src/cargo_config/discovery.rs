@@ -162,6 +162,8 @@ fn parse_cli_config(config_str: &str) -> Result<CargoConfig, CargoConfigError> {
 
     let ok = {
         let mut got_to_value = false;
+        // Cargo has no top-level config values, so the key must have at least two parts.
+        let mut has_dot = false;
         let mut table = doc.as_table();
         let mut is_root = true;
         while table.is_dotted() || is_root {
@@ -180,6 +182,7 @@ fn parse_cli_config(config_str: &str) -> Result<CargoConfig, CargoConfigError> {
                             reason: InvalidCargoCliConfigReason::IncludesNonWhitespaceDecoration,
                         })?;
                     }
+                    has_dot = true;
                     table = nt;
                 }
                 Item::Value(v) if v.is_inline_table() => {
@@ -195,7 +198,7 @@ fn parse_cli_config(config_str: &str) -> Result<CargoConfig, CargoConfigError> {
                             reason: InvalidCargoCliConfigReason::IncludesNonWhitespaceDecoration,
                         })?;
                     }
-                    got_to_value = true;
+                    got_to_value = has_dot;
                     break;
                 }
                 Item::ArrayOfTables(_) => {
@@ -373,12 +376,16 @@ pub(crate) struct CargoConfig {
 #[derive(Deserialize, Default, Debug)]
 pub(crate) struct CargoConfigBuild {
     pub(crate) target: Option<String>,
+    #[serde(default)]
+    pub(crate) rustflags: Option<Rustflags>,
 }
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct CargoConfigRunner {
     #[serde(default)]
     pub(crate) runner: Option<Runner>,
+    #[serde(default)]
+    pub(crate) rustflags: Option<Rustflags>,
 }
 
 #[derive(Clone, Deserialize, Debug, Eq, PartialEq)]
@@ -388,6 +395,23 @@ pub(crate) enum Runner {
     List(Vec<String>),
 }
 
+#[derive(Clone, Deserialize, Debug, Eq, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum Rustflags {
+    Simple(String),
+    List(Vec<String>),
+}
+
+impl Rustflags {
+    /// Returns the individual flags. As with Cargo, a string is split on whitespace.
+    pub(crate) fn to_vec(&self) -> Vec<String> {
+        match self {
+            Self::Simple(flags) => flags.split_whitespace().map(str::to_owned).collect(),
+            Self::List(flags) => flags.clone(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -424,6 +448,11 @@ mod tests {
         InvalidCargoCliConfigReason::NotDottedKv
 
         ; "empty input")]
+    #[test_case(
+        "jobs = 2",
+        InvalidCargoCliConfigReason::NotDottedKv
+
+        ; "no top-level values")]
     #[test_case(
         "a.b={c = \"d\"}",
         InvalidCargoCliConfigReason::SetsValueToInlineTable
@@ -71,6 +71,11 @@ impl EnvironmentMap {
         Self { map }
     }
 
+    /// Returns the names of the environment variables in this map.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.map.values().map(|var| var.name.as_str())
+    }
+
     #[cfg(test)]
     pub(crate) fn empty() -> Self {
         Self {
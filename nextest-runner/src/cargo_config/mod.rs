@@ -8,6 +8,7 @@
 
 mod discovery;
 mod env;
+mod rustflags;
 mod target_triple;
 #[cfg(test)]
 mod test_helpers;
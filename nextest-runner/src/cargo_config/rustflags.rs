@@ -0,0 +1,163 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::cargo_config::{CargoConfig, CargoConfigs, DiscoveredConfig, Rustflags};
+use target_spec::Platform;
+
+impl CargoConfigs {
+    /// Returns the extra flags that Cargo would pass to rustc for `target`, based on Cargo
+    /// configuration alone.
+    ///
+    /// This looks at, in order:
+    ///
+    /// 1. `target.<triple>.rustflags` and matching `target.'cfg(..)'.rustflags`, along with the
+    ///    `CARGO_TARGET_<TRIPLE>_RUSTFLAGS` env var
+    /// 2. `build.rustflags`, along with the `CARGO_BUILD_RUSTFLAGS` env var
+    ///
+    /// Within each of these, the first config that specifies flags is used. The `RUSTFLAGS` and
+    /// `CARGO_ENCODED_RUSTFLAGS` env vars, which take precedence over all of these, are not
+    /// considered.
+    pub fn rustflags(&self, target: &Platform) -> Vec<String> {
+        let target_env_key = format!(
+            "CARGO_TARGET_{}_RUSTFLAGS",
+            target.triple_str().to_ascii_uppercase().replace('-', "_")
+        );
+        if let Some(flags) = self.find_rustflags(&target_env_key, |config| {
+            Self::target_rustflags(config, target)
+        }) {
+            return flags;
+        }
+
+        self.find_rustflags("CARGO_BUILD_RUSTFLAGS", |config| {
+            config.build.rustflags.as_ref().map(Rustflags::to_vec)
+        })
+        .unwrap_or_default()
+    }
+
+    fn find_rustflags(
+        &self,
+        env_key: &str,
+        from_config: impl Fn(&CargoConfig) -> Option<Vec<String>>,
+    ) -> Option<Vec<String>> {
+        self.discovered_configs()
+            .find_map(|discovered_config| match discovered_config {
+                DiscoveredConfig::CliOption { config, .. }
+                | DiscoveredConfig::File { config, .. } => from_config(config),
+                DiscoveredConfig::Env => std::env::var(env_key)
+                    .ok()
+                    .map(|flags| Rustflags::Simple(flags).to_vec()),
+            })
+    }
+
+    fn target_rustflags(config: &CargoConfig, target: &Platform) -> Option<Vec<String>> {
+        let targets = config.target.as_ref()?;
+
+        // Like Cargo, combine the flags for the exact triple with those from any matching
+        // target.'cfg(..)' tables.
+        let triple_flags = targets
+            .get(target.triple_str())
+            .and_then(|table| table.rustflags.as_ref());
+        let cfg_flags = targets
+            .iter()
+            .filter_map(|(key, table)| match &table.rustflags {
+                Some(rustflags) if key.starts_with("cfg(") => {
+                    // Treat invalid expressions as not matching, as with target runners.
+                    let expr = target_spec::TargetExpression::new(key).ok()?;
+                    (expr.eval(target) == Some(true)).then_some(rustflags)
+                }
+                _ => None,
+            });
+
+        let mut found = false;
+        let mut flags = Vec::new();
+        for rustflags in triple_flags.into_iter().chain(cfg_flags) {
+            found = true;
+            flags.extend(rustflags.to_vec());
+        }
+        found.then_some(flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cargo_config::test_helpers::setup_temp_dir;
+    use camino::Utf8PathBuf;
+    use target_spec::TargetFeatures;
+
+    #[test]
+    fn test_rustflags() {
+        let dir = setup_temp_dir().unwrap();
+        let dir_path = Utf8PathBuf::try_from(dir.path().canonicalize().unwrap()).unwrap();
+        let dir_foo_bar_path = dir_path.join("foo/bar");
+
+        let linux = platform("x86_64-unknown-linux-gnu");
+        let windows = platform("x86_64-pc-windows-msvc");
+
+        let rustflags = |cli_configs: &[&str], target: &Platform| {
+            CargoConfigs::new_with_isolation(cli_configs, &dir_foo_bar_path, &dir_path)
+                .unwrap()
+                .rustflags(target)
+        };
+
+        assert_eq!(rustflags(&[], &linux), Vec::<String>::new());
+        assert_eq!(
+            rustflags(
+                &["build.rustflags=\"--cfg foo  -Ctarget-cpu=native\""],
+                &linux
+            ),
+            ["--cfg", "foo", "-Ctarget-cpu=native"],
+        );
+        assert_eq!(
+            rustflags(&["build.rustflags=[\"--cfg\", \"foo bar\"]"], &linux),
+            ["--cfg", "foo bar"],
+        );
+
+        // --config arguments are followed left to right.
+        assert_eq!(
+            rustflags(
+                &[
+                    "build.rustflags=[\"--cfg=a\"]",
+                    "build.rustflags=[\"--cfg=b\"]"
+                ],
+                &linux
+            ),
+            ["--cfg=a"],
+        );
+
+        // Target-specific flags take precedence over build.rustflags, and flags for the triple
+        // are combined with matching cfg expressions.
+        let config_path = dir_path.join("rustflags.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+            [build]
+            rustflags = ["--cfg=build"]
+
+            [target.x86_64-unknown-linux-gnu]
+            rustflags = ["--cfg=triple"]
+
+            [target.'cfg(unix)']
+            rustflags = ["--cfg=unix"]
+
+            [target.'cfg(target_arch = "x86_64")']
+            rustflags = ["--cfg=x86_64"]
+            "#,
+        )
+        .unwrap();
+        let cli_configs = [config_path.as_str()];
+        assert_eq!(
+            rustflags(&cli_configs, &linux),
+            ["--cfg=triple", "--cfg=x86_64", "--cfg=unix"],
+        );
+        assert_eq!(rustflags(&cli_configs, &windows), ["--cfg=x86_64"]);
+        assert_eq!(
+            rustflags(&cli_configs, &platform("aarch64-pc-windows-msvc")),
+            ["--cfg=build"],
+        );
+    }
+
+    fn platform(triple: &'static str) -> Platform {
+        Platform::new(triple.to_owned(), TargetFeatures::Unknown).expect("triple should be valid")
+    }
+}
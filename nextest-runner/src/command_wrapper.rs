@@ -0,0 +1,82 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Wrapping test processes in another command, for `--test-wrapper`.
+
+use crate::errors::CommandWrapperParseError;
+use std::{fmt, str::FromStr};
+
+/// A command that each test process is run under, e.g. `taskset -c 0-3` or `valgrind`.
+///
+/// The wrapper is passed the command that would otherwise have been run as its trailing
+/// arguments: the test binary and its arguments, preceded by the
+/// [target runner](crate::target_runner::TargetRunner) if there is one. Unlike target runners,
+/// wrappers only apply while running tests, not while listing them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommandWrapper {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandWrapper {
+    /// Creates a new wrapper that runs `program` with `args`, followed by the wrapped command.
+    pub fn new(program: impl Into<String>, args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            program: program.into(),
+            args: args.into_iter().collect(),
+        }
+    }
+
+    /// Returns the program that's run.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Returns the arguments passed to the program before the wrapped command.
+    pub fn args(&self) -> impl Iterator<Item = &str> {
+        self.args.iter().map(String::as_str)
+    }
+}
+
+impl FromStr for CommandWrapper {
+    type Err = CommandWrapperParseError;
+
+    /// Parses a wrapper from a command line, split into words with shell quoting rules.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = shell_words::split(s)
+            .map_err(|error| CommandWrapperParseError::new(s, error.to_string()))?
+            .into_iter();
+        match words.next() {
+            Some(program) => Ok(Self::new(program, words)),
+            None => Err(CommandWrapperParseError::new(s, "command is empty")),
+        }
+    }
+}
+
+impl fmt::Display for CommandWrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words = std::iter::once(self.program.as_str()).chain(self.args());
+        write!(f, "{}", shell_words::join(words))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command_wrapper() {
+        let wrapper: CommandWrapper = "taskset -c 0-3".parse().unwrap();
+        assert_eq!(wrapper.program(), "taskset");
+        assert_eq!(wrapper.args().collect::<Vec<_>>(), ["-c", "0-3"]);
+        assert_eq!(wrapper.to_string(), "taskset -c 0-3");
+
+        let wrapper: CommandWrapper = "'/opt/my tools/wrap' --log 'a b'".parse().unwrap();
+        assert_eq!(wrapper.program(), "/opt/my tools/wrap");
+        assert_eq!(wrapper.args().collect::<Vec<_>>(), ["--log", "a b"]);
+
+        "".parse::<CommandWrapper>().unwrap_err();
+        "   ".parse::<CommandWrapper>().unwrap_err();
+        "valgrind 'unclosed".parse::<CommandWrapper>().unwrap_err();
+    }
+}
@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    CompiledOverride, CompiledOverridesByProfile, CustomTestGroup, DeserializedOverride,
-    RetryPolicy, SettingSource, SlowTimeout, TestGroup, TestGroupConfig, TestSettings, TestThreads,
-    ThreadsRequired, ToolConfigFile,
+    min_version::NextestSection, unknown_keys::suggest_key, CompiledOverride,
+    CompiledOverridesByProfile, CustomTestGroup, DeserializedOverride, JunitHostname,
+    JunitPathRewrite, ProfileSelection, RetryFilter, RetryPolicy, SettingSource, SlowTimeout,
+    TestGroup, TestGroupConfig, TestSettings, TestThreads, ThreadsRequired, ToolConfigFile,
 };
 use crate::{
     errors::{
@@ -15,13 +16,15 @@ use crate::{
     reporter::{FinalStatusLevel, StatusLevel, TestOutputDisplay},
 };
 use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, FixedOffset, SecondsFormat};
 use config::{builder::DefaultState, Config, ConfigBuilder, File, FileFormat, FileSourceFile};
 use guppy::graph::PackageGraph;
 use nextest_filtering::TestQuery;
 use once_cell::sync::Lazy;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
+    num::NonZeroUsize,
     time::Duration,
 };
 
@@ -101,16 +104,20 @@ impl NextestConfig {
             config_file,
             tool_config_files.into_iter(),
             |config_file, tool, unknown| {
+                let describe = |key: &str| match suggest_key(key) {
+                    Some(suggestion) => format!("{key} (did you mean `{suggestion}`?)"),
+                    None => key.to_owned(),
+                };
                 let mut unknown_str = String::new();
                 if unknown.len() == 1 {
                     // Print this on the same line.
                     unknown_str.push(' ');
-                    unknown_str.push_str(unknown.iter().next().unwrap());
+                    unknown_str.push_str(&describe(unknown.iter().next().unwrap()));
                 } else {
                     for ignored_key in unknown {
                         unknown_str.push('\n');
                         unknown_str.push_str("  - ");
-                        unknown_str.push_str(ignored_key);
+                        unknown_str.push_str(&describe(ignored_key));
                     }
                 }
 
@@ -123,7 +130,7 @@ impl NextestConfig {
     }
 
     // A custom unknown_callback can be passed in while testing.
-    fn from_sources_impl<'a, I>(
+    pub(super) fn from_sources_impl<'a, I>(
         workspace_root: impl Into<Utf8PathBuf>,
         graph: &PackageGraph,
         config_file: Option<&Utf8Path>,
@@ -191,6 +198,15 @@ impl NextestConfig {
         self.make_profile(name.as_ref())
     }
 
+    /// Returns how a profile is selected if one isn't specified explicitly.
+    pub fn profile_selection(&self) -> ProfileSelection {
+        self.inner.profile_selection
+    }
+
+    pub(super) fn has_profile(&self, name: &str) -> bool {
+        self.inner.has_profile(name)
+    }
+
     // ---
     // Helper methods
     // ---
@@ -419,6 +435,8 @@ impl NextestConfig {
             .collect();
 
         Ok(NextestProfile {
+            name: name.to_owned(),
+            workspace_root: &self.workspace_root,
             store_dir,
             default_profile: &self.inner.default_profile,
             custom_profile,
@@ -463,6 +481,8 @@ pub struct FinalConfig {
 /// Returned by [`NextestConfig::profile`].
 #[derive(Clone, Debug)]
 pub struct NextestProfile<'cfg, State = FinalConfig> {
+    name: String,
+    workspace_root: &'cfg Utf8Path,
     store_dir: Utf8PathBuf,
     default_profile: &'cfg DefaultProfileImpl,
     custom_profile: Option<&'cfg CustomProfileImpl>,
@@ -471,6 +491,16 @@ pub struct NextestProfile<'cfg, State = FinalConfig> {
 }
 
 impl<'cfg, State> NextestProfile<'cfg, State> {
+    /// Returns the name of this profile.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the workspace root that relative paths in this profile are resolved against.
+    pub fn workspace_root(&self) -> &'cfg Utf8Path {
+        self.workspace_root
+    }
+
     /// Returns the absolute profile-specific store directory.
     pub fn store_dir(&self) -> &Utf8Path {
         &self.store_dir
@@ -481,6 +511,16 @@ impl<'cfg, State> NextestProfile<'cfg, State> {
         self.test_groups
     }
 
+    /// Returns the number of jobs Cargo should use to build tests, if any.
+    ///
+    /// Unlike most other settings, this is available before build platforms are applied, since
+    /// it's needed to build tests in the first place.
+    pub fn build_jobs(&self) -> Option<NonZeroUsize> {
+        self.custom_profile
+            .and_then(|profile| profile.build_jobs)
+            .or(self.default_profile.build_jobs)
+    }
+
     #[allow(dead_code)]
     pub(super) fn custom_profile(&self) -> Option<&'cfg CustomProfileImpl> {
         self.custom_profile
@@ -499,6 +539,8 @@ impl<'cfg> NextestProfile<'cfg, PreBuildPlatform> {
             .map(|override_| override_.apply_build_platforms(build_platforms))
             .collect();
         NextestProfile {
+            name: self.name,
+            workspace_root: self.workspace_root,
             store_dir: self.store_dir,
             default_profile: self.default_profile,
             custom_profile: self.custom_profile,
@@ -545,6 +587,45 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
             .unwrap_or(self.default_profile.leak_timeout)
     }
 
+    /// Returns the time below which passing tests are reported as suspiciously fast, if any.
+    pub fn min_run_time(&self) -> Option<Duration> {
+        self.custom_profile
+            .and_then(|profile| profile.min_run_time)
+            .or(self.default_profile.min_run_time)
+    }
+
+    /// Returns the number of failures after which no more tests are run from a test binary, if
+    /// any.
+    pub fn failure_limit_per_binary(&self) -> Option<NonZeroUsize> {
+        self.custom_profile
+            .and_then(|profile| profile.failure_limit_per_binary)
+            .or(self.default_profile.failure_limit_per_binary)
+    }
+
+    /// Returns which failed tests are retried.
+    pub fn retry_filter(&self) -> RetryFilter {
+        self.custom_profile
+            .and_then(|profile| profile.retry_only_exit_codes.as_ref())
+            .or(self.default_profile.retry_only_exit_codes.as_ref())
+            .map_or(RetryFilter::All, |codes| {
+                RetryFilter::ExitCodes(codes.clone())
+            })
+    }
+
+    /// Returns the total time after which no more tests are run from a test binary, if any.
+    pub fn test_binary_timeout(&self) -> Option<Duration> {
+        self.custom_profile
+            .and_then(|profile| profile.test_binary_timeout)
+            .or(self.default_profile.test_binary_timeout)
+    }
+
+    /// Returns the stack size for threads that run tests, in bytes, if any.
+    pub fn test_thread_stack_size(&self) -> Option<u64> {
+        self.custom_profile
+            .and_then(|profile| profile.test_thread_stack_size)
+            .or(self.default_profile.test_thread_stack_size)
+    }
+
     /// Returns the test status level.
     pub fn status_level(&self) -> StatusLevel {
         self.custom_profile
@@ -580,6 +661,13 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
             .unwrap_or(self.default_profile.fail_fast)
     }
 
+    /// Returns true if tests that pass but write to standard error should be marked as failed.
+    pub fn fail_on_stderr(&self) -> bool {
+        self.custom_profile
+            .and_then(|profile| profile.fail_on_stderr)
+            .unwrap_or(self.default_profile.fail_on_stderr)
+    }
+
     /// Returns settings for individual tests.
     pub fn settings_for(&self, query: &TestQuery<'_>) -> TestSettings {
         TestSettings::new(self, query)
@@ -615,11 +703,74 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
                 .custom_profile
                 .and_then(|profile| profile.junit.store_failure_output)
                 .unwrap_or(self.default_profile.junit.store_failure_output);
+            let suitename_style = self
+                .custom_profile
+                .and_then(|profile| profile.junit.suitename_style)
+                .unwrap_or(self.default_profile.junit.suitename_style);
+            let classname_style = self
+                .custom_profile
+                .and_then(|profile| profile.junit.classname_style)
+                .unwrap_or(self.default_profile.junit.classname_style);
+            let failure_format = self
+                .custom_profile
+                .and_then(|profile| profile.junit.failure_format)
+                .unwrap_or(self.default_profile.junit.failure_format);
+            let nest_packages = self
+                .custom_profile
+                .and_then(|profile| profile.junit.nest_packages)
+                .unwrap_or(self.default_profile.junit.nest_packages);
+            let timestamp_format = self
+                .custom_profile
+                .and_then(|profile| profile.junit.timestamp_format)
+                .unwrap_or(self.default_profile.junit.timestamp_format);
+            let elapsed_time_precision = self
+                .custom_profile
+                .and_then(|profile| profile.junit.elapsed_time_precision)
+                .unwrap_or(self.default_profile.junit.elapsed_time_precision);
+            let hostname = self
+                .custom_profile
+                .and_then(|profile| profile.junit.hostname.clone())
+                .unwrap_or_else(|| self.default_profile.junit.hostname.clone());
+            let strip_ansi = self
+                .custom_profile
+                .and_then(|profile| profile.junit.strip_ansi)
+                .unwrap_or(self.default_profile.junit.strip_ansi);
+            let include_passing = self
+                .custom_profile
+                .and_then(|profile| profile.junit.include_passing)
+                .unwrap_or(self.default_profile.junit.include_passing);
+            let path_rewrites = self
+                .custom_profile
+                .and_then(|profile| profile.junit.path_rewrite.clone())
+                .unwrap_or_else(|| self.default_profile.junit.path_rewrite.clone());
+            let truncate_name_at = self
+                .custom_profile
+                .and_then(|profile| profile.junit.truncate_name_at)
+                .or(self.default_profile.junit.truncate_name_at);
+            let use_error_for_panics = self
+                .custom_profile
+                .and_then(|profile| profile.junit.use_error_for_panics)
+                .unwrap_or(self.default_profile.junit.use_error_for_panics);
             NextestJunitConfig {
                 path,
                 report_name,
+                report_name_override: None,
                 store_success_output,
                 store_failure_output,
+                suitename_style,
+                classname_style,
+                failure_format,
+                nest_packages,
+                timestamp_format,
+                elapsed_time_precision,
+                hostname,
+                strip_ansi,
+                include_passing,
+                path_rewrites,
+                truncate_name_at,
+                use_error_for_panics,
+                profile_name: self.name.clone(),
+                workspace_root: self.workspace_root,
             }
         })
     }
@@ -630,8 +781,23 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
 pub struct NextestJunitConfig<'cfg> {
     path: Utf8PathBuf,
     report_name: &'cfg str,
+    report_name_override: Option<String>,
     store_success_output: bool,
     store_failure_output: bool,
+    suitename_style: JunitSuiteNameStyle,
+    classname_style: JunitClassNameStyle,
+    failure_format: JunitFailureFormat,
+    nest_packages: bool,
+    timestamp_format: JunitTimestampFormat,
+    elapsed_time_precision: usize,
+    hostname: JunitHostname,
+    strip_ansi: bool,
+    include_passing: bool,
+    path_rewrites: Vec<JunitPathRewrite>,
+    truncate_name_at: Option<usize>,
+    use_error_for_panics: bool,
+    profile_name: String,
+    workspace_root: &'cfg Utf8Path,
 }
 
 impl<'cfg> NextestJunitConfig<'cfg> {
@@ -645,6 +811,34 @@ impl<'cfg> NextestJunitConfig<'cfg> {
         self.report_name
     }
 
+    /// Returns the name of the JUnit report before template variables are expanded, taking
+    /// overrides into account.
+    pub fn unexpanded_report_name(&self) -> &str {
+        self.report_name_override
+            .as_deref()
+            .unwrap_or(self.report_name)
+    }
+
+    pub(crate) fn set_report_name(&mut self, report_name: String) {
+        self.report_name_override = Some(report_name);
+    }
+
+    /// Returns the name of the JUnit report for a run started at `start_time`.
+    ///
+    /// The template variables `{workspace}` (the name of the workspace root directory),
+    /// `{profile}` (the name of the profile) and `{timestamp}` (the start time in RFC 3339 format)
+    /// are replaced with their values.
+    pub fn expanded_report_name(&self, start_time: DateTime<FixedOffset>) -> String {
+        let workspace = self.workspace_root.file_name().unwrap_or_default();
+        self.unexpanded_report_name()
+            .replace("{workspace}", workspace)
+            .replace("{profile}", &self.profile_name)
+            .replace(
+                "{timestamp}",
+                &start_time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            )
+    }
+
     /// Returns true if success output should be stored.
     pub fn store_success_output(&self) -> bool {
         self.store_success_output
@@ -654,11 +848,208 @@ impl<'cfg> NextestJunitConfig<'cfg> {
     pub fn store_failure_output(&self) -> bool {
         self.store_failure_output
     }
+
+    /// Returns how test suites are named in the JUnit report.
+    pub fn suitename_style(&self) -> JunitSuiteNameStyle {
+        self.suitename_style
+    }
+
+    pub(crate) fn set_suitename_style(&mut self, suitename_style: JunitSuiteNameStyle) {
+        self.suitename_style = suitename_style;
+    }
+
+    /// Returns how the `classname` attribute of test cases is populated in the JUnit report.
+    pub fn classname_style(&self) -> JunitClassNameStyle {
+        self.classname_style
+    }
+
+    pub(crate) fn set_classname_style(&mut self, classname_style: JunitClassNameStyle) {
+        self.classname_style = classname_style;
+    }
+
+    /// Returns how much output is written to the JUnit report for failing tests.
+    pub fn failure_format(&self) -> JunitFailureFormat {
+        self.failure_format
+    }
+
+    pub(crate) fn set_failure_format(&mut self, failure_format: JunitFailureFormat) {
+        self.failure_format = failure_format;
+    }
+
+    /// Returns true if test suites are nested within a parent test suite for their package.
+    pub fn nest_packages(&self) -> bool {
+        self.nest_packages
+    }
+
+    pub(crate) fn set_nest_packages(&mut self, nest_packages: bool) {
+        self.nest_packages = nest_packages;
+    }
+
+    /// Returns the format in which timestamps are written to the JUnit report.
+    pub fn timestamp_format(&self) -> JunitTimestampFormat {
+        self.timestamp_format
+    }
+
+    pub(crate) fn set_timestamp_format(&mut self, timestamp_format: JunitTimestampFormat) {
+        self.timestamp_format = timestamp_format;
+    }
+
+    /// The maximum number of digits after the decimal point that elapsed times can be rounded to,
+    /// i.e. nanosecond precision.
+    pub const MAX_ELAPSED_TIME_PRECISION: usize = 9;
+
+    /// Returns the number of digits after the decimal point that elapsed times in the JUnit report
+    /// are rounded to.
+    pub fn elapsed_time_precision(&self) -> usize {
+        self.elapsed_time_precision
+    }
+
+    pub(crate) fn set_elapsed_time_precision(&mut self, elapsed_time_precision: usize) {
+        self.elapsed_time_precision = elapsed_time_precision;
+    }
+
+    /// Returns the `hostname` attribute of test suites in the JUnit report.
+    pub fn hostname(&self) -> &JunitHostname {
+        &self.hostname
+    }
+
+    /// Returns true if ANSI escape sequences are removed from output written to the JUnit report.
+    pub fn strip_ansi(&self) -> bool {
+        self.strip_ansi
+    }
+
+    pub(crate) fn set_strip_ansi(&mut self, strip_ansi: bool) {
+        self.strip_ansi = strip_ansi;
+    }
+
+    /// Returns true if test cases are written to the JUnit report for tests that passed on their
+    /// first attempt.
+    ///
+    /// If this is false, such tests are still included in the test counts.
+    pub fn include_passing(&self) -> bool {
+        self.include_passing
+    }
+
+    pub(crate) fn set_include_passing(&mut self, include_passing: bool) {
+        self.include_passing = include_passing;
+    }
+
+    /// Returns the regex substitutions applied, in order, to path-like strings in the JUnit report.
+    pub fn path_rewrites(&self) -> &[JunitPathRewrite] {
+        &self.path_rewrites
+    }
+
+    pub(crate) fn set_path_rewrites(&mut self, path_rewrites: Vec<JunitPathRewrite>) {
+        self.path_rewrites = path_rewrites;
+    }
+
+    /// Returns the number of characters that test case names in the JUnit report are truncated
+    /// to, if any.
+    pub fn truncate_name_at(&self) -> Option<usize> {
+        self.truncate_name_at
+    }
+
+    /// The smallest number of characters that test case names can be truncated to, which leaves
+    /// room for at least one character of the name after the `...`.
+    pub const MIN_TRUNCATE_NAME_AT: usize = 4;
+
+    pub(crate) fn set_truncate_name_at(&mut self, truncate_name_at: usize) {
+        self.truncate_name_at = Some(truncate_name_at);
+    }
+
+    /// Returns true if tests that failed due to a panic other than a failed assertion are
+    /// reported as `<error>` rather than `<failure>` in the JUnit report.
+    pub fn use_error_for_panics(&self) -> bool {
+        self.use_error_for_panics
+    }
+
+    pub(crate) fn set_use_error_for_panics(&mut self, use_error_for_panics: bool) {
+        self.use_error_for_panics = use_error_for_panics;
+    }
+
+    pub(crate) fn set_hostname(&mut self, hostname: JunitHostname) {
+        self.hostname = hostname;
+    }
+}
+
+/// How test suites are named in JUnit reports.
+///
+/// Each test binary is a separate test suite, unless multiple binaries end up with the same name.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum JunitSuiteNameStyle {
+    /// The binary ID, e.g. `my-package::my_test`. This is the default.
+    #[default]
+    Full,
+
+    /// The package name, e.g. `my-package`. Tests from all binaries in a package are grouped into
+    /// the same suite.
+    Package,
+
+    /// The binary name, e.g. `my_test`.
+    Binary,
+}
+
+/// How the `classname` attribute of test cases is populated in JUnit reports.
+///
+/// Some JUnit consumers use the classname to group tests hierarchically.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum JunitClassNameStyle {
+    /// The binary ID, e.g. `my-package::my_test`. This is the default.
+    #[default]
+    Full,
+
+    /// The package name, e.g. `my-package`.
+    Package,
+
+    /// The binary name, e.g. `my_test`.
+    Binary,
+
+    /// The module path of the test, e.g. `tests::parser` for a test named
+    /// `tests::parser::test_empty`. Tests at the top level of a binary have no classname.
+    ModulePath,
+
+    /// No classname.
+    None,
+}
+
+/// How much output is written to JUnit reports for failing tests.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum JunitFailureFormat {
+    /// The full description, standard output and standard error. This is the default.
+    #[default]
+    Detailed,
+
+    /// Only the first line of the description, standard output and standard error, followed by
+    /// the number of bytes that were left out. Panic messages printed on the line after the panic
+    /// location are kept as well. Useful for JUnit consumers that truncate long
+    /// `<failure>` elements.
+    Brief,
+}
+
+/// The format in which timestamps are written to JUnit reports.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum JunitTimestampFormat {
+    /// RFC 3339 with millisecond precision, e.g. `2023-06-01T10:52:37.000+00:00`. This is the
+    /// default.
+    #[default]
+    Rfc3339,
+
+    /// `yyyy-MM-dd'T'HH:mm:ss` in UTC, e.g. `2023-06-01T10:52:37`. Some older JUnit consumers,
+    /// such as older Jenkins plugins, require this format.
+    Simple,
+
+    /// Seconds since the Unix epoch with millisecond precision, e.g. `1685616757.000`.
+    Epoch,
 }
 
 #[derive(Clone, Debug)]
 pub(super) struct NextestConfigImpl {
     store: StoreConfigImpl,
+    profile_selection: ProfileSelection,
     test_groups: BTreeMap<CustomTestGroup, TestGroupConfig>,
     default_profile: DefaultProfileImpl,
     other_profiles: HashMap<String, CustomProfileImpl>,
@@ -684,6 +1075,10 @@ impl NextestConfigImpl {
             .chain(std::iter::once(NextestConfig::DEFAULT_PROFILE))
     }
 
+    pub(super) fn has_profile(&self, profile: &str) -> bool {
+        profile == NextestConfig::DEFAULT_PROFILE || self.other_profiles.contains_key(profile)
+    }
+
     pub(super) fn default_profile(&self) -> &DefaultProfileImpl {
         &self.default_profile
     }
@@ -698,8 +1093,13 @@ impl NextestConfigImpl {
 // This is the form of `NextestConfig` that gets deserialized.
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
-struct NextestConfigDeserialize {
+pub(super) struct NextestConfigDeserialize {
     store: StoreConfigImpl,
+    // This is checked separately, through NextestConfig::check_min_version.
+    #[serde(default, rename = "nextest")]
+    _nextest: NextestSection,
+    #[serde(default)]
+    profile_selection: ProfileSelection,
     #[serde(default)]
     test_groups: BTreeMap<CustomTestGroup, TestGroupConfig>,
     #[serde(rename = "profile")]
@@ -716,6 +1116,7 @@ impl NextestConfigDeserialize {
 
         NextestConfigImpl {
             store: self.store,
+            profile_selection: self.profile_selection,
             default_profile,
             test_groups: self.test_groups,
             other_profiles: self.profiles,
@@ -725,7 +1126,7 @@ impl NextestConfigDeserialize {
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
-struct StoreConfigImpl {
+pub(super) struct StoreConfigImpl {
     dir: Utf8PathBuf,
 }
 
@@ -739,8 +1140,15 @@ pub(super) struct DefaultProfileImpl {
     failure_output: TestOutputDisplay,
     success_output: TestOutputDisplay,
     fail_fast: bool,
+    fail_on_stderr: bool,
     slow_timeout: SlowTimeout,
     leak_timeout: Duration,
+    min_run_time: Option<Duration>,
+    failure_limit_per_binary: Option<NonZeroUsize>,
+    retry_only_exit_codes: Option<Vec<i32>>,
+    test_binary_timeout: Option<Duration>,
+    test_thread_stack_size: Option<u64>,
+    build_jobs: Option<NonZeroUsize>,
     overrides: Vec<DeserializedOverride>,
     junit: DefaultJunitImpl,
 }
@@ -768,12 +1176,21 @@ impl DefaultProfileImpl {
                 .success_output
                 .expect("success-output present in default profile"),
             fail_fast: p.fail_fast.expect("fail-fast present in default profile"),
+            fail_on_stderr: p
+                .fail_on_stderr
+                .expect("fail-on-stderr present in default profile"),
             slow_timeout: p
                 .slow_timeout
                 .expect("slow-timeout present in default profile"),
             leak_timeout: p
                 .leak_timeout
                 .expect("leak-timeout present in default profile"),
+            min_run_time: p.min_run_time,
+            failure_limit_per_binary: p.failure_limit_per_binary,
+            retry_only_exit_codes: p.retry_only_exit_codes,
+            test_binary_timeout: p.test_binary_timeout,
+            test_thread_stack_size: p.test_thread_stack_size,
+            build_jobs: p.build_jobs,
             overrides: p.overrides,
             junit: DefaultJunitImpl {
                 path: p.junit.path,
@@ -789,6 +1206,51 @@ impl DefaultProfileImpl {
                     .junit
                     .store_failure_output
                     .expect("junit.store-failure-output present in default profile"),
+                suitename_style: p
+                    .junit
+                    .suitename_style
+                    .expect("junit.suitename-style present in default profile"),
+                classname_style: p
+                    .junit
+                    .classname_style
+                    .expect("junit.classname-style present in default profile"),
+                failure_format: p
+                    .junit
+                    .failure_format
+                    .expect("junit.failure-format present in default profile"),
+                nest_packages: p
+                    .junit
+                    .nest_packages
+                    .expect("junit.nest-packages present in default profile"),
+                timestamp_format: p
+                    .junit
+                    .timestamp_format
+                    .expect("junit.timestamp-format present in default profile"),
+                elapsed_time_precision: p
+                    .junit
+                    .elapsed_time_precision
+                    .expect("junit.elapsed-time-precision present in default profile"),
+                hostname: p
+                    .junit
+                    .hostname
+                    .expect("junit.hostname present in default profile"),
+                strip_ansi: p
+                    .junit
+                    .strip_ansi
+                    .expect("junit.strip-ansi present in default profile"),
+                include_passing: p
+                    .junit
+                    .include_passing
+                    .expect("junit.include-passing present in default profile"),
+                path_rewrite: p
+                    .junit
+                    .path_rewrite
+                    .expect("junit.path-rewrite present in default profile"),
+                truncate_name_at: p.junit.truncate_name_at,
+                use_error_for_panics: p
+                    .junit
+                    .use_error_for_panics
+                    .expect("junit.use-error-for-panics present in default profile"),
             },
         }
     }
@@ -804,6 +1266,18 @@ struct DefaultJunitImpl {
     report_name: String,
     store_success_output: bool,
     store_failure_output: bool,
+    suitename_style: JunitSuiteNameStyle,
+    classname_style: JunitClassNameStyle,
+    failure_format: JunitFailureFormat,
+    nest_packages: bool,
+    timestamp_format: JunitTimestampFormat,
+    elapsed_time_precision: usize,
+    hostname: JunitHostname,
+    strip_ansi: bool,
+    include_passing: bool,
+    path_rewrite: Vec<JunitPathRewrite>,
+    truncate_name_at: Option<usize>,
+    use_error_for_panics: bool,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -825,10 +1299,24 @@ pub(super) struct CustomProfileImpl {
     success_output: Option<TestOutputDisplay>,
     #[serde(default)]
     fail_fast: Option<bool>,
+    #[serde(default)]
+    fail_on_stderr: Option<bool>,
     #[serde(default, deserialize_with = "super::deserialize_slow_timeout")]
     slow_timeout: Option<SlowTimeout>,
     #[serde(default, with = "humantime_serde::option")]
     leak_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde::option")]
+    min_run_time: Option<Duration>,
+    #[serde(default)]
+    failure_limit_per_binary: Option<NonZeroUsize>,
+    #[serde(default)]
+    retry_only_exit_codes: Option<Vec<i32>>,
+    #[serde(default, with = "humantime_serde::option")]
+    test_binary_timeout: Option<Duration>,
+    #[serde(default)]
+    test_thread_stack_size: Option<u64>,
+    #[serde(default)]
+    build_jobs: Option<NonZeroUsize>,
     #[serde(default)]
     overrides: Vec<DeserializedOverride>,
     #[serde(default)]
@@ -848,15 +1336,67 @@ impl CustomProfileImpl {
 
 #[derive(Clone, Debug, Default, Deserialize)]
 #[serde(rename_all = "kebab-case")]
-struct JunitImpl {
+pub(super) struct JunitImpl {
     #[serde(default)]
     path: Option<Utf8PathBuf>,
-    #[serde(default)]
+    #[serde(default, alias = "name")]
     report_name: Option<String>,
     #[serde(default)]
     store_success_output: Option<bool>,
     #[serde(default)]
     store_failure_output: Option<bool>,
+    #[serde(default)]
+    suitename_style: Option<JunitSuiteNameStyle>,
+    #[serde(default)]
+    classname_style: Option<JunitClassNameStyle>,
+    #[serde(default)]
+    failure_format: Option<JunitFailureFormat>,
+    #[serde(default)]
+    nest_packages: Option<bool>,
+    #[serde(default)]
+    timestamp_format: Option<JunitTimestampFormat>,
+    #[serde(default, deserialize_with = "deserialize_elapsed_time_precision")]
+    elapsed_time_precision: Option<usize>,
+    #[serde(default)]
+    hostname: Option<JunitHostname>,
+    #[serde(default)]
+    strip_ansi: Option<bool>,
+    #[serde(default)]
+    include_passing: Option<bool>,
+    #[serde(default)]
+    path_rewrite: Option<Vec<JunitPathRewrite>>,
+    #[serde(default, deserialize_with = "deserialize_truncate_name_at")]
+    truncate_name_at: Option<usize>,
+    #[serde(default)]
+    use_error_for_panics: Option<bool>,
+}
+
+fn deserialize_elapsed_time_precision<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let precision = usize::deserialize(deserializer)?;
+    if precision > NextestJunitConfig::MAX_ELAPSED_TIME_PRECISION {
+        return Err(serde::de::Error::custom(format!(
+            "elapsed-time-precision must be at most {}, found {precision}",
+            NextestJunitConfig::MAX_ELAPSED_TIME_PRECISION,
+        )));
+    }
+    Ok(Some(precision))
+}
+
+fn deserialize_truncate_name_at<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let truncate_name_at = usize::deserialize(deserializer)?;
+    if truncate_name_at < NextestJunitConfig::MIN_TRUNCATE_NAME_AT {
+        return Err(serde::de::Error::custom(format!(
+            "truncate-name-at must be at least {}, found {truncate_name_at}",
+            NextestJunitConfig::MIN_TRUNCATE_NAME_AT,
+        )));
+    }
+    Ok(Some(truncate_name_at))
 }
 
 #[cfg(test)]
@@ -873,6 +1413,46 @@ mod tests {
             .expect("default profile should exist");
     }
 
+    #[test]
+    fn junit_report_name_templates() {
+        let config_contents = r#"
+        [profile.ci.junit]
+        path = "junit.xml"
+        name = "{workspace}-{profile}-{timestamp}-{unknown}"
+        "#;
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+        let graph = temp_workspace(workspace_path, config_contents);
+        let workspace_root = graph.workspace().root();
+        let config =
+            NextestConfig::from_sources(workspace_root, &graph, None, []).expect("config is valid");
+        let mut junit = config
+            .profile("ci")
+            .unwrap()
+            .apply_build_platforms(&build_platforms())
+            .junit()
+            .expect("JUnit is enabled");
+
+        let start_time = DateTime::parse_from_rfc3339("2023-06-01T10:20:30Z").unwrap();
+        assert_eq!(
+            junit.expanded_report_name(start_time),
+            format!(
+                "{}-ci-2023-06-01T10:20:30Z-{{unknown}}",
+                workspace_root.file_name().unwrap()
+            ),
+        );
+
+        junit.set_report_name("override-{profile}".to_owned());
+        assert_eq!(
+            junit.report_name(),
+            "{workspace}-{profile}-{timestamp}-{unknown}",
+            "report_name returns the profile's name"
+        );
+        assert_eq!(junit.unexpanded_report_name(), "override-{profile}");
+        assert_eq!(junit.expanded_report_name(start_time), "override-ci");
+    }
+
     #[test]
     fn ignored_keys() {
         let config_contents = r#"
@@ -959,4 +1539,114 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn error_key_path() {
+        let config_contents = r#"
+        [profile.ci]
+        retries = "abc"
+        "#;
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+
+        let graph = temp_workspace(workspace_path, config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("config is invalid");
+        assert_eq!(error.key_path(), Some("profile.ci.retries"));
+        assert!(
+            error
+                .to_string()
+                .ends_with(": error at key `profile.ci.retries`"),
+            "key path is displayed: {error}"
+        );
+    }
+
+    #[test]
+    fn junit_elapsed_time_precision_too_large() {
+        let config_contents = r#"
+        [profile.ci.junit]
+        path = "junit.xml"
+        elapsed-time-precision = 10
+        "#;
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+
+        let graph = temp_workspace(workspace_path, config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("config is invalid");
+        assert_eq!(
+            error.key_path(),
+            Some("profile.ci.junit.elapsed-time-precision")
+        );
+        let message = match error.kind() {
+            ConfigParseErrorKind::DeserializeError(path_error) => path_error.inner().to_string(),
+            other => panic!("expected ConfigParseErrorKind::DeserializeError, found {other:?}"),
+        };
+        assert!(
+            message.contains("elapsed-time-precision must be at most 9, found 10"),
+            "error message is correct: {message}"
+        );
+    }
+
+    #[test]
+    fn junit_truncate_name_at_too_small() {
+        let config_contents = r#"
+        [profile.ci.junit]
+        path = "junit.xml"
+        truncate-name-at = 3
+        "#;
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+
+        let graph = temp_workspace(workspace_path, config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("config is invalid");
+        assert_eq!(error.key_path(), Some("profile.ci.junit.truncate-name-at"));
+        let message = match error.kind() {
+            ConfigParseErrorKind::DeserializeError(path_error) => path_error.inner().to_string(),
+            other => panic!("expected ConfigParseErrorKind::DeserializeError, found {other:?}"),
+        };
+        assert!(
+            message.contains("truncate-name-at must be at least 4, found 3"),
+            "error message is correct: {message}"
+        );
+    }
+
+    #[test]
+    fn profile_not_found_suggestion() {
+        let config_contents = r#"
+        [profile.ci]
+        retries = 2
+
+        [profile.coverage]
+        retries = 0
+        "#;
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+
+        let graph = temp_workspace(workspace_path, config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let error = config.profile("cii").expect_err("profile doesn't exist");
+        assert_eq!(error.suggestion(), Some("ci"));
+        assert!(
+            error.to_string().ends_with("\n(hint: did you mean `ci`?)"),
+            "suggestion is displayed: {error}"
+        );
+
+        let error = config
+            .profile("covrage")
+            .expect_err("profile doesn't exist");
+        assert_eq!(error.suggestion(), Some("coverage"));
+
+        let error = config
+            .profile("nightly")
+            .expect_err("profile doesn't exist");
+        assert_eq!(error.suggestion(), None, "no profile is close enough");
+    }
 }
@@ -0,0 +1,118 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::JunitHostnameParseError;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Type for the `junit.hostname` config key: the `hostname` attribute of test suites in JUnit
+/// reports.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum JunitHostname {
+    /// Omit the `hostname` attribute. This is the default.
+    #[default]
+    None,
+
+    /// Use this hostname.
+    Fixed(String),
+
+    /// Read the hostname from this environment variable, e.g. one set by the CI system. The
+    /// attribute is omitted if the variable isn't set.
+    Env(String),
+}
+
+impl JunitHostname {
+    /// Returns the hostname to write to the JUnit report, if any.
+    pub fn resolve(&self) -> Option<String> {
+        match self {
+            Self::None => None,
+            Self::Fixed(hostname) => Some(hostname.clone()),
+            Self::Env(var) => std::env::var(var).ok(),
+        }
+    }
+}
+
+impl FromStr for JunitHostname {
+    type Err = JunitHostnameParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "none" {
+            return Ok(Self::None);
+        }
+        match s.strip_prefix("env:") {
+            Some("") => Err(JunitHostnameParseError::new(s)),
+            Some(var) => Ok(Self::Env(var.to_owned())),
+            None if s.is_empty() => Err(JunitHostnameParseError::new(s)),
+            None => Ok(Self::Fixed(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for JunitHostname {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Fixed(hostname) => write!(f, "{hostname}"),
+            Self::Env(var) => write!(f, "env:{var}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JunitHostname {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for JunitHostname {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_junit_hostname() {
+        assert_eq!(
+            "none".parse::<JunitHostname>().unwrap(),
+            JunitHostname::None
+        );
+        assert_eq!(
+            "ci-runner".parse::<JunitHostname>().unwrap(),
+            JunitHostname::Fixed("ci-runner".to_owned())
+        );
+        assert_eq!(
+            "env:RUNNER_NAME".parse::<JunitHostname>().unwrap(),
+            JunitHostname::Env("RUNNER_NAME".to_owned())
+        );
+        "".parse::<JunitHostname>().unwrap_err();
+        "env:".parse::<JunitHostname>().unwrap_err();
+
+        for input in ["none", "ci-runner", "env:RUNNER_NAME"] {
+            let hostname: JunitHostname = input.parse().unwrap();
+            assert_eq!(hostname.to_string(), input, "display round-trips");
+        }
+
+        std::env::set_var("__NEXTEST_TEST_JUNIT_HOSTNAME", "runner-1");
+        assert_eq!(
+            JunitHostname::Env("__NEXTEST_TEST_JUNIT_HOSTNAME".to_owned()).resolve(),
+            Some("runner-1".to_owned())
+        );
+        assert_eq!(
+            JunitHostname::Env("__NEXTEST_TEST_JUNIT_HOSTNAME_UNSET".to_owned()).resolve(),
+            None,
+            "unset variables omit the hostname"
+        );
+        assert_eq!(JunitHostname::None.resolve(), None);
+    }
+}
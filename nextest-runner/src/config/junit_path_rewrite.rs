@@ -0,0 +1,143 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::JunitPathRewriteParseError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt, str::FromStr};
+
+/// Type for entries in the `junit.path-rewrite` config key: a regex substitution applied to
+/// path-like strings in JUnit reports, such as test case classnames.
+///
+/// The replacement can refer to capture groups in the regex, e.g. `$1` or `${name}`.
+#[derive(Clone, Debug)]
+pub struct JunitPathRewrite {
+    regex: Regex,
+    replacement: String,
+}
+
+impl JunitPathRewrite {
+    /// Creates a new rewrite, replacing matches of `regex` with `replacement`.
+    pub fn new(
+        regex: &str,
+        replacement: impl Into<String>,
+    ) -> Result<Self, JunitPathRewriteParseError> {
+        let replacement = replacement.into();
+        let regex = Regex::new(regex).map_err(|error| {
+            JunitPathRewriteParseError::new(format!("{regex}:{replacement}"), error.to_string())
+        })?;
+        Ok(Self { regex, replacement })
+    }
+
+    /// Returns the regex that is matched against.
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Returns the replacement for matches of the regex.
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// Applies each rewrite in `rewrites` to `text`, in order.
+    pub fn apply_all<'a>(rewrites: &[Self], text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for rewrite in rewrites {
+            let replaced = match rewrite
+                .regex
+                .replace_all(&text, rewrite.replacement.as_str())
+            {
+                Cow::Owned(replaced) => Some(replaced),
+                Cow::Borrowed(_) => None,
+            };
+            if let Some(replaced) = replaced {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+}
+
+impl FromStr for JunitPathRewrite {
+    type Err = JunitPathRewriteParseError;
+
+    /// Parses a rewrite of the form `REGEX:REPLACEMENT`, split at the last `:`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once(':') {
+            Some((regex, replacement)) if !regex.is_empty() => Self::new(regex, replacement),
+            _ => Err(JunitPathRewriteParseError::new(
+                s,
+                "expected REGEX:REPLACEMENT",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for JunitPathRewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.regex, self.replacement)
+    }
+}
+
+impl<'de> Deserialize<'de> for JunitPathRewrite {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // Each rewrite is written as a ["REGEX", "REPLACEMENT"] pair.
+        let (regex, replacement) = <(String, String)>::deserialize(deserializer)?;
+        Self::new(&regex, replacement).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for JunitPathRewrite {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        (self.regex.as_str(), self.replacement.as_str()).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_junit_path_rewrite() {
+        let rewrite: JunitPathRewrite = "^/home/[^/]+/src:$$WORKSPACE".parse().unwrap();
+        assert_eq!(rewrite.regex().as_str(), "^/home/[^/]+/src");
+        assert_eq!(rewrite.replacement(), "$$WORKSPACE");
+
+        // The input is split at the last colon, so regexes can contain colons.
+        let rewrite: JunitPathRewrite = r"^C:\\ws:/ws".parse().unwrap();
+        assert_eq!(rewrite.regex().as_str(), r"^C:\\ws");
+        assert_eq!(rewrite.replacement(), "/ws");
+
+        "no-separator".parse::<JunitPathRewrite>().unwrap_err();
+        ":replacement".parse::<JunitPathRewrite>().unwrap_err();
+        "(unclosed:replacement"
+            .parse::<JunitPathRewrite>()
+            .unwrap_err();
+    }
+
+    #[test]
+    fn apply_junit_path_rewrites() {
+        let rewrites = [
+            JunitPathRewrite::new("^/home/[^/]+/", "~/").unwrap(),
+            JunitPathRewrite::new(r"target/(debug|release)/", "target/$1-out/").unwrap(),
+        ];
+        assert_eq!(
+            JunitPathRewrite::apply_all(&rewrites, "/home/me/ws/target/debug/deps/foo"),
+            "~/ws/target/debug-out/deps/foo"
+        );
+        assert!(
+            matches!(
+                JunitPathRewrite::apply_all(&rewrites, "my-crate::tests"),
+                Cow::Borrowed(_)
+            ),
+            "strings without matches aren't copied"
+        );
+        assert_eq!(JunitPathRewrite::apply_all(&[], "/home/me"), "/home/me");
+    }
+}
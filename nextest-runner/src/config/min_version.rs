@@ -0,0 +1,148 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for the `nextest.min-version` setting.
+
+use super::{NextestConfig, ToolConfigFile};
+use crate::errors::{ConfigParseError, ConfigParseErrorKind};
+use camino::Utf8Path;
+use config::{Config, File, FileFormat};
+use semver::Version;
+use serde::{Deserialize, Deserializer};
+
+impl NextestConfig {
+    /// Checks that `current_version` satisfies the `nextest.min-version` setting in each config
+    /// file.
+    ///
+    /// This reads just the `[nextest]` section of each file, so it should be called before
+    /// [`Self::from_sources`]: a config that requires a newer version of nextest may contain keys
+    /// that this version doesn't understand.
+    pub fn check_min_version<'a>(
+        workspace_root: &Utf8Path,
+        config_file: Option<&Utf8Path>,
+        tool_config_files: impl IntoIterator<Item = &'a ToolConfigFile>,
+        current_version: &Version,
+    ) -> Result<(), ConfigParseError> {
+        let config_file = match config_file {
+            Some(file) => (file.to_owned(), true),
+            None => (workspace_root.join(Self::CONFIG_PATH), false),
+        };
+        let sources = std::iter::once((config_file, None)).chain(
+            tool_config_files
+                .into_iter()
+                .map(|file| ((file.config_file.clone(), true), Some(file.tool.as_str()))),
+        );
+
+        for ((config_file, required), tool) in sources {
+            let source = File::new(config_file.as_str(), FileFormat::Toml).required(required);
+            let deserialized: MinVersionDeserialize = Config::builder()
+                .add_source(source)
+                .build()
+                .map_err(|error| {
+                    ConfigParseError::new(
+                        &config_file,
+                        tool,
+                        ConfigParseErrorKind::BuildError(Box::new(error)),
+                    )
+                })
+                .and_then(|config| {
+                    serde_path_to_error::deserialize(config).map_err(|error| {
+                        ConfigParseError::new(
+                            &config_file,
+                            tool,
+                            ConfigParseErrorKind::DeserializeError(Box::new(error)),
+                        )
+                    })
+                })?;
+
+            if let Some(required) = deserialized.nextest.min_version {
+                if current_version < &required {
+                    return Err(ConfigParseError::new(
+                        &config_file,
+                        tool,
+                        ConfigParseErrorKind::VersionTooOld {
+                            required,
+                            actual: current_version.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct MinVersionDeserialize {
+    #[serde(default)]
+    nextest: NextestSection,
+}
+
+/// The `[nextest]` section of a config file.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) struct NextestSection {
+    #[serde(default, deserialize_with = "deserialize_version")]
+    min_version: Option<Version>,
+}
+
+fn deserialize_version<'de, D>(deserializer: D) -> Result<Option<Version>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let version = String::deserialize(deserializer)?;
+    version
+        .parse()
+        .map(Some)
+        .map_err(|error| serde::de::Error::custom(format!("invalid version `{version}`: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_check_min_version() {
+        let config_contents = r#"
+        [nextest]
+        min-version = "0.9.50"
+
+        [profile.default]
+        retries = 2
+        "#;
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+        let graph = temp_workspace(workspace_path, config_contents);
+        let workspace_root = graph.workspace().root();
+
+        let check = |version: &str| {
+            NextestConfig::check_min_version(
+                workspace_root,
+                None,
+                [],
+                &version.parse().expect("valid version"),
+            )
+        };
+
+        check("0.9.50").expect("same version is accepted");
+        check("0.10.0").expect("newer version is accepted");
+        let error = check("0.9.40").expect_err("older version is rejected");
+        match error.kind() {
+            ConfigParseErrorKind::VersionTooOld { required, actual } => {
+                assert_eq!(required, &Version::new(0, 9, 50));
+                assert_eq!(actual, &Version::new(0, 9, 40));
+            }
+            other => panic!("unexpected error kind: {other:?}"),
+        }
+
+        // The main config doesn't warn about the nextest section.
+        NextestConfig::from_sources_impl(workspace_root, &graph, None, [], |_, _, unknown| {
+            panic!("unknown keys: {unknown:?}")
+        })
+        .expect("config is valid");
+    }
+}
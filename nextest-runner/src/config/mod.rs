@@ -5,17 +5,26 @@
 
 mod config_impl;
 mod identifier;
+mod junit_hostname;
+mod junit_path_rewrite;
+mod min_version;
 mod overrides;
+mod profile_selection;
+mod resolved;
 mod retry_policy;
 mod slow_timeout;
 mod test_group;
 mod test_threads;
 mod threads_required;
 mod tool_config;
+mod unknown_keys;
 
 pub use config_impl::*;
 pub use identifier::*;
+pub use junit_hostname::*;
+pub use junit_path_rewrite::*;
 pub use overrides::*;
+pub use profile_selection::*;
 pub use retry_policy::*;
 pub use slow_timeout::*;
 pub use test_group::*;
@@ -8,9 +8,10 @@ use crate::{
     platform::BuildPlatforms,
     reporter::TestOutputDisplay,
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use guppy::graph::{cargo::BuildPlatform, PackageGraph};
 use nextest_filtering::{FilteringExpr, TestQuery};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::{collections::HashMap, time::Duration};
 use target_spec::TargetSpec;
@@ -32,6 +33,9 @@ pub struct TestSettings<Source = ()> {
     failure_output: (TestOutputDisplay, Source),
     junit_store_success_output: (bool, Source),
     junit_store_failure_output: (bool, Source),
+    working_dir: (Option<Utf8PathBuf>, Source),
+    env_file: (Option<Utf8PathBuf>, Source),
+    fail_on_stderr: (bool, Source),
 }
 
 pub(crate) trait TrackSource<'p>: Sized {
@@ -110,6 +114,25 @@ impl TestSettings {
     pub fn junit_store_failure_output(&self) -> bool {
         self.junit_store_failure_output.0
     }
+
+    /// Returns the absolute working directory for this test, if one was set through an override.
+    ///
+    /// If this is `None`, the test is run in the directory of the package it belongs to.
+    pub fn working_dir(&self) -> Option<&Utf8Path> {
+        self.working_dir.0.as_deref()
+    }
+
+    /// Returns the absolute path to the env file for this test, if one was set through an override.
+    ///
+    /// The variables in this file are set for the test's process.
+    pub fn env_file(&self) -> Option<&Utf8Path> {
+        self.env_file.0.as_deref()
+    }
+
+    /// Returns whether this test fails if it writes to standard error.
+    pub fn fail_on_stderr(&self) -> bool {
+        self.fail_on_stderr.0
+    }
 }
 
 #[allow(dead_code)]
@@ -130,6 +153,9 @@ impl<Source: Copy> TestSettings<Source> {
         let mut failure_output = None;
         let mut junit_store_success_output = None;
         let mut junit_store_failure_output = None;
+        let mut working_dir = None;
+        let mut env_file = None;
+        let mut fail_on_stderr = None;
 
         for override_ in &profile.overrides {
             if query.binary_query.platform == BuildPlatform::Host && !override_.state.host_eval {
@@ -191,6 +217,25 @@ impl<Source: Copy> TestSettings<Source> {
                     junit_store_failure_output = Some(Source::track_override(f, override_));
                 }
             }
+            if working_dir.is_none() {
+                if let Some(w) = &override_.data.working_dir {
+                    // Working directories are relative to the workspace root.
+                    let w = profile.workspace_root().join(w);
+                    working_dir = Some(Source::track_override(Some(w), override_));
+                }
+            }
+            if env_file.is_none() {
+                if let Some(e) = &override_.data.env_file {
+                    // Env files are relative to the workspace root.
+                    let e = profile.workspace_root().join(e);
+                    env_file = Some(Source::track_override(Some(e), override_));
+                }
+            }
+            if fail_on_stderr.is_none() {
+                if let Some(f) = override_.data.fail_on_stderr {
+                    fail_on_stderr = Some(Source::track_override(f, override_));
+                }
+            }
         }
 
         // If no overrides were found, use the profile defaults.
@@ -214,6 +259,12 @@ impl<Source: Copy> TestSettings<Source> {
             // If the profile doesn't have JUnit enabled, failure output can just be false.
             Source::track_profile(profile.junit().map_or(false, |j| j.store_failure_output()))
         });
+        // Working directories can only be set through overrides.
+        let working_dir = working_dir.unwrap_or_else(|| Source::track_profile(None));
+        // Env files can only be set through overrides.
+        let env_file = env_file.unwrap_or_else(|| Source::track_profile(None));
+        let fail_on_stderr =
+            fail_on_stderr.unwrap_or_else(|| Source::track_profile(profile.fail_on_stderr()));
 
         TestSettings {
             threads_required,
@@ -225,6 +276,9 @@ impl<Source: Copy> TestSettings<Source> {
             failure_output,
             junit_store_success_output,
             junit_store_failure_output,
+            working_dir,
+            env_file,
+            fail_on_stderr,
         }
     }
 
@@ -326,6 +380,7 @@ pub(crate) struct OverrideId {
 
 #[derive(Clone, Debug)]
 pub(super) struct ProfileOverrideData {
+    platform: Option<String>,
     target_spec: Option<TargetSpec>,
     expr: Option<FilteringExpr>,
     threads_required: Option<ThreadsRequired>,
@@ -336,6 +391,9 @@ pub(super) struct ProfileOverrideData {
     success_output: Option<TestOutputDisplay>,
     failure_output: Option<TestOutputDisplay>,
     junit: DeserializedJunitOutput,
+    working_dir: Option<Utf8PathBuf>,
+    env_file: Option<Utf8PathBuf>,
+    fail_on_stderr: Option<bool>,
 }
 
 impl CompiledOverride<PreBuildPlatform> {
@@ -373,6 +431,7 @@ impl CompiledOverride<PreBuildPlatform> {
                 },
                 state: PreBuildPlatform {},
                 data: ProfileOverrideData {
+                    platform: source.platform.clone(),
                     target_spec,
                     expr,
                     threads_required: source.threads_required,
@@ -383,6 +442,9 @@ impl CompiledOverride<PreBuildPlatform> {
                     success_output: source.success_output,
                     failure_output: source.failure_output,
                     junit: source.junit,
+                    working_dir: source.working_dir.clone(),
+                    env_file: source.env_file.clone(),
+                    fail_on_stderr: source.fail_on_stderr,
                 },
             }),
             (Err(platform_parse_error), Ok(_)) => {
@@ -450,6 +512,52 @@ impl CompiledOverride<FinalConfig> {
     pub(crate) fn filter(&self) -> Option<&FilteringExpr> {
         self.data.expr.as_ref()
     }
+
+    /// Returns true if this override applies to the host or target platform.
+    pub(super) fn applies_to_build_platforms(&self) -> bool {
+        self.state.host_eval || self.state.target_eval
+    }
+
+    /// Returns the settings in this override, in a form that can be serialized as config.
+    pub(super) fn resolved(&self) -> ResolvedOverride<'_> {
+        let data = &self.data;
+        ResolvedOverride {
+            platform: data.platform.as_deref(),
+            filter: data.expr.as_ref().map(|expr| expr.input.as_str()),
+            threads_required: data.threads_required,
+            retries: data.retries,
+            slow_timeout: data.slow_timeout,
+            leak_timeout: data.leak_timeout,
+            test_group: data.test_group.as_ref().map(|group| group.to_string()),
+            success_output: data.success_output,
+            failure_output: data.failure_output,
+            junit: data.junit,
+            working_dir: data.working_dir.as_deref(),
+            env_file: data.env_file.as_deref(),
+            fail_on_stderr: data.fail_on_stderr,
+        }
+    }
+}
+
+/// The serialized form of an override, used to display resolved config.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) struct ResolvedOverride<'a> {
+    platform: Option<&'a str>,
+    filter: Option<&'a str>,
+    threads_required: Option<ThreadsRequired>,
+    retries: Option<RetryPolicy>,
+    slow_timeout: Option<SlowTimeout>,
+    #[serde(with = "humantime_serde")]
+    leak_timeout: Option<Duration>,
+    test_group: Option<String>,
+    success_output: Option<TestOutputDisplay>,
+    failure_output: Option<TestOutputDisplay>,
+    #[serde(skip_serializing_if = "DeserializedJunitOutput::is_empty")]
+    junit: DeserializedJunitOutput,
+    working_dir: Option<&'a Utf8Path>,
+    env_file: Option<&'a Utf8Path>,
+    fail_on_stderr: Option<bool>,
 }
 
 /// Deserialized form of profile overrides before compilation.
@@ -480,20 +588,31 @@ pub(super) struct DeserializedOverride {
     failure_output: Option<TestOutputDisplay>,
     #[serde(default)]
     junit: DeserializedJunitOutput,
+    #[serde(default)]
+    working_dir: Option<Utf8PathBuf>,
+    #[serde(default)]
+    env_file: Option<Utf8PathBuf>,
+    #[serde(default)]
+    fail_on_stderr: Option<bool>,
 }
 
-#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub(super) struct DeserializedJunitOutput {
     store_success_output: Option<bool>,
     store_failure_output: Option<bool>,
 }
 
+impl DeserializedJunitOutput {
+    fn is_empty(&self) -> bool {
+        self.store_success_output.is_none() && self.store_failure_output.is_none()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{test_helpers::*, NextestConfig};
-    use camino::Utf8Path;
     use indoc::indoc;
     use nextest_filtering::BinaryQuery;
     use std::num::NonZeroUsize;
@@ -521,6 +640,9 @@ mod tests {
             test-group = "my-group"
             failure-output = "final"
             junit = { store-failure-output = false }
+            working-dir = "tests/fixtures"
+            env-file = "tests/test.env"
+            fail-on-stderr = true
 
             [profile.default.junit]
             path = "my-path.xml"
@@ -574,7 +696,16 @@ mod tests {
         {
             assert_eq!(overrides.junit_store_success_output(), false);
             assert_eq!(overrides.junit_store_failure_output(), false);
+            assert_eq!(overrides.fail_on_stderr(), true);
         }
+        assert_eq!(
+            overrides.working_dir(),
+            Some(graph.workspace().root().join("tests/fixtures").as_path())
+        );
+        assert_eq!(
+            overrides.env_file(),
+            Some(graph.workspace().root().join("tests/test.env").as_path())
+        );
 
         // This query matches both overrides.
         let query = TestQuery {
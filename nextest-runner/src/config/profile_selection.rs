@@ -0,0 +1,211 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::NextestConfig;
+use serde::Deserialize;
+use std::{ffi::OsString, fmt};
+
+/// How a profile is selected if one isn't specified explicitly.
+///
+/// This is the type for the `profile-selection` config key.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfileSelection {
+    /// Use the `default` profile, or `default-miri` when running under Miri.
+    #[default]
+    Manual,
+
+    /// Additionally, use the `ci` profile if it is defined and a CI environment is detected.
+    Auto,
+}
+
+/// Environment variables that indicate a CI environment, in the order they're checked.
+///
+/// `CI` is set by most CI providers. The others cover providers that don't set it.
+pub const CI_ENV_VARS: &[&str] = &[
+    "CI",
+    "GITHUB_ACTIONS",
+    "GITLAB_CI",
+    "BUILDKITE",
+    "CIRCLECI",
+    "TF_BUILD",
+    "JENKINS_URL",
+    "TEAMCITY_VERSION",
+];
+
+/// A profile selected by [`NextestConfig::select_profile`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SelectedProfile<'cfg> {
+    /// The name of the selected profile.
+    pub name: &'cfg str,
+
+    /// Why this profile was selected.
+    pub reason: ProfileSelectionReason,
+}
+
+/// The reason a profile was selected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProfileSelectionReason {
+    /// No other rules applied, so the default profile was selected.
+    Default,
+
+    /// `MIRI_SYSROOT` is set, so the default Miri profile was selected.
+    Miri,
+
+    /// A CI environment was detected through the given environment variable.
+    Ci {
+        /// The environment variable that indicated a CI environment.
+        env_var: &'static str,
+    },
+}
+
+impl fmt::Display for ProfileSelectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "no profile specified"),
+            Self::Miri => write!(f, "running under Miri"),
+            Self::Ci { env_var } => write!(f, "CI environment detected through `{env_var}`"),
+        }
+    }
+}
+
+impl NextestConfig {
+    /// The name of the profile selected in CI environments with `profile-selection = "auto"`.
+    pub const CI_PROFILE: &'static str = "ci";
+
+    /// Selects a profile to use if one wasn't specified explicitly.
+    ///
+    /// `selection` overrides the `profile-selection` setting in the config. The rules are, in
+    /// order:
+    ///
+    /// 1. If `MIRI_SYSROOT` is set, the `default-miri` profile is selected.
+    /// 2. With [`ProfileSelection::Auto`], if the `ci` profile is defined and one of
+    ///    [`CI_ENV_VARS`] is set (to a value other than an empty string, `0` or `false`), the `ci`
+    ///    profile is selected.
+    /// 3. Otherwise, the `default` profile is selected.
+    pub fn select_profile(&self, selection: Option<ProfileSelection>) -> SelectedProfile<'_> {
+        self.select_profile_impl(selection, |var| std::env::var_os(var))
+    }
+
+    fn select_profile_impl(
+        &self,
+        selection: Option<ProfileSelection>,
+        get_env: impl Fn(&str) -> Option<OsString>,
+    ) -> SelectedProfile<'_> {
+        // The "official" way to detect a miri environment is with MIRI_SYSROOT.
+        // https://github.com/rust-lang/miri/pull/2398#issuecomment-1190747685
+        if get_env("MIRI_SYSROOT").is_some() {
+            return SelectedProfile {
+                name: Self::DEFAULT_MIRI_PROFILE,
+                reason: ProfileSelectionReason::Miri,
+            };
+        }
+
+        let selection = selection.unwrap_or_else(|| self.profile_selection());
+        if selection == ProfileSelection::Auto && self.has_profile(Self::CI_PROFILE) {
+            let ci_env_var = CI_ENV_VARS.iter().find(|var| match get_env(var) {
+                Some(value) => !matches!(value.to_str(), Some("" | "0" | "false")),
+                None => false,
+            });
+            if let Some(env_var) = ci_env_var {
+                return SelectedProfile {
+                    name: Self::CI_PROFILE,
+                    reason: ProfileSelectionReason::Ci { env_var },
+                };
+            }
+        }
+
+        SelectedProfile {
+            name: Self::DEFAULT_PROFILE,
+            reason: ProfileSelectionReason::Default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_select_profile() {
+        let config_contents = r#"
+        profile-selection = "auto"
+
+        [profile.ci]
+        retries = 2
+        "#;
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &camino::Utf8Path = workspace_dir.path().try_into().unwrap();
+        let graph = temp_workspace(workspace_path, config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let select = |selection: Option<ProfileSelection>, env: &[(&str, &str)]| {
+            let env: Vec<(String, OsString)> = env
+                .iter()
+                .map(|(k, v)| (k.to_string(), OsString::from(v)))
+                .collect();
+            config.select_profile_impl(selection, |var| {
+                env.iter().find(|(k, _)| k == var).map(|(_, v)| v.clone())
+            })
+        };
+
+        assert_eq!(
+            select(None, &[]),
+            SelectedProfile {
+                name: "default",
+                reason: ProfileSelectionReason::Default,
+            },
+            "no CI environment"
+        );
+        assert_eq!(
+            select(None, &[("CI", "true"), ("GITHUB_ACTIONS", "true")]),
+            SelectedProfile {
+                name: "ci",
+                reason: ProfileSelectionReason::Ci { env_var: "CI" },
+            },
+            "CI is checked first"
+        );
+        assert_eq!(
+            select(None, &[("CI", "false"), ("GITLAB_CI", "true")]),
+            SelectedProfile {
+                name: "ci",
+                reason: ProfileSelectionReason::Ci {
+                    env_var: "GITLAB_CI"
+                },
+            },
+            "CI=false is skipped"
+        );
+        assert_eq!(
+            select(Some(ProfileSelection::Manual), &[("CI", "true")]),
+            SelectedProfile {
+                name: "default",
+                reason: ProfileSelectionReason::Default,
+            },
+            "manual selection overrides the config"
+        );
+        assert_eq!(
+            select(None, &[("CI", "true"), ("MIRI_SYSROOT", "/sysroot")]),
+            SelectedProfile {
+                name: "default-miri",
+                reason: ProfileSelectionReason::Miri,
+            },
+            "Miri takes precedence"
+        );
+
+        // Without a ci profile, the default profile is used.
+        let config = NextestConfig::default_config(workspace_path);
+        assert_eq!(
+            config
+                .select_profile_impl(Some(ProfileSelection::Auto), |var| {
+                    (var == "CI").then(|| OsString::from("true"))
+                })
+                .name,
+            "default",
+            "no ci profile defined"
+        );
+    }
+}
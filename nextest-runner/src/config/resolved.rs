@@ -0,0 +1,221 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The resolved form of a profile, for debugging layered configuration.
+
+use super::{
+    overrides::ResolvedOverride, FinalConfig, JunitClassNameStyle, JunitFailureFormat,
+    JunitHostname, JunitPathRewrite, JunitSuiteNameStyle, JunitTimestampFormat, NextestProfile,
+    RetryFilter, RetryPolicy, SlowTimeout, TestThreads, ThreadsRequired,
+};
+use crate::reporter::{FinalStatusLevel, StatusLevel, TestOutputDisplay};
+use serde::Serialize;
+use std::{collections::BTreeMap, num::NonZeroUsize, time::Duration};
+
+impl<'cfg> NextestProfile<'cfg, FinalConfig> {
+    /// Returns this profile as TOML, with all config sources merged.
+    ///
+    /// Every setting is included, whether it was set in this profile or inherited from the default
+    /// profile. Overrides from both this profile and the default profile are listed in order of
+    /// precedence, skipping those whose `platform` doesn't match the host or target platform. The
+    /// JUnit path is absolute.
+    pub fn to_resolved_toml(&self) -> String {
+        let junit = self.junit().map(|junit| ResolvedJunit {
+            path: junit.path().to_owned().into_string(),
+            report_name: junit.report_name().to_owned(),
+            store_success_output: junit.store_success_output(),
+            store_failure_output: junit.store_failure_output(),
+            suitename_style: junit.suitename_style(),
+            classname_style: junit.classname_style(),
+            failure_format: junit.failure_format(),
+            nest_packages: junit.nest_packages(),
+            timestamp_format: junit.timestamp_format(),
+            elapsed_time_precision: junit.elapsed_time_precision(),
+            hostname: junit.hostname().clone(),
+            strip_ansi: junit.strip_ansi(),
+            include_passing: junit.include_passing(),
+            path_rewrite: junit.path_rewrites().to_vec(),
+            truncate_name_at: junit.truncate_name_at(),
+            use_error_for_panics: junit.use_error_for_panics(),
+        });
+        let profile = ResolvedProfile {
+            retries: self.retries(),
+            test_threads: self.test_threads(),
+            threads_required: self.threads_required(),
+            status_level: self.status_level(),
+            final_status_level: self.final_status_level(),
+            failure_output: self.failure_output(),
+            success_output: self.success_output(),
+            fail_fast: self.fail_fast(),
+            fail_on_stderr: self.fail_on_stderr(),
+            slow_timeout: self.slow_timeout(),
+            leak_timeout: self.leak_timeout(),
+            min_run_time: self.min_run_time(),
+            failure_limit_per_binary: self.failure_limit_per_binary(),
+            retry_only_exit_codes: match self.retry_filter() {
+                RetryFilter::All => None,
+                RetryFilter::ExitCodes(codes) => Some(codes),
+            },
+            test_binary_timeout: self.test_binary_timeout(),
+            test_thread_stack_size: self.test_thread_stack_size(),
+            build_jobs: self.build_jobs(),
+            junit,
+            overrides: self
+                .overrides
+                .iter()
+                .filter(|override_| override_.applies_to_build_platforms())
+                .map(|override_| override_.resolved())
+                .collect(),
+        };
+
+        let config = ResolvedConfig {
+            profile: std::iter::once((self.name(), profile)).collect(),
+            test_groups: self
+                .test_group_config()
+                .iter()
+                .map(|(name, config)| {
+                    (
+                        name.to_string(),
+                        ResolvedTestGroup {
+                            max_threads: config.max_threads,
+                        },
+                    )
+                })
+                .collect(),
+        };
+        toml::to_string_pretty(&config).expect("resolved config can be serialized as TOML")
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ResolvedConfig<'a> {
+    profile: BTreeMap<&'a str, ResolvedProfile<'a>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    test_groups: BTreeMap<String, ResolvedTestGroup>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ResolvedProfile<'a> {
+    retries: RetryPolicy,
+    test_threads: TestThreads,
+    threads_required: ThreadsRequired,
+    status_level: StatusLevel,
+    final_status_level: FinalStatusLevel,
+    failure_output: TestOutputDisplay,
+    success_output: TestOutputDisplay,
+    fail_fast: bool,
+    fail_on_stderr: bool,
+    slow_timeout: SlowTimeout,
+    #[serde(with = "humantime_serde")]
+    leak_timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    min_run_time: Option<Duration>,
+    failure_limit_per_binary: Option<NonZeroUsize>,
+    retry_only_exit_codes: Option<Vec<i32>>,
+    #[serde(with = "humantime_serde")]
+    test_binary_timeout: Option<Duration>,
+    test_thread_stack_size: Option<u64>,
+    build_jobs: Option<NonZeroUsize>,
+    junit: Option<ResolvedJunit>,
+    overrides: Vec<ResolvedOverride<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ResolvedJunit {
+    path: String,
+    report_name: String,
+    store_success_output: bool,
+    store_failure_output: bool,
+    suitename_style: JunitSuiteNameStyle,
+    classname_style: JunitClassNameStyle,
+    failure_format: JunitFailureFormat,
+    nest_packages: bool,
+    timestamp_format: JunitTimestampFormat,
+    elapsed_time_precision: usize,
+    hostname: JunitHostname,
+    strip_ansi: bool,
+    include_passing: bool,
+    path_rewrite: Vec<JunitPathRewrite>,
+    truncate_name_at: Option<usize>,
+    use_error_for_panics: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ResolvedTestGroup {
+    max_threads: TestThreads,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino::Utf8Path;
+    use indoc::indoc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn resolved_toml() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            retries = 2
+
+            [profile.ci]
+            fail-fast = false
+            slow-timeout = { period = "30s", terminate-after = 2 }
+
+            [[profile.ci.overrides]]
+            filter = "test(test_foo)"
+            retries = { backoff = "exponential", count = 3, delay = "1s" }
+            test-group = "serial"
+
+            [[profile.ci.overrides]]
+            platform = "cfg(target_os = \"nonexistent\")"
+            retries = 10
+
+            [test-groups.serial]
+            max-threads = 1
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+        let graph = temp_workspace(workspace_path, config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let profile = config
+            .profile("ci")
+            .expect("ci profile exists")
+            .apply_build_platforms(&build_platforms());
+
+        let resolved = profile.to_resolved_toml();
+        let value: toml::Value = toml::from_str(&resolved).expect("resolved config is valid TOML");
+        let ci = &value["profile"]["ci"];
+        assert_eq!(
+            ci["retries"]["count"].as_integer(),
+            Some(2),
+            "retries is inherited from the default profile"
+        );
+        assert_eq!(ci["fail-fast"].as_bool(), Some(false));
+        assert_eq!(ci["slow-timeout"]["period"].as_str(), Some("30s"));
+        assert_eq!(ci["slow-timeout"]["terminate-after"].as_integer(), Some(2));
+
+        let overrides = ci["overrides"].as_array().expect("overrides is an array");
+        assert_eq!(
+            overrides.len(),
+            1,
+            "override for a nonexistent platform is skipped"
+        );
+        assert_eq!(overrides[0]["filter"].as_str(), Some("test(test_foo)"));
+        assert_eq!(
+            overrides[0]["retries"]["backoff"].as_str(),
+            Some("exponential")
+        );
+        assert_eq!(overrides[0]["test-group"].as_str(), Some("serial"));
+        assert_eq!(
+            value["test-groups"]["serial"]["max-threads"].as_integer(),
+            Some(1)
+        );
+    }
+}
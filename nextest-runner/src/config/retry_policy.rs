@@ -1,11 +1,11 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{cmp::Ordering, fmt, time::Duration};
 
 /// Type for the retry config key.
-#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(tag = "backoff", rename_all = "kebab-case", deny_unknown_fields)]
 pub enum RetryPolicy {
     /// Fixed backoff.
@@ -68,6 +68,32 @@ impl RetryPolicy {
     }
 }
 
+/// Which failed tests are retried.
+///
+/// Tests that aren't matched by the filter fail straight away, regardless of their retry policy.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum RetryFilter {
+    /// Retry all failed tests. This is the default.
+    #[default]
+    All,
+
+    /// Only retry tests that exit with one of these exit codes.
+    ///
+    /// Tests that fail without an exit code, e.g. because they time out or are aborted by a
+    /// signal, aren't retried.
+    ExitCodes(Vec<i32>),
+}
+
+impl RetryFilter {
+    /// Returns true if a failed attempt with this exit code should be retried.
+    pub fn matches(&self, exit_code: Option<i32>) -> bool {
+        match self {
+            Self::All => true,
+            Self::ExitCodes(codes) => exit_code.map_or(false, |code| codes.contains(&code)),
+        }
+    }
+}
+
 pub(super) fn deserialize_retry_policy<'de, D>(
     deserializer: D,
 ) -> Result<Option<RetryPolicy>, D::Error>
@@ -180,6 +206,72 @@ mod tests {
     use tempfile::tempdir;
     use test_case::test_case;
 
+    #[test]
+    fn retry_filter_matches() {
+        assert!(RetryFilter::All.matches(Some(1)));
+        assert!(RetryFilter::All.matches(None));
+
+        let filter = RetryFilter::ExitCodes(vec![2, 3]);
+        assert!(filter.matches(Some(2)));
+        assert!(filter.matches(Some(3)));
+        assert!(!filter.matches(Some(101)), "unlisted exit code");
+        assert!(!filter.matches(None), "no exit code");
+    }
+
+    #[test]
+    fn parse_retry_only_exit_codes() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            retry-only-exit-codes = [2, 3]
+
+            [profile.ci]
+            retry-only-exit-codes = [4]
+
+            [profile.inherit]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let workspace_path: &Utf8Path = workspace_dir.path().try_into().unwrap();
+
+        let graph = temp_workspace(workspace_path, config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let retry_filter = |name: &str| {
+            config
+                .profile(name)
+                .expect("profile exists")
+                .apply_build_platforms(&build_platforms())
+                .retry_filter()
+        };
+        assert_eq!(
+            retry_filter("default"),
+            RetryFilter::ExitCodes(vec![2, 3]),
+            "default retry filter matches"
+        );
+        assert_eq!(
+            retry_filter("ci"),
+            RetryFilter::ExitCodes(vec![4]),
+            "ci retry filter matches"
+        );
+        assert_eq!(
+            retry_filter("inherit"),
+            RetryFilter::ExitCodes(vec![2, 3]),
+            "inherit retry filter is inherited from the default profile"
+        );
+
+        let default_config = NextestConfig::default_config("/fake/dir");
+        assert_eq!(
+            default_config
+                .profile(NextestConfig::DEFAULT_PROFILE)
+                .expect("default profile exists")
+                .apply_build_platforms(&build_platforms())
+                .retry_filter(),
+            RetryFilter::All,
+            "all failing tests are retried by default"
+        );
+    }
+
     #[test]
     fn parse_retries_valid() {
         let config_contents = indoc! {r#"
@@ -3,7 +3,7 @@
 
 use super::get_num_cpus;
 use crate::errors::TestThreadsParseError;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{cmp::Ordering, fmt, str::FromStr};
 
 /// Type for the test-threads config key.
@@ -106,6 +106,18 @@ impl<'de> Deserialize<'de> for TestThreads {
     }
 }
 
+impl Serialize for TestThreads {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Count(threads) => serializer.serialize_u64(*threads as u64),
+            Self::NumCpus => serializer.serialize_str("num-cpus"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
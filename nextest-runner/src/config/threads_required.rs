@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::get_num_cpus;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{cmp::Ordering, fmt};
 
 /// Type for the threads-required config key.
@@ -89,6 +89,19 @@ impl<'de> Deserialize<'de> for ThreadsRequired {
     }
 }
 
+impl Serialize for ThreadsRequired {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Count(threads) => serializer.serialize_u64(*threads as u64),
+            Self::NumCpus => serializer.serialize_str("num-cpus"),
+            Self::NumTestThreads => serializer.serialize_str("num-test-threads"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
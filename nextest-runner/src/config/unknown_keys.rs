@@ -0,0 +1,115 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Suggestions for unknown configuration keys, e.g. `retries` for `reties`.
+
+use super::{
+    config_impl::{CustomProfileImpl, JunitImpl, NextestConfigDeserialize, StoreConfigImpl},
+    min_version::NextestSection,
+    overrides::{DeserializedJunitOutput, DeserializedOverride},
+    TestGroupConfig,
+};
+use crate::helpers::edit_distance;
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+
+/// Returns the known key closest to the last component of `path`, a path to an unknown key as
+/// reported by `serde_ignored` (e.g. `profile.default.reties`).
+///
+/// Returns `None` if no known key is close enough to be a likely typo.
+pub(super) fn suggest_key(path: &str) -> Option<&'static str> {
+    let components: Vec<_> = path.split('.').collect();
+    let (key, table) = components.split_last()?;
+    let fields = match table {
+        [] => struct_fields::<NextestConfigDeserialize>(),
+        ["store"] => struct_fields::<StoreConfigImpl>(),
+        ["nextest"] => struct_fields::<NextestSection>(),
+        ["test-groups", _] => struct_fields::<TestGroupConfig>(),
+        ["profile", _] => struct_fields::<CustomProfileImpl>(),
+        ["profile", _, "junit"] => struct_fields::<JunitImpl>(),
+        ["profile", _, "overrides", _] => struct_fields::<DeserializedOverride>(),
+        ["profile", _, "overrides", _, "junit"] => struct_fields::<DeserializedJunitOutput>(),
+        _ => return None,
+    };
+
+    // As with profile names, ties are broken by picking the first field. This threshold is low
+    // enough to avoid suggesting unrelated keys that happen to share a few letters.
+    fields
+        .iter()
+        .map(|field| (edit_distance(key, field), *field))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, field)| field)
+}
+
+/// The largest edit distance between an unknown key and a suggestion for it.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+/// Returns the names of the fields that `T` deserializes from.
+///
+/// Serde passes these to [`Deserializer::deserialize_struct`], so they reflect any renames.
+fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    // This always returns an error, since FieldsRecorder never produces any data.
+    let _ = T::deserialize(FieldsRecorder {
+        fields: &mut fields,
+    });
+    fields
+}
+
+struct FieldsRecorder<'a> {
+    fields: &'a mut &'static [&'static str],
+}
+
+impl<'de, 'a> Deserializer<'de> for FieldsRecorder<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.fields = fields;
+        Err(de::Error::custom("fields recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_key() {
+        let cases = [
+            ("stroe", Some("store")),
+            ("profile.default.reties", Some("retries")),
+            ("profile.ci.test-thread", Some("test-threads")),
+            ("profile.ci.junit.pth", Some("path")),
+            ("profile.ci.overrides.0.filer", Some("filter")),
+            (
+                "profile.ci.overrides.0.junit.store-success-outptu",
+                Some("store-success-output"),
+            ),
+            ("test-groups.serial.max-thread", Some("max-threads")),
+            ("nextest.min-verison", Some("min-version")),
+            ("profile.default.completely-unrelated", None),
+            ("profile.default.foo", None),
+            ("profile.default.retries.foo.bar", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(suggest_key(path), expected, "suggestion for {path}");
+        }
+    }
+}
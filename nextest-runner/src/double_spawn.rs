@@ -27,7 +27,8 @@
 //!
 //! With this approach, the race condition between posix_spawn and `SIGTSTP` no longer exists.
 
-use std::path::Path;
+use crate::helpers::PRELOAD_ENV_VAR;
+use std::{path::Path, process::Command};
 
 /// Information about double-spawning processes. This determines whether a process will be
 /// double-spawned.
@@ -42,6 +43,13 @@ impl DoubleSpawnInfo {
     /// The name of the double-spawn subcommand, used throughout nextest.
     pub const SUBCOMMAND_NAME: &'static str = "__double-spawn";
 
+    /// The environment variable that passes libraries to preload into the test to the
+    /// double-spawn child.
+    ///
+    /// The child moves the value to `LD_PRELOAD`, or `DYLD_INSERT_LIBRARIES` on macOS, just before
+    /// it executes the test, so the libraries aren't loaded into the child itself.
+    pub const PRELOAD_ENV: &'static str = "__NEXTEST_DOUBLE_SPAWN_PRELOAD";
+
     /// Attempts to enable double-spawning and returns a new `DoubleSpawnInfo`.
     ///
     /// If double-spawning is not available, [`current_exe`](Self::current_exe) returns `None`.
@@ -98,6 +106,16 @@ pub fn double_spawn_child_init() {
     imp::double_spawn_child_init()
 }
 
+/// Prepares `command`, which the double-spawn child is about to execute.
+pub fn double_spawn_child_prepare(command: &mut Command) {
+    if let Some(libraries) = std::env::var_os(DoubleSpawnInfo::PRELOAD_ENV) {
+        command.env_remove(DoubleSpawnInfo::PRELOAD_ENV);
+        if let Some(var) = PRELOAD_ENV_VAR {
+            command.env(var, libraries);
+        }
+    }
+}
+
 #[cfg(unix)]
 mod imp {
     use super::*;
@@ -0,0 +1,88 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Reading `.env` files set through the `env-file` override.
+//!
+//! Each line is either blank, a comment starting with `#`, or `KEY=VALUE`, optionally preceded by
+//! `export`. Values may be wrapped in single or double quotes, which are removed. Escapes and
+//! variable references aren't expanded.
+
+use crate::errors::EnvFileError;
+use camino::Utf8Path;
+
+/// Reads the variables in the env file at `path`, in the order they're defined.
+pub(crate) fn read_env_file(path: &Utf8Path) -> Result<Vec<(String, String)>, EnvFileError> {
+    let contents = std::fs::read_to_string(path).map_err(|error| EnvFileError::Read {
+        path: path.to_owned(),
+        error,
+    })?;
+    parse_env_file(&contents).map_err(|line_number| EnvFileError::InvalidLine {
+        path: path.to_owned(),
+        line_number,
+    })
+}
+
+/// Parses the contents of an env file, returning the (1-based) number of the first invalid line on
+/// error.
+fn parse_env_file(contents: &str) -> Result<Vec<(String, String)>, usize> {
+    let mut vars = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=').ok_or(index + 1)?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(index + 1);
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|&quote| {
+                value
+                    .strip_prefix(quote)
+                    .and_then(|value| value.strip_suffix(quote))
+            })
+            .unwrap_or(value);
+        vars.push((key.to_owned(), value.to_owned()));
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_parse_env_file() {
+        let contents = indoc! {r#"
+            # Database for integration tests.
+            DATABASE_URL=postgres://localhost:5433/test
+
+            export PORT = 8081
+            GREETING="hello world"
+            QUOTED_HASH='# not a comment'
+            EMPTY=
+        "#};
+        assert_eq!(
+            parse_env_file(contents),
+            Ok(vec![
+                (
+                    "DATABASE_URL".to_owned(),
+                    "postgres://localhost:5433/test".to_owned()
+                ),
+                ("PORT".to_owned(), "8081".to_owned()),
+                ("GREETING".to_owned(), "hello world".to_owned()),
+                ("QUOTED_HASH".to_owned(), "# not a comment".to_owned()),
+                ("EMPTY".to_owned(), String::new()),
+            ])
+        );
+
+        assert_eq!(parse_env_file("A=1\nnot a variable\n"), Err(2));
+        assert_eq!(parse_env_file("=value"), Err(1));
+        assert_eq!(parse_env_file("MY KEY=value"), Err(1));
+    }
+}
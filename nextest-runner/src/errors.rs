@@ -6,9 +6,10 @@
 use crate::{
     cargo_config::{TargetTriple, TargetTripleSource},
     config::{CustomTestGroup, TestGroup},
-    helpers::{dylib_path_envvar, extract_abort_status},
+    helpers::{dylib_path_envvar, edit_distance, extract_abort_status},
+    list::TestCountBound,
     reuse_build::ArchiveFormat,
-    runner::AbortStatus,
+    runner::{AbortStatus, RunStats},
     target_runner::PlatformRunnerSource,
 };
 use camino::{FromPathBufError, Utf8Path, Utf8PathBuf};
@@ -16,6 +17,7 @@ use config::ConfigError;
 use itertools::Itertools;
 use nextest_filtering::errors::FilterExpressionParseErrors;
 use nextest_metadata::RustBinaryId;
+use semver::Version;
 use smol_str::SmolStr;
 use std::{borrow::Cow, collections::BTreeSet, env::JoinPathsError, fmt, process::ExitStatus};
 use target_spec_miette::IntoMietteDiagnostic;
@@ -24,13 +26,15 @@ use thiserror::Error;
 /// An error that occurred while parsing the config.
 #[derive(Debug, Error)]
 #[error(
-    "failed to parse nextest config at `{config_file}`{}",
-    provided_by_tool(tool.as_deref())
+    "failed to parse nextest config at `{config_file}`{}{}",
+    provided_by_tool(tool.as_deref()),
+    display_key_path(key_path.as_deref())
 )]
 #[non_exhaustive]
 pub struct ConfigParseError {
     config_file: Utf8PathBuf,
     tool: Option<String>,
+    key_path: Option<String>,
     #[source]
     kind: ConfigParseErrorKind,
 }
@@ -41,9 +45,21 @@ impl ConfigParseError {
         tool: Option<&str>,
         kind: ConfigParseErrorKind,
     ) -> Self {
+        let key_path = match &kind {
+            ConfigParseErrorKind::BuildError(error) => config_error_key_path(error),
+            ConfigParseErrorKind::DeserializeError(error) => {
+                if error.path().iter().next().is_some() {
+                    Some(error.path().to_string())
+                } else {
+                    config_error_key_path(error.inner())
+                }
+            }
+            _ => None,
+        };
         Self {
             config_file: config_file.into(),
             tool: tool.map(|s| s.to_owned()),
+            key_path,
             kind,
         }
     }
@@ -58,12 +74,33 @@ impl ConfigParseError {
         self.tool.as_deref()
     }
 
+    /// Returns the dotted path to the key that failed validation, e.g. `profile.ci.retries`.
+    ///
+    /// This is only available for errors that occurred while building or deserializing the config.
+    pub fn key_path(&self) -> Option<&str> {
+        self.key_path.as_deref()
+    }
+
     /// Returns the kind of error this is.
     pub fn kind(&self) -> &ConfigParseErrorKind {
         &self.kind
     }
 }
 
+fn config_error_key_path(error: &ConfigError) -> Option<String> {
+    match error {
+        ConfigError::Type { key: Some(key), .. } | ConfigError::NotFound(key) => Some(key.clone()),
+        _ => None,
+    }
+}
+
+fn display_key_path(key_path: Option<&str>) -> String {
+    match key_path {
+        Some(key_path) => format!(": error at key `{key_path}`"),
+        None => String::new(),
+    }
+}
+
 /// Returns the string ` provided by tool <tool>`, if `tool` is `Some`.
 pub fn provided_by_tool(tool: Option<&str>) -> String {
     match tool {
@@ -81,8 +118,11 @@ pub enum ConfigParseErrorKind {
     /// An error occurred while building the config.
     #[error(transparent)]
     BuildError(Box<ConfigError>),
-    #[error(transparent)]
     /// An error occurred while deserializing the config.
+    ///
+    /// The path to the key is part of the [`ConfigParseError`] message, so only the inner error is
+    /// displayed here.
+    #[error("{}", .0.inner())]
     DeserializeError(Box<serde_path_to_error::Error<ConfigError>>),
     /// Errors occurred while parsing overrides.
     #[error("error parsing overrides (destructure this variant for more details)")]
@@ -103,6 +143,19 @@ pub enum ConfigParseErrorKind {
         /// Known groups up to this point.
         known_groups: BTreeSet<TestGroup>,
     },
+    /// The config requires a newer version of nextest than the one running.
+    #[error(
+        "this config requires nextest {required} or later, but the current version is {actual}\n\
+         (hint: run `cargo nextest self update`, or download the latest version from \
+         https://nexte.st/book/pre-built-binaries)"
+    )]
+    VersionTooOld {
+        /// The minimum version required by the config.
+        required: Version,
+
+        /// The version of nextest that's running.
+        actual: Version,
+    },
 }
 
 /// An error that occurred while parsing config overrides.
@@ -164,24 +217,48 @@ pub struct UnknownTestGroupError {
 
 /// An error which indicates that a profile was requested but not known to nextest.
 #[derive(Clone, Debug, Error)]
-#[error("profile `{profile} not found (known profiles: {})`", .all_profiles.join(", "))]
+#[error(
+    "profile `{profile}` not found (known profiles: {}){}",
+    .all_profiles.join(", "),
+    .suggestion.as_ref().map_or(String::new(), |suggestion| {
+        format!("\n(hint: did you mean `{suggestion}`?)")
+    }),
+)]
 pub struct ProfileNotFound {
     profile: String,
     all_profiles: Vec<String>,
+    suggestion: Option<String>,
 }
 
 impl ProfileNotFound {
+    /// The maximum edit distance at which a known profile is suggested.
+    const MAX_SUGGESTION_DISTANCE: usize = 3;
+
     pub(crate) fn new(
         profile: impl Into<String>,
         all_profiles: impl IntoIterator<Item = impl Into<String>>,
     ) -> Self {
+        let profile = profile.into();
         let mut all_profiles: Vec<_> = all_profiles.into_iter().map(|s| s.into()).collect();
         all_profiles.sort_unstable();
+        // Ties are broken by picking the first profile in sorted order.
+        let suggestion = all_profiles
+            .iter()
+            .map(|candidate| (edit_distance(&profile, candidate), candidate))
+            .filter(|(distance, _)| *distance <= Self::MAX_SUGGESTION_DISTANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate.clone());
         Self {
-            profile: profile.into(),
+            profile,
             all_profiles,
+            suggestion,
         }
     }
+
+    /// Returns the known profile closest to the requested one, if any is close enough.
+    pub fn suggestion(&self) -> Option<&str> {
+        self.suggestion.as_deref()
+    }
 }
 
 /// An identifier is invalid.
@@ -265,6 +342,142 @@ impl TestThreadsParseError {
     }
 }
 
+/// Error returned while parsing a [`ShuffleSeed`](crate::runner::ShuffleSeed) value.
+#[derive(Clone, Debug, Error)]
+#[error("unrecognized value for seed: {input}\n(hint: expected either an integer or \"random\")")]
+pub struct ShuffleSeedParseError {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
+impl ShuffleSeedParseError {
+    pub(crate) fn new(input: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+        }
+    }
+}
+
+/// Error returned while parsing an [`EnvInheritance`](crate::runner::EnvInheritance) value.
+#[derive(Clone, Debug, Error)]
+#[error(
+    "invalid environment variable name `{name}`\n\
+     (hint: expected \"all\", \"none\" or a comma-separated list of variable names)"
+)]
+pub struct EnvInheritanceParseError {
+    /// The variable name that failed to parse.
+    pub name: String,
+}
+
+impl EnvInheritanceParseError {
+    pub(crate) fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+/// An error that occurs while parsing a [`JunitProperty`](crate::reporter::JunitProperty).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "invalid JUnit property `{input}`: the name is empty\n\
+     (hint: expected KEY=VALUE, or KEY to read the value from the environment)"
+)]
+pub struct JunitPropertyParseError {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
+impl JunitPropertyParseError {
+    pub(crate) fn new(input: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+        }
+    }
+}
+
+/// An error that occurs while parsing a [`JunitHostname`](crate::config::JunitHostname).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "invalid JUnit hostname `{input}`\n\
+     (hint: expected a hostname, \"none\", or \"env:VAR\" to read it from the environment)"
+)]
+pub struct JunitHostnameParseError {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
+impl JunitHostnameParseError {
+    pub(crate) fn new(input: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+        }
+    }
+}
+
+/// An error that occurs while parsing a [`JunitPathRewrite`](crate::config::JunitPathRewrite).
+#[derive(Clone, Debug, Error)]
+#[error("invalid JUnit path rewrite `{input}`: {message}")]
+pub struct JunitPathRewriteParseError {
+    /// The input that failed to parse.
+    pub input: String,
+
+    /// The reason the input failed to parse.
+    pub message: String,
+}
+
+impl JunitPathRewriteParseError {
+    pub(crate) fn new(input: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// An error that occurs while parsing a
+/// [`CommandWrapper`](crate::command_wrapper::CommandWrapper).
+#[derive(Clone, Debug, Error)]
+#[error("invalid test wrapper `{input}`: {message}")]
+pub struct CommandWrapperParseError {
+    /// The input that failed to parse.
+    pub input: String,
+
+    /// The reason the input failed to parse.
+    pub message: String,
+}
+
+impl CommandWrapperParseError {
+    pub(crate) fn new(input: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// An error that occurs while parsing an
+/// [`ImmediateRetryCondition`](crate::runner::ImmediateRetryCondition).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "invalid retry condition `{input}`: {message}\n\
+     (hint: expected a condition like \"signal:SIGSEGV\")"
+)]
+pub struct ImmediateRetryConditionParseError {
+    /// The input that failed to parse.
+    pub input: String,
+
+    /// A description of why the input failed to parse.
+    pub message: &'static str,
+}
+
+impl ImmediateRetryConditionParseError {
+    pub(crate) fn new(input: impl Into<String>, message: &'static str) -> Self {
+        Self {
+            input: input.into(),
+            message,
+        }
+    }
+}
+
 /// An error that occurs while parsing a
 /// [`PartitionerBuilder`](crate::partition::PartitionerBuilder) input.
 #[derive(Clone, Debug, Error)]
@@ -300,6 +513,176 @@ impl fmt::Display for PartitionerBuilderParseError {
     }
 }
 
+/// An error returned when a partition doesn't match the tests being run.
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum PartitionError {
+    /// More shards were requested than the selected tests can be split across.
+    #[error(
+        "{test_count} selected tests can't be split into {total_shards} shards with at least \
+         {min_tests_per_shard} tests each (hint: use at most {max_shards} shards)"
+    )]
+    TooManyShards {
+        /// The total number of shards requested.
+        total_shards: u64,
+
+        /// The number of tests selected before partitioning.
+        test_count: usize,
+
+        /// The minimum number of tests per shard.
+        min_tests_per_shard: usize,
+
+        /// The maximum number of shards with at least `min_tests_per_shard` tests each, or 1 if
+        /// there are fewer tests than that.
+        max_shards: usize,
+    },
+}
+
+/// An error that occurs while parsing a [`BinaryNameGlob`](crate::test_filter::BinaryNameGlob).
+#[derive(Clone, Debug, Error)]
+#[error("invalid test binary glob `{input}`: {}", error.kind())]
+pub struct BinaryNameGlobParseError {
+    input: String,
+    error: globset::Error,
+}
+
+impl BinaryNameGlobParseError {
+    pub(crate) fn new(input: impl Into<String>, error: globset::Error) -> Self {
+        Self {
+            input: input.into(),
+            error,
+        }
+    }
+}
+
+/// An error that occurs while parsing a
+/// [`WorkspaceFilterExpr`](crate::test_filter::WorkspaceFilterExpr).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "invalid workspace filter `{input}`: {message}\n\
+     (hint: expected `metadata.key = \"value\"` or `metadata.key exists`)"
+)]
+pub struct WorkspaceFilterParseError {
+    input: String,
+    message: &'static str,
+}
+
+impl WorkspaceFilterParseError {
+    pub(crate) fn new(input: impl Into<String>, message: &'static str) -> Self {
+        Self {
+            input: input.into(),
+            message,
+        }
+    }
+}
+
+/// An error that occurs while parsing a
+/// [`WorkspacePathGlob`](crate::test_filter::WorkspacePathGlob).
+#[derive(Clone, Debug, Error)]
+#[error("invalid workspace path glob `{input}`: {}", error.kind())]
+pub struct WorkspacePathGlobParseError {
+    input: String,
+    error: globset::Error,
+}
+
+impl WorkspacePathGlobParseError {
+    pub(crate) fn new(input: impl Into<String>, error: globset::Error) -> Self {
+        Self {
+            input: input.into(),
+            error,
+        }
+    }
+}
+
+/// An error that occurs while parsing a [`BinaryPathOverride`](crate::list::BinaryPathOverride).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "invalid test binary path override `{input}`: {message}\n\
+     (hint: expected BINARY_ID=PATH)"
+)]
+pub struct BinaryPathOverrideParseError {
+    input: String,
+    message: &'static str,
+}
+
+impl BinaryPathOverrideParseError {
+    pub(crate) fn new(input: impl Into<String>, message: &'static str) -> Self {
+        Self {
+            input: input.into(),
+            message,
+        }
+    }
+}
+
+/// An error that occurs while applying a [`BinaryPathOverride`](crate::list::BinaryPathOverride).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum BinaryPathOverrideError {
+    /// No test binary has the given ID.
+    #[error(
+        "test binary path override: no test binary with ID `{binary_id}`\n\
+         (hint: run `cargo nextest list --list-type binaries-only` to see binary IDs)"
+    )]
+    BinaryIdNotFound {
+        /// The binary ID that wasn't found.
+        binary_id: RustBinaryId,
+    },
+
+    /// The path couldn't be resolved.
+    #[error("test binary path override for `{binary_id}`: failed to resolve `{path}`")]
+    PathResolve {
+        /// The binary ID being overridden.
+        binary_id: RustBinaryId,
+
+        /// The path that couldn't be resolved.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+}
+
+/// An error that occurs while parsing an [`ExpectedTestCount`](crate::list::ExpectedTestCount).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "invalid expected test count `{input}`\n\
+     (hint: expected a number of tests, optionally prefixed with a package name, e.g. `my-crate:10`)"
+)]
+pub struct ExpectedTestCountParseError {
+    input: String,
+}
+
+impl ExpectedTestCountParseError {
+    pub(crate) fn new(input: impl Into<String>) -> Self {
+        Self {
+            input: input.into(),
+        }
+    }
+}
+
+/// An error returned when the number of tests in a [`TestList`](crate::list::TestList) doesn't
+/// match an [`ExpectedTestCount`](crate::list::ExpectedTestCount).
+#[derive(Clone, Debug, Error)]
+#[error(
+    "expected {bound} {expected} tests to run{}, but found {actual}\n\
+     (hint: if tests were added or removed on purpose, update the expected count)",
+    .package.as_ref().map_or_else(String::new, |package| format!(" in package `{package}`"))
+)]
+pub struct TestCountAssertionError {
+    /// The package whose tests were counted, or `None` for all packages.
+    pub package: Option<String>,
+
+    /// How the actual count was compared against the expected count.
+    pub bound: TestCountBound,
+
+    /// The expected number of tests.
+    pub expected: usize,
+
+    /// The number of tests that will actually be run.
+    pub actual: usize,
+}
+
 /// An error that occures while operating on a
 /// [`TestFilterBuilder`](crate::test_filter::TestFilterBuilder).
 #[derive(Clone, Debug, Error)]
@@ -488,6 +871,29 @@ pub enum CreateTestListError {
         stderr: Vec<u8>,
     },
 
+    /// The test binary panicked while gathering the list of tests, e.g. during global
+    /// initialization.
+    #[error(
+        "for `{binary_id}`, command `{}` panicked while listing tests ({})\n--- panic:\n{}\n---\n\
+         (hint: the panic happened outside of any test, such as in global initialization)",
+        shell_words::join(command),
+        display_exit_status(*exit_status),
+        stderr,
+    )]
+    BinaryPanickedDuringList {
+        /// The binary ID for which gathering the list of tests failed.
+        binary_id: RustBinaryId,
+
+        /// The command that was run.
+        command: Vec<String>,
+
+        /// The exit status with which the command failed.
+        exit_status: ExitStatus,
+
+        /// The panic message and backtrace, extracted from standard error.
+        stderr: String,
+    },
+
     /// Running a command to gather the list of tests produced a non-UTF-8 standard output.
     #[error(
         "for `{binary_id}`, command `{}` produced non-UTF-8 output:\n--- stdout:\n{}\n--- stderr:\n{}\n---",
@@ -595,6 +1001,19 @@ pub enum WriteTestListError {
     /// An error occurred while serializing JSON, or while writing it to the provided output.
     #[error("error serializing to JSON")]
     Json(#[source] serde_json::Error),
+
+    /// The list can't be written in the requested format.
+    #[error(
+        "{list} can't be written in {format} format\n\
+         (hint: {format} output is only supported when listing tests)"
+    )]
+    UnsupportedFormat {
+        /// The kind of list being written.
+        list: &'static str,
+
+        /// The requested format.
+        format: &'static str,
+    },
 }
 
 /// An error occurred while configuring handles.
@@ -608,6 +1027,43 @@ pub enum ConfigureHandleInheritanceError {
     WindowsError(#[from] windows::core::Error),
 }
 
+/// An error returned when an extra argument to test binaries conflicts with an argument passed in
+/// by nextest.
+///
+/// Returned by [`check_extra_args`](crate::list::check_extra_args).
+#[derive(Clone, Debug, Error)]
+#[error("extra argument `{arg}` conflicts with arguments passed to test binaries by nextest")]
+pub struct ExtraArgConflictError {
+    arg: String,
+}
+
+impl ExtraArgConflictError {
+    pub(crate) fn new(arg: String) -> Self {
+        Self { arg }
+    }
+
+    /// Returns the conflicting argument.
+    pub fn arg(&self) -> &str {
+        &self.arg
+    }
+}
+
+/// An error returned by [`TestRunner::try_execute`](crate::runner::TestRunner::try_execute).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RunError<E> {
+    /// The test run was cancelled through a cancellation token.
+    #[error("test run was cancelled")]
+    Cancelled {
+        /// Statistics for the tests that finished before the run was cancelled.
+        run_stats: RunStats,
+    },
+
+    /// The callback passed into the runner returned an error.
+    #[error(transparent)]
+    Callback(E),
+}
+
 /// An error that occurs while building the test runner.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -619,6 +1075,115 @@ pub enum TestRunnerBuildError {
     /// An error occurred while setting up signals.
     #[error("error setting up signals")]
     SignalHandlerSetupError(#[from] SignalHandlerSetupError),
+
+    /// Stdin passthrough was requested along with more than one test thread.
+    #[error("invalid test runner configuration")]
+    StdinPassthroughWithParallelism(#[from] StdinPassthroughWithParallelismError),
+
+    /// Blocking network access was requested on a platform that doesn't support it.
+    #[error("blocking network access for tests is only supported on Linux (x86_64 and aarch64)")]
+    NetworkSandboxUnsupported,
+
+    /// Detecting leaked processes was requested on a platform that doesn't support it.
+    #[error("detecting leaked processes is only supported on Linux and Windows")]
+    ProcessLeakDetectionUnsupported,
+
+    /// A library to preload into test processes doesn't exist.
+    #[error("library `{0}` to preload into tests doesn't exist")]
+    PreloadLibraryNotFound(Utf8PathBuf),
+
+    /// A directory to exempt from read-only filesystem access doesn't exist.
+    #[error("directory `{0}` to exempt from read-only filesystem access doesn't exist")]
+    FilesystemAccessExceptionNotFound(Utf8PathBuf),
+}
+
+/// An error returned when stdin passthrough is requested with more than one test thread.
+///
+/// Tests can only share the parent's stdin if they run serially.
+#[derive(Clone, Debug, Error)]
+#[error(
+    "stdin passthrough requires tests to be run serially, but {test_threads} test threads were requested"
+)]
+pub struct StdinPassthroughWithParallelismError {
+    test_threads: usize,
+}
+
+impl StdinPassthroughWithParallelismError {
+    pub(crate) fn new(test_threads: usize) -> Self {
+        Self { test_threads }
+    }
+
+    /// Returns the number of test threads that were requested.
+    pub fn test_threads(&self) -> usize {
+        self.test_threads
+    }
+}
+
+/// An error returned while setting up the reporter for `--report-artifacts-to`.
+#[cfg(feature = "remote-reporting")]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RemoteReporterError {
+    /// The URL to report results to is invalid.
+    #[error("invalid URL `{url}` to report results to: {message}")]
+    InvalidUrl {
+        /// The URL that was specified.
+        url: String,
+
+        /// A description of why the URL is invalid.
+        message: String,
+    },
+
+    /// The thread posting results couldn't be spawned.
+    #[error("error spawning thread to report results")]
+    WorkerSpawn(#[source] std::io::Error),
+}
+
+/// An error returned when the `env-file` set for a test can't be read.
+///
+/// This is checked just before each test is run, and reported as the test's output.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum EnvFileError {
+    /// The env file couldn't be read.
+    #[error("error reading env file `{path}` for this test: {error}")]
+    Read {
+        /// The path to the env file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        error: std::io::Error,
+    },
+
+    /// A line in the env file isn't a comment or a `KEY=VALUE` assignment.
+    #[error("invalid line {line_number} in env file `{path}` for this test (expected KEY=VALUE)")]
+    InvalidLine {
+        /// The path to the env file.
+        path: Utf8PathBuf,
+
+        /// The 1-based number of the invalid line.
+        line_number: usize,
+    },
+}
+
+/// An error returned when the `working-dir` set for a test isn't an existing directory.
+///
+/// This is checked just before each test is run, and reported as the test's output.
+#[derive(Clone, Debug, Error)]
+#[error("working directory `{path}` for this test doesn't exist or isn't a directory")]
+pub struct InvalidWorkingDirError {
+    path: Utf8PathBuf,
+}
+
+impl InvalidWorkingDirError {
+    pub(crate) fn new(path: impl Into<Utf8PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the working directory that was requested.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
 }
 
 /// Represents an unknown archive format.
@@ -674,6 +1239,47 @@ pub enum ArchiveCreateError {
         error: std::io::Error,
     },
 
+    /// An error occurred while listing workspace source files.
+    #[error("error listing source files tracked by git in `{workspace_root}`")]
+    SourceList {
+        /// The workspace root.
+        workspace_root: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// The total size of workspace source files exceeded the limit.
+    #[error(
+        "workspace source files are {size} bytes in total, exceeding the limit of {max_size} bytes"
+    )]
+    SourceTooLarge {
+        /// The total size of source files, in bytes.
+        size: u64,
+
+        /// The maximum size of source files, in bytes.
+        max_size: u64,
+    },
+
+    /// An error occurred while creating a temporary directory for stripped binaries.
+    #[error("error creating temporary directory for stripped binaries")]
+    TempDirCreate(#[source] std::io::Error),
+
+    /// An error occurred while stripping debug info from a binary.
+    #[error("error stripping debug info from `{path}` with `{tool}`")]
+    StripDebugInfo {
+        /// The binary that debug info couldn't be stripped from.
+        path: Utf8PathBuf,
+
+        /// The tool used to strip debug info.
+        tool: String,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+
     /// An error occurred while writing data to the output file.
     #[error("error writing to archive")]
     OutputArchiveIo(#[source] std::io::Error),
@@ -705,8 +1311,8 @@ pub enum ArchiveReadError {
     #[error("path in archive `{}` wasn't valid UTF-8", String::from_utf8_lossy(.0))]
     NonUtf8Path(Vec<u8>),
 
-    /// A file path within the archive didn't begin with "target/".
-    #[error("path in archive `{0}` doesn't start with `target/`")]
+    /// A file path within the archive didn't begin with "target/" or "source/".
+    #[error("path in archive `{0}` doesn't start with `target/` or `source/`")]
     NoTargetPrefix(Utf8PathBuf),
 
     /// A file path within the archive had an invalid component within it.
@@ -850,6 +1456,60 @@ pub enum WriteEventError {
     },
 }
 
+/// An error that occurs while reading a [`RunSummary`](crate::reporter::RunSummary).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RunSummaryReadError {
+    /// An error occurred while reading the summary file.
+    #[error("error reading run summary from `{path}`")]
+    Read {
+        /// The path to the summary file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// The summary file couldn't be parsed.
+    #[error("error parsing run summary from `{path}`")]
+    Parse {
+        /// The path to the summary file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: serde_json::Error,
+    },
+}
+
+/// An error that occurs while reading [`TimingData`](crate::partition::TimingData) from a file.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TimingDataReadError {
+    /// An error occurred while reading the timing data file.
+    #[error("error reading timing data from `{path}`")]
+    Read {
+        /// The path to the timing data file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// The timing data file couldn't be parsed.
+    #[error("error parsing timing data from `{path}`")]
+    Parse {
+        /// The path to the timing data file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: serde_json::Error,
+    },
+}
+
 /// An error occurred while constructing a [`CargoConfigs`](crate::cargo_config::CargoConfigs)
 /// instance.
 #[derive(Debug, Error)]
@@ -1014,6 +1674,42 @@ pub enum TargetRunnerError {
         /// The value that was read from the key
         value: String,
     },
+
+    /// The runner binary runs binaries for a different architecture than the platform it was
+    /// configured for.
+    #[error(
+        "runner `{runner}` is for architecture `{runner_arch}`, but tests are built for `{target_arch}`"
+    )]
+    IncompatibleRunnerArch {
+        /// The runner binary.
+        runner: Utf8PathBuf,
+
+        /// The architecture the runner runs binaries for.
+        runner_arch: String,
+
+        /// The architecture of the platform tests are built for.
+        target_arch: String,
+    },
+
+    /// The runner binary couldn't be found.
+    #[error("runner `{runner}` specified by {key} was not found")]
+    BinaryNotFound {
+        /// The runner binary.
+        runner: Utf8PathBuf,
+
+        /// The source under consideration.
+        key: PlatformRunnerSource,
+    },
+
+    /// The runner binary was found but isn't executable.
+    #[error("runner `{runner}` specified by {key} is not executable")]
+    BinaryNotExecutable {
+        /// The path to the runner binary.
+        runner: Utf8PathBuf,
+
+        /// The source under consideration.
+        key: PlatformRunnerSource,
+    },
 }
 
 /// An error that occurred while setting up the signal handler.
@@ -0,0 +1,279 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Making the workspace read-only for test processes.
+//!
+//! On Linux, each test process is moved into new user and mount namespaces just before it's
+//! executed, and the workspace is bind-mounted over itself as read-only. Writes to the workspace
+//! then fail with `EROFS`. Exceptions are bind-mounted over themselves before the workspace is made
+//! read-only, so they stay writable.
+
+pub(crate) use imp::ReadOnlySandbox;
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use camino::{Utf8Path, Utf8PathBuf};
+    use std::{
+        ffi::{CStr, CString},
+        io,
+        os::unix::process::CommandExt,
+        process::Command,
+        ptr,
+        sync::Arc,
+    };
+
+    /// Paths and data needed to set up the sandbox, prepared ahead of time since allocating memory
+    /// isn't safe in pre_exec.
+    #[derive(Debug)]
+    struct SandboxData {
+        workspace_root: CString,
+        exceptions: Vec<CString>,
+        // Mount flags such as nosuid that must be preserved when remounting the workspace.
+        locked_flags: libc::c_ulong,
+        uid_map: CString,
+        gid_map: CString,
+    }
+
+    #[derive(Clone, Debug)]
+    pub(crate) struct ReadOnlySandbox {
+        data: Arc<SandboxData>,
+    }
+
+    impl ReadOnlySandbox {
+        /// Prepares a sandbox for `workspace_root`, where `exceptions` stay writable.
+        ///
+        /// Exceptions outside the workspace are ignored, since they're writable anyway. Returns an
+        /// error if the sandbox can't be set up on this system, e.g. because unprivileged user
+        /// namespaces are disabled.
+        pub(crate) fn new(
+            workspace_root: &Utf8Path,
+            exceptions: &[Utf8PathBuf],
+        ) -> io::Result<Self> {
+            let workspace_root = workspace_root.canonicalize_utf8()?;
+            let mut canonical_exceptions = Vec::with_capacity(exceptions.len());
+            for exception in exceptions {
+                let exception = exception.canonicalize_utf8()?;
+                if exception.starts_with(&workspace_root) {
+                    canonical_exceptions.push(to_cstring(&exception)?);
+                }
+            }
+
+            let workspace_root = to_cstring(&workspace_root)?;
+            let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+            if unsafe { libc::statvfs(workspace_root.as_ptr(), stat.as_mut_ptr()) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let stat = unsafe { stat.assume_init() };
+            let locked_flags = [
+                (libc::ST_NOSUID, libc::MS_NOSUID),
+                (libc::ST_NODEV, libc::MS_NODEV),
+                (libc::ST_NOEXEC, libc::MS_NOEXEC),
+                (libc::ST_NOATIME, libc::MS_NOATIME),
+                (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+                (libc::ST_RELATIME, libc::MS_RELATIME),
+            ]
+            .iter()
+            .filter(|(st_flag, _)| stat.f_flag & st_flag != 0)
+            .fold(0, |flags, (_, ms_flag)| flags | ms_flag);
+
+            // Map the current user and group to themselves within the user namespace, so that
+            // tests see the same IDs as outside it.
+            let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+            let sandbox = Self {
+                data: Arc::new(SandboxData {
+                    workspace_root,
+                    exceptions: canonical_exceptions,
+                    locked_flags,
+                    uid_map: CString::new(format!("{uid} {uid} 1")).expect("no nul bytes"),
+                    gid_map: CString::new(format!("{gid} {gid} 1")).expect("no nul bytes"),
+                }),
+            };
+            sandbox.probe()?;
+            Ok(sandbox)
+        }
+
+        /// Sets up the sandbox in the process spawned by `cmd`.
+        pub(crate) fn apply(&self, cmd: &mut Command) {
+            let data = self.data.clone();
+            unsafe {
+                cmd.pre_exec(move || data.enter());
+            }
+        }
+
+        /// Checks that the sandbox can be set up, by doing so in a forked child process.
+        fn probe(&self) -> io::Result<()> {
+            unsafe {
+                match libc::fork() {
+                    -1 => Err(io::Error::last_os_error()),
+                    0 => {
+                        // Only async-signal-safe functions may be called here.
+                        let code = match self.data.enter() {
+                            Ok(()) => 0,
+                            Err(error) => error.raw_os_error().unwrap_or(libc::EINVAL),
+                        };
+                        libc::_exit(code);
+                    }
+                    pid => {
+                        let mut status = 0;
+                        if libc::waitpid(pid, &mut status, 0) == -1 {
+                            return Err(io::Error::last_os_error());
+                        }
+                        match libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status)) {
+                            Some(0) => Ok(()),
+                            Some(errno) => Err(io::Error::from_raw_os_error(errno)),
+                            None => Err(io::Error::new(
+                                io::ErrorKind::Other,
+                                "sandbox probe process was killed",
+                            )),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    impl SandboxData {
+        fn enter(&self) -> io::Result<()> {
+            unsafe {
+                check(libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS))?;
+
+                // setgroups must be denied before gid_map can be written. (This file doesn't exist
+                // on kernels older than 3.19.)
+                match write_file(cstr(b"/proc/self/setgroups\0"), b"deny") {
+                    Err(error) if error.raw_os_error() == Some(libc::ENOENT) => {}
+                    other => other?,
+                }
+                write_file(cstr(b"/proc/self/uid_map\0"), self.uid_map.as_bytes())?;
+                write_file(cstr(b"/proc/self/gid_map\0"), self.gid_map.as_bytes())?;
+
+                // Don't propagate any of the mounts below outside the mount namespace.
+                check(libc::mount(
+                    ptr::null(),
+                    cstr(b"/\0").as_ptr(),
+                    ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    ptr::null(),
+                ))?;
+                bind_mount(&self.workspace_root)?;
+                for exception in &self.exceptions {
+                    bind_mount(exception)?;
+                }
+                // This only affects the workspace mount, not the exceptions mounted within it.
+                check(libc::mount(
+                    ptr::null(),
+                    self.workspace_root.as_ptr(),
+                    ptr::null(),
+                    libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY | self.locked_flags,
+                    ptr::null(),
+                ))?;
+            }
+            Ok(())
+        }
+    }
+
+    unsafe fn bind_mount(path: &CStr) -> io::Result<()> {
+        check(libc::mount(
+            path.as_ptr(),
+            path.as_ptr(),
+            ptr::null(),
+            libc::MS_BIND | libc::MS_REC,
+            ptr::null(),
+        ))
+    }
+
+    unsafe fn write_file(path: &CStr, contents: &[u8]) -> io::Result<()> {
+        let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        check(fd)?;
+        let written = libc::write(fd, contents.as_ptr().cast(), contents.len());
+        let res = if written == contents.len() as isize {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        };
+        libc::close(fd);
+        res
+    }
+
+    fn check(ret: libc::c_int) -> io::Result<()> {
+        if ret == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn cstr(bytes: &'static [u8]) -> &'static CStr {
+        CStr::from_bytes_with_nul(bytes).expect("static string is nul-terminated")
+    }
+
+    fn to_cstring(path: &Utf8Path) -> io::Result<CString> {
+        CString::new(path.as_str())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use tempfile::tempdir;
+
+        #[test]
+        fn test_read_only_sandbox() {
+            let dir = tempdir().unwrap();
+            let workspace_root: &Utf8Path = dir.path().try_into().unwrap();
+            let exception = workspace_root.join("writable");
+            std::fs::create_dir(&exception).unwrap();
+
+            let sandbox =
+                match ReadOnlySandbox::new(workspace_root, std::slice::from_ref(&exception)) {
+                    Ok(sandbox) => sandbox,
+                    Err(error) => {
+                        eprintln!("skipping test, sandbox is unsupported: {error}");
+                        return;
+                    }
+                };
+
+            let write = |path: &Utf8Path| {
+                let mut cmd = Command::new("sh");
+                cmd.args(["-c", &format!("echo hello > {path}")]);
+                sandbox.apply(&mut cmd);
+                cmd.output().expect("sh is available")
+            };
+
+            let output = write(&workspace_root.join("file"));
+            assert!(!output.status.success(), "workspace is read-only");
+            assert!(
+                String::from_utf8_lossy(&output.stderr).contains("Read-only file system"),
+                "write fails with EROFS: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert!(!workspace_root.join("file").exists());
+
+            let output = write(&exception.join("file"));
+            assert!(output.status.success(), "exceptions are writable");
+            assert!(exception.join("file").exists());
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use camino::{Utf8Path, Utf8PathBuf};
+    use std::{io, process::Command};
+
+    #[derive(Clone, Debug)]
+    pub(crate) struct ReadOnlySandbox(());
+
+    impl ReadOnlySandbox {
+        pub(crate) fn new(
+            _workspace_root: &Utf8Path,
+            _exceptions: &[Utf8PathBuf],
+        ) -> io::Result<Self> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "read-only filesystem access is only supported on Linux",
+            ))
+        }
+
+        pub(crate) fn apply(&self, _cmd: &mut Command) {}
+    }
+}
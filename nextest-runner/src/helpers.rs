@@ -85,6 +85,16 @@ pub(crate) fn dylib_path() -> Vec<PathBuf> {
     }
 }
 
+/// The environment variable that lists libraries to load into processes ahead of all others, if
+/// the platform has one.
+pub(crate) const PRELOAD_ENV_VAR: Option<&str> = if cfg!(target_os = "macos") {
+    Some("DYLD_INSERT_LIBRARIES")
+} else if cfg!(windows) {
+    None
+} else {
+    Some("LD_PRELOAD")
+};
+
 /// On Windows, convert relative paths to always use forward slashes.
 #[cfg(windows)]
 pub(crate) fn convert_rel_path_to_forward_slash(rel_path: &Utf8Path) -> Utf8PathBuf {
@@ -198,6 +208,25 @@ where
     }
 }
 
+/// Returns the Levenshtein distance between `a` and `b`, counted in characters.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // prev_row[j] is the distance between the previous prefix of a and the first j chars of b.
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    let mut row = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution_cost = usize::from(a_char != *b_char);
+            row[j + 1] = (prev_row[j] + substitution_cost)
+                .min(prev_row[j + 1] + 1)
+                .min(row[j] + 1);
+        }
+        std::mem::swap(&mut prev_row, &mut row);
+    }
+    prev_row[b.len()]
+}
+
 // From https://twitter.com/8051Enthusiast/status/1571909110009921538
 extern "C" {
     fn __nextest_external_symbol_that_does_not_exist();
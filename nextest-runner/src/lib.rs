@@ -10,23 +10,31 @@
 //! post](https://sunshowers.io/posts/nextest-and-tokio/).
 
 pub mod cargo_config;
+pub mod command_wrapper;
 pub mod config;
 #[cfg(feature = "experimental-tokio-console")]
 pub mod console;
 pub mod double_spawn;
+mod env_file;
 pub mod errors;
+mod filesystem_sandbox;
 mod helpers;
+mod libtest_output;
 pub mod list;
+mod network_sandbox;
 pub mod partition;
 pub mod platform;
+mod process_leak;
 pub mod reporter;
 pub mod reuse_build;
 pub mod runner;
 pub mod show_config;
 pub mod signal;
+pub mod state_snapshot;
 pub mod target_runner;
 mod test_command;
 pub mod test_filter;
 mod time;
+mod timestamped_reader;
 #[cfg(feature = "self-update")]
 pub mod update;
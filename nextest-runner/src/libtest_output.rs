@@ -0,0 +1,151 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parsing the output of a test binary that ran several tests in one process.
+//!
+//! This is used for `--test-isolation thread` and `none`. Test binaries are run with
+//! `--show-output`, so libtest prints a `test <name> ... <result>` line for each test, followed by
+//! a `---- <name> stdout ----` section with the captured output of each test that printed
+//! anything.
+
+use std::collections::HashMap;
+
+/// The result of a single test, as reported by libtest.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum LibtestResult {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// The result and captured output of a single test.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct LibtestTestOutput {
+    /// The result, or `None` if the test binary exited before the test finished.
+    pub(crate) result: Option<LibtestResult>,
+    pub(crate) output: String,
+}
+
+/// Parses libtest's stdout, returning the results and output of each test, keyed by test name.
+pub(crate) fn parse_libtest_output(stdout: &str) -> HashMap<&str, LibtestTestOutput> {
+    let mut tests: HashMap<&str, LibtestTestOutput> = HashMap::new();
+    // The test whose output section is being read, along with the output so far.
+    let mut section: Option<(&str, String)> = None;
+
+    for line in stdout.lines() {
+        let header = line
+            .strip_prefix("---- ")
+            .and_then(|line| line.strip_suffix(" stdout ----"));
+        if header.is_some() || line == "successes:" || line == "failures:" {
+            if let Some((name, output)) = section.take() {
+                tests.entry(name).or_default().output = trim_output(output);
+            }
+            section = header.map(|name| (name, String::new()));
+            continue;
+        }
+
+        if let Some((_, output)) = &mut section {
+            output.push_str(line);
+            output.push('\n');
+        } else if let Some((name, result)) = line
+            .strip_prefix("test ")
+            .and_then(|line| line.split_once(" ... "))
+        {
+            let result = if result == "ok" || result.starts_with("bench:") {
+                LibtestResult::Passed
+            } else if result == "FAILED" {
+                LibtestResult::Failed
+            } else if result.starts_with("ignored") {
+                LibtestResult::Ignored
+            } else {
+                continue;
+            };
+            tests.entry(name).or_default().result = Some(result);
+        }
+    }
+
+    if let Some((name, output)) = section {
+        tests.entry(name).or_default().output = trim_output(output);
+    }
+    tests
+}
+
+/// Removes the blank lines libtest prints after each output section.
+fn trim_output(mut output: String) -> String {
+    output.truncate(output.trim_end().len());
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_parse_libtest_output() {
+        let stdout = indoc! {"
+
+            running 4 tests
+            test tests::quiet ... ok
+            test tests::noisy ... ok
+            test tests::fails ... FAILED
+            test tests::skipped ... ignored, not ready
+
+            successes:
+
+            ---- tests::noisy stdout ----
+            hello
+            world
+
+
+            successes:
+                tests::noisy
+                tests::quiet
+
+            failures:
+
+            ---- tests::fails stdout ----
+            thread 'tests::fails' panicked at 'assertion failed', src/lib.rs:10:9
+            note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+            failures:
+                tests::fails
+
+            test result: FAILED. 2 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+        "};
+
+        let tests = parse_libtest_output(stdout);
+        assert_eq!(tests.len(), 4);
+        assert_eq!(
+            tests["tests::quiet"],
+            LibtestTestOutput {
+                result: Some(LibtestResult::Passed),
+                output: String::new(),
+            }
+        );
+        assert_eq!(
+            tests["tests::noisy"],
+            LibtestTestOutput {
+                result: Some(LibtestResult::Passed),
+                output: "hello\nworld\n".to_owned(),
+            }
+        );
+        assert_eq!(tests["tests::fails"].result, Some(LibtestResult::Failed));
+        assert!(tests["tests::fails"]
+            .output
+            .starts_with("thread 'tests::fails' panicked"));
+        assert!(tests["tests::fails"]
+            .output
+            .ends_with("to display a backtrace\n"));
+        assert_eq!(tests["tests::skipped"].result, Some(LibtestResult::Ignored));
+
+        // A test that was running when the process crashed has no result.
+        let tests = parse_libtest_output("running 2 tests\ntest a ... ok\n");
+        assert_eq!(tests.len(), 1);
+        assert!(!tests.contains_key("b"));
+    }
+}
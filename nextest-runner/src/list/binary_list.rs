@@ -12,7 +12,7 @@ use cargo_metadata::{Artifact, BuildScript, Message, PackageId};
 use guppy::graph::PackageGraph;
 use nextest_metadata::{
     BinaryListSummary, BuildPlatform, RustBinaryId, RustNonTestBinaryKind,
-    RustNonTestBinarySummary, RustTestBinaryKind, RustTestBinarySummary,
+    RustNonTestBinarySummary, RustTestBinaryKind, RustTestBinarySummary, SkippedPackageSummary,
 };
 use owo_colors::OwoColorize;
 use std::{fmt::Write as _, io, io::Write};
@@ -76,8 +76,17 @@ impl BinaryList {
                 build_platform: bin.build_platform,
             })
             .collect();
+        let mut rust_build_meta = RustBuildMeta::from_summary(summary.rust_build_meta)?;
+        rust_build_meta.build_script_failures = summary
+            .skipped_packages
+            .into_iter()
+            .filter_map(|skipped| match skipped {
+                SkippedPackageSummary::BuildScriptFailed(failure) => Some(failure),
+                _ => None,
+            })
+            .collect();
         Ok(Self {
-            rust_build_meta: RustBuildMeta::from_summary(summary.rust_build_meta)?,
+            rust_build_meta,
             rust_binaries,
         })
     }
@@ -96,6 +105,10 @@ impl BinaryList {
             OutputFormat::Serializable(format) => format
                 .to_writer(&self.to_summary(), writer)
                 .map_err(WriteTestListError::Json),
+            OutputFormat::Dot => Err(WriteTestListError::UnsupportedFormat {
+                list: "binary lists",
+                format: "DOT",
+            }),
         }
     }
 
@@ -119,6 +132,7 @@ impl BinaryList {
         BinaryListSummary {
             rust_build_meta: self.rust_build_meta.to_summary(),
             rust_binaries,
+            skipped_packages: self.rust_build_meta.skipped_packages_summary(),
         }
     }
 
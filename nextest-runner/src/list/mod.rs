@@ -19,6 +19,9 @@ pub use output_format::*;
 pub use rust_build_meta::*;
 pub use test_list::*;
 
+#[cfg(test)]
+pub(crate) mod test_helpers;
+
 /// Typestate for [`BinaryList`].
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum BinaryListState {}
@@ -21,6 +21,11 @@ pub enum OutputFormat {
 
     /// Machine-readable output format.
     Serializable(SerializableFormat),
+
+    /// A [GraphViz](https://graphviz.org) DOT graph.
+    ///
+    /// Only supported for test lists.
+    Dot,
 }
 
 /// A serialized, machine-readable output format.
@@ -10,7 +10,9 @@ use crate::{
     reuse_build::PathMapper,
 };
 use camino::Utf8PathBuf;
-use nextest_metadata::{RustBuildMetaSummary, RustNonTestBinarySummary};
+use nextest_metadata::{
+    BuildScriptFailure, RustBuildMetaSummary, RustNonTestBinarySummary, SkippedPackageSummary,
+};
 use std::{
     collections::{BTreeMap, BTreeSet},
     marker::PhantomData,
@@ -41,6 +43,15 @@ pub struct RustBuildMeta<State> {
     /// The target triple used while compiling the artifacts
     pub target_triple: Option<TargetTriple>,
 
+    /// Packages whose build scripts failed. Tests for these packages weren't built.
+    ///
+    /// These are serialized as skipped packages in binary and test list summaries, not as part of
+    /// the [`RustBuildMetaSummary`].
+    pub build_script_failures: Vec<BuildScriptFailure>,
+
+    /// Whether debug info was stripped from the binaries while archiving them.
+    pub debug_info_stripped: bool,
+
     state: PhantomData<State>,
 }
 
@@ -57,6 +68,8 @@ impl RustBuildMeta<BinaryListState> {
             linked_paths: BTreeMap::new(),
             state: PhantomData,
             target_triple,
+            build_script_failures: Vec::new(),
+            debug_info_stripped: false,
         }
     }
 
@@ -73,6 +86,8 @@ impl RustBuildMeta<BinaryListState> {
             linked_paths: self.linked_paths.clone(),
             state: PhantomData,
             target_triple: self.target_triple.clone(),
+            build_script_failures: self.build_script_failures.clone(),
+            debug_info_stripped: self.debug_info_stripped,
         }
     }
 }
@@ -88,6 +103,8 @@ impl RustBuildMeta<TestListState> {
             linked_paths: BTreeMap::new(),
             state: PhantomData,
             target_triple: None,
+            build_script_failures: Vec::new(),
+            debug_info_stripped: false,
         }
     }
 
@@ -150,6 +167,8 @@ impl<State> RustBuildMeta<State> {
                 .collect(),
             state: PhantomData,
             target_triple,
+            build_script_failures: Vec::new(),
+            debug_info_stripped: summary.debug_info_stripped,
         })
     }
 
@@ -168,6 +187,16 @@ impl<State> RustBuildMeta<State> {
             target_platforms: TargetTriple::serialize(self.target_triple.as_ref())
                 .into_iter()
                 .collect(),
+            debug_info_stripped: self.debug_info_stripped,
         }
     }
+
+    /// Returns the packages whose tests weren't built, in a serializable form.
+    pub fn skipped_packages_summary(&self) -> Vec<SkippedPackageSummary> {
+        self.build_script_failures
+            .iter()
+            .cloned()
+            .map(SkippedPackageSummary::BuildScriptFailed)
+            .collect()
+    }
 }
@@ -0,0 +1,27 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use guppy::{
+    graph::{PackageGraph, PackageMetadata},
+    CargoMetadata, PackageId,
+};
+use once_cell::sync::Lazy;
+
+pub(crate) static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
+    static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
+    let metadata = CargoMetadata::parse_json(FIXTURE_JSON).expect("fixture is valid JSON");
+    metadata
+        .build_graph()
+        .expect("fixture is valid PackageGraph")
+});
+
+pub(crate) static HELPER_PACKAGE_ID: &str =
+    "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)";
+pub(crate) static BASE_PACKAGE_ID: &str =
+    "metadata-base 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-base)";
+
+pub(crate) fn package_metadata(id: &str) -> PackageMetadata<'static> {
+    PACKAGE_GRAPH_FIXTURE
+        .metadata(&PackageId::new(id))
+        .expect("package ID is valid")
+}
@@ -4,8 +4,13 @@
 use super::{DisplayFilterMatcher, TestListDisplayFilter};
 use crate::{
     cargo_config::EnvironmentMap,
+    command_wrapper::CommandWrapper,
     double_spawn::DoubleSpawnInfo,
-    errors::{CreateTestListError, FromMessagesError, WriteTestListError},
+    errors::{
+        BinaryPathOverrideError, BinaryPathOverrideParseError, CreateTestListError,
+        ExpectedTestCountParseError, ExtraArgConflictError, FromMessagesError,
+        TestCountAssertionError, WriteTestListError,
+    },
     helpers::{convert_build_platform, dylib_path, dylib_path_envvar, write_test_name},
     list::{BinaryList, OutputFormat, RustBuildMeta, Styles, TestListState},
     reuse_build::PathMapper,
@@ -13,7 +18,7 @@ use crate::{
     test_command::{LocalExecuteContext, TestCommand},
     test_filter::TestFilterBuilder,
 };
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use futures::prelude::*;
 use guppy::{
     graph::{PackageGraph, PackageMetadata},
@@ -21,18 +26,22 @@ use guppy::{
 };
 use nextest_filtering::{BinaryQuery, TestQuery};
 use nextest_metadata::{
-    BuildPlatform, RustBinaryId, RustNonTestBinaryKind, RustTestBinaryKind, RustTestBinarySummary,
-    RustTestCaseSummary, RustTestSuiteStatusSummary, RustTestSuiteSummary, TestListSummary,
+    BuildPlatform, FilterMatch, MismatchReason, RustBinaryId, RustNonTestBinaryKind,
+    RustTestBinaryKind, RustTestBinarySummary, RustTestCaseSummary, RustTestSuiteStatusSummary,
+    RustTestSuiteSummary, TestListSummary,
 };
 use once_cell::sync::{Lazy, OnceCell};
 use owo_colors::OwoColorize;
+use regex::{Regex, RegexBuilder};
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     ffi::{OsStr, OsString},
-    io,
+    fmt, io,
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    str::FromStr,
     sync::Arc,
+    time::SystemTime,
 };
 use tokio::runtime::Runtime;
 
@@ -144,6 +153,47 @@ impl<'g> RustTestArtifact<'g> {
         Ok(binaries)
     }
 
+    /// Replaces the paths of test binaries with the given overrides.
+    ///
+    /// Relative paths are resolved against the current directory. A warning is logged for each
+    /// override that's older than the newest source file in its package, since it may be out of
+    /// date.
+    pub fn apply_path_overrides(
+        artifacts: &mut [Self],
+        overrides: &[BinaryPathOverride],
+    ) -> Result<(), BinaryPathOverrideError> {
+        for path_override in overrides {
+            let artifact = artifacts
+                .iter_mut()
+                .find(|artifact| artifact.binary_id == path_override.binary_id)
+                .ok_or_else(|| BinaryPathOverrideError::BinaryIdNotFound {
+                    binary_id: path_override.binary_id.clone(),
+                })?;
+            let path = path_override.path.canonicalize_utf8().map_err(|error| {
+                BinaryPathOverrideError::PathResolve {
+                    binary_id: path_override.binary_id.clone(),
+                    path: path_override.path.clone(),
+                    error,
+                }
+            })?;
+
+            let binary_mtime = path.metadata().and_then(|metadata| metadata.modified());
+            if let (Ok(binary_mtime), Some(source_mtime)) =
+                (binary_mtime, newest_source_mtime(&artifact.cwd))
+            {
+                if binary_mtime < source_mtime {
+                    log::warn!(
+                        "test binary `{path}` for `{}` is older than the source files in its \
+                         package (hint: it may need to be rebuilt)",
+                        artifact.binary_id,
+                    );
+                }
+            }
+            artifact.binary_path = path;
+        }
+        Ok(())
+    }
+
     // ---
     // Helper methods
     // ---
@@ -175,6 +225,131 @@ impl<'g> RustTestArtifact<'g> {
     }
 }
 
+/// Returns the modification time of the newest source file in the package at `package_dir`.
+fn newest_source_mtime(package_dir: &Utf8Path) -> Option<SystemTime> {
+    fn visit(path: &Path, newest: &mut Option<SystemTime>) {
+        let metadata = match path.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => return,
+        };
+        if metadata.is_dir() {
+            if let Ok(entries) = std::fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    visit(&entry.path(), newest);
+                }
+            }
+        } else if let Ok(modified) = metadata.modified() {
+            *newest = (*newest).max(Some(modified));
+        }
+    }
+
+    let mut newest = None;
+    for name in [
+        "Cargo.toml",
+        "build.rs",
+        "src",
+        "tests",
+        "benches",
+        "examples",
+    ] {
+        visit(package_dir.join(name).as_std_path(), &mut newest);
+    }
+    newest
+}
+
+/// Replaces the path of a test binary, e.g. with one that was rebuilt outside of Cargo.
+///
+/// Parsed from `BINARY_ID=PATH`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BinaryPathOverride {
+    /// The ID of the binary to replace.
+    pub binary_id: RustBinaryId,
+
+    /// The path to the replacement binary.
+    pub path: Utf8PathBuf,
+}
+
+impl FromStr for BinaryPathOverride {
+    type Err = BinaryPathOverrideParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (binary_id, path) = s
+            .split_once('=')
+            .ok_or_else(|| BinaryPathOverrideParseError::new(s, "missing `=`"))?;
+        if binary_id.is_empty() {
+            return Err(BinaryPathOverrideParseError::new(s, "binary ID is empty"));
+        }
+        if path.is_empty() {
+            return Err(BinaryPathOverrideParseError::new(s, "path is empty"));
+        }
+        Ok(Self {
+            binary_id: RustBinaryId::new(binary_id),
+            path: path.into(),
+        })
+    }
+}
+
+/// The number of tests expected to run, e.g. to catch tests that were deleted by accident.
+///
+/// Parsed from `N`, or `PACKAGE:N` to count tests in a single package.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpectedTestCount {
+    /// The package to count tests in, or `None` to count tests across all packages.
+    pub package: Option<String>,
+
+    /// The expected number of tests.
+    pub count: usize,
+}
+
+impl FromStr for ExpectedTestCount {
+    type Err = ExpectedTestCountParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (package, count) = match s.rsplit_once(':') {
+            Some((package, count)) if !package.is_empty() => (Some(package.to_owned()), count),
+            Some(_) => return Err(ExpectedTestCountParseError::new(s)),
+            None => (None, s),
+        };
+        let count = count
+            .parse()
+            .map_err(|_| ExpectedTestCountParseError::new(s))?;
+        Ok(Self { package, count })
+    }
+}
+
+/// How the number of tests to run is compared against an [`ExpectedTestCount`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TestCountBound {
+    /// The number of tests must be exactly the expected count.
+    Exact,
+
+    /// The number of tests must be at least the expected count.
+    Min,
+
+    /// The number of tests must be at most the expected count.
+    Max,
+}
+
+impl TestCountBound {
+    fn is_satisfied(self, actual: usize, expected: usize) -> bool {
+        match self {
+            Self::Exact => actual == expected,
+            Self::Min => actual >= expected,
+            Self::Max => actual <= expected,
+        }
+    }
+}
+
+impl fmt::Display for TestCountBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact => write!(f, "exactly"),
+            Self::Min => write!(f, "at least"),
+            Self::Max => write!(f, "at most"),
+        }
+    }
+}
+
 /// List of test instances, obtained by querying the [`RustTestArtifact`] instances generated by Cargo.
 #[derive(Clone, Debug)]
 pub struct TestList<'g> {
@@ -183,6 +358,7 @@ pub struct TestList<'g> {
     rust_suites: BTreeMap<RustBinaryId, RustTestSuite<'g>>,
     env: EnvironmentMap,
     updated_dylib_path: OsString,
+    extra_args: Vec<String>,
     // Computed on first access.
     skip_count: OnceCell<usize>,
 }
@@ -207,11 +383,13 @@ impl<'g> TestList<'g> {
             dylib_path_envvar(),
             updated_dylib_path.to_string_lossy(),
         );
+        let extra_args = ctx.extra_args.to_vec();
         let ctx = LocalExecuteContext {
             double_spawn: ctx.double_spawn,
             runner: ctx.target_runner,
             dylib_path: &updated_dylib_path,
             env: &env,
+            extra_args: ctx.extra_args,
         };
 
         let runtime = Runtime::new().map_err(CreateTestListError::TokioRuntimeCreate)?;
@@ -252,6 +430,7 @@ impl<'g> TestList<'g> {
             env,
             rust_build_meta,
             updated_dylib_path,
+            extra_args,
             test_count,
             skip_count: OnceCell::new(),
         })
@@ -259,7 +438,7 @@ impl<'g> TestList<'g> {
 
     /// Creates a new test list with the given binary names and outputs.
     #[cfg(test)]
-    fn new_with_outputs(
+    pub(crate) fn new_with_outputs(
         test_bin_outputs: impl IntoIterator<
             Item = (RustTestArtifact<'g>, impl AsRef<str>, impl AsRef<str>),
         >,
@@ -295,6 +474,7 @@ impl<'g> TestList<'g> {
             env,
             rust_build_meta,
             updated_dylib_path,
+            extra_args: Vec::new(),
             test_count,
             skip_count: OnceCell::new(),
         })
@@ -326,6 +506,36 @@ impl<'g> TestList<'g> {
         self.test_count - self.skip_count()
     }
 
+    /// Checks the number of tests that will be run against `expected`.
+    ///
+    /// Skipped tests aren't counted.
+    pub fn check_test_count(
+        &self,
+        bound: TestCountBound,
+        expected: &ExpectedTestCount,
+    ) -> Result<(), TestCountAssertionError> {
+        let actual = match &expected.package {
+            Some(package) => self
+                .iter_tests()
+                .filter(|instance| {
+                    instance.suite_info.package.name() == package
+                        && instance.test_info.filter_match.is_match()
+                })
+                .count(),
+            None => self.run_count(),
+        };
+        if bound.is_satisfied(actual, expected.count) {
+            Ok(())
+        } else {
+            Err(TestCountAssertionError {
+                package: expected.package.clone(),
+                bound,
+                expected: expected.count,
+                actual,
+            })
+        }
+    }
+
     /// Returns the total number of binaries that contain tests.
     pub fn binary_count(&self) -> usize {
         self.rust_suites.len()
@@ -336,6 +546,19 @@ impl<'g> TestList<'g> {
         &self.updated_dylib_path
     }
 
+    /// Returns the environment variables from the `[env]` section of Cargo configuration.
+    pub(crate) fn cargo_env(&self) -> &EnvironmentMap {
+        &self.env
+    }
+
+    /// Returns the extra arguments passed to every test binary invocation.
+    ///
+    /// These are the arguments the test list was created with, and are also used while running
+    /// tests.
+    pub fn extra_args(&self) -> &[String] {
+        &self.extra_args
+    }
+
     /// Constructs a serializble summary for this test list.
     pub fn to_summary(&self) -> TestListSummary {
         let rust_suites = self
@@ -363,6 +586,7 @@ impl<'g> TestList<'g> {
         let mut summary = TestListSummary::new(self.rust_build_meta.to_summary());
         summary.test_count = self.test_count;
         summary.rust_suites = rust_suites;
+        summary.skipped_packages = self.rust_build_meta.skipped_packages_summary();
         summary
     }
 
@@ -370,7 +594,7 @@ impl<'g> TestList<'g> {
     pub fn write(
         &self,
         output_format: OutputFormat,
-        writer: impl Write,
+        mut writer: impl Write,
         colorize: bool,
     ) -> Result<(), WriteTestListError> {
         match output_format {
@@ -380,6 +604,9 @@ impl<'g> TestList<'g> {
             OutputFormat::Serializable(format) => format
                 .to_writer(&self.to_summary(), writer)
                 .map_err(WriteTestListError::Json),
+            OutputFormat::Dot => writer
+                .write_all(self.to_dot_graph().as_bytes())
+                .map_err(WriteTestListError::Io),
         }
     }
 
@@ -398,6 +625,101 @@ impl<'g> TestList<'g> {
         })
     }
 
+    /// Returns a copy of this list where only the tests for which `f` returns true are run.
+    ///
+    /// Other tests that would have been run are marked as not matching, with the given reason.
+    pub fn retain_tests(
+        &self,
+        mut f: impl FnMut(&TestInstance<'_>) -> bool,
+        reason: MismatchReason,
+    ) -> Self {
+        let to_skip: HashSet<(&RustBinaryId, &str)> = self
+            .iter_tests()
+            .filter(|instance| instance.test_info.filter_match.is_match() && !f(instance))
+            .map(|instance| (&instance.suite_info.binary_id, instance.name))
+            .collect();
+
+        let mut list = self.clone();
+        list.skip_count = OnceCell::new();
+        for (binary_id, suite) in &mut list.rust_suites {
+            if let RustTestSuiteStatus::Listed { test_cases } = &mut suite.status {
+                for (name, test_case) in test_cases {
+                    if to_skip.contains(&(binary_id, name.as_str())) {
+                        test_case.filter_match = FilterMatch::Mismatch { reason };
+                    }
+                }
+            }
+        }
+        list
+    }
+
+    /// Returns this list as a [GraphViz](https://graphviz.org) DOT graph.
+    ///
+    /// Each package is a cluster containing its test binaries, labeled with the number of tests
+    /// that will be run. Binaries skipped by filter expressions are dashed. Non-test binaries that
+    /// a test binary can depend on (through `CARGO_BIN_EXE_<name>`) are boxes, with an edge from
+    /// each test binary to them.
+    pub fn to_dot_graph(&self) -> String {
+        let mut packages: BTreeMap<&str, Vec<&RustTestSuite<'_>>> = BTreeMap::new();
+        for suite in self.rust_suites.values() {
+            packages
+                .entry(suite.package.name())
+                .or_default()
+                .push(suite);
+        }
+
+        let mut out = String::from("digraph tests {\n");
+        for (cluster_index, (package_name, suites)) in packages.into_iter().enumerate() {
+            out.push_str(&format!("    subgraph cluster_{cluster_index} {{\n"));
+            out.push_str(&format!("        label={};\n", dot_quote(package_name)));
+
+            let mut non_test_binaries = BTreeSet::new();
+            for suite in &suites {
+                let label = match &suite.status {
+                    RustTestSuiteStatus::Listed { test_cases } => {
+                        let run_count = test_cases
+                            .values()
+                            .filter(|test_case| test_case.filter_match.is_match())
+                            .count();
+                        let plural = if run_count == 1 { "test" } else { "tests" };
+                        format!("{}\n{run_count} {plural}", suite.binary_id)
+                    }
+                    RustTestSuiteStatus::Skipped => format!("{}\nskipped", suite.binary_id),
+                };
+                let style = match &suite.status {
+                    RustTestSuiteStatus::Listed { .. } => "",
+                    RustTestSuiteStatus::Skipped => ", style=dashed",
+                };
+                out.push_str(&format!(
+                    "        {} [label={}{style}];\n",
+                    dot_quote(suite.binary_id.as_str()),
+                    dot_quote(&label),
+                ));
+                non_test_binaries.extend(suite.non_test_binaries.iter());
+            }
+
+            for (name, path) in &non_test_binaries {
+                out.push_str(&format!(
+                    "        {} [label={}, shape=box];\n",
+                    dot_quote(path.as_str()),
+                    dot_quote(name),
+                ));
+            }
+            for suite in &suites {
+                for (_, path) in &suite.non_test_binaries {
+                    out.push_str(&format!(
+                        "        {} -> {};\n",
+                        dot_quote(suite.binary_id.as_str()),
+                        dot_quote(path.as_str()),
+                    ));
+                }
+            }
+            out.push_str("    }\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
     /// Outputs this list as a string with the given format.
     pub fn to_string(&self, output_format: OutputFormat) -> Result<String, WriteTestListError> {
         // Ugh this sucks. String really should have an io::Write impl that errors on non-UTF8 text.
@@ -418,6 +740,7 @@ impl<'g> TestList<'g> {
             rust_build_meta: RustBuildMeta::empty(),
             env: EnvironmentMap::empty(),
             updated_dylib_path: OsString::new(),
+            extra_args: Vec::new(),
             rust_suites: BTreeMap::new(),
             skip_count: OnceCell::new(),
         }
@@ -639,6 +962,22 @@ impl<'g> TestList<'g> {
     }
 }
 
+/// Quotes `s` as a DOT string, escaping it as necessary.
+fn dot_quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 /// A suite of tests within a single Rust test binary.
 ///
 /// This is a representation of [`nextest_metadata::RustTestSuiteSummary`] used internally by the runner.
@@ -721,6 +1060,7 @@ impl<'g> RustTestArtifact<'g> {
         if ignored {
             argv.push("--ignored");
         }
+        argv.extend(ctx.extra_args.iter().map(String::as_str));
 
         let mut cmd = TestCommand::new(
             ctx,
@@ -766,19 +1106,43 @@ impl<'g> RustTestArtifact<'g> {
                 stderr: output.stderr,
             })
         } else {
-            Err(CreateTestListError::CommandFail {
-                binary_id: self.binary_id.clone(),
-                command: std::iter::once(program)
-                    .chain(argv.iter().map(|&s| s.to_owned()))
-                    .collect(),
-                exit_status: output.status,
-                stdout: output.stdout,
-                stderr: output.stderr,
-            })
+            let command = std::iter::once(program)
+                .chain(argv.iter().map(|&s| s.to_owned()))
+                .collect();
+            match heuristic_panic_message(&String::from_utf8_lossy(&output.stderr)) {
+                Some(stderr) => Err(CreateTestListError::BinaryPanickedDuringList {
+                    binary_id: self.binary_id.clone(),
+                    command,
+                    exit_status: output.status,
+                    stderr,
+                }),
+                None => Err(CreateTestListError::CommandFail {
+                    binary_id: self.binary_id.clone(),
+                    command,
+                    exit_status: output.status,
+                    stdout: output.stdout,
+                    stderr: output.stderr,
+                }),
+            }
         }
     }
 }
 
+// This matches the output of the default panic handler, both before and after Rust 1.73 (which
+// moved the panic message to the next line).
+static PANICKED_AT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let mut builder = RegexBuilder::new("^thread '[^']*' panicked at ");
+    builder.multi_line(true);
+    builder.build().unwrap()
+});
+
+/// Returns the panic message and any backtrace from the standard error of a test binary that
+/// failed while listing tests.
+fn heuristic_panic_message(stderr: &str) -> Option<String> {
+    let panicked_at_match = PANICKED_AT_REGEX.find(stderr)?;
+    Some(stderr[panicked_at_match.start()..].trim_end().to_owned())
+}
+
 /// Serializable information about the status of and test cases within a test suite.
 ///
 /// Part of a [`RustTestSuiteSummary`].
@@ -882,44 +1246,100 @@ impl<'a> TestInstance<'a> {
         &self,
         ctx: &TestExecuteContext<'_>,
         test_list: &TestList<'_>,
+        bench: bool,
+        test_wrapper: Option<&CommandWrapper>,
     ) -> TestCommand {
-        let platform_runner = ctx
-            .target_runner
-            .for_build_platform(self.suite_info.build_platform);
-        // TODO: non-rust tests
-
-        let mut args = Vec::new();
-
-        let program: String = match platform_runner {
-            Some(runner) => {
-                args.extend(runner.args());
-                args.push(self.suite_info.binary_path.as_str());
-                runner.binary().into()
-            }
-            None => self.suite_info.binary_path.to_owned().into(),
-        };
-
-        args.extend(["--exact", self.name, "--nocapture"]);
+        let mut args = vec!["--exact", self.name, "--nocapture"];
         if self.test_info.ignored {
             args.push("--ignored");
         }
+        make_suite_command(self.suite_info, &args, ctx, test_list, bench, test_wrapper)
+    }
 
-        let ctx = LocalExecuteContext {
-            double_spawn: ctx.double_spawn,
-            runner: ctx.target_runner,
-            dylib_path: test_list.updated_dylib_path(),
-            env: &test_list.env,
-        };
+    /// Creates a command that runs all of `tests`, which must be from the same binary, in one
+    /// process with `test_threads` threads.
+    ///
+    /// Unlike [`Self::make_command`], libtest captures the output of each test, and prints it out
+    /// at the end because of `--show-output`.
+    pub(crate) fn make_batch_command(
+        tests: &[Self],
+        test_threads: usize,
+        ctx: &TestExecuteContext<'_>,
+        test_list: &TestList<'_>,
+        bench: bool,
+        test_wrapper: Option<&CommandWrapper>,
+    ) -> TestCommand {
+        let suite_info = tests.first().expect("at least one test").suite_info;
+        let test_threads = test_threads.to_string();
+        let mut args = vec!["--exact", "--show-output", "--test-threads", &test_threads];
+        if tests.iter().any(|test| test.test_info.ignored) {
+            // Only the named tests are run, so this doesn't run other ignored tests.
+            args.push("--include-ignored");
+        }
+        args.extend(tests.iter().map(|test| test.name));
+        make_suite_command(suite_info, &args, ctx, test_list, bench, test_wrapper)
+    }
+}
 
-        TestCommand::new(
-            &ctx,
-            program,
-            &args,
-            &self.suite_info.cwd,
-            &self.suite_info.package,
-            &self.suite_info.non_test_binaries,
-        )
+/// Creates a command that runs the test binary for `suite_info` with `test_args`, under
+/// `test_wrapper` if specified.
+fn make_suite_command(
+    suite_info: &RustTestSuite<'_>,
+    test_args: &[&str],
+    ctx: &TestExecuteContext<'_>,
+    test_list: &TestList<'_>,
+    bench: bool,
+    test_wrapper: Option<&CommandWrapper>,
+) -> TestCommand {
+    let platform_runner = ctx
+        .target_runner
+        .for_build_platform(suite_info.build_platform);
+    // TODO: non-rust tests
+
+    let mut args = Vec::new();
+
+    let program: String = match platform_runner {
+        Some(runner) => {
+            args.extend(runner.args());
+            args.push(suite_info.binary_path.as_str());
+            runner.binary().into()
+        }
+        None => suite_info.binary_path.to_owned().into(),
+    };
+
+    args.extend(test_args);
+    if bench {
+        args.push("--bench");
     }
+    args.extend(ctx.extra_args.iter().map(String::as_str));
+
+    // The wrapper runs the whole command, including the target runner.
+    let (program, args) = match test_wrapper {
+        Some(wrapper) => {
+            let mut wrapped_args: Vec<_> = wrapper.args().collect();
+            wrapped_args.push(&program);
+            wrapped_args.extend(args);
+            (wrapper.program().to_owned(), wrapped_args)
+        }
+        None => (program, args),
+    };
+
+    let ctx = LocalExecuteContext {
+        double_spawn: ctx.double_spawn,
+        runner: ctx.target_runner,
+        dylib_path: test_list.updated_dylib_path(),
+        env: &test_list.env,
+        extra_args: ctx.extra_args,
+    };
+
+    TestCommand::new(
+        &ctx,
+        program,
+        &args,
+        &suite_info.cwd,
+        &suite_info.package,
+        &suite_info.non_test_binaries,
+    )
 }
 
 /// Context required for test execution.
@@ -930,22 +1350,52 @@ pub struct TestExecuteContext<'a> {
 
     /// Target runner.
     pub target_runner: &'a TargetRunner,
+
+    /// Extra arguments passed to every test binary invocation, both while listing and while
+    /// running tests.
+    ///
+    /// These must not conflict with arguments passed in by nextest: see [`check_extra_args`].
+    pub extra_args: &'a [String],
+}
+
+/// Arguments that nextest passes in to test binaries.
+const NEXTEST_INJECTED_ARGS: &[&str] = &[
+    "--list",
+    "--format",
+    "--ignored",
+    "--exact",
+    "--nocapture",
+    "--bench",
+];
+
+/// Checks that extra arguments to test binaries don't conflict with arguments passed in by
+/// nextest.
+pub fn check_extra_args(extra_args: &[String]) -> Result<(), ExtraArgConflictError> {
+    for arg in extra_args {
+        let name = arg.split_once('=').map_or(arg.as_str(), |(name, _)| name);
+        if NEXTEST_INJECTED_ARGS.contains(&name) {
+            return Err(ExtraArgConflictError::new(arg.clone()));
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        cargo_config::{TargetTriple, TargetTripleSource},
-        list::SerializableFormat,
+        cargo_config::{CargoConfigs, TargetTriple, TargetTripleSource},
+        list::{
+            test_helpers::{package_metadata, HELPER_PACKAGE_ID, PACKAGE_GRAPH_FIXTURE},
+            SerializableFormat,
+        },
+        platform::BuildPlatforms,
         test_filter::RunIgnored,
     };
-    use guppy::CargoMetadata;
     use indoc::indoc;
     use maplit::btreemap;
     use nextest_filtering::FilteringExpr;
     use nextest_metadata::{FilterMatch, MismatchReason};
-    use once_cell::sync::Lazy;
     use pretty_assertions::assert_eq;
     use std::iter;
     use target_spec::Platform;
@@ -982,7 +1432,7 @@ mod tests {
         let test_binary = RustTestArtifact {
             binary_path: "/fake/binary".into(),
             cwd: fake_cwd.clone(),
-            package: package_metadata(),
+            package: package_metadata(HELPER_PACKAGE_ID),
             binary_name: fake_binary_name.clone(),
             binary_id: fake_binary_id.clone(),
             kind: RustTestBinaryKind::LIB,
@@ -995,7 +1445,7 @@ mod tests {
         let skipped_binary = RustTestArtifact {
             binary_path: "/fake/skipped-binary".into(),
             cwd: fake_cwd.clone(),
-            package: package_metadata(),
+            package: package_metadata(HELPER_PACKAGE_ID),
             binary_name: skipped_binary_name.clone(),
             binary_id: skipped_binary_id.clone(),
             kind: RustTestBinaryKind::PROC_MACRO,
@@ -1062,7 +1512,7 @@ mod tests {
                     },
                     cwd: fake_cwd.clone(),
                     build_platform: BuildPlatform::Target,
-                    package: package_metadata(),
+                    package: package_metadata(HELPER_PACKAGE_ID),
                     binary_name: fake_binary_name,
                     binary_id: fake_binary_id,
                     binary_path: "/fake/binary".into(),
@@ -1073,7 +1523,7 @@ mod tests {
                     status: RustTestSuiteStatus::Skipped,
                     cwd: fake_cwd,
                     build_platform: BuildPlatform::Host,
-                    package: package_metadata(),
+                    package: package_metadata(HELPER_PACKAGE_ID),
                     binary_name: skipped_binary_name,
                     binary_id: skipped_binary_id,
                     binary_path: "/fake/skipped-binary".into(),
@@ -1107,6 +1557,15 @@ mod tests {
               build platform: host
                 (test binary did not match filter expressions, skipped)
         "};
+        static EXPECTED_DOT: &str = indoc! {r#"
+            digraph tests {
+                subgraph cluster_0 {
+                    label="metadata-helper";
+                    "fake-package::fake-binary" [label="fake-package::fake-binary\n3 tests"];
+                    "fake-package::skipped-binary" [label="fake-package::skipped-binary\nskipped", style=dashed];
+                }
+            }
+        "#};
         static EXPECTED_JSON_PRETTY: &str = indoc! {r#"
             {
               "rust-build-meta": {
@@ -1215,20 +1674,222 @@ mod tests {
                 .expect("json-pretty succeeded"),
             EXPECTED_JSON_PRETTY
         );
+        assert_eq!(
+            test_list
+                .to_string(OutputFormat::Dot)
+                .expect("dot succeeded"),
+            EXPECTED_DOT
+        );
     }
 
-    static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
-        static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
-        let metadata = CargoMetadata::parse_json(FIXTURE_JSON).expect("fixture is valid JSON");
-        metadata
-            .build_graph()
-            .expect("fixture is valid PackageGraph")
-    });
-
-    static PACKAGE_METADATA_ID: &str = "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)";
-    fn package_metadata() -> PackageMetadata<'static> {
-        PACKAGE_GRAPH_FIXTURE
-            .metadata(&PackageId::new(PACKAGE_METADATA_ID))
-            .expect("package ID is valid")
+    #[test]
+    fn test_binary_path_overrides() {
+        let parsed: BinaryPathOverride = "my-package::bin/foo=target/foo".parse().unwrap();
+        assert_eq!(parsed.binary_id, RustBinaryId::new("my-package::bin/foo"));
+        assert_eq!(parsed.path, "target/foo");
+        "my-package".parse::<BinaryPathOverride>().unwrap_err();
+        "=target/foo".parse::<BinaryPathOverride>().unwrap_err();
+        "my-package=".parse::<BinaryPathOverride>().unwrap_err();
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path: &Utf8Path = dir.path().try_into().unwrap();
+        let new_binary = dir_path.join("new-binary");
+        std::fs::write(&new_binary, "").unwrap();
+
+        let mut artifacts = vec![RustTestArtifact {
+            binary_path: "/fake/binary".into(),
+            cwd: dir_path.to_owned(),
+            package: package_metadata(HELPER_PACKAGE_ID),
+            binary_name: "fake-binary".to_owned(),
+            binary_id: RustBinaryId::new("fake-package::fake-binary"),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        }];
+        let path_override = |binary_id: &str, path: &Utf8Path| BinaryPathOverride {
+            binary_id: RustBinaryId::new(binary_id),
+            path: path.to_owned(),
+        };
+
+        RustTestArtifact::apply_path_overrides(
+            &mut artifacts,
+            &[path_override("fake-package::fake-binary", &new_binary)],
+        )
+        .expect("override applied");
+        assert_eq!(
+            artifacts[0].binary_path,
+            new_binary.canonicalize_utf8().unwrap()
+        );
+
+        let error = RustTestArtifact::apply_path_overrides(
+            &mut artifacts,
+            &[path_override("fake-package::other", &new_binary)],
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, BinaryPathOverrideError::BinaryIdNotFound { .. }),
+            "unknown binary ID: {error:?}"
+        );
+        let error = RustTestArtifact::apply_path_overrides(
+            &mut artifacts,
+            &[path_override(
+                "fake-package::fake-binary",
+                &dir_path.join("missing"),
+            )],
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, BinaryPathOverrideError::PathResolve { .. }),
+            "missing path: {error:?}"
+        );
+    }
+
+    #[test]
+    fn test_check_test_count() {
+        let parsed: ExpectedTestCount = "12".parse().unwrap();
+        assert_eq!(parsed.package, None);
+        assert_eq!(parsed.count, 12);
+        let parsed: ExpectedTestCount = "my-crate:3".parse().unwrap();
+        assert_eq!(parsed.package.as_deref(), Some("my-crate"));
+        assert_eq!(parsed.count, 3);
+        "my-crate".parse::<ExpectedTestCount>().unwrap_err();
+        ":3".parse::<ExpectedTestCount>().unwrap_err();
+        "my-crate:-1".parse::<ExpectedTestCount>().unwrap_err();
+
+        let non_ignored_output = indoc! {"
+            tests::test_foo: test
+            tests::test_bar: test
+        "};
+        let ignored_output = indoc! {"
+            tests::test_ignored: test
+        "};
+        let test_filter =
+            TestFilterBuilder::new(RunIgnored::Default, None, iter::empty::<String>(), vec![])
+                .unwrap();
+        let test_binary = RustTestArtifact {
+            binary_path: "/fake/binary".into(),
+            cwd: "/fake/cwd".into(),
+            package: package_metadata(HELPER_PACKAGE_ID),
+            binary_name: "fake-binary".to_owned(),
+            binary_id: RustBinaryId::new("metadata-helper::fake-binary"),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let rust_build_meta = RustBuildMeta::new("/fake", None).map_paths(&PathMapper::noop());
+        let test_list = TestList::new_with_outputs(
+            [(test_binary, &non_ignored_output, &ignored_output)],
+            rust_build_meta,
+            &test_filter,
+            EnvironmentMap::empty(),
+        )
+        .expect("valid output");
+
+        let check = |bound: TestCountBound, expected: &str| {
+            test_list.check_test_count(bound, &expected.parse().unwrap())
+        };
+        // The ignored test is skipped, so it isn't counted.
+        check(TestCountBound::Exact, "2").expect("exact count matches");
+        check(TestCountBound::Exact, "metadata-helper:2").expect("package count matches");
+        check(TestCountBound::Min, "1").expect("count is above the minimum");
+        check(TestCountBound::Max, "2").expect("count is at the maximum");
+
+        let error = check(TestCountBound::Exact, "3").unwrap_err();
+        assert_eq!(error.actual, 2);
+        let error = check(TestCountBound::Min, "other-package:1").unwrap_err();
+        assert_eq!(error.package.as_deref(), Some("other-package"));
+        assert_eq!(error.actual, 0);
+        check(TestCountBound::Max, "1").unwrap_err();
+    }
+
+    #[test]
+    fn test_make_command_with_wrapper_and_runner() {
+        let test_filter = TestFilterBuilder::any(RunIgnored::Default);
+        let test_binary = RustTestArtifact {
+            binary_path: "/fake/binary".into(),
+            cwd: "/fake/cwd".into(),
+            package: package_metadata(HELPER_PACKAGE_ID),
+            binary_name: "fake-binary".to_owned(),
+            binary_id: RustBinaryId::new("metadata-helper::fake-binary"),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let rust_build_meta = RustBuildMeta::new("/fake", None).map_paths(&PathMapper::noop());
+        let test_list = TestList::new_with_outputs(
+            [(test_binary, "tests::test_foo: test\n", "")],
+            rust_build_meta,
+            &test_filter,
+            EnvironmentMap::empty(),
+        )
+        .expect("valid output");
+        let test_instance = test_list.iter_tests().next().expect("test list has a test");
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir = Utf8Path::from_path(dir.path()).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let configs = CargoConfigs::new_with_isolation(
+            [format!(
+                "target.{}.runner=[\"my-runner\", \"--runner-arg\"]",
+                build_platforms.host.triple_str()
+            )],
+            dir,
+            dir,
+        )
+        .unwrap();
+        let target_runner = TargetRunner::new(&configs, &build_platforms).unwrap();
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let ctx = TestExecuteContext {
+            double_spawn: &double_spawn,
+            target_runner: &target_runner,
+            extra_args: &[],
+        };
+        let wrapper = CommandWrapper::new("my-wrapper", ["--wrapper-arg".to_owned()]);
+
+        let mut command = test_instance.make_command(&ctx, &test_list, false, Some(&wrapper));
+        let command = command.command_mut();
+        // The wrapper runs the target runner, which runs the test binary.
+        assert_eq!(command.get_program(), "my-wrapper");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            [
+                "--wrapper-arg",
+                "my-runner",
+                "--runner-arg",
+                "/fake/binary",
+                "--exact",
+                "tests::test_foo",
+                "--nocapture",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_heuristic_panic_message() {
+        let tests = [
+            ("", None),
+            ("error: unrecognized option\n", None),
+            (
+                "thread 'main' panicked at 'config.toml not found', src/lib.rs:10:5\n\
+                 note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n",
+                Some(
+                    "thread 'main' panicked at 'config.toml not found', src/lib.rs:10:5\n\
+                     note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace",
+                ),
+            ),
+            (
+                "warning: something\nthread 'main' panicked at src/lib.rs:10:5:\n\
+                 config.toml not found\n",
+                Some("thread 'main' panicked at src/lib.rs:10:5:\nconfig.toml not found"),
+            ),
+        ];
+
+        for (stderr, expected) in tests {
+            assert_eq!(
+                heuristic_panic_message(stderr).as_deref(),
+                expected,
+                "stderr: {stderr:?}"
+            );
+        }
     }
 }
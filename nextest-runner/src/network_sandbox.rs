@@ -0,0 +1,158 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Blocking network access for test processes.
+//!
+//! On Linux, this is done by installing a seccomp filter in the test process just before it's
+//! executed. The filter kills the process with `SIGSYS` if it calls `connect`, `bind` or `sendto`.
+//! The filter is inherited by any processes the test spawns.
+
+use crate::runner::ExecutionResult;
+
+/// True if network access can be blocked on this platform.
+pub(crate) const IS_SUPPORTED: bool = cfg!(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+));
+
+pub(crate) use imp::block_network_access;
+
+/// Returns true if a test with this result was killed by the filter.
+pub(crate) fn is_violation(result: ExecutionResult) -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            matches!(
+                result,
+                ExecutionResult::Fail {
+                    abort_status: Some(crate::runner::AbortStatus::UnixSignal(libc::SIGSYS)),
+                    ..
+                }
+            )
+        } else {
+            let _ = result;
+            false
+        }
+    }
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod imp {
+    use libc::{
+        sock_filter, sock_fprog, BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W,
+        SECCOMP_RET_ALLOW, SECCOMP_RET_KILL_PROCESS,
+    };
+    use std::{io, os::unix::process::CommandExt, process::Command};
+
+    // From linux/audit.h.
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+    // Offsets into struct seccomp_data.
+    const SECCOMP_DATA_NR: u32 = 0;
+    const SECCOMP_DATA_ARCH: u32 = 4;
+
+    /// Installs the seccomp filter in the process spawned by `cmd`.
+    pub(crate) fn block_network_access(cmd: &mut Command) {
+        // Build the filter ahead of time, since allocating memory isn't safe in pre_exec.
+        let filter = network_filter();
+        unsafe {
+            cmd.pre_exec(move || {
+                let prog = sock_fprog {
+                    len: filter.len() as u16,
+                    filter: filter.as_ptr() as *mut sock_filter,
+                };
+                // This is required to install a filter without CAP_SYS_ADMIN.
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &prog as *const sock_fprog,
+                ) != 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    fn network_filter() -> Vec<sock_filter> {
+        let blocked = [libc::SYS_connect, libc::SYS_bind, libc::SYS_sendto];
+
+        let mut filter = vec![
+            stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARCH),
+            // Syscalls made through a different ABI (e.g. by 32-bit binaries) have different
+            // numbers, so they're let through rather than checked against the wrong list.
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH, 1, 0),
+            stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+            stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_NR),
+        ];
+        for (index, nr) in blocked.iter().enumerate() {
+            // Jump to the kill instruction at the end, skipping the rest of the checks and the
+            // allow instruction.
+            let to_kill = (blocked.len() - index) as u8;
+            filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, *nr as u32, to_kill, 0));
+        }
+        filter.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+        filter.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS));
+        filter
+    }
+
+    fn stmt(code: u32, k: u32) -> sock_filter {
+        jump(code, k, 0, 0)
+    }
+
+    fn jump(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+        sock_filter {
+            code: code as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::os::unix::process::ExitStatusExt;
+
+        #[test]
+        fn test_block_network_access() {
+            let mut cmd = Command::new("bash");
+            cmd.args(["-c", "echo hello"]);
+            block_network_access(&mut cmd);
+            let output = cmd.output().expect("bash is available");
+            assert!(
+                output.status.success(),
+                "processes without network access run"
+            );
+            assert_eq!(output.stdout, b"hello\n");
+
+            // bash calls connect to open /dev/tcp paths.
+            let mut cmd = Command::new("bash");
+            cmd.args(["-c", "exec 3<>/dev/tcp/127.0.0.1/9"]);
+            block_network_access(&mut cmd);
+            let status = cmd.status().expect("bash is available");
+            assert_eq!(status.signal(), Some(libc::SIGSYS), "connect is blocked");
+        }
+    }
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+mod imp {
+    use std::process::Command;
+
+    /// Blocking network access isn't supported on this platform: the runner checks for this
+    /// through `IS_SUPPORTED` before tests are run.
+    pub(crate) fn block_network_access(_cmd: &mut Command) {}
+}
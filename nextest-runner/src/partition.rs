@@ -3,15 +3,24 @@
 
 //! Support for partitioning test runs across several machines.
 //!
-//! At the moment this only supports simple hash-based and count-based sharding. In the future it
-//! could potentially be made smarter: e.g. using data to pick different sets of binaries and tests
-//! to run, with an aim to minimize total build and test times.
+//! The [`PartitionerBuilder`] supports simple hash-based and count-based sharding while listing
+//! tests. Given historical timing data, a listed [`TestList`] can also be split into shards with
+//! approximately equal total run times: see [`TestList::partition_optimally`].
 
-use crate::errors::PartitionerBuilderParseError;
+use crate::{
+    errors::{PartitionError, PartitionerBuilderParseError, TimingDataReadError, WriteEventError},
+    list::{TestInstance, TestList},
+};
+use camino::Utf8Path;
+use nextest_metadata::{FilterMatch, MismatchReason, RustBinaryId};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fmt,
     hash::{Hash, Hasher},
+    num::NonZeroUsize,
     str::FromStr,
+    time::Duration,
 };
 use twox_hash::XxHash64;
 
@@ -48,6 +57,14 @@ pub trait Partitioner: fmt::Debug {
 }
 
 impl PartitionerBuilder {
+    /// Returns the total number of shards.
+    pub fn total_shards(&self) -> u64 {
+        match self {
+            PartitionerBuilder::Count { total_shards, .. }
+            | PartitionerBuilder::Hash { total_shards, .. } => *total_shards,
+        }
+    }
+
     /// Creates a new `Partitioner` from this `PartitionerBuilder`.
     pub fn build(&self) -> Box<dyn Partitioner> {
         // Note we don't use test_binary at the moment but might in the future.
@@ -184,9 +201,402 @@ impl Partitioner for HashPartitioner {
     }
 }
 
+/// Historical run times for tests, used by [`TestList::partition_optimally`] and to check for
+/// timing regressions.
+#[derive(Clone, Debug, Default)]
+pub struct TimingData {
+    timings: HashMap<(RustBinaryId, String), Duration>,
+}
+
+impl TimingData {
+    /// Creates a new, empty `TimingData`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads timing data from a JSON file at `path`.
+    ///
+    /// The file has the form `{"tests": [{"binary-id": "...", "test-name": "...", "duration-ms":
+    /// 123}, ...]}`.
+    pub fn read_json(path: &Utf8Path) -> Result<Self, TimingDataReadError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| TimingDataReadError::Read {
+                path: path.to_owned(),
+                error,
+            })?;
+        let file: TimingDataFile =
+            serde_json::from_str(&contents).map_err(|error| TimingDataReadError::Parse {
+                path: path.to_owned(),
+                error,
+            })?;
+
+        let mut timings = Self::new();
+        for test in file.tests {
+            timings.insert(
+                RustBinaryId::new(&test.binary_id),
+                test.test_name,
+                Duration::from_millis(test.duration_ms),
+            );
+        }
+        Ok(timings)
+    }
+
+    /// Writes timing data to a JSON file at `path`, in the format read by [`Self::read_json`].
+    ///
+    /// Tests are sorted by binary ID and then by name, so that the file is stable across runs.
+    pub fn write_json(&self, path: &Utf8Path) -> Result<(), WriteEventError> {
+        let mut tests: Vec<_> = self
+            .timings
+            .iter()
+            .map(|((binary_id, test_name), time_taken)| TimingDataTest {
+                binary_id: binary_id.to_string(),
+                test_name: test_name.clone(),
+                duration_ms: u64::try_from(time_taken.as_millis()).unwrap_or(u64::MAX),
+            })
+            .collect();
+        tests.sort_by(|a, b| (&a.binary_id, &a.test_name).cmp(&(&b.binary_id, &b.test_name)));
+        let contents = serde_json::to_vec_pretty(&TimingDataFile { tests })
+            .expect("serializing JSON succeeds");
+        std::fs::write(path, contents).map_err(|error| WriteEventError::Fs {
+            file: path.to_owned(),
+            error,
+        })
+    }
+
+    /// Records the time taken by a test, replacing any previous value.
+    pub fn insert(
+        &mut self,
+        binary_id: RustBinaryId,
+        test_name: impl Into<String>,
+        time_taken: Duration,
+    ) -> &mut Self {
+        self.timings
+            .insert((binary_id, test_name.into()), time_taken);
+        self
+    }
+
+    /// Returns the time taken by a test, if known.
+    pub fn get(&self, binary_id: &RustBinaryId, test_name: &str) -> Option<Duration> {
+        // The map is keyed by an owned tuple, so a lookup needs an owned key.
+        self.timings
+            .get(&(binary_id.clone(), test_name.to_owned()))
+            .copied()
+    }
+
+    /// Returns the number of tests with timing data.
+    pub fn len(&self) -> usize {
+        self.timings.len()
+    }
+
+    /// Returns true if there's no timing data.
+    pub fn is_empty(&self) -> bool {
+        self.timings.is_empty()
+    }
+
+    /// Returns the mean time across all tests, or `None` if there's no timing data.
+    fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.timings.len()).ok().filter(|&n| n > 0)?;
+        Some(self.timings.values().sum::<Duration>() / count)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingDataFile {
+    tests: Vec<TimingDataTest>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingDataTest {
+    binary_id: String,
+    test_name: String,
+    duration_ms: u64,
+}
+
+/// A subset of the tests in a [`TestList`], produced by [`TestList::partition_optimally`].
+#[derive(Clone, Debug)]
+pub struct FilteredTestList<'a> {
+    tests: Vec<TestInstance<'a>>,
+    expected_time: Duration,
+}
+
+impl<'a> FilteredTestList<'a> {
+    /// Returns the tests in this shard, sorted by binary ID and test name.
+    pub fn iter_tests(&self) -> impl Iterator<Item = TestInstance<'a>> + '_ {
+        self.tests.iter().copied()
+    }
+
+    /// Returns the number of tests in this shard.
+    pub fn test_count(&self) -> usize {
+        self.tests.len()
+    }
+
+    /// Returns the expected total run time of this shard, based on the timing data.
+    pub fn expected_time(&self) -> Duration {
+        self.expected_time
+    }
+}
+
+impl<'g> TestList<'g> {
+    /// Checks that the tests selected before partitioning can be split across the shards in
+    /// `partitioner`, with at least `min_tests_per_shard` tests in each shard on average.
+    ///
+    /// This is used for `--min-shards-for-balance`, to catch shard counts that leave some shards
+    /// without any tests. With count-based partitioning, tests are dealt out to shards separately
+    /// for each test binary, so shards can still be unbalanced if many binaries have few tests.
+    pub fn check_partition_balance(
+        &self,
+        partitioner: &PartitionerBuilder,
+        min_tests_per_shard: NonZeroUsize,
+    ) -> Result<(), PartitionError> {
+        // Partitioning is applied after all other filters, so tests that were only rejected by the
+        // partition were selected by everything else.
+        let test_count = self
+            .iter_tests()
+            .filter(|test_instance| {
+                matches!(
+                    test_instance.test_info.filter_match,
+                    FilterMatch::Matches
+                        | FilterMatch::Mismatch {
+                            reason: MismatchReason::Partition
+                        }
+                )
+            })
+            .count();
+        // Always allow at least 1 shard, so the suggested shard count is usable.
+        let max_shards = (test_count / min_tests_per_shard.get()).max(1);
+        let total_shards = partitioner.total_shards();
+        if total_shards > max_shards as u64 {
+            return Err(PartitionError::TooManyShards {
+                total_shards,
+                test_count,
+                min_tests_per_shard: min_tests_per_shard.get(),
+                max_shards,
+            });
+        }
+        Ok(())
+    }
+
+    /// Splits the tests that match the filter into `shards` groups with approximately equal
+    /// total run times.
+    ///
+    /// Run times are taken from `timings`. Tests without timing data are assumed to take the mean
+    /// time across all tests with timing data, or 1 second if there's no timing data at all.
+    ///
+    /// Uses the multifit bin-packing algorithm. The result is deterministic for a given test list
+    /// and timing data, so separate machines can each compute the full partition and pick their
+    /// own shard out of it.
+    pub fn partition_optimally(
+        &self,
+        timings: &TimingData,
+        shards: NonZeroUsize,
+    ) -> Vec<FilteredTestList<'_>> {
+        let default_time = timings.mean().unwrap_or(Duration::from_secs(1));
+        let items: Vec<_> = self
+            .iter_tests()
+            .filter(|test_instance| test_instance.test_info.filter_match.is_match())
+            .map(|test_instance| {
+                let time = timings
+                    .get(&test_instance.suite_info.binary_id, test_instance.name)
+                    .unwrap_or(default_time);
+                (test_instance, time)
+            })
+            .collect();
+
+        let sizes: Vec<_> = items.iter().map(|(_, time)| *time).collect();
+        multifit(&sizes, shards.get())
+            .into_iter()
+            .map(|bin| {
+                let mut tests: Vec<_> = bin.iter().map(|&index| items[index].0).collect();
+                tests.sort_by_key(|test_instance| test_instance.sort_key());
+                let expected_time = bin.iter().map(|&index| items[index].1).sum();
+                FilteredTestList {
+                    tests,
+                    expected_time,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The number of binary search iterations performed by [`multifit`].
+const MULTIFIT_ITERATIONS: usize = 10;
+
+/// Packs items with the given sizes into exactly `bins` bins, aiming to minimize the largest bin.
+///
+/// Returns the indexes of the items in each bin.
+fn multifit(sizes: &[Duration], bins: usize) -> Vec<Vec<usize>> {
+    // Sort items by decreasing size, breaking ties by index for determinism.
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]).then(a.cmp(&b)));
+
+    let total: Duration = sizes.iter().sum();
+    let largest = order.first().map_or(Duration::ZERO, |&index| sizes[index]);
+    // bins is derived from a NonZeroUsize, so these divisions are well-defined.
+    let bins_u32 = u32::try_from(bins).unwrap_or(u32::MAX);
+    let mut lower = (total / bins_u32).max(largest);
+    let mut upper = (total * 2 / bins_u32).max(largest);
+
+    // First-fit decreasing always succeeds with the upper bound, so start with it.
+    let mut best = first_fit_decreasing(sizes, &order, bins, upper)
+        .expect("first-fit decreasing succeeds with the upper bound");
+    for _ in 0..MULTIFIT_ITERATIONS {
+        let capacity = (lower + upper) / 2;
+        match first_fit_decreasing(sizes, &order, bins, capacity) {
+            Some(packing) => {
+                best = packing;
+                upper = capacity;
+            }
+            None => lower = capacity,
+        }
+    }
+
+    best
+}
+
+/// Packs items into at most `bins` bins of the given capacity, or returns `None` if that isn't
+/// possible.
+fn first_fit_decreasing(
+    sizes: &[Duration],
+    order: &[usize],
+    bins: usize,
+    capacity: Duration,
+) -> Option<Vec<Vec<usize>>> {
+    let mut packing = vec![Vec::new(); bins];
+    let mut loads = vec![Duration::ZERO; bins];
+    for &index in order {
+        let bin = loads
+            .iter()
+            .position(|&load| load + sizes[index] <= capacity)?;
+        packing[bin].push(index);
+        loads[bin] += sizes[index];
+    }
+    Some(packing)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        cargo_config::EnvironmentMap,
+        list::{
+            test_helpers::{package_metadata, HELPER_PACKAGE_ID},
+            RustBuildMeta, RustTestArtifact,
+        },
+        reuse_build::PathMapper,
+        test_filter::{RunIgnored, TestFilterBuilder},
+    };
+    use indoc::indoc;
+    use nextest_metadata::{BuildPlatform, RustTestBinaryKind};
+    use std::{collections::BTreeSet, iter};
+
+    #[test]
+    fn multifit_packing() {
+        let sizes: Vec<_> = [8, 7, 6, 5, 4, 3, 2, 1]
+            .into_iter()
+            .map(Duration::from_secs)
+            .collect();
+        let packing = multifit(&sizes, 3);
+        assert_eq!(packing.len(), 3, "exactly 3 bins");
+
+        let mut all_items: Vec<_> = packing.iter().flatten().copied().collect();
+        all_items.sort_unstable();
+        assert_eq!(
+            all_items,
+            (0..8).collect::<Vec<_>>(),
+            "each item packed once"
+        );
+
+        // The total is 36 seconds, so an optimal packing has 12 seconds in each bin.
+        for bin in &packing {
+            let load: Duration = bin.iter().map(|&index| sizes[index]).sum();
+            assert_eq!(load, Duration::from_secs(12), "bin {bin:?} is balanced");
+        }
+
+        // More bins than items leaves some bins empty.
+        let packing = multifit(&sizes[..2], 4);
+        assert_eq!(packing.iter().filter(|bin| bin.is_empty()).count(), 2);
+
+        // No items at all.
+        assert_eq!(multifit(&[], 2), vec![Vec::<usize>::new(), Vec::new()]);
+    }
+
+    #[test]
+    fn timing_data_read_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir: &Utf8Path = dir.path().try_into().unwrap();
+
+        let path = dir.join("timings.json");
+        std::fs::write(
+            &path,
+            r#"{"tests": [
+                {"binary-id": "my-crate::tests", "test-name": "foo", "duration-ms": 1500},
+                {"binary-id": "my-crate", "test-name": "bar::baz", "duration-ms": 20}
+            ]}"#,
+        )
+        .unwrap();
+        let timings = TimingData::read_json(&path).unwrap();
+        assert_eq!(timings.len(), 2);
+        assert_eq!(
+            timings.get(&RustBinaryId::new("my-crate::tests"), "foo"),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            timings.get(&RustBinaryId::new("my-crate"), "bar::baz"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(timings.get(&RustBinaryId::new("my-crate"), "foo"), None);
+
+        std::fs::write(&path, r#"{"tests": [{"binary-id": "my-crate"}]}"#).unwrap();
+        assert!(matches!(
+            TimingData::read_json(&path),
+            Err(TimingDataReadError::Parse { .. })
+        ));
+        assert!(matches!(
+            TimingData::read_json(&dir.join("missing.json")),
+            Err(TimingDataReadError::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn timing_data_write_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir: &Utf8Path = dir.path().try_into().unwrap();
+
+        let mut timings = TimingData::new();
+        timings
+            .insert(
+                RustBinaryId::new("my-crate::tests"),
+                "foo",
+                Duration::from_millis(1500),
+            )
+            .insert(
+                RustBinaryId::new("my-crate"),
+                "bar::baz",
+                Duration::from_micros(20_900),
+            );
+        let path = dir.join("timings.json");
+        timings.write_json(&path).unwrap();
+
+        let contents: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            contents,
+            serde_json::json!({"tests": [
+                {"binary-id": "my-crate", "test-name": "bar::baz", "duration-ms": 20},
+                {"binary-id": "my-crate::tests", "test-name": "foo", "duration-ms": 1500},
+            ]}),
+            "tests are sorted, with durations in whole milliseconds"
+        );
+
+        let read_back = TimingData::read_json(&path).unwrap();
+        assert_eq!(read_back.len(), 2);
+        assert_eq!(
+            read_back.get(&RustBinaryId::new("my-crate::tests"), "foo"),
+            Some(Duration::from_millis(1500))
+        );
+    }
 
     #[test]
     fn partitioner_builder_from_str() {
@@ -242,4 +652,75 @@ mod tests {
                 .expect_err(&format!("expected input '{input}' to fail"));
         }
     }
+
+    #[test]
+    fn check_partition_balance() {
+        let non_ignored_output = indoc! {"
+            tests::test_foo: test
+            tests::test_bar: test
+            tests::test_baz: test
+        "};
+        let ignored_output = indoc! {"
+            tests::test_ignored: test
+        "};
+        let partitioner: PartitionerBuilder = "count:1/2".parse().unwrap();
+        let test_filter = TestFilterBuilder::new(
+            RunIgnored::Default,
+            Some(partitioner.clone()),
+            iter::empty::<String>(),
+            vec![],
+        )
+        .unwrap();
+        let test_binary = RustTestArtifact {
+            binary_path: "/fake/binary".into(),
+            cwd: "/fake/cwd".into(),
+            package: package_metadata(HELPER_PACKAGE_ID),
+            binary_name: "fake-binary".to_owned(),
+            binary_id: RustBinaryId::new("metadata-helper::fake-binary"),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let rust_build_meta = RustBuildMeta::new("/fake", None).map_paths(&PathMapper::noop());
+        let test_list = TestList::new_with_outputs(
+            [(test_binary, &non_ignored_output, &ignored_output)],
+            rust_build_meta,
+            &test_filter,
+            EnvironmentMap::empty(),
+        )
+        .expect("valid output");
+        assert_eq!(
+            test_list.test_count() - test_list.skip_count(),
+            2,
+            "shard 1 of 2 has 2 tests"
+        );
+
+        let one = NonZeroUsize::new(1).unwrap();
+        // Tests in other shards are counted, but the ignored test isn't.
+        test_list
+            .check_partition_balance(&partitioner, one)
+            .expect("3 tests can be split across 2 shards");
+        test_list
+            .check_partition_balance(&"hash:1/3".parse().unwrap(), one)
+            .expect("3 tests can be split across 3 shards");
+
+        let error = test_list
+            .check_partition_balance(&"count:1/4".parse().unwrap(), one)
+            .unwrap_err();
+        let PartitionError::TooManyShards { max_shards, .. } = error;
+        assert_eq!(max_shards, 3);
+
+        let error = test_list
+            .check_partition_balance(&partitioner, NonZeroUsize::new(2).unwrap())
+            .unwrap_err();
+        let PartitionError::TooManyShards { max_shards, .. } = error;
+        assert_eq!(max_shards, 1);
+
+        // With fewer tests than the minimum, at least 1 shard is suggested.
+        let error = test_list
+            .check_partition_balance(&partitioner, NonZeroUsize::new(4).unwrap())
+            .unwrap_err();
+        let PartitionError::TooManyShards { max_shards, .. } = error;
+        assert_eq!(max_shards, 1);
+    }
 }
@@ -0,0 +1,136 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Detecting processes that tests leave running after they exit.
+//!
+//! On Unix, each test is started in its own process group, and on Windows, it's assigned to its
+//! own job object. Processes that are still in the group or job after the test process has exited
+//! were spawned by the test and never waited for. Processes that move themselves into a new group
+//! or session, e.g. daemons, aren't detected.
+
+/// True if leaked processes can be detected on this platform.
+pub(crate) const IS_SUPPORTED: bool = cfg!(any(target_os = "linux", windows));
+
+/// Returns the IDs of live processes in the process group `pgid`, in ascending order.
+///
+/// This reads `/proc`, and skips processes that exit while it's being read.
+#[cfg(target_os = "linux")]
+pub(crate) fn processes_in_group(pgid: u32) -> Vec<u32> {
+    let entries = match std::fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("failed to read /proc to detect leaked processes: {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut pids: Vec<u32> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            (parse_stat_pgrp(&stat)? == pgid).then_some(pid)
+        })
+        .collect();
+    pids.sort_unstable();
+    pids
+}
+
+/// Returns the process group from the contents of `/proc/<pid>/stat`, or `None` if the process has
+/// already exited.
+#[cfg(target_os = "linux")]
+fn parse_stat_pgrp(stat: &str) -> Option<u32> {
+    // The second field is the command name in parentheses, which can itself contain spaces and
+    // parentheses, so start after the last `)`.
+    let (_, rest) = stat.rsplit_once(')')?;
+    let mut fields = rest.split_ascii_whitespace();
+    // Zombies are reaped by init once their parent exits, so they don't count as leaked.
+    if matches!(fields.next()?, "Z" | "X") {
+        return None;
+    }
+    // Skip the parent process ID.
+    fields.next()?;
+    fields.next()?.parse().ok()
+}
+
+/// Returns the IDs of processes assigned to `job`, in ascending order.
+#[cfg(windows)]
+pub(crate) fn processes_in_job(job: &win32job::Job) -> Vec<u32> {
+    use windows::Win32::{
+        Foundation::HANDLE,
+        System::JobObjects::{JobObjectBasicProcessIdList, QueryInformationJobObject},
+    };
+
+    // JOBOBJECT_BASIC_PROCESS_ID_LIST, with room for a fixed number of process IDs. The windows
+    // crate declares the list as a one-element array.
+    #[repr(C)]
+    struct ProcessIdList {
+        number_of_assigned_processes: u32,
+        number_of_process_ids_in_list: u32,
+        process_id_list: [usize; 256],
+    }
+
+    let mut list = ProcessIdList {
+        number_of_assigned_processes: 0,
+        number_of_process_ids_in_list: 0,
+        process_id_list: [0; 256],
+    };
+    unsafe {
+        // This fails with ERROR_MORE_DATA if the list is too small, but still fills it in, so the
+        // result is ignored.
+        QueryInformationJobObject(
+            HANDLE(job.handle() as isize),
+            JobObjectBasicProcessIdList,
+            &mut list as *mut ProcessIdList as *mut std::ffi::c_void,
+            std::mem::size_of::<ProcessIdList>() as u32,
+            None,
+        );
+    }
+
+    let len = (list.number_of_process_ids_in_list as usize).min(list.process_id_list.len());
+    let mut pids: Vec<u32> = list.process_id_list[..len]
+        .iter()
+        .map(|&pid| pid as u32)
+        .collect();
+    pids.sort_unstable();
+    pids
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat_pgrp() {
+        assert_eq!(
+            parse_stat_pgrp("1234 (sleep) S 1200 1234 1200 0 -1 4194304 95 0 0 0"),
+            Some(1234)
+        );
+        assert_eq!(
+            parse_stat_pgrp("1235 (my (weird) cmd) R 1 1200 1200 0 -1 4194304 95 0 0 0"),
+            Some(1200)
+        );
+        assert_eq!(
+            parse_stat_pgrp("1236 (defunct) Z 1 1200 1200 0 -1 4194304 95 0 0 0"),
+            None
+        );
+        assert_eq!(parse_stat_pgrp("garbage"), None);
+    }
+
+    #[test]
+    fn test_processes_in_group() {
+        use std::os::unix::process::CommandExt;
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .expect("sleep spawned");
+        let pids = processes_in_group(child.id());
+        child.kill().expect("sleep killed");
+        child.wait().expect("sleep waited for");
+
+        assert_eq!(pids, [child.id()]);
+        assert_eq!(processes_in_group(child.id()), Vec::<u32>::new());
+    }
+}
@@ -6,35 +6,50 @@
 //! The main structure in this module is [`TestReporter`].
 
 mod aggregator;
+#[cfg(feature = "remote-reporting")]
+mod remote;
+mod summary;
 use crate::{
-    config::NextestProfile,
-    errors::WriteEventError,
-    helpers::write_test_name,
-    list::{TestInstance, TestList},
+    config::{
+        JunitClassNameStyle, JunitFailureFormat, JunitHostname, JunitPathRewrite,
+        JunitSuiteNameStyle, JunitTimestampFormat, NextestProfile,
+    },
+    errors::{JunitPropertyParseError, WriteEventError},
+    helpers::{format_duration, write_test_name},
+    list::{RustTestSuite, TestInstance, TestList},
     reporter::aggregator::EventAggregator,
     runner::{
         AbortStatus, ExecuteStatus, ExecutionDescription, ExecutionResult, ExecutionStatuses,
-        RetryData, RunStats,
+        RetryData, RetryReason, RunStats,
     },
+    state_snapshot::StateMutation,
 };
 pub use aggregator::heuristic_extract_description;
+use bytes::Bytes;
+use camino::Utf8PathBuf;
 use debug_ignore::DebugIgnore;
 use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use nextest_metadata::MismatchReason;
 use owo_colors::{OwoColorize, Style};
-use serde::Deserialize;
+#[cfg(feature = "remote-reporting")]
+pub use remote::{RemoteReporter, RemoteTestResult};
+use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
     cmp::Reverse,
+    collections::BTreeMap,
     fmt::{self, Write as _},
     io,
     io::{BufWriter, Write},
+    num::NonZeroUsize,
+    str::FromStr,
     time::{Duration, SystemTime},
 };
+pub use summary::{RunHistory, RunHistoryEntry, RunSummary, RunSummaryFailure, RunSummaryRecorder};
 use uuid::Uuid;
 
 /// When to display test output in the reporter.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum TestOutputDisplay {
     /// Show output immediately on execution completion.
@@ -74,7 +89,7 @@ impl TestOutputDisplay {
 ///
 /// Status levels are incremental: each level causes all the statuses listed above it to be output. For example,
 /// [`Slow`](Self::Slow) implies [`Retry`](Self::Retry) and [`Fail`](Self::Fail).
-#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 #[non_exhaustive]
 pub enum StatusLevel {
@@ -110,7 +125,7 @@ pub enum StatusLevel {
 /// This differs from [`StatusLevel`] in two ways:
 /// * It has a "flaky" test indicator that's different from "retry" (though "retry" works as an alias.)
 /// * It has a different ordering: skipped tests are prioritized over passing ones.
-#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 #[non_exhaustive]
 pub enum FinalStatusLevel {
@@ -140,6 +155,56 @@ pub enum FinalStatusLevel {
     All,
 }
 
+/// How the summary at the end of a test run is displayed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SummaryFormat {
+    /// A single line with the number of tests that passed, failed and were skipped.
+    Compact,
+
+    /// The summary line, followed by a line for each package with its own counts.
+    Verbose,
+
+    /// The summary line, followed by a table with a row for each package.
+    Table,
+}
+
+impl SummaryFormat {
+    /// The minimum terminal width at which [`Table`](Self::Table) is the default.
+    pub const TABLE_MIN_WIDTH: u16 = 80;
+}
+
+/// How progress is displayed while tests are running.
+///
+/// The progress display is only shown if standard error is a terminal.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProgressBarStyle {
+    /// No progress display.
+    None,
+
+    /// A bar that fills up as tests finish, followed by the number of tests that have finished.
+    Bar,
+
+    /// The number of tests that have finished, e.g. `42/100 tests`, without a bar.
+    Fraction,
+
+    /// An animated spinner, without the number of tests that have finished. Useful for terminals
+    /// that can't redraw a full-width bar well.
+    Spinner,
+}
+
+impl ProgressBarStyle {
+    /// Returns the default style for a terminal with this `TERM` environment variable.
+    ///
+    /// This is [`Spinner`](Self::Spinner) for dumb terminals, and [`Bar`](Self::Bar) otherwise.
+    pub fn default_for_term(term: Option<&str>) -> Self {
+        if term == Some("dumb") {
+            Self::Spinner
+        } else {
+            Self::Bar
+        }
+    }
+}
+
 /// Standard error destination for the reporter.
 ///
 /// This is usually a terminal, but can be an in-memory buffer for tests.
@@ -153,16 +218,81 @@ pub enum ReporterStderr<'a> {
     Buffer(&'a mut Vec<u8>),
 }
 
+/// A property added to the root `<testsuites>` element of the JUnit report.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JunitProperty {
+    /// The name of the property.
+    pub name: String,
+
+    /// The value of the property.
+    ///
+    /// If `None`, the value is read from the environment variable with the same name, e.g. a
+    /// `CI_COMMIT_SHA` variable set by the CI system. The property is skipped if that variable
+    /// isn't set.
+    pub value: Option<String>,
+}
+
+impl JunitProperty {
+    fn resolve(&self) -> Option<(String, String)> {
+        let value = match &self.value {
+            Some(value) => value.clone(),
+            None => std::env::var(&self.name).ok()?,
+        };
+        Some((self.name.clone(), value))
+    }
+}
+
+impl FromStr for JunitProperty {
+    type Err = JunitPropertyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_owned())),
+            None => (s, None),
+        };
+        if name.is_empty() {
+            return Err(JunitPropertyParseError::new(s));
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            value,
+        })
+    }
+}
+
 /// Test reporter builder.
 #[derive(Debug, Default)]
 pub struct TestReporterBuilder {
     no_capture: bool,
+    bench: bool,
+    stream_output: bool,
     failure_output: Option<TestOutputDisplay>,
     success_output: Option<TestOutputDisplay>,
+    suppress_pass_output_threshold: Option<u64>,
     status_level: Option<StatusLevel>,
     final_status_level: Option<FinalStatusLevel>,
     verbose: bool,
     hide_progress_bar: bool,
+    progress_bar_style: Option<ProgressBarStyle>,
+    aggregate_failures: bool,
+    display_only_failing_packages: bool,
+    summary_format: Option<SummaryFormat>,
+    junit_name: Option<String>,
+    junit_suitename_style: Option<JunitSuiteNameStyle>,
+    junit_classname_style: Option<JunitClassNameStyle>,
+    junit_failure_format: Option<JunitFailureFormat>,
+    junit_nest_packages: bool,
+    junit_strip_ansi: bool,
+    junit_timestamp_format: Option<JunitTimestampFormat>,
+    junit_elapsed_time_precision: Option<usize>,
+    junit_hostname: Option<JunitHostname>,
+    junit_include_passing: Option<bool>,
+    junit_path_rewrites: Option<Vec<JunitPathRewrite>>,
+    junit_truncate_name_at: Option<usize>,
+    junit_use_error_for_panics: bool,
+    junit_rerun_threshold: Option<(usize, RunHistory)>,
+    junit_properties: Vec<JunitProperty>,
+    capture_all: Option<(Utf8PathBuf, u64)>,
 }
 
 impl TestReporterBuilder {
@@ -175,6 +305,16 @@ impl TestReporterBuilder {
         self
     }
 
+    /// Sets stream-output mode.
+    ///
+    /// In this mode, lines reported through [`TestEvent::TestOutputLine`] are written as they
+    /// arrive, prefixed with the test they came from. `failure_output` and `success_output` are
+    /// ignored, since the output has already been displayed.
+    pub fn set_stream_output(&mut self, stream_output: bool) -> &mut Self {
+        self.stream_output = stream_output;
+        self
+    }
+
     /// Sets the conditions under which test failures are output.
     pub fn set_failure_output(&mut self, failure_output: TestOutputDisplay) -> &mut Self {
         self.failure_output = Some(failure_output);
@@ -187,6 +327,15 @@ impl TestReporterBuilder {
         self
     }
 
+    /// Suppresses output for passing tests that write fewer than `threshold` bytes in total to
+    /// standard output and standard error.
+    ///
+    /// This only has an effect if output for passing tests would otherwise be displayed.
+    pub fn set_suppress_pass_output_threshold(&mut self, threshold: u64) -> &mut Self {
+        self.suppress_pass_output_threshold = Some(threshold);
+        self
+    }
+
     /// Sets the kinds of statuses to output.
     pub fn set_status_level(&mut self, status_level: StatusLevel) -> &mut Self {
         self.status_level = Some(status_level);
@@ -199,6 +348,15 @@ impl TestReporterBuilder {
         self
     }
 
+    /// Sets benchmark mode, for runs started with `cargo nextest bench`.
+    ///
+    /// In this mode, the JUnit report records the mean iteration time that libtest prints out for
+    /// each benchmark, rather than the time taken by the whole test process.
+    pub fn set_bench(&mut self, bench: bool) -> &mut Self {
+        self.bench = bench;
+        self
+    }
+
     /// Sets verbose output.
     pub fn set_verbose(&mut self, verbose: bool) -> &mut Self {
         self.verbose = verbose;
@@ -211,6 +369,175 @@ impl TestReporterBuilder {
         self.hide_progress_bar = hide_progress_bar;
         self
     }
+
+    /// Sets how progress is displayed while tests are running.
+    ///
+    /// By default, this is chosen based on the `TERM` environment variable: see
+    /// [`ProgressBarStyle::default_for_term`]. Hiding the progress bar with
+    /// [`Self::set_hide_progress_bar`] takes precedence over this.
+    pub fn set_progress_bar_style(&mut self, progress_bar_style: ProgressBarStyle) -> &mut Self {
+        self.progress_bar_style = Some(progress_bar_style);
+        self
+    }
+
+    /// Sets whether output for failing tests is deferred until the end of the run.
+    ///
+    /// In this mode, status lines are still printed as tests finish, but any output that would
+    /// have been displayed immediately for failing tests is buffered and printed together once
+    /// the run is complete, in order of increasing test duration.
+    pub fn set_aggregate_failures(&mut self, aggregate_failures: bool) -> &mut Self {
+        self.aggregate_failures = aggregate_failures;
+        self
+    }
+
+    /// Sets whether to only display test results for packages with at least one failing test.
+    ///
+    /// In this mode, results for each package are held back until all of its tests have finished.
+    /// They're then printed under a package header if any test failed, and discarded otherwise.
+    /// The final summary still counts tests in all packages.
+    pub fn set_display_only_failing_packages(
+        &mut self,
+        display_only_failing_packages: bool,
+    ) -> &mut Self {
+        self.display_only_failing_packages = display_only_failing_packages;
+        self
+    }
+
+    /// Sets how the summary at the end of the run is displayed.
+    ///
+    /// If this isn't set, [`SummaryFormat::Table`] is used when writing to a terminal at least
+    /// [`SummaryFormat::TABLE_MIN_WIDTH`] columns wide, and [`SummaryFormat::Compact`] otherwise.
+    pub fn set_summary_format(&mut self, summary_format: SummaryFormat) -> &mut Self {
+        self.summary_format = Some(summary_format);
+        self
+    }
+
+    /// Writes the output of every test to `output_dir`, regardless of whether the test passed.
+    ///
+    /// The output of each attempt is written to `<binary-id>/<test-name>/attempt-<n>.stdout` and
+    /// `.stderr` within `output_dir`. At most `max_bytes` bytes are written in total: output beyond
+    /// that is truncated.
+    pub fn set_capture_all(
+        &mut self,
+        output_dir: impl Into<Utf8PathBuf>,
+        max_bytes: u64,
+    ) -> &mut Self {
+        self.capture_all = Some((output_dir.into(), max_bytes));
+        self
+    }
+
+    /// Adds a property to the root `<testsuites>` element of the JUnit report.
+    pub fn add_junit_property(&mut self, property: JunitProperty) -> &mut Self {
+        self.junit_properties.push(property);
+        self
+    }
+
+    /// Sets how test suites are named in the JUnit report, overriding the profile.
+    pub fn set_junit_suitename_style(&mut self, suitename_style: JunitSuiteNameStyle) -> &mut Self {
+        self.junit_suitename_style = Some(suitename_style);
+        self
+    }
+
+    /// Sets how the classname of test cases is populated in the JUnit report, overriding the
+    /// profile.
+    pub fn set_junit_classname_style(&mut self, classname_style: JunitClassNameStyle) -> &mut Self {
+        self.junit_classname_style = Some(classname_style);
+        self
+    }
+
+    /// Sets how much output is written to the JUnit report for failing tests, overriding the
+    /// profile.
+    pub fn set_junit_failure_format(&mut self, failure_format: JunitFailureFormat) -> &mut Self {
+        self.junit_failure_format = Some(failure_format);
+        self
+    }
+
+    /// Nests each test suite in the JUnit report within a parent test suite for its package,
+    /// overriding the profile if true.
+    pub fn set_junit_nest_packages(&mut self, nest_packages: bool) -> &mut Self {
+        self.junit_nest_packages = nest_packages;
+        self
+    }
+
+    /// Sets the format of timestamps in the JUnit report, overriding the profile.
+    pub fn set_junit_timestamp_format(
+        &mut self,
+        timestamp_format: JunitTimestampFormat,
+    ) -> &mut Self {
+        self.junit_timestamp_format = Some(timestamp_format);
+        self
+    }
+
+    /// Sets the number of digits after the decimal point that elapsed times in the JUnit report are
+    /// rounded to, overriding the profile.
+    pub fn set_junit_elapsed_time_precision(&mut self, precision: usize) -> &mut Self {
+        self.junit_elapsed_time_precision = Some(precision);
+        self
+    }
+
+    /// Removes ANSI escape sequences from output written to the JUnit report.
+    ///
+    /// If this is false, the profile's `junit.strip-ansi` setting is used.
+    pub fn set_junit_strip_ansi(&mut self, strip_ansi: bool) -> &mut Self {
+        self.junit_strip_ansi = strip_ansi;
+        self
+    }
+
+    /// Sets the `hostname` attribute of test suites in the JUnit report, overriding the profile.
+    pub fn set_junit_hostname(&mut self, hostname: JunitHostname) -> &mut Self {
+        self.junit_hostname = Some(hostname);
+        self
+    }
+
+    /// Sets whether tests that passed on their first attempt get test cases in the JUnit report,
+    /// overriding the profile.
+    pub fn set_junit_include_passing(&mut self, include_passing: bool) -> &mut Self {
+        self.junit_include_passing = Some(include_passing);
+        self
+    }
+
+    /// Sets the name of the JUnit report, overriding the profile.
+    ///
+    /// The name can contain the template variables described in
+    /// [`NextestJunitConfig::expanded_report_name`](crate::config::NextestJunitConfig::expanded_report_name).
+    pub fn set_junit_name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.junit_name = Some(name.into());
+        self
+    }
+
+    /// Sets the regex substitutions applied to path-like strings in the JUnit report, overriding
+    /// the profile.
+    pub fn set_junit_path_rewrites(&mut self, path_rewrites: Vec<JunitPathRewrite>) -> &mut Self {
+        self.junit_path_rewrites = Some(path_rewrites);
+        self
+    }
+
+    /// Sets the number of characters that test case names in the JUnit report are truncated to,
+    /// overriding the profile.
+    pub fn set_junit_truncate_name_at(&mut self, truncate_name_at: usize) -> &mut Self {
+        self.junit_truncate_name_at = Some(truncate_name_at);
+        self
+    }
+
+    /// Reports tests that failed due to a panic as errors rather than failures in the JUnit
+    /// report.
+    ///
+    /// If this is false, the profile's `junit.use-error-for-panics` setting is used.
+    pub fn set_junit_use_error_for_panics(&mut self, use_error_for_panics: bool) -> &mut Self {
+        self.junit_use_error_for_panics = use_error_for_panics;
+        self
+    }
+
+    /// Marks tests that failed in more than `threshold` of the runs in `history` as skipped in the
+    /// JUnit report, so that the report focuses on new failures.
+    pub fn set_junit_rerun_threshold(
+        &mut self,
+        threshold: usize,
+        history: RunHistory,
+    ) -> &mut Self {
+        self.junit_rerun_threshold = Some((threshold, history));
+        self
+    }
 }
 
 impl TestReporterBuilder {
@@ -229,7 +556,61 @@ impl TestReporterBuilder {
             })
             .max()
             .unwrap_or_default();
-        let aggregator = EventAggregator::new(profile);
+        let mut junit_config = profile.junit();
+        if let Some(junit_config) = &mut junit_config {
+            if let Some(name) = &self.junit_name {
+                junit_config.set_report_name(name.clone());
+            }
+            if let Some(suitename_style) = self.junit_suitename_style {
+                junit_config.set_suitename_style(suitename_style);
+            }
+            if let Some(classname_style) = self.junit_classname_style {
+                junit_config.set_classname_style(classname_style);
+            }
+            if let Some(failure_format) = self.junit_failure_format {
+                junit_config.set_failure_format(failure_format);
+            }
+            if self.junit_nest_packages {
+                junit_config.set_nest_packages(true);
+            }
+            if self.junit_strip_ansi {
+                junit_config.set_strip_ansi(true);
+            }
+            if let Some(timestamp_format) = self.junit_timestamp_format {
+                junit_config.set_timestamp_format(timestamp_format);
+            }
+            if let Some(precision) = self.junit_elapsed_time_precision {
+                junit_config.set_elapsed_time_precision(precision);
+            }
+            if let Some(hostname) = &self.junit_hostname {
+                junit_config.set_hostname(hostname.clone());
+            }
+            if let Some(include_passing) = self.junit_include_passing {
+                junit_config.set_include_passing(include_passing);
+            }
+            if let Some(path_rewrites) = &self.junit_path_rewrites {
+                junit_config.set_path_rewrites(path_rewrites.clone());
+            }
+            if let Some(truncate_name_at) = self.junit_truncate_name_at {
+                junit_config.set_truncate_name_at(truncate_name_at);
+            }
+            if self.junit_use_error_for_panics {
+                junit_config.set_use_error_for_panics(true);
+            }
+        }
+        let junit_properties = self
+            .junit_properties
+            .iter()
+            .filter_map(JunitProperty::resolve)
+            .collect();
+        let aggregator = EventAggregator::new(
+            profile,
+            junit_config,
+            junit_properties,
+            self.junit_rerun_threshold.clone(),
+            self.bench,
+            self.capture_all.clone(),
+        );
 
         let status_level = self.status_level.unwrap_or_else(|| profile.status_level());
         let status_level = match self.no_capture {
@@ -242,16 +623,36 @@ impl TestReporterBuilder {
             .unwrap_or_else(|| profile.final_status_level());
 
         // failure_output and success_output are meaningless if the runner isn't capturing any
-        // output.
-        let force_success_output = match self.no_capture {
+        // output, or if it's already been streamed.
+        let force_success_output = match self.no_capture || self.stream_output {
             true => Some(TestOutputDisplay::Never),
             false => self.success_output,
         };
-        let force_failure_output = match self.no_capture {
+        let force_failure_output = match self.no_capture || self.stream_output {
             true => Some(TestOutputDisplay::Never),
             false => self.failure_output,
         };
 
+        let summary_format = self.summary_format.unwrap_or_else(|| {
+            let is_wide_terminal = matches!(output, ReporterStderr::Terminal)
+                && console::Term::stderr()
+                    .size_checked()
+                    .map_or(false, |(_, width)| width >= SummaryFormat::TABLE_MIN_WIDTH);
+            if is_wide_terminal {
+                SummaryFormat::Table
+            } else {
+                SummaryFormat::Compact
+            }
+        });
+
+        let progress_bar_style = if self.hide_progress_bar {
+            ProgressBarStyle::None
+        } else {
+            self.progress_bar_style.unwrap_or_else(|| {
+                ProgressBarStyle::default_for_term(std::env::var("TERM").ok().as_deref())
+            })
+        };
+
         let stderr = match output {
             ReporterStderr::Terminal if self.no_capture => {
                 // Do not use a progress bar if --no-capture is passed in. This is required since we
@@ -269,7 +670,7 @@ impl TestReporterBuilder {
                 // in these environments.
                 ReporterStderrImpl::TerminalWithoutBar
             }
-            ReporterStderr::Terminal if self.hide_progress_bar => {
+            ReporterStderr::Terminal if progress_bar_style == ProgressBarStyle::None => {
                 ReporterStderrImpl::TerminalWithoutBar
             }
 
@@ -283,16 +684,22 @@ impl TestReporterBuilder {
                 // Note: ideally we'd use the same format as our other duration displays for the elapsed time,
                 // but that isn't possible due to https://github.com/console-rs/indicatif/issues/440. Use
                 // {{elapsed_precise}} as an OK tradeoff here.
-                let template = format!(
-                    "{{prefix:>12}} [{{elapsed_precise:>9}}] [{{wide_bar}}] \
-                    {{pos:>{test_count_width}}}/{{len:{test_count_width}}}: {{msg}}     "
-                );
-                progress_bar.set_style(
-                    ProgressStyle::default_bar()
+                let style = match progress_bar_style {
+                    ProgressBarStyle::Bar => ProgressStyle::default_bar()
                         .progress_chars("=> ")
-                        .template(&template)
-                        .expect("template is known to be valid"),
-                );
+                        .template(&format!(
+                            "{{prefix:>12}} [{{elapsed_precise:>9}}] [{{wide_bar}}] \
+                            {{pos:>{test_count_width}}}/{{len:{test_count_width}}}: {{msg}}     "
+                        )),
+                    ProgressBarStyle::Fraction => ProgressStyle::default_bar().template(&format!(
+                        "{{prefix:>12}} [{{elapsed_precise:>9}}] \
+                        {{pos:>{test_count_width}}}/{{len:{test_count_width}}} tests: {{msg}}     "
+                    )),
+                    ProgressBarStyle::Spinner => ProgressStyle::default_spinner()
+                        .template("{prefix:>12} [{elapsed_precise:>9}] {spinner} {msg}     "),
+                    ProgressBarStyle::None => unreachable!("handled above"),
+                };
+                progress_bar.set_style(style.expect("template is known to be valid"));
                 // NOTE: set_draw_target must be called before enable_steady_tick to avoid a
                 // spurious extra line from being printed as the draw target changes.
                 //
@@ -306,17 +713,47 @@ impl TestReporterBuilder {
             ReporterStderr::Buffer(buf) => ReporterStderrImpl::Buffer(buf),
         };
 
+        let package_outputs = self.display_only_failing_packages.then(|| {
+            let mut package_outputs = BTreeMap::new();
+            for test_suite in test_list.iter() {
+                let output: &mut PackageOutput = package_outputs
+                    .entry(test_suite.package.name().to_owned())
+                    .or_default();
+                output.remaining += test_suite.status.test_count();
+            }
+            package_outputs
+        });
+
+        let package_summaries = (summary_format != SummaryFormat::Compact).then(|| {
+            let mut package_summaries = BTreeMap::new();
+            for test_suite in test_list.iter() {
+                if test_suite.status.test_count() > 0 {
+                    package_summaries
+                        .entry(test_suite.package.name().to_owned())
+                        .or_insert_with(PackageSummary::default);
+                }
+            }
+            package_summaries
+        });
+
         TestReporter {
             inner: TestReporterImpl {
                 status_level,
                 final_status_level,
                 force_success_output,
                 force_failure_output,
+                suppress_pass_output_threshold: self.suppress_pass_output_threshold,
                 no_capture: self.no_capture,
+                aggregate_failures: self.aggregate_failures,
                 binary_id_width,
                 styles,
                 cancel_status: None,
+                global_retry_cap: None,
                 final_outputs: DebugIgnore(vec![]),
+                aggregated_failures: DebugIgnore(vec![]),
+                package_outputs,
+                summary_format,
+                package_summaries,
             },
             stderr,
             metadata_reporter: aggregator,
@@ -348,6 +785,16 @@ impl<'a> TestReporter<'a> {
         self.write_event(event)
     }
 
+    /// Returns a handle for writing lines to stderr from other threads without corrupting this
+    /// reporter's progress bar.
+    pub fn stderr_line_writer(&self) -> StderrLineWriter {
+        let progress_bar = match &self.stderr {
+            ReporterStderrImpl::TerminalWithBar(progress_bar) => Some(progress_bar.clone()),
+            ReporterStderrImpl::TerminalWithoutBar | ReporterStderrImpl::Buffer(_) => None,
+        };
+        StderrLineWriter { progress_bar }
+    }
+
     // ---
     // Helper methods
     // ---
@@ -388,6 +835,31 @@ impl<'a> TestReporter<'a> {
     }
 }
 
+/// Writes lines to stderr while a [`TestReporter`] is active, hiding its progress bar while each
+/// line is written.
+///
+/// Created with [`TestReporter::stderr_line_writer`]. This holds on to the progress bar, so it
+/// should be dropped once the reporter is done.
+#[derive(Clone, Debug)]
+pub struct StderrLineWriter {
+    progress_bar: Option<ProgressBar>,
+}
+
+impl StderrLineWriter {
+    /// Writes `line` to stderr, followed by a newline. Write errors are ignored.
+    pub fn write_line(&self, line: &str) {
+        let write = || {
+            _ = writeln!(std::io::stderr(), "{line}");
+        };
+        match &self.progress_bar {
+            // As in TestReporter::write_event, suspend rather than println so that the line is
+            // written even if the bar is hidden.
+            Some(progress_bar) => progress_bar.suspend(write),
+            None => write(),
+        }
+    }
+}
+
 fn update_progress_bar(event: &TestEvent<'_>, styles: &Styles, progress_bar: &mut ProgressBar) {
     match event {
         TestEvent::TestStarted {
@@ -410,6 +882,16 @@ fn update_progress_bar(event: &TestEvent<'_>, styles: &Styles, progress_bar: &mu
             progress_bar.set_length(current_stats.initial_run_count as u64);
             progress_bar.set_position(current_stats.finished_count as u64);
         }
+        TestEvent::TestCancelled {
+            reason,
+            current_stats,
+            running,
+            ..
+        } => {
+            let running_state = RunningState::Canceling(*reason);
+            progress_bar.set_prefix(running_state.progress_bar_prefix(styles));
+            progress_bar.set_message(progress_bar_msg(current_stats, *running, styles));
+        }
         TestEvent::RunBeginCancel { reason, .. } => {
             let running_state = RunningState::Canceling(*reason);
             progress_bar.set_prefix(running_state.progress_bar_prefix(styles));
@@ -518,6 +1000,15 @@ fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut String) ->
         )?;
     }
 
+    if run_stats.cancelled > 0 {
+        write!(
+            out,
+            "{} {}, ",
+            run_stats.cancelled.style(styles.count),
+            "cancelled".style(styles.skip),
+        )?;
+    }
+
     write!(
         out,
         "{} {}",
@@ -546,16 +1037,53 @@ impl FinalOutput {
     }
 }
 
+/// Output for a failing test, deferred until the end of the run.
+struct AggregatedFailure<'a> {
+    test_instance: TestInstance<'a>,
+    run_status: ExecuteStatus,
+    is_retry: bool,
+}
+
+/// Output for the tests in a package, held back until all of them have finished.
+#[derive(Default)]
+struct PackageOutput {
+    remaining: usize,
+    finished: usize,
+    failed: usize,
+    buf: Vec<u8>,
+}
+
+/// Test counts for a package, displayed in the summary at the end of the run.
+#[derive(Default)]
+struct PackageSummary {
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    time_taken: Duration,
+}
+
 struct TestReporterImpl<'a> {
     status_level: StatusLevel,
     final_status_level: FinalStatusLevel,
     force_success_output: Option<TestOutputDisplay>,
     force_failure_output: Option<TestOutputDisplay>,
+    suppress_pass_output_threshold: Option<u64>,
     no_capture: bool,
+    aggregate_failures: bool,
     binary_id_width: usize,
     styles: Box<Styles>,
     cancel_status: Option<CancelReason>,
+    // Set once the global retry cap is reached.
+    global_retry_cap: Option<usize>,
     final_outputs: DebugIgnore<Vec<(TestInstance<'a>, FinalOutput)>>,
+    // Only populated if aggregate_failures is true.
+    aggregated_failures: DebugIgnore<Vec<AggregatedFailure<'a>>>,
+    // Only present if display_only_failing_packages is true. Packages are removed once all of
+    // their tests have finished.
+    package_outputs: Option<BTreeMap<String, PackageOutput>>,
+    summary_format: SummaryFormat,
+    // Only present if summary_format isn't compact.
+    package_summaries: Option<BTreeMap<String, PackageSummary>>,
 }
 
 impl<'a> TestReporterImpl<'a> {
@@ -563,6 +1091,147 @@ impl<'a> TestReporterImpl<'a> {
         &mut self,
         event: &TestEvent<'a>,
         writer: &mut impl Write,
+    ) -> io::Result<()> {
+        self.update_package_summary(event);
+        if self.package_outputs.is_none() {
+            return self.write_event_inner(event, writer);
+        }
+
+        let test_instance = match event {
+            TestEvent::TestStarted { test_instance, .. }
+            | TestEvent::TestSlow { test_instance, .. }
+            | TestEvent::TestAttemptFailedWillRetry { test_instance, .. }
+            | TestEvent::TestRetryStarted { test_instance, .. }
+            | TestEvent::TestFinished { test_instance, .. }
+            | TestEvent::TestCancelled { test_instance, .. }
+            | TestEvent::TestSkipped { test_instance, .. }
+            | TestEvent::LeakyTestDetected { test_instance, .. }
+            | TestEvent::SuspiciouslyFastTest { test_instance, .. }
+            | TestEvent::TimingRegression { test_instance, .. }
+            | TestEvent::TestExceededAbsoluteMaxTime { test_instance, .. }
+            | TestEvent::NetworkAccessViolation { test_instance }
+            | TestEvent::ProcessLeak { test_instance, .. } => test_instance,
+            TestEvent::RunFinished { .. } => {
+                // Packages that still have tests remaining were cut short by a cancellation.
+                // Print the ones that have failed so far.
+                let package_outputs = self.package_outputs.take().expect("checked above");
+                for (package_name, output) in package_outputs {
+                    self.write_package_output(&package_name, output, writer)?;
+                }
+                return self.write_event_inner(event, writer);
+            }
+            _ => return self.write_event_inner(event, writer),
+        };
+
+        let mut buf = Vec::new();
+        self.write_event_inner(event, &mut buf)?;
+
+        let package_outputs = self.package_outputs.as_mut().expect("checked above");
+        let package_name = test_instance.suite_info.package.name();
+        let output = match package_outputs.get_mut(package_name) {
+            Some(output) => output,
+            // Every test in the test list is accounted for, so this shouldn't happen. Write the
+            // output directly rather than losing it.
+            None => return writer.write_all(&buf),
+        };
+        output.buf.extend(buf);
+        match event {
+            TestEvent::TestFinished { run_statuses, .. } => {
+                output.remaining = output.remaining.saturating_sub(1);
+                output.finished += 1;
+                if !run_statuses.last_status().result.is_success() {
+                    output.failed += 1;
+                }
+            }
+            TestEvent::TestCancelled { .. } | TestEvent::TestSkipped { .. } => {
+                output.remaining = output.remaining.saturating_sub(1);
+            }
+            _ => {}
+        }
+
+        if output.remaining == 0 {
+            let output = package_outputs
+                .remove(package_name)
+                .expect("package output exists");
+            self.write_package_output(package_name, output, writer)?;
+        }
+        Ok(())
+    }
+
+    fn update_package_summary(&mut self, event: &TestEvent<'a>) {
+        let package_summaries = match &mut self.package_summaries {
+            Some(package_summaries) => package_summaries,
+            None => return,
+        };
+        let total_time = |run_statuses: &ExecutionStatuses| {
+            run_statuses
+                .iter()
+                .map(|status| status.time_taken)
+                .sum::<Duration>()
+        };
+
+        match event {
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let summary = package_summaries
+                    .entry(test_instance.suite_info.package.name().to_owned())
+                    .or_default();
+                if run_statuses.last_status().result.is_success() {
+                    summary.passed += 1;
+                } else {
+                    summary.failed += 1;
+                }
+                summary.time_taken += total_time(run_statuses);
+            }
+            TestEvent::TestCancelled {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let summary = package_summaries
+                    .entry(test_instance.suite_info.package.name().to_owned())
+                    .or_default();
+                summary.time_taken += total_time(run_statuses);
+            }
+            TestEvent::TestSkipped { test_instance, .. } => {
+                package_summaries
+                    .entry(test_instance.suite_info.package.name().to_owned())
+                    .or_default()
+                    .skipped += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes out the held-back output for a package if any of its tests failed.
+    fn write_package_output(
+        &self,
+        package_name: &str,
+        output: PackageOutput,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        if output.failed == 0 {
+            return Ok(());
+        }
+
+        writeln!(
+            writer,
+            "{:>12} {}: {}/{} tests failed",
+            "PACKAGE".style(self.styles.fail),
+            package_name.style(self.styles.list_styles.binary_id),
+            output.failed.style(self.styles.count),
+            output.finished.style(self.styles.count),
+        )?;
+        writer.write_all(&output.buf)
+    }
+
+    fn write_event_inner(
+        &mut self,
+        event: &TestEvent<'a>,
+        writer: &mut impl Write,
     ) -> io::Result<()> {
         match event {
             TestEvent::RunStarted { test_list, .. } => {
@@ -640,6 +1309,7 @@ impl<'a> TestReporterImpl<'a> {
                 run_status,
                 delay_before_next_attempt,
                 failure_output,
+                ..
             } => {
                 if self.status_level >= StatusLevel::Retry {
                     let try_status_string = format!(
@@ -654,7 +1324,7 @@ impl<'a> TestReporterImpl<'a> {
                     )?;
 
                     // Next, print the time taken.
-                    self.write_duration(run_status.time_taken, writer)?;
+                    self.write_time_taken(run_status, writer)?;
 
                     // Print the name of the test.
                     self.write_instance(*test_instance, writer)?;
@@ -666,7 +1336,15 @@ impl<'a> TestReporterImpl<'a> {
                         "only failing tests are retried"
                     );
                     if self.failure_output(*failure_output).is_immediate() {
-                        self.write_stdout_stderr(test_instance, run_status, true, writer)?;
+                        if self.aggregate_failures {
+                            self.aggregated_failures.push(AggregatedFailure {
+                                test_instance: *test_instance,
+                                run_status: run_status.clone(),
+                                is_retry: true,
+                            });
+                        } else {
+                            self.write_stdout_stderr(test_instance, run_status, true, writer)?;
+                        }
                     }
 
                     // The final output doesn't show retries, so don't store this result in
@@ -717,6 +1395,9 @@ impl<'a> TestReporterImpl<'a> {
                 let describe = run_statuses.describe();
                 let last_status = run_statuses.last_status();
                 let test_output_display = match last_status.result.is_success() {
+                    true if self.is_below_pass_output_threshold(last_status) => {
+                        TestOutputDisplay::Never
+                    }
                     true => self.success_output(*success_output),
                     false => self.failure_output(*failure_output),
                 };
@@ -729,7 +1410,15 @@ impl<'a> TestReporterImpl<'a> {
                     if self.cancel_status < Some(CancelReason::Signal)
                         && test_output_display.is_immediate()
                     {
-                        self.write_stdout_stderr(test_instance, last_status, false, writer)?;
+                        if self.aggregate_failures && !last_status.result.is_success() {
+                            self.aggregated_failures.push(AggregatedFailure {
+                                test_instance: *test_instance,
+                                run_status: last_status.clone(),
+                                is_retry: false,
+                            });
+                        } else {
+                            self.write_stdout_stderr(test_instance, last_status, false, writer)?;
+                        }
                     }
                 }
 
@@ -747,33 +1436,199 @@ impl<'a> TestReporterImpl<'a> {
                     ));
                 }
             }
-            TestEvent::TestSkipped {
+            TestEvent::TestCancelled {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                if self.status_level >= StatusLevel::Fail {
+                    write!(writer, "{:>12} ", "CANCEL".style(self.styles.skip))?;
+                    self.write_time_taken(run_statuses.last_status(), writer)?;
+                    self.write_instance(*test_instance, writer)?;
+                    writeln!(writer)?;
+                }
+            }
+            TestEvent::TestSkipped {
+                test_instance,
+                reason,
+            } => {
+                if self.status_level >= StatusLevel::Skip {
+                    self.write_skip_line(*test_instance, writer)?;
+                }
+                if self.final_status_level >= FinalStatusLevel::Skip {
+                    self.final_outputs
+                        .push((*test_instance, FinalOutput::Skipped(*reason)));
+                }
+            }
+            TestEvent::LeakyTestDetected {
+                test_instance,
+                mutations,
+            } => {
+                if self.status_level >= StatusLevel::Fail {
+                    write!(writer, "{:>12} ", "MUTATED".style(self.styles.skip))?;
+                    // Add spacing to align test instances.
+                    write!(writer, "[{:<9}] ", "")?;
+                    self.write_instance(*test_instance, writer)?;
+                    writeln!(writer)?;
+                    for mutation in mutations {
+                        writeln!(writer, "{:>12}   - {mutation}", "")?;
+                    }
+                }
+            }
+            TestEvent::SuspiciouslyFastTest {
+                test_instance,
+                time_taken,
+                min_run_time,
+            } => {
+                if self.status_level >= StatusLevel::Fail {
+                    write!(writer, "{:>12} ", "TOO FAST".style(self.styles.skip))?;
+                    // Durations this short are printed in their natural unit (e.g. "850ns").
+                    write!(writer, "[{:>8}] ", format!("{time_taken:?}"))?;
+                    self.write_instance(*test_instance, writer)?;
+                    writeln!(
+                        writer,
+                        " (minimum run time: {})",
+                        format!("{min_run_time:?}").style(self.styles.count)
+                    )?;
+                }
+            }
+            TestEvent::TimingRegression {
+                test_instance,
+                baseline,
+                current,
+                regression_pct,
+            } => {
+                if self.status_level >= StatusLevel::Fail {
+                    write!(writer, "{:>12} ", "REGRESSED".style(self.styles.fail))?;
+                    self.write_duration(*current, writer)?;
+                    self.write_instance(*test_instance, writer)?;
+                    writeln!(
+                        writer,
+                        " ({} slower than baseline of {:.3}s)",
+                        format!("{regression_pct:.1}%").style(self.styles.count),
+                        baseline.as_secs_f64(),
+                    )?;
+                }
+            }
+            TestEvent::TestExceededAbsoluteMaxTime {
+                test_instance,
+                retry_data,
+                max_test_time,
+            } => {
+                let (required_status_level, style) = if retry_data.is_last_attempt() {
+                    (StatusLevel::Fail, self.styles.fail)
+                } else {
+                    (StatusLevel::Retry, self.styles.retry)
+                };
+                if self.status_level >= required_status_level {
+                    write!(writer, "{:>12} ", "MAX TIME".style(style))?;
+                    self.write_duration(*max_test_time, writer)?;
+                    self.write_instance(*test_instance, writer)?;
+                    writeln!(writer, " (killed after exceeding the maximum test time)")?;
+                }
+            }
+            TestEvent::NetworkAccessViolation { test_instance } => {
+                if self.status_level >= StatusLevel::Fail {
+                    write!(writer, "{:>12} ", "NETWORK".style(self.styles.fail))?;
+                    // Add spacing to align test instances.
+                    write!(writer, "[{:<9}] ", "")?;
+                    self.write_instance(*test_instance, writer)?;
+                    writeln!(writer, " (killed for attempting to access the network)")?;
+                }
+            }
+            TestEvent::ProcessLeak {
                 test_instance,
-                reason,
+                retry_data,
+                leaked_pids,
             } => {
-                if self.status_level >= StatusLevel::Skip {
-                    self.write_skip_line(*test_instance, writer)?;
+                let (required_status_level, style) = if retry_data.is_last_attempt() {
+                    (StatusLevel::Fail, self.styles.fail)
+                } else {
+                    (StatusLevel::Retry, self.styles.retry)
+                };
+                if self.status_level >= required_status_level {
+                    write!(writer, "{:>12} ", "PROC LEAK".style(style))?;
+                    // Add spacing to align test instances.
+                    write!(writer, "[{:<9}] ", "")?;
+                    self.write_instance(*test_instance, writer)?;
+                    let pids: Vec<_> = leaked_pids.iter().map(|pid| pid.to_string()).collect();
+                    writeln!(
+                        writer,
+                        " (left {} {} running: {})",
+                        leaked_pids.len().style(self.styles.count),
+                        if leaked_pids.len() == 1 {
+                            "process"
+                        } else {
+                            "processes"
+                        },
+                        pids.join(", "),
+                    )?;
                 }
-                if self.final_status_level >= FinalStatusLevel::Skip {
-                    self.final_outputs
-                        .push((*test_instance, FinalOutput::Skipped(*reason)));
+            }
+            TestEvent::TestOutputLine {
+                test_instance,
+                line,
+                ..
+            } => {
+                write!(
+                    writer,
+                    "[{}::{}] ",
+                    test_instance
+                        .suite_info
+                        .binary_id
+                        .style(self.styles.list_styles.binary_id),
+                    test_instance.name.style(self.styles.list_styles.test_name),
+                )?;
+                writer.write_all(line)?;
+                if !line.ends_with(b"\n") {
+                    writeln!(writer)?;
                 }
             }
+            TestEvent::BinaryFailureLimitReached {
+                suite_info,
+                failure_limit,
+            } => {
+                writeln!(
+                    writer,
+                    "{:>12} running tests from {} after {} failures",
+                    "Stopping".style(self.styles.fail),
+                    suite_info
+                        .binary_id
+                        .style(self.styles.list_styles.binary_id),
+                    failure_limit.style(self.styles.count),
+                )?;
+            }
+            TestEvent::BinaryTimedOut {
+                suite_info,
+                timeout,
+            } => {
+                writeln!(
+                    writer,
+                    "{:>12} running tests from {} after {}",
+                    "Stopping".style(self.styles.fail),
+                    suite_info
+                        .binary_id
+                        .style(self.styles.list_styles.binary_id),
+                    format_duration(*timeout).style(self.styles.count),
+                )?;
+            }
+            TestEvent::GlobalRetryCapReached { max_retries } => {
+                self.global_retry_cap = Some(*max_retries);
+                writeln!(
+                    writer,
+                    "{:>12} retrying failed tests after {} retries (global retry limit reached)",
+                    "Stopping".style(self.styles.retry),
+                    max_retries.style(self.styles.count),
+                )?;
+            }
             TestEvent::RunBeginCancel { running, reason } => {
                 self.cancel_status = self.cancel_status.max(Some(*reason));
 
                 write!(writer, "{:>12} ", "Canceling".style(self.styles.fail))?;
-                let reason_str = match reason {
-                    CancelReason::TestFailure => "test failure",
-                    CancelReason::ReportError => "error",
-                    CancelReason::Signal => "signal",
-                    CancelReason::Interrupt => "interrupt",
-                };
-
                 writeln!(
                     writer,
                     "due to {}: {} tests still running",
-                    reason_str.style(self.styles.fail),
+                    reason.style(self.styles.fail),
                     running.style(self.styles.count)
                 )?;
             }
@@ -801,6 +1656,21 @@ impl<'a> TestReporterImpl<'a> {
                 run_stats,
                 ..
             } => {
+                // Print out aggregated failures, quickest first. Don't do this if canceled due to
+                // Ctrl-C.
+                if self.cancel_status < Some(CancelReason::Signal) {
+                    self.aggregated_failures
+                        .sort_by_key(|failure| failure.run_status.time_taken);
+                    for failure in &*self.aggregated_failures {
+                        self.write_stdout_stderr(
+                            &failure.test_instance,
+                            &failure.run_status,
+                            failure.is_retry,
+                            writer,
+                        )?;
+                    }
+                }
+
                 let summary_style = if run_stats.any_failed() {
                     self.styles.fail
                 } else {
@@ -835,6 +1705,15 @@ impl<'a> TestReporterImpl<'a> {
                 // Writing to a string is infallible.
                 let _ = write_summary_str(run_stats, &self.styles, &mut summary_str);
                 writeln!(writer, " tests run: {summary_str}")?;
+                if let Some(max_retries) = self.global_retry_cap {
+                    writeln!(
+                        writer,
+                        "{:>12} global retry limit of {} was reached: later failures weren't retried",
+                        "Note".style(self.styles.retry),
+                        max_retries.style(self.styles.count),
+                    )?;
+                }
+                self.write_package_summaries(writer)?;
 
                 // Don't print out final outputs if canceled due to Ctrl-C.
                 if self.cancel_status < Some(CancelReason::Signal) {
@@ -889,6 +1768,100 @@ impl<'a> TestReporterImpl<'a> {
         Ok(())
     }
 
+    fn write_package_summaries(&self, writer: &mut impl Write) -> io::Result<()> {
+        let package_summaries = match &self.package_summaries {
+            Some(package_summaries) => package_summaries,
+            None => return Ok(()),
+        };
+
+        match self.summary_format {
+            SummaryFormat::Compact => {}
+            SummaryFormat::Verbose => {
+                for (package_name, summary) in package_summaries {
+                    let summary_style = if summary.failed > 0 {
+                        self.styles.fail
+                    } else {
+                        self.styles.pass
+                    };
+                    writeln!(
+                        writer,
+                        "{:>12} [{:>8.3?}s] {}: {} {}, {} {}, {} {}",
+                        "Package".style(summary_style),
+                        summary.time_taken.as_secs_f64(),
+                        package_name.style(self.styles.list_styles.binary_id),
+                        summary.passed.style(self.styles.count),
+                        "passed".style(self.styles.pass),
+                        summary.failed.style(self.styles.count),
+                        "failed".style(self.styles.fail),
+                        summary.skipped.style(self.styles.count),
+                        "skipped".style(self.styles.skip),
+                    )?;
+                }
+            }
+            SummaryFormat::Table => {
+                const HEADERS: [&str; 5] = ["Package", "Passed", "Failed", "Skipped", "Time"];
+
+                let rows: Vec<_> = package_summaries
+                    .iter()
+                    .map(|(package_name, summary)| {
+                        [
+                            package_name.clone(),
+                            summary.passed.to_string(),
+                            summary.failed.to_string(),
+                            summary.skipped.to_string(),
+                            format!("{:.3}s", summary.time_taken.as_secs_f64()),
+                        ]
+                    })
+                    .collect();
+                let mut widths = HEADERS.map(str::len);
+                for row in &rows {
+                    for (width, cell) in widths.iter_mut().zip(row) {
+                        *width = (*width).max(cell.len());
+                    }
+                }
+
+                // The package column is left-aligned and the others are right-aligned.
+                write!(writer, "{:>12} ", "")?;
+                for (index, (header, width)) in HEADERS.iter().zip(widths).enumerate() {
+                    let header = header.style(self.styles.count);
+                    match index {
+                        0 => write!(writer, "{header:<width$}")?,
+                        _ => write!(writer, "  {header:>width$}")?,
+                    }
+                }
+                writeln!(writer)?;
+
+                write!(writer, "{:>12} ", "")?;
+                for (index, width) in widths.iter().enumerate() {
+                    let sep = if index == 0 { "" } else { "  " };
+                    write!(writer, "{sep}{}", "-".repeat(*width))?;
+                }
+                writeln!(writer)?;
+
+                for (row, (_, summary)) in rows.iter().zip(package_summaries) {
+                    write!(
+                        writer,
+                        "{:>12} {:<width$}",
+                        "",
+                        row[0].style(self.styles.list_styles.binary_id),
+                        width = widths[0],
+                    )?;
+                    for (index, (cell, width)) in row.iter().zip(widths).enumerate().skip(1) {
+                        // Highlight the failure count for packages that have failing tests.
+                        if index == 2 && summary.failed > 0 {
+                            write!(writer, "  {:>width$}", cell.style(self.styles.fail))?;
+                        } else {
+                            write!(writer, "  {cell:>width$}")?;
+                        }
+                    }
+                    writeln!(writer)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn write_skip_line(
         &self,
         test_instance: TestInstance<'a>,
@@ -947,7 +1920,7 @@ impl<'a> TestReporterImpl<'a> {
         };
 
         // Next, print the time taken.
-        self.write_duration(last_status.time_taken, writer)?;
+        self.write_time_taken(last_status, writer)?;
 
         // Print the name of the test.
         self.write_instance(test_instance, writer)?;
@@ -962,6 +1935,9 @@ impl<'a> TestReporterImpl<'a> {
         {
             self.write_windows_message_line(nt_status, writer)?;
         }
+        if let ExecutionResult::StderrWritten { .. } = last_status.result {
+            self.write_stderr_written_message_line(last_status, writer)?;
+        }
 
         Ok(())
     }
@@ -1022,7 +1998,7 @@ impl<'a> TestReporterImpl<'a> {
         };
 
         // Next, print the time taken.
-        self.write_duration(last_status.time_taken, writer)?;
+        self.write_time_taken(last_status, writer)?;
 
         // Print the name of the test.
         self.write_instance(test_instance, writer)?;
@@ -1037,6 +2013,9 @@ impl<'a> TestReporterImpl<'a> {
         {
             self.write_windows_message_line(nt_status, writer)?;
         }
+        if let ExecutionResult::StderrWritten { .. } = last_status.result {
+            self.write_stderr_written_message_line(last_status, writer)?;
+        }
 
         Ok(())
     }
@@ -1066,6 +2045,19 @@ impl<'a> TestReporterImpl<'a> {
         write!(writer, "[{:>8.3?}s] ", duration.as_secs_f64())
     }
 
+    fn write_time_taken(
+        &self,
+        run_status: &ExecuteStatus,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        if run_status.time_taken_known {
+            self.write_duration(run_status.time_taken, writer)
+        } else {
+            // Pad to the same width as write_duration.
+            write!(writer, "[{:>9}] ", "unknown")
+        }
+    }
+
     fn write_duration_by(&self, duration: Duration, writer: &mut impl Write) -> io::Result<()> {
         // * > means right-align.
         // * 7 is the number of characters to pad to.
@@ -1098,6 +2090,22 @@ impl<'a> TestReporterImpl<'a> {
         Ok(())
     }
 
+    fn write_stderr_written_message_line(
+        &self,
+        run_status: &ExecuteStatus,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        write!(writer, "{:>12} ", "Message".style(self.styles.fail))?;
+        write!(writer, "[         ] ")?;
+        writeln!(
+            writer,
+            "test passed, but wrote {} bytes to stderr with fail-on-stderr set",
+            run_status.stderr.len()
+        )?;
+
+        Ok(())
+    }
+
     fn write_stdout_stderr(
         &self,
         test_instance: &TestInstance<'a>,
@@ -1191,6 +2199,16 @@ impl<'a> TestReporterImpl<'a> {
         self.force_success_output.unwrap_or(test_setting)
     }
 
+    /// Returns true if a passing test produced too little output to be worth displaying.
+    fn is_below_pass_output_threshold(&self, run_status: &ExecuteStatus) -> bool {
+        match self.suppress_pass_output_threshold {
+            Some(threshold) => {
+                ((run_status.stdout.len() + run_status.stderr.len()) as u64) < threshold
+            }
+            None => false,
+        }
+    }
+
     fn failure_output(&self, test_setting: TestOutputDisplay) -> TestOutputDisplay {
         self.force_failure_output.unwrap_or(test_setting)
     }
@@ -1237,6 +2255,10 @@ fn status_str(result: ExecutionResult) -> Cow<'static, str> {
         ExecutionResult::Pass => "PASS".into(),
         ExecutionResult::Leak => "LEAK".into(),
         ExecutionResult::Timeout => "TIMEOUT".into(),
+        ExecutionResult::StderrWritten { leaked: true } => "STDERR+LEAK".into(),
+        ExecutionResult::StderrWritten { leaked: false } => "FAIL STDERR".into(),
+        ExecutionResult::SimulatedFailure => "FAIL SIM".into(),
+        ExecutionResult::ProcessLeak => "FAIL ORPHAN".into(),
     }
 }
 
@@ -1268,6 +2290,9 @@ fn short_status_str(result: ExecutionResult) -> Cow<'static, str> {
         ExecutionResult::Pass => "PASS".into(),
         ExecutionResult::Leak => "LEAK".into(),
         ExecutionResult::Timeout => "TMT".into(),
+        ExecutionResult::StderrWritten { .. } => "STDERR".into(),
+        ExecutionResult::SimulatedFailure => "SIM".into(),
+        ExecutionResult::ProcessLeak => "ORPHAN".into(),
     }
 }
 
@@ -1335,6 +2360,9 @@ pub enum TestEvent<'a> {
 
         /// Whether failure outputs are printed out.
         failure_output: TestOutputDisplay,
+
+        /// Why the test is being retried.
+        retry_reason: RetryReason,
     },
 
     /// A retry has started.
@@ -1376,6 +2404,28 @@ pub enum TestEvent<'a> {
         cancel_state: Option<CancelReason>,
     },
 
+    /// A test was terminated while running because the run was canceled.
+    ///
+    /// This is sent instead of [`TestEvent::TestFinished`], and the test is counted in
+    /// [`RunStats::cancelled`] rather than as a failure.
+    TestCancelled {
+        /// The test instance that was cancelled.
+        test_instance: TestInstance<'a>,
+
+        /// The reason the run was canceled.
+        reason: CancelReason,
+
+        /// Information about all the runs for this test, including the attempt that was
+        /// terminated.
+        run_statuses: ExecutionStatuses,
+
+        /// Current statistics for number of tests so far.
+        current_stats: RunStats,
+
+        /// The number of tests that are currently running, excluding this one.
+        running: usize,
+    },
+
     /// A test was skipped.
     TestSkipped {
         /// The test instance that was skipped.
@@ -1385,6 +2435,156 @@ pub enum TestEvent<'a> {
         reason: MismatchReason,
     },
 
+    /// A test was found to have mutated global state while it ran.
+    ///
+    /// Only emitted if leaky test detection is enabled: see
+    /// [`TestRunnerBuilder::set_detect_leaky_tests`](crate::runner::TestRunnerBuilder::set_detect_leaky_tests).
+    /// This event is sent before the corresponding [`TestEvent::TestFinished`].
+    LeakyTestDetected {
+        /// The test instance that mutated state.
+        test_instance: TestInstance<'a>,
+
+        /// The mutations that were observed, sorted by path.
+        mutations: Vec<StateMutation>,
+    },
+
+    /// A test passed faster than the configured minimum run time.
+    ///
+    /// Tests that finish this quickly are often not running their assertions at all. Only emitted
+    /// if a minimum run time is set: see
+    /// [`TestRunnerBuilder::set_min_run_time`](crate::runner::TestRunnerBuilder::set_min_run_time).
+    /// This event is sent before the corresponding [`TestEvent::TestFinished`].
+    SuspiciouslyFastTest {
+        /// The test instance that finished quickly.
+        test_instance: TestInstance<'a>,
+
+        /// The time taken by the final attempt of the test.
+        time_taken: Duration,
+
+        /// The configured minimum run time.
+        min_run_time: Duration,
+    },
+
+    /// A test passed, but took longer than its baseline time by more than the configured
+    /// threshold.
+    ///
+    /// Only emitted if baseline timings are set: see
+    /// [`TestRunnerBuilder::set_timing_baseline`](crate::runner::TestRunnerBuilder::set_timing_baseline).
+    /// This event is sent before the corresponding [`TestEvent::TestFinished`].
+    TimingRegression {
+        /// The test instance that regressed.
+        test_instance: TestInstance<'a>,
+
+        /// The time taken by the test in the baseline.
+        baseline: Duration,
+
+        /// The time taken by the final attempt of the test.
+        current: Duration,
+
+        /// How much longer the test took than the baseline, as a percentage of the baseline.
+        regression_pct: f64,
+    },
+
+    /// A test was killed for running for longer than the absolute maximum test time.
+    ///
+    /// Only emitted if a maximum test time is set: see
+    /// [`TestRunnerBuilder::set_max_test_time`](crate::runner::TestRunnerBuilder::set_max_test_time).
+    /// This event is sent for each attempt that's killed, before the corresponding
+    /// [`TestEvent::TestAttemptFailedWillRetry`] or [`TestEvent::TestFinished`].
+    TestExceededAbsoluteMaxTime {
+        /// The test instance that was killed.
+        test_instance: TestInstance<'a>,
+
+        /// Retry data.
+        retry_data: RetryData,
+
+        /// The maximum test time.
+        max_test_time: Duration,
+    },
+
+    /// A test was killed for attempting to access the network.
+    ///
+    /// Only emitted if network access is blocked: see
+    /// [`TestRunnerBuilder::set_network_access`](crate::runner::TestRunnerBuilder::set_network_access).
+    /// This event is sent before the corresponding [`TestEvent::TestFinished`].
+    NetworkAccessViolation {
+        /// The test instance that attempted to access the network.
+        test_instance: TestInstance<'a>,
+    },
+
+    /// A test left processes running after it exited.
+    ///
+    /// Only emitted if leaked processes are detected: see
+    /// [`TestRunnerBuilder::set_fail_on_leaked_processes`](crate::runner::TestRunnerBuilder::set_fail_on_leaked_processes).
+    /// This event is sent for each attempt that leaks processes, before the corresponding
+    /// [`TestEvent::TestAttemptFailedWillRetry`] or [`TestEvent::TestFinished`].
+    ProcessLeak {
+        /// The test instance that leaked processes.
+        test_instance: TestInstance<'a>,
+
+        /// Retry data.
+        retry_data: RetryData,
+
+        /// The IDs of the processes that were still running, in ascending order.
+        leaked_pids: Vec<u32>,
+    },
+
+    /// A running test wrote a line of output.
+    ///
+    /// Only emitted in stream-output mode: see
+    /// [`TestRunnerBuilder::set_stream_output`](crate::runner::TestRunnerBuilder::set_stream_output).
+    /// The output is still captured, and is also part of the [`ExecuteStatus`] for the attempt.
+    TestOutputLine {
+        /// The test instance that wrote the line.
+        test_instance: TestInstance<'a>,
+
+        /// Whether the line was written to standard output or standard error.
+        stream: TestOutputStream,
+
+        /// The line, including the trailing newline if there was one.
+        line: Bytes,
+    },
+
+    /// A test binary reached its failure limit, so no more tests from it will be started.
+    ///
+    /// Only emitted if a failure limit is set: see
+    /// [`TestRunnerBuilder::set_failure_limit_per_binary`](crate::runner::TestRunnerBuilder::set_failure_limit_per_binary).
+    /// This event is sent after the [`TestEvent::TestFinished`] for the failure that reached the
+    /// limit. Tests from the binary that are already running are allowed to finish, and tests that
+    /// haven't started yet are reported through [`TestEvent::TestSkipped`].
+    BinaryFailureLimitReached {
+        /// The test binary that reached its failure limit.
+        suite_info: &'a RustTestSuite<'a>,
+
+        /// The configured failure limit.
+        failure_limit: NonZeroUsize,
+    },
+
+    /// A test binary exceeded its timeout, so no more tests from it will be started.
+    ///
+    /// Only emitted if a timeout is set: see
+    /// [`TestRunnerBuilder::set_test_binary_timeout`](crate::runner::TestRunnerBuilder::set_test_binary_timeout).
+    /// This event is sent once, when the timeout is first noticed: either when a running test from
+    /// the binary is terminated, or when a test that hasn't started yet is skipped. Skipped tests
+    /// are reported through [`TestEvent::TestSkipped`].
+    BinaryTimedOut {
+        /// The test binary that timed out.
+        suite_info: &'a RustTestSuite<'a>,
+
+        /// The configured timeout.
+        timeout: Duration,
+    },
+
+    /// The global retry cap was reached, so tests that fail from now on won't be retried.
+    ///
+    /// Only emitted if a cap is set: see
+    /// [`TestRunnerBuilder::set_max_retries_global`](crate::runner::TestRunnerBuilder::set_max_retries_global).
+    /// This event is sent when a retry is first skipped because of the cap.
+    GlobalRetryCapReached {
+        /// The configured cap.
+        max_retries: usize,
+    },
+
     /// A cancellation notice was received.
     RunBeginCancel {
         /// The number of tests still running.
@@ -1422,6 +2622,16 @@ pub enum TestEvent<'a> {
     },
 }
 
+/// The stream a [`TestEvent::TestOutputLine`] was written to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TestOutputStream {
+    /// Standard output.
+    Stdout,
+
+    /// Standard error.
+    Stderr,
+}
+
 // Note: the order here matters -- it indicates severity of cancellation
 /// The reason why a test run is being cancelled.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -1439,6 +2649,17 @@ pub enum CancelReason {
     Interrupt,
 }
 
+impl fmt::Display for CancelReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TestFailure => write!(f, "test failure"),
+            Self::ReportError => write!(f, "error"),
+            Self::Signal => write!(f, "signal"),
+            Self::Interrupt => write!(f, "interrupt"),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 struct Styles {
     is_colorized: bool,
@@ -1471,7 +2692,45 @@ impl Styles {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config::NextestConfig, platform::BuildPlatforms};
+    use crate::{
+        cargo_config::EnvironmentMap,
+        config::NextestConfig,
+        list::{
+            test_helpers::{package_metadata, BASE_PACKAGE_ID, HELPER_PACKAGE_ID},
+            RustBuildMeta, RustTestArtifact,
+        },
+        platform::BuildPlatforms,
+        reuse_build::PathMapper,
+        runner::RetryData,
+        test_filter::{RunIgnored, TestFilterBuilder},
+    };
+    use nextest_metadata::{BuildPlatform, RustBinaryId, RustTestBinaryKind};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn junit_property() {
+        assert_eq!(
+            "build=a=b".parse::<JunitProperty>().unwrap(),
+            JunitProperty {
+                name: "build".to_owned(),
+                value: Some("a=b".to_owned()),
+            }
+        );
+        assert_eq!(
+            "CI_COMMIT_SHA".parse::<JunitProperty>().unwrap(),
+            JunitProperty {
+                name: "CI_COMMIT_SHA".to_owned(),
+                value: None,
+            }
+        );
+        "=value".parse::<JunitProperty>().unwrap_err();
+
+        let from_env = JunitProperty {
+            name: "__NEXTEST_JUNIT_PROPERTY_UNSET".to_owned(),
+            value: None,
+        };
+        assert_eq!(from_env.resolve(), None, "unset variables are skipped");
+    }
 
     #[test]
     fn no_capture_settings() {
@@ -1511,4 +2770,223 @@ mod tests {
             "status level is pass, overriding other settings"
         );
     }
+
+    #[test]
+    fn progress_bar_style_default() {
+        assert_eq!(
+            ProgressBarStyle::default_for_term(Some("dumb")),
+            ProgressBarStyle::Spinner
+        );
+        assert_eq!(
+            ProgressBarStyle::default_for_term(Some("xterm-256color")),
+            ProgressBarStyle::Bar
+        );
+        assert_eq!(
+            ProgressBarStyle::default_for_term(None),
+            ProgressBarStyle::Bar
+        );
+    }
+
+    #[test]
+    fn summary_formats() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let profile = profile.apply_build_platforms(&build_platforms);
+
+        let write_summaries = |summary_format| {
+            let mut builder = TestReporterBuilder::default();
+            builder.set_summary_format(summary_format);
+            let mut buf: Vec<u8> = Vec::new();
+            let mut reporter =
+                builder.build(&test_list, &profile, ReporterStderr::Buffer(&mut buf));
+            if let Some(package_summaries) = &mut reporter.inner.package_summaries {
+                package_summaries.insert(
+                    "my-package".to_owned(),
+                    PackageSummary {
+                        passed: 12,
+                        failed: 1,
+                        skipped: 2,
+                        time_taken: Duration::from_millis(1500),
+                    },
+                );
+                package_summaries.insert(
+                    "other".to_owned(),
+                    PackageSummary {
+                        passed: 3,
+                        ..PackageSummary::default()
+                    },
+                );
+            }
+            let mut out = Vec::new();
+            reporter.inner.write_package_summaries(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(write_summaries(SummaryFormat::Compact), "");
+        assert_eq!(
+            write_summaries(SummaryFormat::Verbose),
+            "     Package [   1.500s] my-package: 12 passed, 1 failed, 2 skipped\n     \
+             Package [   0.000s] other: 3 passed, 0 failed, 0 skipped\n"
+        );
+        assert_eq!(
+            write_summaries(SummaryFormat::Table),
+            "             Package     Passed  Failed  Skipped    Time\n             \
+             ----------  ------  ------  -------  ------\n             \
+             my-package      12       1        2  1.500s\n             \
+             other            3       0        0  0.000s\n"
+        );
+    }
+
+    #[test]
+    fn display_only_failing_packages() {
+        let helper_binary = RustTestArtifact {
+            binary_path: "/fake/helper".into(),
+            cwd: "/fake/cwd".into(),
+            package: package_metadata(HELPER_PACKAGE_ID),
+            binary_name: "helper".to_owned(),
+            binary_id: RustBinaryId::new("metadata-helper"),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let base_binary = RustTestArtifact {
+            binary_path: "/fake/base".into(),
+            cwd: "/fake/cwd".into(),
+            package: package_metadata(BASE_PACKAGE_ID),
+            binary_name: "base".to_owned(),
+            binary_id: RustBinaryId::new("metadata-base"),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let test_filter = TestFilterBuilder::any(RunIgnored::Default);
+        let rust_build_meta = RustBuildMeta::new("/fake", None).map_paths(&PathMapper::noop());
+        let test_list = TestList::new_with_outputs(
+            [
+                (helper_binary, "helper_pass: test\nhelper_fail: test\n", ""),
+                (base_binary, "base_fail: test\nbase_cancelled: test\n", ""),
+            ],
+            rust_build_meta,
+            &test_filter,
+            EnvironmentMap::empty(),
+        )
+        .expect("valid output");
+        let test_instance = |name: &str| {
+            test_list
+                .iter_tests()
+                .find(|test_instance| test_instance.name == name)
+                .unwrap_or_else(|| panic!("{name} is in the test list"))
+        };
+
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let mut builder = TestReporterBuilder::default();
+        builder
+            .set_display_only_failing_packages(true)
+            .set_status_level(StatusLevel::Pass);
+        let mut reporter_buf: Vec<u8> = Vec::new();
+        let mut reporter = builder.build(
+            &test_list,
+            &profile.apply_build_platforms(&build_platforms),
+            ReporterStderr::Buffer(&mut reporter_buf),
+        );
+
+        let finished = |name: &str, result: ExecutionResult| {
+            let status = ExecuteStatus {
+                retry_data: RetryData {
+                    attempt: 1,
+                    total_attempts: 1,
+                },
+                stdout: Bytes::new(),
+                stderr: Bytes::new(),
+                result,
+                start_time: SystemTime::UNIX_EPOCH,
+                time_taken: Duration::from_millis(100),
+                time_taken_known: true,
+                is_slow: false,
+                delay_before_start: Duration::ZERO,
+            };
+            TestEvent::TestFinished {
+                test_instance: test_instance(name),
+                success_output: TestOutputDisplay::Never,
+                failure_output: TestOutputDisplay::Never,
+                junit_store_success_output: false,
+                junit_store_failure_output: false,
+                run_statuses: ExecutionStatuses::new(vec![status], false),
+                current_stats: RunStats::default(),
+                running: 0,
+                cancel_state: None,
+            }
+        };
+        let fail = || ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+
+        // Events from the two packages are interleaved.
+        let mut buf = Vec::new();
+        for event in [
+            finished("base_fail", fail()),
+            finished("helper_pass", ExecutionResult::Pass),
+            finished("helper_fail", fail()),
+        ] {
+            reporter.inner.write_event_impl(&event, &mut buf).unwrap();
+        }
+        let output = String::from_utf8(buf.clone()).unwrap();
+        assert_eq!(
+            output.lines().count(),
+            3,
+            "only metadata-helper's output is written once all of its tests have finished: \
+             {output}"
+        );
+        assert!(
+            output
+                .lines()
+                .next()
+                .unwrap()
+                .contains("metadata-helper: 1/2 tests failed"),
+            "output starts with the package header: {output}"
+        );
+        assert!(
+            output.find("helper_pass").unwrap() < output.find("helper_fail").unwrap(),
+            "metadata-helper's tests are written in order: {output}"
+        );
+        assert!(
+            !output.contains("base_fail"),
+            "metadata-base still has tests remaining: {output}"
+        );
+
+        // The run is cancelled before base_cancelled finishes, so metadata-base's output is written
+        // at the end of the run.
+        buf.clear();
+        reporter
+            .inner
+            .write_event_impl(
+                &TestEvent::RunFinished {
+                    run_id: Uuid::new_v4(),
+                    start_time: SystemTime::UNIX_EPOCH,
+                    elapsed: Duration::from_secs(1),
+                    run_stats: RunStats::default(),
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let output = String::from_utf8(buf).unwrap();
+        let header_pos = output
+            .find("metadata-base: 1/1 tests failed")
+            .unwrap_or_else(|| panic!("metadata-base's output is written: {output}"));
+        let test_pos = output.find("base_fail").unwrap();
+        let summary_pos = output.find("Summary").unwrap();
+        assert!(
+            header_pos < test_pos && test_pos < summary_pos,
+            "metadata-base's output is written before the summary: {output}"
+        );
+        assert!(
+            !output[..summary_pos].contains("helper"),
+            "metadata-helper's output isn't written again: {output}"
+        );
+    }
 }
@@ -6,19 +6,30 @@
 #[cfg(any(unix, windows))]
 use crate::runner::AbortStatus;
 use crate::{
-    config::{NextestJunitConfig, NextestProfile},
+    config::{
+        JunitClassNameStyle, JunitFailureFormat, JunitPathRewrite, JunitSuiteNameStyle,
+        JunitTimestampFormat, NextestJunitConfig, NextestProfile,
+    },
     errors::WriteEventError,
     list::TestInstance,
-    reporter::TestEvent,
+    reporter::{RunHistory, TestEvent},
     runner::{ExecuteStatus, ExecutionDescription, ExecutionResult},
+    timestamped_reader::strip_timestamps,
 };
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use chrono::{DateTime, FixedOffset, Utc};
 use debug_ignore::DebugIgnore;
 use once_cell::sync::Lazy;
-use quick_junit::{NonSuccessKind, Output, Report, TestCase, TestCaseStatus, TestRerun, TestSuite};
+use quick_junit::{
+    NonSuccessKind, Output, Report, TestCase, TestCaseStatus, TestRerun, TestSuite, TimestampFormat,
+};
 use regex::{Regex, RegexBuilder};
-use std::{borrow::Cow, collections::HashMap, fs::File, time::SystemTime};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    time::{Duration, SystemTime},
+};
 
 #[derive(Clone, Debug)]
 #[allow(dead_code)]
@@ -27,17 +38,32 @@ pub(crate) struct EventAggregator<'cfg> {
     // TODO: log information in a JSONable report (converting that to XML later) instead of directly
     // writing it to XML
     junit: Option<MetadataJunit<'cfg>>,
+    captured_output: Option<CapturedOutputWriter>,
 }
 
 impl<'cfg> EventAggregator<'cfg> {
-    pub(crate) fn new(profile: &NextestProfile<'cfg>) -> Self {
+    pub(crate) fn new(
+        profile: &NextestProfile<'cfg>,
+        junit_config: Option<NextestJunitConfig<'cfg>>,
+        junit_properties: Vec<(String, String)>,
+        junit_rerun_threshold: Option<(usize, RunHistory)>,
+        bench: bool,
+        capture_all: Option<(Utf8PathBuf, u64)>,
+    ) -> Self {
         Self {
             store_dir: profile.store_dir().to_owned(),
-            junit: profile.junit().map(MetadataJunit::new),
+            junit: junit_config.map(|config| {
+                MetadataJunit::new(config, junit_properties, junit_rerun_threshold, bench)
+            }),
+            captured_output: capture_all
+                .map(|(output_dir, max_bytes)| CapturedOutputWriter::new(output_dir, max_bytes)),
         }
     }
 
     pub(crate) fn write_event(&mut self, event: TestEvent<'cfg>) -> Result<(), WriteEventError> {
+        if let Some(captured_output) = &mut self.captured_output {
+            captured_output.write_event(&event)?;
+        }
         if let Some(junit) = &mut self.junit {
             junit.write_event(event)?;
         }
@@ -45,17 +71,151 @@ impl<'cfg> EventAggregator<'cfg> {
     }
 }
 
+/// Writes the output of every test attempt to a directory, regardless of outcome.
+#[derive(Clone, Debug)]
+struct CapturedOutputWriter {
+    output_dir: Utf8PathBuf,
+    max_bytes: u64,
+    bytes_written: u64,
+    truncated_count: usize,
+    // The binary ID and test name each test directory (relative to output_dir) was assigned to.
+    test_dirs: HashMap<Utf8PathBuf, (String, String)>,
+}
+
+impl CapturedOutputWriter {
+    fn new(output_dir: Utf8PathBuf, max_bytes: u64) -> Self {
+        Self {
+            output_dir,
+            max_bytes,
+            bytes_written: 0,
+            truncated_count: 0,
+            test_dirs: HashMap::new(),
+        }
+    }
+
+    fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        match event {
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let test_dir = self.test_dir(
+                    test_instance.suite_info.binary_id.as_str(),
+                    test_instance.name,
+                );
+                let test_dir = self.output_dir.join(test_dir);
+                std::fs::create_dir_all(&test_dir).map_err(|error| WriteEventError::Fs {
+                    file: test_dir.clone(),
+                    error,
+                })?;
+
+                let mut truncated = false;
+                for run_status in run_statuses.iter() {
+                    let attempt = run_status.retry_data.attempt;
+                    for (extension, output) in [
+                        ("stdout", &run_status.stdout),
+                        ("stderr", &run_status.stderr),
+                    ] {
+                        let path = test_dir.join(format!("attempt-{attempt}.{extension}"));
+                        truncated |= self.write_file(&path, output)?;
+                    }
+                }
+                if truncated {
+                    self.truncated_count += 1;
+                }
+            }
+            TestEvent::RunFinished { .. } if self.truncated_count > 0 => {
+                log::warn!(
+                    "output for {} tests written to {} was truncated: \
+                     the limit of {} bytes was reached",
+                    self.truncated_count,
+                    self.output_dir,
+                    self.max_bytes,
+                );
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the directory for a test's output, relative to the output directory.
+    ///
+    /// Distinct names can be the same once sanitized (e.g. `a::b` and `a__b`), so if the
+    /// directory is already taken by another test, a `-2`, `-3`, ... suffix is appended.
+    fn test_dir(&mut self, binary_id: &str, test_name: &str) -> Utf8PathBuf {
+        let binary_dir = sanitize_path_component(binary_id);
+        let test_dir = sanitize_path_component(test_name);
+        let mut suffix = 1;
+        loop {
+            let dir = if suffix == 1 {
+                Utf8PathBuf::from(&binary_dir).join(&test_dir)
+            } else {
+                Utf8PathBuf::from(&binary_dir).join(format!("{test_dir}-{suffix}"))
+            };
+            match self.test_dirs.get(&dir) {
+                Some((id, name)) if id == binary_id && name == test_name => return dir,
+                Some(_) => suffix += 1,
+                None => {
+                    self.test_dirs
+                        .insert(dir.clone(), (binary_id.to_owned(), test_name.to_owned()));
+                    return dir;
+                }
+            }
+        }
+    }
+
+    /// Writes as much of `output` to `path` as the remaining budget allows, returning true if it
+    /// was truncated.
+    fn write_file(&mut self, path: &Utf8Path, output: &[u8]) -> Result<bool, WriteEventError> {
+        let remaining = self.max_bytes.saturating_sub(self.bytes_written);
+        let len = (output.len() as u64).min(remaining) as usize;
+        std::fs::write(path, &output[..len]).map_err(|error| WriteEventError::Fs {
+            file: path.to_owned(),
+            error,
+        })?;
+        self.bytes_written += len as u64;
+        Ok(len < output.len())
+    }
+}
+
+/// Replaces characters that aren't valid in file names on some platforms.
+fn sanitize_path_component(name: &str) -> String {
+    name.replace("::", "__")
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 struct MetadataJunit<'cfg> {
     config: NextestJunitConfig<'cfg>,
-    test_suites: DebugIgnore<HashMap<&'cfg str, TestSuite>>,
+    // Test suites by name, along with the name of the package they belong to.
+    test_suites: DebugIgnore<HashMap<&'cfg str, (&'cfg str, TestSuite)>>,
+    properties: Vec<(String, String)>,
+    // The rerun threshold and the history of recent runs, if historically-failing tests are
+    // excluded.
+    rerun_threshold: Option<(usize, RunHistory)>,
+    // Whether this is a `cargo nextest bench` run.
+    bench: bool,
 }
 
 impl<'cfg> MetadataJunit<'cfg> {
-    fn new(config: NextestJunitConfig<'cfg>) -> Self {
+    fn new(
+        config: NextestJunitConfig<'cfg>,
+        properties: Vec<(String, String)>,
+        rerun_threshold: Option<(usize, RunHistory)>,
+        bench: bool,
+    ) -> Self {
         Self {
             config,
             test_suites: DebugIgnore(HashMap::new()),
+            properties,
+            rerun_threshold,
+            bench,
         }
     }
 
@@ -76,7 +236,18 @@ impl<'cfg> MetadataJunit<'cfg> {
                 junit_store_failure_output,
                 ..
             } => {
-                fn kind_ty(run_status: &ExecuteStatus) -> (NonSuccessKind, Cow<'static, str>) {
+                fn kind_ty(
+                    run_status: &ExecuteStatus,
+                    use_error_for_panics: bool,
+                ) -> (NonSuccessKind, Cow<'static, str>) {
+                    if use_error_for_panics
+                        && matches!(run_status.result, ExecutionResult::Fail { .. })
+                        && heuristic_is_unexpected_panic(&String::from_utf8_lossy(
+                            &run_status.stderr,
+                        ))
+                    {
+                        return (NonSuccessKind::Error, "panic".into());
+                    }
                     match run_status.result {
                         ExecutionResult::Fail {
                             abort_status: Some(_),
@@ -103,6 +274,21 @@ impl<'cfg> MetadataJunit<'cfg> {
                         ExecutionResult::Timeout => {
                             (NonSuccessKind::Failure, "test timeout".into())
                         }
+                        ExecutionResult::StderrWritten { leaked: true } => (
+                            NonSuccessKind::Failure,
+                            "test passed but wrote to stderr and leaked handles".into(),
+                        ),
+                        ExecutionResult::StderrWritten { leaked: false } => (
+                            NonSuccessKind::Failure,
+                            "test passed but wrote to stderr".into(),
+                        ),
+                        ExecutionResult::SimulatedFailure => {
+                            (NonSuccessKind::Failure, "simulated failure".into())
+                        }
+                        ExecutionResult::ProcessLeak => (
+                            NonSuccessKind::Failure,
+                            "test passed but left processes running".into(),
+                        ),
                         ExecutionResult::ExecFail => {
                             (NonSuccessKind::Error, "execution failure".into())
                         }
@@ -116,8 +302,47 @@ impl<'cfg> MetadataJunit<'cfg> {
                     }
                 }
 
+                let classname = classname(
+                    self.config.classname_style(),
+                    test_instance.suite_info.binary_id.as_str(),
+                    test_instance.suite_info.package.name(),
+                    &test_instance.suite_info.binary_name,
+                    test_instance.name,
+                )
+                .map(|classname| {
+                    JunitPathRewrite::apply_all(self.config.path_rewrites(), classname)
+                });
+                let failure_format = self.config.failure_format();
+                let strip_ansi = self.config.strip_ansi();
+                let include_passing = self.config.include_passing();
+                let truncate_name_at = self.config.truncate_name_at();
+                let use_error_for_panics = self.config.use_error_for_panics();
+                let bench = self.bench;
+
+                if let Some(message) = self.historical_failure_message(test_instance) {
+                    self.add_skipped_test_case(
+                        test_instance,
+                        run_statuses.last_status(),
+                        message,
+                        "historically-failing",
+                    );
+                    return Ok(());
+                }
+
                 let testsuite = self.testsuite_for(test_instance);
 
+                if !include_passing
+                    && matches!(
+                        run_statuses.describe(),
+                        ExecutionDescription::Success { .. }
+                    )
+                {
+                    // Omit the test case, but still count the test so that the totals for the
+                    // suite and the report include every test.
+                    testsuite.tests += 1;
+                    return Ok(());
+                }
+
                 let (mut testcase_status, main_status, reruns) = match run_statuses.describe() {
                     ExecutionDescription::Success { single_status } => {
                         (TestCaseStatus::success(), single_status, &[][..])
@@ -131,38 +356,57 @@ impl<'cfg> MetadataJunit<'cfg> {
                         retries,
                         ..
                     } => {
-                        let (kind, ty) = kind_ty(first_status);
+                        let (kind, ty) = kind_ty(first_status, use_error_for_panics);
                         let mut testcase_status = TestCaseStatus::non_success(kind);
                         testcase_status.set_type(ty);
                         (testcase_status, first_status, retries)
                     }
                 };
 
-                for rerun in reruns {
-                    let (kind, ty) = kind_ty(rerun);
-                    let stdout = String::from_utf8_lossy(&rerun.stdout);
-                    let stderr = String::from_utf8_lossy(&rerun.stderr);
+                // With runs per test, attempts after the first failure may have passed. They aren't
+                // reruns in the JUnit sense, so leave them out.
+                for rerun in reruns.iter().filter(|rerun| !rerun.result.is_success()) {
+                    let (kind, ty) = kind_ty(rerun, use_error_for_panics);
+                    let stdout = output_text(&rerun.stdout, strip_ansi);
+                    let stderr = output_text(&rerun.stderr, strip_ansi);
                     let stack_trace = heuristic_extract_description(rerun.result, &stdout, &stderr);
 
                     let mut test_rerun = TestRerun::new(kind);
                     if let Some(description) = stack_trace {
-                        test_rerun.set_description(description);
+                        test_rerun.set_description(failure_text(failure_format, &description));
                     }
                     test_rerun
                         .set_timestamp(to_datetime(rerun.start_time))
                         .set_time(rerun.time_taken)
                         .set_type(ty)
-                        .set_system_out(stdout)
-                        .set_system_err(stderr);
+                        .set_system_out(failure_text(failure_format, &stdout))
+                        .set_system_err(failure_text(failure_format, &stderr));
                     // TODO: also publish time? it won't be standard JUnit (but maybe that's ok?)
                     testcase_status.add_rerun(test_rerun);
                 }
 
-                let mut testcase = TestCase::new(test_instance.name, testcase_status);
-                testcase
-                    .set_classname(test_instance.suite_info.binary_id.as_str())
-                    .set_timestamp(to_datetime(main_status.start_time))
-                    .set_time(main_status.time_taken);
+                // For benchmarks, record the mean iteration time rather than the time taken by
+                // the whole process. The time is left out if it isn't known.
+                let bench_time = if bench {
+                    heuristic_bench_time(&String::from_utf8_lossy(&main_status.stdout))
+                } else {
+                    None
+                };
+                let time = bench_time.or_else(|| {
+                    main_status
+                        .time_taken_known
+                        .then_some(main_status.time_taken)
+                });
+
+                let mut testcase =
+                    new_test_case(test_instance.name, testcase_status, truncate_name_at);
+                testcase.set_timestamp(to_datetime(main_status.start_time));
+                if let Some(time) = time {
+                    testcase.set_time(time);
+                }
+                if let Some(classname) = classname {
+                    testcase.set_classname(classname);
+                }
 
                 // TODO: allure seems to want the output to be in a format where text files are
                 // written out to disk:
@@ -170,25 +414,56 @@ impl<'cfg> MetadataJunit<'cfg> {
                 // we may have to update this format to handle that.
                 let is_success = main_status.result.is_success();
                 if !is_success {
-                    let stdout = String::from_utf8_lossy(&main_status.stdout);
-                    let stderr = String::from_utf8_lossy(&main_status.stderr);
+                    let stdout = output_text(&main_status.stdout, strip_ansi);
+                    let stderr = output_text(&main_status.stderr, strip_ansi);
                     let description =
                         heuristic_extract_description(main_status.result, &stdout, &stderr);
                     if let Some(description) = description {
-                        testcase.status.set_description(description);
+                        testcase
+                            .status
+                            .set_description(failure_text(failure_format, &description));
                     }
                 }
 
-                if (junit_store_success_output && is_success)
-                    || (junit_store_failure_output && !is_success)
-                {
+                if junit_store_success_output && is_success {
                     testcase
-                        .set_system_out_lossy(&main_status.stdout)
-                        .set_system_err_lossy(&main_status.stderr);
+                        .set_system_out(output_text(&main_status.stdout, strip_ansi))
+                        .set_system_err(output_text(&main_status.stderr, strip_ansi));
+                } else if junit_store_failure_output && !is_success {
+                    let stdout = output_text(&main_status.stdout, strip_ansi);
+                    let stderr = output_text(&main_status.stderr, strip_ansi);
+                    testcase
+                        .set_system_out(failure_text(failure_format, &stdout))
+                        .set_system_err(failure_text(failure_format, &stderr));
                 }
 
                 testsuite.add_test_case(testcase);
             }
+            TestEvent::TestCancelled {
+                test_instance,
+                reason,
+                run_statuses,
+                ..
+            } => {
+                // Cancelled tests are recorded as skipped rather than failed, since they didn't
+                // get a chance to finish.
+                self.add_skipped_test_case(
+                    test_instance,
+                    run_statuses.last_status(),
+                    format!("test cancelled due to {reason}"),
+                    "test cancelled",
+                );
+            }
+            TestEvent::LeakyTestDetected { .. }
+            | TestEvent::SuspiciouslyFastTest { .. }
+            | TestEvent::TimingRegression { .. }
+            | TestEvent::TestExceededAbsoluteMaxTime { .. }
+            | TestEvent::NetworkAccessViolation { .. }
+            | TestEvent::ProcessLeak { .. }
+            | TestEvent::TestOutputLine { .. }
+            | TestEvent::BinaryFailureLimitReached { .. }
+            | TestEvent::BinaryTimedOut { .. }
+            | TestEvent::GlobalRetryCapReached { .. } => {}
             TestEvent::TestSkipped { .. } => {
                 // TODO: report skipped tests? causes issues if we want to aggregate runs across
                 // skipped and non-skipped tests. Probably needs to be made configurable.
@@ -209,12 +484,29 @@ impl<'cfg> MetadataJunit<'cfg> {
                 ..
             } => {
                 // Write out the report to the given file.
-                let mut report = Report::new(self.config.report_name());
+                let mut report =
+                    Report::new(self.config.expanded_report_name(to_datetime(start_time)));
                 report
                     .set_uuid(run_id)
                     .set_timestamp(to_datetime(start_time))
+                    .set_timestamp_format(timestamp_format(self.config.timestamp_format()))
+                    .set_time_precision(self.config.elapsed_time_precision())
                     .set_time(elapsed)
-                    .add_test_suites(self.test_suites.drain().map(|(_, testsuite)| testsuite));
+                    .add_properties(self.properties.drain(..).map(|(name, value)| {
+                        let value =
+                            JunitPathRewrite::apply_all(self.config.path_rewrites(), &value)
+                                .into_owned();
+                        (name, value)
+                    }));
+                let test_suites = self.test_suites.drain().map(|(_, suite)| suite);
+                if self.config.nest_packages() {
+                    report.add_test_suites(nest_by_package(test_suites));
+                } else {
+                    report.add_test_suites(test_suites.map(|(_, testsuite)| testsuite));
+                }
+                if let Some(hostname) = self.config.hostname().resolve() {
+                    set_hostname(&mut report.test_suites, &hostname);
+                }
 
                 let junit_path = self.config.path();
                 let junit_dir = junit_path.parent().expect("junit path must have a parent");
@@ -240,9 +532,255 @@ impl<'cfg> MetadataJunit<'cfg> {
     }
 
     fn testsuite_for(&mut self, test_instance: TestInstance<'cfg>) -> &mut TestSuite {
-        self.test_suites
-            .entry(test_instance.suite_info.binary_id.as_str())
-            .or_insert_with(|| TestSuite::new(test_instance.suite_info.binary_id.as_str()))
+        let suite_info = test_instance.suite_info;
+        let name = suite_name(
+            self.config.suitename_style(),
+            suite_info.binary_id.as_str(),
+            suite_info.package.name(),
+            &suite_info.binary_name,
+        );
+        let (_, testsuite) = self
+            .test_suites
+            .entry(name)
+            .or_insert_with(|| (suite_info.package.name(), TestSuite::new(name)));
+        testsuite
+    }
+
+    /// Adds a test case for a test that ran but is reported as skipped, with the given message and
+    /// type.
+    fn add_skipped_test_case(
+        &mut self,
+        test_instance: TestInstance<'cfg>,
+        last_status: &ExecuteStatus,
+        message: String,
+        ty: &str,
+    ) {
+        let classname = classname(
+            self.config.classname_style(),
+            test_instance.suite_info.binary_id.as_str(),
+            test_instance.suite_info.package.name(),
+            &test_instance.suite_info.binary_name,
+            test_instance.name,
+        )
+        .map(|classname| JunitPathRewrite::apply_all(self.config.path_rewrites(), classname));
+        let mut testcase_status = TestCaseStatus::skipped();
+        testcase_status.set_message(message).set_type(ty);
+        let mut testcase = new_test_case(
+            test_instance.name,
+            testcase_status,
+            self.config.truncate_name_at(),
+        );
+        testcase.set_timestamp(to_datetime(last_status.start_time));
+        if last_status.time_taken_known {
+            testcase.set_time(last_status.time_taken);
+        }
+        if let Some(classname) = classname {
+            testcase.set_classname(classname);
+        }
+        self.testsuite_for(test_instance).add_test_case(testcase);
+    }
+
+    /// Returns the skip message for a test if it's excluded for having failed in more than the
+    /// rerun threshold of recent runs.
+    fn historical_failure_message(&self, test_instance: TestInstance<'_>) -> Option<String> {
+        let (threshold, history) = self.rerun_threshold.as_ref()?;
+        let failure_count = history.failure_count(
+            test_instance.suite_info.binary_id.as_str(),
+            test_instance.name,
+        );
+        (failure_count > *threshold).then(|| {
+            format!(
+                "test failed in {failure_count} of the last {} runs",
+                history.runs.len()
+            )
+        })
+    }
+}
+
+/// Sets the `hostname` attribute of `test_suites` and any test suites nested within them.
+fn set_hostname(test_suites: &mut [TestSuite], hostname: &str) {
+    for testsuite in test_suites {
+        testsuite
+            .extra
+            .insert("hostname".to_owned(), hostname.to_owned());
+        set_hostname(&mut testsuite.test_suites, hostname);
+    }
+}
+
+/// Wraps test suites in a parent test suite for each package, ordered by package name.
+fn nest_by_package<'a>(
+    test_suites: impl IntoIterator<Item = (&'a str, TestSuite)>,
+) -> Vec<TestSuite> {
+    let mut packages: BTreeMap<&str, TestSuite> = BTreeMap::new();
+    for (package_name, testsuite) in test_suites {
+        packages
+            .entry(package_name)
+            .or_insert_with(|| TestSuite::new(package_name))
+            .add_test_suite(testsuite);
+    }
+    packages.into_values().collect()
+}
+
+fn suite_name<'a>(
+    style: JunitSuiteNameStyle,
+    binary_id: &'a str,
+    package_name: &'a str,
+    binary_name: &'a str,
+) -> &'a str {
+    match style {
+        JunitSuiteNameStyle::Full => binary_id,
+        JunitSuiteNameStyle::Package => package_name,
+        JunitSuiteNameStyle::Binary => binary_name,
+    }
+}
+
+fn classname<'a>(
+    style: JunitClassNameStyle,
+    binary_id: &'a str,
+    package_name: &'a str,
+    binary_name: &'a str,
+    test_name: &'a str,
+) -> Option<&'a str> {
+    match style {
+        JunitClassNameStyle::Full => Some(binary_id),
+        JunitClassNameStyle::Package => Some(package_name),
+        JunitClassNameStyle::Binary => Some(binary_name),
+        JunitClassNameStyle::ModulePath => test_name
+            .rsplit_once("::")
+            .map(|(module_path, _)| module_path),
+        JunitClassNameStyle::None => None,
+    }
+}
+
+/// Converts captured output to text for the JUnit report, removing ANSI escape sequences if
+/// `strip_ansi` is true.
+fn output_text(output: &[u8], strip_ansi: bool) -> Cow<'_, str> {
+    let text = String::from_utf8_lossy(output);
+    if strip_ansi && text.contains('\x1b') {
+        Cow::Owned(strip_ansi_escapes(&text))
+    } else {
+        text
+    }
+}
+
+/// Creates a test case for the test `name`, truncating the name to `truncate_name_at` characters
+/// if it's longer than that.
+///
+/// If the name is truncated, the full name is added as a `full-name` property.
+fn new_test_case(name: &str, status: TestCaseStatus, truncate_name_at: Option<usize>) -> TestCase {
+    match truncate_name_at.and_then(|max_len| truncate_test_name(name, max_len)) {
+        Some(truncated) => {
+            let mut testcase = TestCase::new(truncated, status);
+            testcase.add_property(("full-name", name));
+            testcase
+        }
+        None => TestCase::new(name, status),
+    }
+}
+
+/// Truncates `name` to `max_len` characters, or returns `None` if it's short enough already.
+///
+/// The end of the name is the most meaningful part, so the start is removed and replaced with
+/// `...`. Where possible, the name is cut at a `::` boundary.
+fn truncate_test_name(name: &str, max_len: usize) -> Option<String> {
+    const ELLIPSIS: &str = "...";
+
+    let len = name.chars().count();
+    if len <= max_len {
+        return None;
+    }
+    let keep = max_len.saturating_sub(ELLIPSIS.len());
+    // Suffixes are checked from longest to shortest.
+    let suffix = name
+        .match_indices("::")
+        .map(|(index, separator)| &name[index + separator.len()..])
+        .find(|suffix| suffix.chars().count() <= keep)
+        .unwrap_or_else(|| {
+            let start = name
+                .char_indices()
+                .nth(len - keep)
+                .map_or(name.len(), |(index, _)| index);
+            &name[start..]
+        });
+    Some(format!("{ELLIPSIS}{suffix}"))
+}
+
+/// Removes ANSI control sequences such as `\x1b[1;31m` from `text`.
+///
+/// A control sequence is `ESC [`, followed by any number of parameter and intermediate bytes, and
+/// ends with a final byte in the range `@` to `~`. Other escape characters are left alone.
+fn strip_ansi_escapes(text: &str) -> String {
+    enum State {
+        Text,
+        Escape,
+        ControlSequence,
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut state = State::Text;
+    for c in text.chars() {
+        state = match (state, c) {
+            (State::Text, '\x1b') => State::Escape,
+            (State::Text, c) => {
+                output.push(c);
+                State::Text
+            }
+            (State::Escape, '[') => State::ControlSequence,
+            (State::Escape, c) => {
+                output.push('\x1b');
+                if c == '\x1b' {
+                    State::Escape
+                } else {
+                    output.push(c);
+                    State::Text
+                }
+            }
+            (State::ControlSequence, '@'..='~') => State::Text,
+            (State::ControlSequence, _) => State::ControlSequence,
+        };
+    }
+    if matches!(state, State::Escape) {
+        output.push('\x1b');
+    }
+    output
+}
+
+/// Returns `text` as it should be written to the report for a failing test.
+///
+/// With the brief format, only the first line is kept, followed by the number of bytes left out. If
+/// the first line is a panic location, the panic message on the line after it is kept as well.
+fn failure_text(format: JunitFailureFormat, text: &str) -> Cow<'_, str> {
+    match format {
+        JunitFailureFormat::Detailed => Cow::Borrowed(text),
+        JunitFailureFormat::Brief => {
+            let mut lines = text.split_inclusive('\n');
+            let first_line = lines.next().unwrap_or_default();
+            // Newer versions of Rust print "panicked at <location>:" followed by the message on the
+            // next line.
+            let kept_len =
+                if PANICKED_AT_REGEX.is_match(first_line) && first_line.trim_end().ends_with(':') {
+                    first_line.len() + lines.next().map_or(0, str::len)
+                } else {
+                    first_line.len()
+                };
+            let kept = text[..kept_len].trim_end();
+            if text.trim_end() == kept {
+                Cow::Borrowed(text)
+            } else {
+                Cow::Owned(format!(
+                    "{kept}\n... ({} bytes truncated)",
+                    text.len() - kept.len()
+                ))
+            }
+        }
+    }
+}
+
+fn timestamp_format(format: JunitTimestampFormat) -> TimestampFormat {
+    match format {
+        JunitTimestampFormat::Rfc3339 => TimestampFormat::Rfc3339,
+        JunitTimestampFormat::Simple => TimestampFormat::Simple,
+        JunitTimestampFormat::Epoch => TimestampFormat::Epoch,
     }
 }
 
@@ -253,14 +791,28 @@ fn to_datetime(system_time: SystemTime) -> DateTime<FixedOffset> {
 }
 
 // This regex works for the default panic handler for Rust -- other panic handlers may not work,
-// which is why this is heuristic.
-static PANICKED_AT_REGEX_STR: &str = "^thread '([^']+)' panicked at '";
+// which is why this is heuristic. Newer versions of Rust print the thread ID after the thread name,
+// and the location rather than a quoted message after "panicked at".
+static PANICKED_AT_REGEX_STR: &str = r"^thread '([^']+)'(?: \(\d+\))? panicked at ";
 static PANICKED_AT_REGEX: Lazy<Regex> = Lazy::new(|| {
     let mut builder = RegexBuilder::new(PANICKED_AT_REGEX_STR);
     builder.multi_line(true);
     builder.build().unwrap()
 });
 
+// Matches both the old format, where the message is quoted on the same line, and the format used
+// since Rust 1.73, where the message starts on the line after the location. Newer versions also
+// print the thread ID after the thread name.
+static PANIC_MESSAGE_REGEX_STR: &str = r"^thread '[^']+'(?: \(\d+\))? panicked at (?:'|.*:\n)(.*)";
+static PANIC_MESSAGE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let mut builder = RegexBuilder::new(PANIC_MESSAGE_REGEX_STR);
+    builder.multi_line(true);
+    builder.build().unwrap()
+});
+
+static BENCH_REGEX_STR: &str = r"bench:\s+([0-9,]+(?:\.[0-9]+)?) ns/iter";
+static BENCH_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(BENCH_REGEX_STR).unwrap());
+
 static ERROR_REGEX_STR: &str = "^Error: ";
 static ERROR_REGEX: Lazy<Regex> = Lazy::new(|| {
     let mut builder = RegexBuilder::new(ERROR_REGEX_STR);
@@ -276,6 +828,9 @@ pub fn heuristic_extract_description<'a>(
     stdout: &'a str,
     stderr: &'a str,
 ) -> Option<String> {
+    let stdout = strip_timestamps(stdout);
+    let stderr = strip_timestamps(stderr);
+
     // If the test crashed with a signal, use that.
     #[cfg(unix)]
     if let ExecutionResult::Fail {
@@ -317,14 +872,27 @@ pub fn heuristic_extract_description<'a>(
         );
     }
 
+    // The test passed, so anything it wrote to stderr is the reason it failed.
+    if let ExecutionResult::StderrWritten { leaked } = exec_result {
+        return Some(format!(
+            "Test passed but wrote to stderr with fail-on-stderr set{}:\n{}",
+            if leaked {
+                ", and also leaked handles"
+            } else {
+                ""
+            },
+            stderr.trim_end()
+        ));
+    }
+
     // Try the heuristic stack trace extraction first as they're the more common kinds of test.
-    if let Some(description) = heuristic_stack_trace(stderr) {
+    if let Some(description) = heuristic_stack_trace(&stderr) {
         return Some(description);
     }
-    if let Some(description) = heuristic_error_str(stderr) {
+    if let Some(description) = heuristic_error_str(&stderr) {
         return Some(description);
     }
-    heuristic_should_panic(stdout)
+    heuristic_should_panic(&stdout)
 }
 
 fn heuristic_should_panic(stdout: &str) -> Option<String> {
@@ -352,6 +920,23 @@ fn heuristic_stack_trace(stderr: &str) -> Option<String> {
     Some(Output::new(stderr[start..].trim_end()).into_string())
 }
 
+/// Returns true if `stderr` shows that the test panicked, other than through a failed assertion.
+fn heuristic_is_unexpected_panic(stderr: &str) -> bool {
+    match PANIC_MESSAGE_REGEX.captures(&strip_timestamps(stderr)) {
+        // Assertion macros panic with messages like "assertion failed: ..." or
+        // "assertion `left == right` failed". Result-based test failures are reported the same way.
+        Some(captures) => !captures[1].starts_with("assertion"),
+        None => false,
+    }
+}
+
+/// Extracts the mean iteration time from the output of a benchmark run with `--bench`.
+fn heuristic_bench_time(stdout: &str) -> Option<Duration> {
+    let captures = BENCH_REGEX.captures(stdout)?;
+    let nanos: f64 = captures[1].replace(',', "").parse().ok()?;
+    Some(Duration::from_secs_f64(nanos / 1e9))
+}
+
 fn heuristic_error_str(stderr: &str) -> Option<String> {
     // Starting Rust 1.66, Result-based errors simply print out "Error: ".
     let error_match = ERROR_REGEX.find(stderr)?;
@@ -363,6 +948,154 @@ fn heuristic_error_str(stderr: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_nest_by_package() {
+        let suite = |name: &str, tests: usize| {
+            let mut testsuite = TestSuite::new(name);
+            for index in 0..tests {
+                testsuite.add_test_case(TestCase::new(
+                    format!("test_{index}"),
+                    TestCaseStatus::success(),
+                ));
+            }
+            testsuite
+        };
+
+        let nested = nest_by_package([
+            ("pkg-b", suite("pkg-b::foo", 1)),
+            ("pkg-a", suite("pkg-a", 2)),
+            ("pkg-b", suite("pkg-b::bar", 3)),
+        ]);
+        let summary: Vec<_> = nested
+            .iter()
+            .map(|package| {
+                let children: Vec<_> = package
+                    .test_suites
+                    .iter()
+                    .map(|testsuite| testsuite.name.as_str())
+                    .collect();
+                (package.name.as_str(), package.tests, children)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("pkg-a", 2, vec!["pkg-a"]),
+                ("pkg-b", 4, vec!["pkg-b::foo", "pkg-b::bar"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate_test_name() {
+        let name = "tests::parser::expressions::test_nested_parens";
+        assert_eq!(truncate_test_name(name, name.len()), None);
+        assert_eq!(
+            truncate_test_name(name, 40).as_deref(),
+            Some("...expressions::test_nested_parens")
+        );
+        assert_eq!(
+            truncate_test_name(name, 24).as_deref(),
+            Some("...test_nested_parens")
+        );
+        // If even the last segment doesn't fit, it's cut in the middle.
+        assert_eq!(truncate_test_name(name, 10).as_deref(), Some("..._parens"));
+        // Lengths are counted in characters, not bytes.
+        assert_eq!(
+            truncate_test_name("tëst::ünïcödé", 10).as_deref(),
+            Some("...ünïcödé")
+        );
+
+        let testcase = new_test_case(name, TestCaseStatus::success(), Some(24));
+        assert_eq!(testcase.name, "...test_nested_parens");
+        assert_eq!(testcase.properties.len(), 1);
+        assert_eq!(testcase.properties[0].name, "full-name");
+        assert_eq!(testcase.properties[0].value, name);
+
+        let testcase = new_test_case(name, TestCaseStatus::success(), None);
+        assert_eq!(testcase.name, name);
+        assert!(testcase.properties.is_empty());
+    }
+
+    #[test]
+    fn test_set_hostname() {
+        let mut test_suites = nest_by_package([
+            ("pkg-a", TestSuite::new("pkg-a::foo")),
+            ("pkg-a", TestSuite::new("pkg-a::bar")),
+        ]);
+        set_hostname(&mut test_suites, "ci-runner");
+
+        let hostname = |testsuite: &TestSuite| testsuite.extra.get("hostname").cloned();
+        assert_eq!(hostname(&test_suites[0]), Some("ci-runner".to_owned()));
+        for child in &test_suites[0].test_suites {
+            assert_eq!(
+                hostname(child),
+                Some("ci-runner".to_owned()),
+                "nested test suites have a hostname"
+            );
+        }
+    }
+
+    #[test]
+    fn test_sanitize_path_component() {
+        assert_eq!(
+            sanitize_path_component("tests::foo::bar"),
+            "tests__foo__bar"
+        );
+        assert_eq!(
+            sanitize_path_component("my-crate::bin/my-bin"),
+            "my-crate__bin_my-bin"
+        );
+        assert_eq!(sanitize_path_component("a<b>:c"), "a_b__c");
+    }
+
+    #[test]
+    fn test_captured_output_test_dir() {
+        let mut writer = CapturedOutputWriter::new("/out".into(), 1024);
+        assert_eq!(writer.test_dir("my-crate", "a::b"), "my-crate/a__b");
+        assert_eq!(
+            writer.test_dir("my-crate", "a__b"),
+            "my-crate/a__b-2",
+            "names that collide once sanitized get a suffix"
+        );
+        assert_eq!(writer.test_dir("my-crate", "a:b"), "my-crate/a_b");
+        assert_eq!(writer.test_dir("my-crate", "a_b"), "my-crate/a_b-2");
+        assert_eq!(
+            writer.test_dir("my-crate", "a::b"),
+            "my-crate/a__b",
+            "the same test always maps to the same directory"
+        );
+        assert_eq!(writer.test_dir("my-crate", "a__b"), "my-crate/a__b-2");
+        assert_eq!(writer.test_dir("other-crate", "a__b"), "other-crate/a__b");
+    }
+
+    #[test]
+    fn test_captured_output_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = Utf8Path::from_path(dir.path()).unwrap();
+        let mut writer = CapturedOutputWriter::new(dir.to_owned(), 10);
+
+        let first = dir.join("first");
+        assert!(!writer.write_file(&first, b"123456").unwrap());
+        assert_eq!(std::fs::read(&first).unwrap(), b"123456");
+
+        let second = dir.join("second");
+        assert!(
+            writer.write_file(&second, b"abcdefgh").unwrap(),
+            "output past the limit is truncated"
+        );
+        assert_eq!(std::fs::read(&second).unwrap(), b"abcd");
+
+        let third = dir.join("third");
+        assert!(writer.write_file(&third, b"xyz").unwrap());
+        assert_eq!(std::fs::read(&third).unwrap(), b"");
+        assert!(
+            !writer.write_file(&dir.join("empty"), b"").unwrap(),
+            "empty output is never truncated"
+        );
+        assert_eq!(writer.bytes_written, 10);
+    }
+
     #[test]
     fn test_heuristic_extract_description() {
         let tests: &[(&str, &str)] = &[(
@@ -384,6 +1117,33 @@ test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 13 filtered out;
         for (input, output) in tests {
             assert_eq!(heuristic_should_panic(input).as_deref(), Some(*output));
         }
+
+        assert_eq!(
+            heuristic_extract_description(
+                ExecutionResult::StderrWritten { leaked: false },
+                "",
+                "thread 'main' panicked at 'foo', src/lib.rs:1\nwarning: deprecated\n",
+            )
+            .as_deref(),
+            Some(
+                "Test passed but wrote to stderr with fail-on-stderr set:\n\
+                 thread 'main' panicked at 'foo', src/lib.rs:1\nwarning: deprecated"
+            ),
+            "stderr is included in full, even if it looks like a panic"
+        );
+        assert_eq!(
+            heuristic_extract_description(
+                ExecutionResult::StderrWritten { leaked: true },
+                "",
+                "warning: deprecated\n",
+            )
+            .as_deref(),
+            Some(
+                "Test passed but wrote to stderr with fail-on-stderr set, \
+                 and also leaked handles:\nwarning: deprecated"
+            ),
+            "leaked handles are reported"
+        );
     }
 
     #[test]
@@ -420,6 +1180,50 @@ note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"#,
         }
     }
 
+    #[test]
+    fn test_heuristic_is_unexpected_panic() {
+        let tests: &[(&str, bool)] = &[
+            (
+                "thread 'main' panicked at 'index out of bounds', src/lib.rs:1\n",
+                true,
+            ),
+            (
+                "thread 'main' panicked at src/lib.rs:1:5:\n\
+                 called `Option::unwrap()` on a `None` value\n",
+                true,
+            ),
+            (
+                "thread 'main' (8262) panicked at src/lib.rs:1:5:\nexplicit panic\n",
+                true,
+            ),
+            (
+                "thread 'main' panicked at 'assertion failed: `(left == right)`\n  \
+                 left: `1`', src/lib.rs:1\n",
+                false,
+            ),
+            (
+                "thread 'main' panicked at src/lib.rs:1:5:\n\
+                 assertion `left == right` failed\n  left: 1\n right: 2\n",
+                false,
+            ),
+            (
+                "thread 'main' panicked at src/lib.rs:1:5:\n\
+                 assertion failed: x.is_empty()\n",
+                false,
+            ),
+            ("Error: Custom { kind: InvalidData }\n", false),
+            ("", false),
+        ];
+
+        for (input, output) in tests {
+            assert_eq!(
+                heuristic_is_unexpected_panic(input),
+                *output,
+                "input: {input}"
+            );
+        }
+    }
+
     #[test]
     fn test_heuristic_error_str() {
         let tests: &[(&str, &str)] = &[(
@@ -431,4 +1235,144 @@ note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"#,
             assert_eq!(heuristic_error_str(input).as_deref(), Some(*output));
         }
     }
+
+    #[test]
+    fn test_heuristic_bench_time() {
+        let tests: &[(&str, Option<Duration>)] = &[
+            (
+                "\nrunning 1 test\ntest bench_foo ... bench:       1,234 ns/iter (+/- 56)\n",
+                Some(Duration::from_nanos(1234)),
+            ),
+            (
+                "test bench_foo ... bench:          12.50 ns/iter (+/- 0.25)\n",
+                Some(Duration::from_secs_f64(12.5 / 1e9)),
+            ),
+            ("test foo ... ok\n", None),
+        ];
+
+        for (input, output) in tests {
+            assert_eq!(heuristic_bench_time(input), *output, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_suite_name() {
+        let tests = [
+            (JunitSuiteNameStyle::Full, "my-package::my_test"),
+            (JunitSuiteNameStyle::Package, "my-package"),
+            (JunitSuiteNameStyle::Binary, "my_test"),
+        ];
+
+        for (style, expected) in tests {
+            assert_eq!(
+                suite_name(style, "my-package::my_test", "my-package", "my_test"),
+                expected,
+                "style {style:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_strip_ansi_escapes() {
+        let tests: &[(&str, &str)] = &[
+            ("plain text", "plain text"),
+            ("\x1b[31mred\x1b[0m", "red"),
+            ("\x1b[1;32mPASS\x1b[m test_foo", "PASS test_foo"),
+            ("line one\x1b[K\nline two", "line one\nline two"),
+            // Escape characters that don't start a control sequence are kept.
+            ("\x1b]0;title", "\x1b]0;title"),
+            ("trailing \x1b", "trailing \x1b"),
+            // An unterminated control sequence is dropped.
+            ("cut off \x1b[3", "cut off "),
+        ];
+        for &(input, expected) in tests {
+            assert_eq!(strip_ansi_escapes(input), expected, "input: {input:?}");
+        }
+
+        assert_eq!(
+            output_text(b"\x1b[31mred\x1b[0m", false),
+            "\x1b[31mred\x1b[0m",
+            "escapes are kept unless stripping is enabled"
+        );
+        assert_eq!(output_text(b"\x1b[31mred\x1b[0m", true), "red");
+    }
+
+    #[test]
+    fn test_failure_text() {
+        let tests = [
+            (JunitFailureFormat::Detailed, "foo\nbar\n", "foo\nbar\n"),
+            (JunitFailureFormat::Brief, "", ""),
+            (JunitFailureFormat::Brief, "foo\n", "foo\n"),
+            (
+                JunitFailureFormat::Brief,
+                "foo\nbar\nbaz\n",
+                "foo\n... (9 bytes truncated)",
+            ),
+            (
+                JunitFailureFormat::Brief,
+                "thread 'foo' panicked at src/x.rs:1:5:\nmessage\nnote: run with `RUST_BACKTRACE=1`\n",
+                "thread 'foo' panicked at src/x.rs:1:5:\nmessage\n... (35 bytes truncated)",
+            ),
+            (
+                JunitFailureFormat::Brief,
+                "thread 'foo' panicked at src/x.rs:1:5:\nmessage\n",
+                "thread 'foo' panicked at src/x.rs:1:5:\nmessage\n",
+            ),
+            (
+                JunitFailureFormat::Brief,
+                "thread 'foo' panicked at 'message', src/x.rs:1:5\nnote: run with `RUST_BACKTRACE=1`\n",
+                "thread 'foo' panicked at 'message', src/x.rs:1:5\n... (35 bytes truncated)",
+            ),
+        ];
+
+        for (format, input, expected) in tests {
+            assert_eq!(
+                failure_text(format, input),
+                expected,
+                "format {format:?}, input {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_classname() {
+        let tests = [
+            (
+                JunitClassNameStyle::Full,
+                "parser::tests::test_empty",
+                Some("my-package::my_test"),
+            ),
+            (
+                JunitClassNameStyle::Package,
+                "parser::tests::test_empty",
+                Some("my-package"),
+            ),
+            (
+                JunitClassNameStyle::Binary,
+                "parser::tests::test_empty",
+                Some("my_test"),
+            ),
+            (
+                JunitClassNameStyle::ModulePath,
+                "parser::tests::test_empty",
+                Some("parser::tests"),
+            ),
+            (JunitClassNameStyle::ModulePath, "test_empty", None),
+            (JunitClassNameStyle::None, "parser::tests::test_empty", None),
+        ];
+
+        for (style, test_name, expected) in tests {
+            assert_eq!(
+                classname(
+                    style,
+                    "my-package::my_test",
+                    "my-package",
+                    "my_test",
+                    test_name
+                ),
+                expected,
+                "style {style:?}, test name {test_name:?}"
+            );
+        }
+    }
 }
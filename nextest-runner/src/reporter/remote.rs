@@ -0,0 +1,310 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Posting test results to a remote service, for `--report-artifacts-to`.
+//!
+//! Results are posted from a background thread, so that slow or unavailable services don't hold
+//! up the test run. Requests that fail with transient errors are retried a few times.
+
+use super::{status_str, TestEvent};
+use crate::errors::RemoteReporterError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    sync::mpsc::{self, Receiver, SyncSender, TrySendError},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// The result of a single test, as posted to the remote service.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RemoteTestResult {
+    /// The UUID for the run the test is part of.
+    pub run_id: String,
+
+    /// The binary ID of the test.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub test_name: String,
+
+    /// The status of the last attempt, for example `PASS` or `FAIL`.
+    pub status: String,
+
+    /// Whether the last attempt passed.
+    pub passed: bool,
+
+    /// The number of times the test was run.
+    pub attempts: usize,
+
+    /// The time at which the last attempt started, in RFC 3339 format.
+    pub start_time: String,
+
+    /// The time taken by the last attempt, in seconds, or `None` if it isn't known.
+    pub duration_secs: Option<f64>,
+
+    /// Standard output from the last attempt, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<String>,
+
+    /// Standard error from the last attempt, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+/// Posts the result of each finished test to a remote service as JSON.
+#[derive(Debug)]
+pub struct RemoteReporter {
+    run_id: String,
+    sender: SyncSender<RemoteTestResult>,
+    worker: JoinHandle<usize>,
+    dropped: usize,
+}
+
+impl RemoteReporter {
+    /// The maximum number of results waiting to be posted. Results reported while the queue is full
+    /// are dropped.
+    pub const QUEUE_CAPACITY: usize = 1024;
+
+    /// The maximum number of times each result is posted, if earlier attempts fail with transient
+    /// errors.
+    pub const MAX_ATTEMPTS: usize = 3;
+
+    /// Creates a reporter that posts results to `url`, authenticating with `token` as a bearer
+    /// token if specified.
+    pub fn new(url: &str, token: Option<String>) -> Result<Self, RemoteReporterError> {
+        let parsed_url =
+            reqwest::Url::parse(url).map_err(|error| RemoteReporterError::InvalidUrl {
+                url: url.to_owned(),
+                message: error.to_string(),
+            })?;
+        if !matches!(parsed_url.scheme(), "http" | "https") {
+            return Err(RemoteReporterError::InvalidUrl {
+                url: url.to_owned(),
+                message: "only http and https URLs are supported".to_owned(),
+            });
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(Self::QUEUE_CAPACITY);
+        let worker = std::thread::Builder::new()
+            .name("nextest-remote-reporter".to_owned())
+            .spawn(move || post_results(parsed_url, token, receiver))
+            .map_err(RemoteReporterError::WorkerSpawn)?;
+
+        Ok(Self {
+            run_id: String::new(),
+            sender,
+            worker,
+            dropped: 0,
+        })
+    }
+
+    /// Queues the result of a finished test to be posted.
+    pub fn report_event(&mut self, event: &TestEvent<'_>) {
+        match event {
+            TestEvent::RunStarted { run_id, .. } => {
+                self.run_id = run_id.to_string();
+            }
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let last_status = run_statuses.last_status();
+                let passed = last_status.result.is_success();
+                let output =
+                    |bytes: &[u8]| (!passed).then(|| String::from_utf8_lossy(bytes).into_owned());
+                let result = RemoteTestResult {
+                    run_id: self.run_id.clone(),
+                    binary_id: test_instance.suite_info.binary_id.to_string(),
+                    test_name: test_instance.name.to_owned(),
+                    status: status_str(last_status.result).into_owned(),
+                    passed,
+                    attempts: run_statuses.len(),
+                    start_time: DateTime::<Utc>::from(last_status.start_time).to_rfc3339(),
+                    duration_secs: last_status
+                        .time_taken_known
+                        .then_some(last_status.time_taken.as_secs_f64()),
+                    stdout: output(&last_status.stdout),
+                    stderr: output(&last_status.stderr),
+                };
+                match self.sender.try_send(result) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => self.dropped += 1,
+                    Err(TrySendError::Disconnected(_)) => {
+                        // The worker exited early, and has already logged why.
+                        self.dropped += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Waits for queued results to be posted, returning the number of results that couldn't be
+    /// posted or were dropped because the queue was full.
+    pub fn finish(self) -> usize {
+        drop(self.sender);
+        let failed = self.worker.join().expect("remote reporter thread panicked");
+        failed + self.dropped
+    }
+}
+
+/// Posts results from `receiver` until the sender is dropped, returning the number of results that
+/// couldn't be posted.
+fn post_results(
+    url: reqwest::Url,
+    token: Option<String>,
+    receiver: Receiver<RemoteTestResult>,
+) -> usize {
+    // The client is created on this thread because the blocking client can't be created or
+    // dropped within the runner's async runtime.
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .user_agent(concat!("nextest/", env!("CARGO_PKG_VERSION")))
+        .build()
+    {
+        Ok(client) => client,
+        Err(error) => {
+            log::warn!("unable to create HTTP client to report results to {url}: {error}");
+            return receiver.iter().count();
+        }
+    };
+
+    let mut failed = 0;
+    for result in receiver {
+        let body = serde_json::to_vec(&result).expect("test result can be serialized as JSON");
+        let mut attempt = 1;
+        loop {
+            let mut request = client
+                .post(url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(token) = &token {
+                request = request.bearer_auth(token);
+            }
+            let error = match request.send() {
+                Ok(response) if response.status().is_success() => break,
+                Ok(response) => {
+                    let status = response.status();
+                    let is_transient = status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    (format!("server responded with {status}"), is_transient)
+                }
+                Err(error) => {
+                    let is_transient = error.is_timeout() || error.is_connect();
+                    (error.to_string(), is_transient)
+                }
+            };
+
+            match error {
+                (_, true) if attempt < RemoteReporter::MAX_ATTEMPTS => {
+                    std::thread::sleep(Duration::from_secs(1 << (attempt - 1)));
+                    attempt += 1;
+                }
+                (message, _) => {
+                    log::warn!(
+                        "failed to report result for {} {} to {url}: {message}",
+                        result.binary_id,
+                        result.test_name,
+                    );
+                    failed += 1;
+                    break;
+                }
+            }
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn test_invalid_url() {
+        let error = RemoteReporter::new("not a url", None).unwrap_err();
+        assert!(matches!(error, RemoteReporterError::InvalidUrl { .. }));
+        let error = RemoteReporter::new("ftp://example.com/results", None).unwrap_err();
+        assert!(matches!(error, RemoteReporterError::InvalidUrl { .. }));
+    }
+
+    #[test]
+    fn test_post_results_retries() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = reqwest::Url::parse(&format!(
+            "http://{}/results",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+
+        // Fail the first request with a transient error, then accept the retry.
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status_line in ["503 Service Unavailable", "200 OK"] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut headers = Vec::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_owned();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    headers.push(line);
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status_line}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                )
+                .unwrap();
+                requests.push((headers, body));
+            }
+            requests
+        });
+
+        let (sender, receiver) = mpsc::sync_channel(RemoteReporter::QUEUE_CAPACITY);
+        sender
+            .send(RemoteTestResult {
+                run_id: "run".to_owned(),
+                binary_id: "my-crate::tests".to_owned(),
+                test_name: "tests::fails".to_owned(),
+                status: "FAIL".to_owned(),
+                passed: false,
+                attempts: 1,
+                start_time: "2023-06-01T00:00:00+00:00".to_owned(),
+                duration_secs: Some(0.5),
+                stdout: Some("output\n".to_owned()),
+                stderr: Some(String::new()),
+            })
+            .unwrap();
+        drop(sender);
+        let failed = post_results(url, Some("secret".to_owned()), receiver);
+        assert_eq!(failed, 0, "result was posted on the second attempt");
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (headers, body) = &requests[1];
+        assert!(headers[0].starts_with("POST /results "));
+        assert!(headers
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case("authorization: Bearer secret")));
+        let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(body["binary-id"], "my-crate::tests");
+        assert_eq!(body["test-name"], "tests::fails");
+        assert_eq!(body["status"], "FAIL");
+        assert_eq!(body["stdout"], "output\n");
+    }
+}
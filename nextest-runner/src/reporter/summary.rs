@@ -0,0 +1,363 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Summaries of test runs, saved so that they can be displayed again without re-running tests.
+
+use super::{status_str, write_summary_str, Styles, TestEvent};
+use crate::{
+    errors::{RunSummaryReadError, WriteEventError},
+    runner::RunStats,
+};
+use camino::Utf8Path;
+use chrono::{DateTime, Utc};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+/// A summary of a finished test run.
+///
+/// `cargo nextest run` writes this to [`Self::FILE_NAME`] within the profile's store directory, and
+/// `cargo nextest status` reads it back.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunSummary {
+    /// The UUID for this run.
+    pub run_id: String,
+
+    /// The name of the profile used for this run.
+    pub profile: String,
+
+    /// The time at which the run started, in RFC 3339 format.
+    pub start_time: String,
+
+    /// The time taken by the run, in seconds.
+    pub elapsed_secs: f64,
+
+    /// Statistics for the run.
+    pub run_stats: RunStats,
+
+    /// The tests that failed, in the order in which they finished.
+    pub failures: Vec<RunSummaryFailure>,
+}
+
+/// A test that failed, as recorded in a [`RunSummary`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunSummaryFailure {
+    /// The binary ID of the test.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub test_name: String,
+
+    /// The status of the last attempt, e.g. `FAIL` or `TIMEOUT`.
+    pub status: String,
+}
+
+impl RunSummary {
+    /// The name of the file within the profile's store directory that summaries are written to.
+    pub const FILE_NAME: &'static str = "last-run-summary.json";
+
+    /// Writes this summary to `store_dir`, replacing the summary of any earlier run.
+    pub fn write_to_store_dir(&self, store_dir: &Utf8Path) -> Result<(), WriteEventError> {
+        let path = store_dir.join(Self::FILE_NAME);
+        let contents = serde_json::to_vec_pretty(self).expect("serializing JSON succeeds");
+        std::fs::write(&path, contents).map_err(|error| WriteEventError::Fs { file: path, error })
+    }
+
+    /// Reads the summary of the last run from `store_dir`.
+    ///
+    /// Returns `Ok(None)` if no run has been recorded there.
+    pub fn read_from_store_dir(store_dir: &Utf8Path) -> Result<Option<Self>, RunSummaryReadError> {
+        let path = store_dir.join(Self::FILE_NAME);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(RunSummaryReadError::Read { path, error }),
+        };
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|error| RunSummaryReadError::Parse { path, error })
+    }
+
+    /// Writes this summary in a human-readable form, similar to the end of a test run.
+    pub fn write_human(&self, colorize: bool, writer: &mut dyn Write) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        writeln!(
+            writer,
+            "{:>12} {} with profile {}, started at {}",
+            "Last run".style(styles.pass),
+            self.run_id.style(styles.count),
+            self.profile.style(styles.count),
+            self.start_time,
+        )?;
+
+        let summary_style = if self.run_stats.any_failed() {
+            styles.fail
+        } else {
+            styles.pass
+        };
+        write!(
+            writer,
+            "------------\n{:>12} [{:>8.3?}s] {}",
+            "Summary".style(summary_style),
+            self.elapsed_secs,
+            self.run_stats.finished_count.style(styles.count),
+        )?;
+        if self.run_stats.finished_count != self.run_stats.initial_run_count {
+            write!(
+                writer,
+                "/{}",
+                self.run_stats.initial_run_count.style(styles.count)
+            )?;
+        }
+        let mut summary_str = String::new();
+        // Writing to a string is infallible.
+        let _ = write_summary_str(&self.run_stats, &styles, &mut summary_str);
+        writeln!(writer, " tests run: {summary_str}")?;
+
+        for failure in &self.failures {
+            writeln!(
+                writer,
+                "{:>12} {} {}",
+                failure.status.style(styles.fail),
+                failure.binary_id.style(styles.list_styles.binary_id),
+                failure.test_name.style(styles.list_styles.test_name),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The failures in recent test runs, used to tell chronic failures apart from new ones.
+///
+/// `cargo nextest run` adds each run to [`Self::FILE_NAME`] within the profile's store directory,
+/// keeping the last [`Self::MAX_RUNS`] runs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunHistory {
+    /// The recorded runs, oldest first.
+    pub runs: Vec<RunHistoryEntry>,
+}
+
+/// A run recorded in a [`RunHistory`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunHistoryEntry {
+    /// The UUID for this run.
+    pub run_id: String,
+
+    /// The tests that failed in this run.
+    pub failures: Vec<RunSummaryFailure>,
+}
+
+impl RunHistory {
+    /// The name of the file within the profile's store directory that the history is written to.
+    pub const FILE_NAME: &'static str = "run-history.json";
+
+    /// The number of runs that are kept in the history.
+    pub const MAX_RUNS: usize = 10;
+
+    /// Reads the history from `store_dir`.
+    ///
+    /// Returns an empty history if no runs have been recorded there.
+    pub fn read_from_store_dir(store_dir: &Utf8Path) -> Result<Self, RunSummaryReadError> {
+        let path = store_dir.join(Self::FILE_NAME);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(RunSummaryReadError::Read { path, error }),
+        };
+        serde_json::from_str(&contents).map_err(|error| RunSummaryReadError::Parse { path, error })
+    }
+
+    /// Writes the history to `store_dir`.
+    pub fn write_to_store_dir(&self, store_dir: &Utf8Path) -> Result<(), WriteEventError> {
+        let path = store_dir.join(Self::FILE_NAME);
+        let contents = serde_json::to_vec_pretty(self).expect("serializing JSON succeeds");
+        std::fs::write(&path, contents).map_err(|error| WriteEventError::Fs { file: path, error })
+    }
+
+    /// Adds a finished run to the history, dropping the oldest runs beyond [`Self::MAX_RUNS`].
+    pub fn record(&mut self, summary: &RunSummary) {
+        self.runs.push(RunHistoryEntry {
+            run_id: summary.run_id.clone(),
+            failures: summary.failures.clone(),
+        });
+        let excess = self.runs.len().saturating_sub(Self::MAX_RUNS);
+        self.runs.drain(..excess);
+    }
+
+    /// Returns the number of recorded runs in which the given test failed.
+    pub fn failure_count(&self, binary_id: &str, test_name: &str) -> usize {
+        self.runs
+            .iter()
+            .filter(|run| {
+                run.failures
+                    .iter()
+                    .any(|failure| failure.binary_id == binary_id && failure.test_name == test_name)
+            })
+            .count()
+    }
+}
+
+/// Records the events of a test run into a [`RunSummary`].
+#[derive(Debug)]
+pub struct RunSummaryRecorder {
+    profile: String,
+    run_id: Option<String>,
+    failures: Vec<RunSummaryFailure>,
+}
+
+impl RunSummaryRecorder {
+    /// Creates a new recorder for a run with the given profile.
+    pub fn new(profile: impl Into<String>) -> Self {
+        Self {
+            profile: profile.into(),
+            run_id: None,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Records an event, returning the summary once the run has finished.
+    pub fn record_event(&mut self, event: &TestEvent<'_>) -> Option<RunSummary> {
+        match event {
+            TestEvent::RunStarted { run_id, .. } => {
+                self.run_id = Some(run_id.to_string());
+            }
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let last_status = run_statuses.last_status();
+                if !last_status.result.is_success() {
+                    self.failures.push(RunSummaryFailure {
+                        binary_id: test_instance.suite_info.binary_id.to_string(),
+                        test_name: test_instance.name.to_owned(),
+                        status: status_str(last_status.result).into_owned(),
+                    });
+                }
+            }
+            TestEvent::RunFinished {
+                start_time,
+                elapsed,
+                run_stats,
+                ..
+            } => {
+                return Some(RunSummary {
+                    run_id: self.run_id.take().unwrap_or_default(),
+                    profile: self.profile.clone(),
+                    start_time: DateTime::<Utc>::from(*start_time).to_rfc3339(),
+                    elapsed_secs: elapsed.as_secs_f64(),
+                    run_stats: *run_stats,
+                    failures: std::mem::take(&mut self.failures),
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn run_summary_round_trip() {
+        let dir = tempdir().unwrap();
+        let store_dir: &Utf8Path = dir.path().try_into().unwrap();
+        assert!(
+            RunSummary::read_from_store_dir(store_dir)
+                .expect("missing summary isn't an error")
+                .is_none(),
+            "no summary before the first run"
+        );
+
+        let summary = RunSummary {
+            run_id: "4f8d6f3c-6a3b-4a59-9b3c-5c2f1f2e7d10".to_owned(),
+            profile: "ci".to_owned(),
+            start_time: "2023-06-01T10:52:37+00:00".to_owned(),
+            elapsed_secs: 1.5,
+            run_stats: RunStats {
+                initial_run_count: 3,
+                finished_count: 3,
+                passed: 2,
+                failed: 1,
+                ..RunStats::default()
+            },
+            failures: vec![RunSummaryFailure {
+                binary_id: "my-crate".to_owned(),
+                test_name: "tests::test_failure".to_owned(),
+                status: "FAIL".to_owned(),
+            }],
+        };
+        summary.write_to_store_dir(store_dir).unwrap();
+
+        let read = RunSummary::read_from_store_dir(store_dir)
+            .unwrap()
+            .expect("summary was written");
+        assert_eq!(read.run_stats, summary.run_stats);
+        assert_eq!(read.failures, summary.failures);
+
+        let mut human = Vec::new();
+        read.write_human(false, &mut human).unwrap();
+        assert_eq!(
+            String::from_utf8(human).unwrap(),
+            "    Last run 4f8d6f3c-6a3b-4a59-9b3c-5c2f1f2e7d10 with profile ci, \
+             started at 2023-06-01T10:52:37+00:00\n\
+             ------------\n     \
+             Summary [   1.500s] 3 tests run: 2 passed, 1 failed, 0 skipped\n        \
+             FAIL my-crate tests::test_failure\n"
+        );
+
+        std::fs::write(store_dir.join(RunSummary::FILE_NAME), "not json").unwrap();
+        RunSummary::read_from_store_dir(store_dir).expect_err("invalid summary is an error");
+    }
+
+    #[test]
+    fn run_history_record() {
+        let dir = tempdir().unwrap();
+        let store_dir: &Utf8Path = dir.path().try_into().unwrap();
+        let mut history = RunHistory::read_from_store_dir(store_dir).unwrap();
+        assert!(history.runs.is_empty(), "no history before the first run");
+
+        let summary = |run: usize, failures: &[&str]| RunSummary {
+            run_id: format!("run-{run}"),
+            profile: "default".to_owned(),
+            start_time: "2023-06-01T10:52:37+00:00".to_owned(),
+            elapsed_secs: 1.0,
+            run_stats: RunStats::default(),
+            failures: failures
+                .iter()
+                .map(|test_name| RunSummaryFailure {
+                    binary_id: "my-crate".to_owned(),
+                    test_name: (*test_name).to_owned(),
+                    status: "FAIL".to_owned(),
+                })
+                .collect(),
+        };
+        history.record(&summary(0, &["test_old"]));
+        for run in 1..=RunHistory::MAX_RUNS {
+            history.record(&summary(run, &["test_chronic"]));
+        }
+        history.write_to_store_dir(store_dir).unwrap();
+
+        let history = RunHistory::read_from_store_dir(store_dir).unwrap();
+        assert_eq!(history.runs.len(), RunHistory::MAX_RUNS);
+        assert_eq!(history.runs[0].run_id, "run-1", "oldest run is dropped");
+        assert_eq!(
+            history.failure_count("my-crate", "test_chronic"),
+            RunHistory::MAX_RUNS
+        );
+        assert_eq!(history.failure_count("my-crate", "test_old"), 0);
+        assert_eq!(history.failure_count("other-crate", "test_chronic"), 0);
+    }
+}
@@ -40,6 +40,7 @@ impl ArchiveReporter {
                 test_binary_count,
                 non_test_binary_count,
                 linked_path_count,
+                debug_info_stripped,
                 output_file,
             } => {
                 write!(writer, "{:>12} ", "Archiving".style(self.styles.success))?;
@@ -51,18 +52,24 @@ impl ArchiveReporter {
                     &mut writer,
                 )?;
 
-                writeln!(writer, " to {}", output_file.style(self.styles.bold))?;
+                write!(writer, " to {}", output_file.style(self.styles.bold))?;
+                if debug_info_stripped {
+                    write!(writer, ", stripping debug info")?;
+                }
+                writeln!(writer)?;
             }
             ArchiveEvent::Archived {
                 file_count,
+                source_file_count,
                 output_file,
                 elapsed,
             } => {
                 write!(writer, "{:>12} ", "Archived".style(self.styles.success))?;
                 writeln!(
                     writer,
-                    "{} files to {} in {}",
+                    "{} files{} to {} in {}",
                     file_count.style(self.styles.bold),
+                    self.source_file_text(source_file_count),
                     output_file.style(self.styles.bold),
                     format_duration(elapsed),
                 )?;
@@ -86,14 +93,16 @@ impl ArchiveReporter {
             }
             ArchiveEvent::Extracted {
                 file_count,
+                source_file_count,
                 dest_dir: destination_dir,
                 elapsed,
             } => {
                 write!(writer, "{:>12} ", "Extracted".style(self.styles.success))?;
                 writeln!(
                     writer,
-                    "{} files to {} in {}",
+                    "{} files{} to {} in {}",
                     file_count.style(self.styles.bold),
+                    self.source_file_text(source_file_count),
                     destination_dir.style(self.styles.bold),
                     format_duration(elapsed),
                 )?;
@@ -103,6 +112,17 @@ impl ArchiveReporter {
         Ok(())
     }
 
+    fn source_file_text(&self, source_file_count: usize) -> String {
+        if source_file_count > 0 {
+            format!(
+                " (including {} source files)",
+                source_file_count.style(self.styles.bold)
+            )
+        } else {
+            "".to_owned()
+        }
+    }
+
     fn report_binary_counts(
         &mut self,
         test_binary_count: usize,
@@ -166,6 +186,9 @@ pub enum ArchiveEvent<'a> {
         /// The number of linked paths to archive.
         linked_path_count: usize,
 
+        /// Whether debug info is being stripped from binaries.
+        debug_info_stripped: bool,
+
         /// The archive output file.
         output_file: &'a Utf8Path,
     },
@@ -175,6 +198,9 @@ pub enum ArchiveEvent<'a> {
         /// The number of files archived.
         file_count: usize,
 
+        /// The number of workspace source files archived, included in `file_count`.
+        source_file_count: usize,
+
         /// The archive output file.
         output_file: &'a Utf8Path,
 
@@ -202,6 +228,9 @@ pub enum ArchiveEvent<'a> {
         /// The number of files extracted.
         file_count: usize,
 
+        /// The number of workspace source files extracted, included in `file_count`.
+        source_file_count: usize,
+
         /// The destination directory.
         dest_dir: &'a Utf8Path,
 
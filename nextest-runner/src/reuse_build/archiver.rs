@@ -1,7 +1,7 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{ArchiveEvent, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME};
+use super::{ArchiveEvent, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME, SOURCE_DIR_NAME};
 use crate::{
     config::get_num_cpus,
     errors::{ArchiveCreateError, UnknownArchiveFormat},
@@ -13,9 +13,12 @@ use atomicwrites::{AtomicFile, OverwriteBehavior};
 use camino::{Utf8Path, Utf8PathBuf};
 use std::{
     collections::HashSet,
+    fs,
     io::{self, BufWriter, Write},
+    process::Command,
     time::{Instant, SystemTime},
 };
+use tempfile::TempDir;
 use zstd::Encoder;
 
 /// Archive format.
@@ -46,13 +49,55 @@ impl ArchiveFormat {
     }
 }
 
+/// Options for bundling workspace source files into an archive.
+///
+/// Source files are stored under the `source/` directory within the archive.
+#[derive(Clone, Debug)]
+pub struct ArchiveSourceOptions {
+    /// The workspace root. All files tracked by git within it are archived.
+    pub workspace_root: Utf8PathBuf,
+
+    /// The maximum total size of source files, in bytes.
+    pub max_size: u64,
+}
+
+/// Options for stripping debug info from binaries before archiving them.
+#[derive(Clone, Debug)]
+pub struct StripDebugInfoOptions {
+    /// The tool to strip debug info with.
+    ///
+    /// The tool is invoked as `<tool> --strip-debug <input> <output>`, which is supported by
+    /// `objcopy` and `llvm-objcopy`.
+    pub tool: String,
+}
+
+impl StripDebugInfoOptions {
+    /// The tool used by default.
+    pub const DEFAULT_TOOL: &'static str = "objcopy";
+}
+
+impl Default for StripDebugInfoOptions {
+    fn default() -> Self {
+        Self {
+            tool: Self::DEFAULT_TOOL.to_owned(),
+        }
+    }
+}
+
 /// Archives test binaries along with metadata to the given file.
 ///
+/// If `source` is specified, workspace source files are archived as well. If `strip_debug_info`
+/// is specified, debug info is stripped from test and non-test binaries, and the binaries metadata
+/// in the archive records this.
+///
 /// The output file is a Zstandard-compressed tarball (`.tar.zst`).
+#[allow(clippy::too_many_arguments)]
 pub fn archive_to_file<'a, F>(
     binary_list: &'a BinaryList,
     cargo_metadata: &'a str,
     path_mapper: &'a PathMapper,
+    source: Option<&'a ArchiveSourceOptions>,
+    strip_debug_info: Option<&'a StripDebugInfoOptions>,
     format: ArchiveFormat,
     zstd_level: i32,
     output_file: &'a Utf8Path,
@@ -67,12 +112,13 @@ where
     let linked_path_count = binary_list.rust_build_meta.linked_paths.len();
     let start_time = Instant::now();
 
-    let file_count = file
+    let (file_count, source_file_count) = file
         .write(|file| {
             callback(ArchiveEvent::ArchiveStarted {
                 test_binary_count,
                 non_test_binary_count,
                 linked_path_count,
+                debug_info_stripped: strip_debug_info.is_some(),
                 output_file,
             })
             .map_err(ArchiveCreateError::ReporterIo)?;
@@ -81,12 +127,14 @@ where
                 binary_list,
                 cargo_metadata,
                 path_mapper,
+                source,
+                strip_debug_info,
                 format,
                 zstd_level,
                 file,
             )?;
-            let (_, file_count) = archiver.archive()?;
-            Ok(file_count)
+            let (_, file_count, source_file_count) = archiver.archive()?;
+            Ok((file_count, source_file_count))
         })
         .map_err(|err| match err {
             atomicwrites::Error::Internal(err) => ArchiveCreateError::OutputArchiveIo(err),
@@ -97,6 +145,7 @@ where
 
     callback(ArchiveEvent::Archived {
         file_count,
+        source_file_count,
         output_file,
         elapsed,
     })
@@ -109,20 +158,27 @@ struct Archiver<'a, W: Write> {
     binary_list: &'a BinaryList,
     cargo_metadata: &'a str,
     path_mapper: &'a PathMapper,
+    source: Option<&'a ArchiveSourceOptions>,
+    strip_debug_info: Option<DebugInfoStripper<'a>>,
     builder: tar::Builder<Encoder<'static, BufWriter<W>>>,
     unix_timestamp: u64,
     added_files: HashSet<Utf8PathBuf>,
 }
 
 impl<'a, W: Write> Archiver<'a, W> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         binary_list: &'a BinaryList,
         cargo_metadata: &'a str,
         path_mapper: &'a PathMapper,
+        source: Option<&'a ArchiveSourceOptions>,
+        strip_debug_info: Option<&'a StripDebugInfoOptions>,
         format: ArchiveFormat,
         compression_level: i32,
         writer: W,
     ) -> Result<Self, ArchiveCreateError> {
+        let strip_debug_info = strip_debug_info.map(DebugInfoStripper::new).transpose()?;
+
         let buf_writer = BufWriter::new(writer);
         let builder = match format {
             ArchiveFormat::TarZst => {
@@ -147,18 +203,25 @@ impl<'a, W: Write> Archiver<'a, W> {
             binary_list,
             cargo_metadata,
             path_mapper,
+            source,
+            strip_debug_info,
             builder,
             unix_timestamp,
             added_files: HashSet::new(),
         })
     }
 
-    fn archive(mut self) -> Result<(W, usize), ArchiveCreateError> {
+    fn archive(mut self) -> Result<(W, usize, usize), ArchiveCreateError> {
         // Add the binaries metadata first so that while unarchiving, reports are instant.
-        let binaries_metadata = self
-            .binary_list
-            .to_string(OutputFormat::Serializable(SerializableFormat::JsonPretty))
-            .map_err(ArchiveCreateError::CreateBinaryList)?;
+        let binaries_metadata = if self.strip_debug_info.is_some() {
+            let mut binary_list = self.binary_list.clone();
+            binary_list.rust_build_meta.debug_info_stripped = true;
+            binary_list.to_string(OutputFormat::Serializable(SerializableFormat::JsonPretty))
+        } else {
+            self.binary_list
+                .to_string(OutputFormat::Serializable(SerializableFormat::JsonPretty))
+        }
+        .map_err(ArchiveCreateError::CreateBinaryList)?;
 
         self.append_from_memory(BINARIES_METADATA_FILE_NAME, &binaries_metadata)?;
 
@@ -177,7 +240,7 @@ impl<'a, W: Write> Archiver<'a, W> {
             let rel_path = Utf8Path::new("target").join(rel_path);
             let rel_path = convert_rel_path_to_forward_slash(&rel_path);
 
-            self.append_path(&binary.path, &rel_path)?;
+            self.append_binary(&binary.path, &rel_path)?;
         }
         for non_test_binary in self
             .binary_list
@@ -196,7 +259,7 @@ impl<'a, W: Write> Archiver<'a, W> {
             let rel_path = Utf8Path::new("target").join(&non_test_binary.path);
             let rel_path = convert_rel_path_to_forward_slash(&rel_path);
 
-            self.append_path(&src_path, &rel_path)?;
+            self.append_binary(&src_path, &rel_path)?;
         }
 
         // Write linked paths to the archive.
@@ -231,6 +294,12 @@ impl<'a, W: Write> Archiver<'a, W> {
             self.append_dir_one_level(&rel_path, &src_path)?;
         }
 
+        // Write workspace source files to the archive.
+        let source_file_count = match self.source {
+            Some(source) => self.append_source(source)?,
+            None => 0,
+        };
+
         // TODO: add extra files.
 
         // Finish writing the archive.
@@ -246,7 +315,53 @@ impl<'a, W: Write> Archiver<'a, W> {
             .into_inner()
             .map_err(|err| ArchiveCreateError::OutputArchiveIo(err.into_error()))?;
 
-        Ok((writer, self.added_files.len()))
+        Ok((writer, self.added_files.len(), source_file_count))
+    }
+
+    fn append_source(
+        &mut self,
+        source: &ArchiveSourceOptions,
+    ) -> Result<usize, ArchiveCreateError> {
+        let workspace_root = &source.workspace_root;
+        let mut files = Vec::new();
+        let mut total_size = 0;
+        for rel_path in git_ls_files(workspace_root)? {
+            let src_path = workspace_root.join(&rel_path);
+            let metadata = match fs::metadata(&src_path) {
+                Ok(metadata) => metadata,
+                // Files that are tracked but deleted in the working tree are skipped.
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => {
+                    return Err(ArchiveCreateError::InputFileRead {
+                        path: src_path,
+                        is_dir: None,
+                        error,
+                    })
+                }
+            };
+            // Submodules are listed as directories: skip them.
+            if metadata.is_dir() {
+                continue;
+            }
+            total_size += metadata.len();
+            files.push((src_path, rel_path));
+        }
+
+        // Check the total size before writing anything out.
+        if total_size > source.max_size {
+            return Err(ArchiveCreateError::SourceTooLarge {
+                size: total_size,
+                max_size: source.max_size,
+            });
+        }
+
+        for (src_path, rel_path) in &files {
+            let dest = Utf8Path::new(SOURCE_DIR_NAME).join(rel_path);
+            let dest = convert_rel_path_to_forward_slash(&dest);
+            self.append_path(src_path, &dest)?;
+        }
+
+        Ok(files.len())
     }
 
     // ---
@@ -306,6 +421,17 @@ impl<'a, W: Write> Archiver<'a, W> {
         Ok(())
     }
 
+    fn append_binary(&mut self, src: &Utf8Path, dest: &Utf8Path) -> Result<(), ArchiveCreateError> {
+        match &self.strip_debug_info {
+            // Check added_files first to avoid stripping binaries that won't be archived.
+            Some(stripper) if !self.added_files.contains(dest) => {
+                let stripped = stripper.strip(src, dest)?;
+                self.append_path(&stripped, dest)
+            }
+            _ => self.append_path(src, dest),
+        }
+    }
+
     fn append_path(&mut self, src: &Utf8Path, dest: &Utf8Path) -> Result<(), ArchiveCreateError> {
         // Check added_files to ensure we aren't adding duplicate files.
         if !self.added_files.contains(dest) {
@@ -322,9 +448,106 @@ impl<'a, W: Write> Archiver<'a, W> {
     }
 }
 
+/// Strips debug info from binaries, writing the stripped copies to a temporary directory.
+struct DebugInfoStripper<'a> {
+    options: &'a StripDebugInfoOptions,
+    dir: Utf8PathBuf,
+    // Removes the directory on drop.
+    _temp_dir: TempDir,
+}
+
+impl<'a> DebugInfoStripper<'a> {
+    fn new(options: &'a StripDebugInfoOptions) -> Result<Self, ArchiveCreateError> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("nextest-strip-")
+            .tempdir()
+            .map_err(ArchiveCreateError::TempDirCreate)?;
+        let dir = Utf8PathBuf::try_from(temp_dir.path().to_path_buf()).map_err(|err| {
+            ArchiveCreateError::TempDirCreate(io::Error::new(io::ErrorKind::InvalidData, err))
+        })?;
+        Ok(Self {
+            options,
+            dir,
+            _temp_dir: temp_dir,
+        })
+    }
+
+    /// Strips debug info from `src`, returning the path to the stripped copy.
+    fn strip(
+        &self,
+        src: &Utf8Path,
+        rel_path: &Utf8Path,
+    ) -> Result<Utf8PathBuf, ArchiveCreateError> {
+        let strip_error = |error| ArchiveCreateError::StripDebugInfo {
+            path: src.to_owned(),
+            tool: self.options.tool.clone(),
+            error,
+        };
+
+        let stripped = self.dir.join(rel_path);
+        if let Some(parent) = stripped.parent() {
+            fs::create_dir_all(parent).map_err(strip_error)?;
+        }
+        let output = Command::new(&self.options.tool)
+            .arg("--strip-debug")
+            .arg(src)
+            .arg(&stripped)
+            .output()
+            .map_err(strip_error)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(strip_error(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "`{}` failed with {}: {}",
+                    self.options.tool,
+                    output.status,
+                    stderr.trim()
+                ),
+            )));
+        }
+
+        Ok(stripped)
+    }
+}
+
+/// Returns the list of files tracked by git within `workspace_root`, relative to it.
+fn git_ls_files(workspace_root: &Utf8Path) -> Result<Vec<Utf8PathBuf>, ArchiveCreateError> {
+    let list_error = |error| ArchiveCreateError::SourceList {
+        workspace_root: workspace_root.to_owned(),
+        error,
+    };
+
+    let output = Command::new("git")
+        .args(["ls-files", "-z"])
+        .current_dir(workspace_root)
+        .output()
+        .map_err(list_error)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(list_error(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "`git ls-files` failed with {}: {}",
+                output.status,
+                stderr.trim()
+            ),
+        )));
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|err| list_error(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+    Ok(stdout
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(Utf8PathBuf::from)
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::list::RustBuildMeta;
 
     #[test]
     fn test_archive_format_autodetect() {
@@ -339,4 +562,85 @@ mod tests {
         ArchiveFormat::autodetect("foo".as_ref()).unwrap_err();
         ArchiveFormat::autodetect("/".as_ref()).unwrap_err();
     }
+
+    #[test]
+    fn test_archive_source_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = Utf8Path::from_path(temp_dir.path()).unwrap();
+        let workspace_root = temp_path.join("workspace");
+        fs::create_dir_all(workspace_root.join("src")).unwrap();
+        fs::write(workspace_root.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(workspace_root.join("src/lib.rs"), "// lib\n").unwrap();
+        fs::write(workspace_root.join("src/deleted.rs"), "// deleted\n").unwrap();
+        fs::write(workspace_root.join("untracked.txt"), "untracked\n").unwrap();
+        for args in [&["init", "-q"][..], &["add", "Cargo.toml", "src"]] {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(&workspace_root)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {args:?} succeeded");
+        }
+        // Tracked files that are deleted in the working tree are skipped.
+        fs::remove_file(workspace_root.join("src/deleted.rs")).unwrap();
+
+        let binary_list = BinaryList {
+            rust_build_meta: RustBuildMeta::new(temp_path.join("target"), None),
+            rust_binaries: Vec::new(),
+        };
+        let archive = |max_size, output_file: &Utf8Path| {
+            let source = ArchiveSourceOptions {
+                workspace_root: workspace_root.clone(),
+                max_size,
+            };
+            archive_to_file(
+                &binary_list,
+                "{}",
+                &PathMapper::noop(),
+                Some(&source),
+                None,
+                ArchiveFormat::TarZst,
+                0,
+                output_file,
+                |_| Ok(()),
+            )
+        };
+
+        let archive_file = temp_path.join("archive.tar.zst");
+        archive(u64::MAX, &archive_file).expect("archive created");
+        let decoder = zstd::Decoder::new(fs::File::open(&archive_file).unwrap()).unwrap();
+        let mut entries = Vec::new();
+        for entry in tar::Archive::new(decoder).entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_str().unwrap().to_owned();
+            let mut contents = String::new();
+            io::Read::read_to_string(&mut entry, &mut contents).unwrap();
+            entries.push((path, contents));
+        }
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|(path, _)| path.starts_with(SOURCE_DIR_NAME))
+                .map(|(path, contents)| (path.as_str(), contents.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("source/Cargo.toml", "[package]\n"),
+                ("source/src/lib.rs", "// lib\n"),
+            ],
+        );
+
+        // Cargo.toml and src/lib.rs are 17 bytes in total.
+        archive(17, &temp_path.join("exact.tar.zst")).expect("archive at the limit created");
+        let too_large_file = temp_path.join("too-large.tar.zst");
+        match archive(16, &too_large_file) {
+            Err(ArchiveCreateError::SourceTooLarge { size, max_size }) => {
+                assert_eq!((size, max_size), (17, 16));
+            }
+            other => panic!("expected SourceTooLarge, got {other:?}"),
+        }
+        assert!(
+            !too_large_file.exists(),
+            "no archive is written if sources are too large",
+        );
+    }
 }
@@ -32,6 +32,9 @@ pub const CARGO_METADATA_FILE_NAME: &str = "target/nextest/cargo-metadata.json";
 /// The name of the file in which binaries metadata is stored.
 pub const BINARIES_METADATA_FILE_NAME: &str = "target/nextest/binaries-metadata.json";
 
+/// The directory within an archive that workspace source files are stored in.
+pub const SOURCE_DIR_NAME: &str = "source";
+
 /// Reuse build information.
 #[derive(Debug, Default)]
 pub struct ReuseBuildInfo {
@@ -41,6 +44,10 @@ pub struct ReuseBuildInfo {
     /// Binaries metadata JSON and remapping for the target directory.
     pub binaries_metadata: Option<MetadataWithRemap<BinaryList>>,
 
+    /// The workspace root to use instead of the one in Cargo metadata, whether or not the build is
+    /// being reused.
+    pub workspace_root_override: Option<Utf8PathBuf>,
+
     /// Optional temporary directory used for cleanup.
     _temp_dir: Option<TempDir>,
 }
@@ -54,6 +61,7 @@ impl ReuseBuildInfo {
         Self {
             cargo_metadata,
             binaries_metadata,
+            workspace_root_override: None,
             _temp_dir: None,
         }
     }
@@ -93,6 +101,7 @@ impl ReuseBuildInfo {
         Ok(Self {
             cargo_metadata: Some(cargo_metadata),
             binaries_metadata: Some(binaries_metadata),
+            workspace_root_override: None,
             _temp_dir: temp_dir,
         })
     }
@@ -120,6 +129,20 @@ impl ReuseBuildInfo {
             .and_then(|m| m.remap.as_deref())
     }
 
+    /// Sets the workspace root to use instead of the one in Cargo metadata.
+    ///
+    /// Unlike [`Self::workspace_remap`], this applies even if the build isn't being reused, and
+    /// takes precedence over the workspace remap.
+    pub fn set_workspace_root_override(&mut self, workspace_root: Utf8PathBuf) -> &mut Self {
+        self.workspace_root_override = Some(workspace_root);
+        self
+    }
+
+    /// Returns the workspace root set through [`Self::set_workspace_root_override`], if any.
+    pub fn workspace_root_override(&self) -> Option<&Utf8Path> {
+        self.workspace_root_override.as_deref()
+    }
+
     /// Returns the new target directory.
     pub fn target_dir_remap(&self) -> Option<&Utf8Path> {
         self.binaries_metadata
@@ -1,7 +1,10 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{ArchiveEvent, ArchiveFormat, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME};
+use super::{
+    ArchiveEvent, ArchiveFormat, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME,
+    SOURCE_DIR_NAME,
+};
 use crate::{
     errors::{ArchiveExtractError, ArchiveReadError},
     list::BinaryList,
@@ -98,6 +101,7 @@ impl<'a> Unarchiver<'a> {
         let cargo_metadata_path = Utf8Path::new(CARGO_METADATA_FILE_NAME);
 
         let mut file_count = 0;
+        let mut source_file_count = 0;
 
         for entry in archive_reader
             .entries()
@@ -105,6 +109,9 @@ impl<'a> Unarchiver<'a> {
         {
             file_count += 1;
             let (mut entry, path) = entry.map_err(ArchiveExtractError::Read)?;
+            if path.starts_with(SOURCE_DIR_NAME) {
+                source_file_count += 1;
+            }
 
             entry
                 .unpack_in(&dest_dir)
@@ -191,6 +198,7 @@ impl<'a> Unarchiver<'a> {
         // Report end extraction.
         callback(ArchiveEvent::Extracted {
             file_count,
+            source_file_count,
             dest_dir: &dest_dir,
             elapsed,
         })
@@ -206,6 +214,60 @@ impl<'a> Unarchiver<'a> {
     }
 }
 
+/// Information about an archive, returned by [`inspect_archive`].
+#[derive(Debug)]
+pub struct ArchiveInspectInfo {
+    /// The [`BinaryList`] read from the archive.
+    pub binary_list: BinaryList,
+
+    /// The number of source files in the archive, added with `--include-source`.
+    pub source_file_count: usize,
+}
+
+/// Reads information about an archive without extracting it.
+///
+/// Every entry has to be read to count source files, so this decompresses the whole archive.
+pub fn inspect_archive(
+    archive_file: &Utf8Path,
+    format: ArchiveFormat,
+) -> Result<ArchiveInspectInfo, ArchiveExtractError> {
+    let mut file = fs::File::open(archive_file)
+        .map_err(|error| ArchiveExtractError::Read(ArchiveReadError::Io(error)))?;
+    let mut archive_reader =
+        ArchiveReader::new(&mut file, format).map_err(ArchiveExtractError::Read)?;
+    let binaries_metadata_path = Utf8Path::new(BINARIES_METADATA_FILE_NAME);
+
+    let mut binary_list = None;
+    let mut source_file_count = 0;
+    for entry in archive_reader
+        .entries()
+        .map_err(ArchiveExtractError::Read)?
+    {
+        let (entry, path) = entry.map_err(ArchiveExtractError::Read)?;
+        if path.starts_with(SOURCE_DIR_NAME) {
+            source_file_count += 1;
+        } else if path == binaries_metadata_path {
+            let summary: BinaryListSummary = serde_json::from_reader(entry).map_err(|error| {
+                ArchiveExtractError::Read(ArchiveReadError::MetadataDeserializeError {
+                    path: binaries_metadata_path,
+                    error,
+                })
+            })?;
+            binary_list = Some(BinaryList::from_summary(summary)?);
+        }
+    }
+
+    match binary_list {
+        Some(binary_list) => Ok(ArchiveInspectInfo {
+            binary_list,
+            source_file_count,
+        }),
+        None => Err(ArchiveExtractError::Read(
+            ArchiveReadError::MetadataFileNotFound(binaries_metadata_path),
+        )),
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ExtractInfo {
     /// The destination directory.
@@ -252,8 +314,8 @@ impl<'a> ArchiveReader<'a> {
             // Validation: entry paths must be valid UTF-8.
             let path = entry_path(&entry)?;
 
-            // Validation: paths start with "target".
-            if !path.starts_with("target") {
+            // Validation: paths start with "target" or "source".
+            if !path.starts_with("target") && !path.starts_with(SOURCE_DIR_NAME) {
                 return Err(ArchiveReadError::NoTargetPrefix(path));
             }
 
@@ -6,28 +6,55 @@
 //! The main structure in this module is [`TestRunner`].
 
 use crate::{
-    config::{NextestProfile, RetryPolicy, TestGroup, TestSettings, TestThreads},
+    cargo_config::EnvironmentMap,
+    command_wrapper::CommandWrapper,
+    config::{NextestProfile, RetryFilter, RetryPolicy, TestGroup, TestSettings, TestThreads},
     double_spawn::DoubleSpawnInfo,
-    errors::{ConfigureHandleInheritanceError, TestRunnerBuildError},
-    list::{TestExecuteContext, TestInstance, TestList},
-    reporter::{CancelReason, FinalStatusLevel, StatusLevel, TestEvent, TestOutputDisplay},
+    env_file,
+    errors::{
+        ConfigureHandleInheritanceError, EnvInheritanceParseError,
+        ImmediateRetryConditionParseError, InvalidWorkingDirError, RunError, ShuffleSeedParseError,
+        StdinPassthroughWithParallelismError, TestRunnerBuildError,
+    },
+    filesystem_sandbox::ReadOnlySandbox,
+    helpers::PRELOAD_ENV_VAR,
+    libtest_output::{self, LibtestResult},
+    list::{RustTestSuite, TestExecuteContext, TestInstance, TestList},
+    network_sandbox,
+    partition::TimingData,
+    process_leak,
+    reporter::{
+        CancelReason, FinalStatusLevel, StatusLevel, TestEvent, TestOutputDisplay, TestOutputStream,
+    },
     signal::{JobControlEvent, ShutdownEvent, SignalEvent, SignalHandler, SignalHandlerKind},
+    state_snapshot::{StateMutation, StateSnapshot},
     target_runner::TargetRunner,
     time::{StopwatchEnd, StopwatchStart},
+    timestamped_reader::TimestampedReader,
 };
 use async_scoped::TokioScope;
 use bytes::Bytes;
+use camino::Utf8PathBuf;
 use future_queue::StreamExt;
 use futures::{future::try_join, prelude::*};
 use nextest_metadata::{FilterMatch, MismatchReason};
-use rand::{distributions::OpenClosed01, thread_rng, Rng};
+use once_cell::sync::OnceCell;
+use rand::{
+    distributions::OpenClosed01, rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng,
+};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeSet, HashMap},
     convert::Infallible,
+    ffi::OsString,
+    fmt,
+    hash::{Hash, Hasher},
     marker::PhantomData,
     num::NonZeroUsize,
     process::Stdio,
-    sync::atomic::{AtomicBool, Ordering},
-    time::{Duration, SystemTime},
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::{
     io::{AsyncRead, AsyncReadExt},
@@ -35,8 +62,67 @@ use tokio::{
     runtime::Runtime,
     sync::mpsc::UnboundedSender,
 };
+use tokio_util::sync::CancellationToken;
+use twox_hash::XxHash64;
 use uuid::Uuid;
 
+/// Returns the value of the preload environment variable for `libraries`, followed by the libraries
+/// in `existing`.
+fn preload_env_value(libraries: &[Utf8PathBuf], existing: Option<OsString>) -> OsString {
+    let libraries: Vec<_> = libraries.iter().map(|l| l.as_str()).collect();
+    let mut value = OsString::from(libraries.join(":"));
+    if let Some(existing) = existing.filter(|existing| !existing.is_empty()) {
+        value.push(":");
+        value.push(existing);
+    }
+    value
+}
+
+/// The maximum total length of the test names passed to one test binary process with
+/// `--test-isolation`. Windows limits command lines to 32,767 characters, so this leaves room for
+/// the binary path, target runner and other arguments.
+const MAX_BATCH_NAMES_LEN: usize = 16 * 1024;
+
+/// Splits `items` into consecutive chunks, each with a total length of at most `max_len` unless it
+/// consists of a single item.
+fn split_by_total_len<T>(items: &[T], max_len: usize, len: impl Fn(&T) -> usize) -> Vec<&[T]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut chunk_len = 0;
+    for (index, item) in items.iter().enumerate() {
+        let item_len = len(item);
+        if index > start && chunk_len + item_len > max_len {
+            chunks.push(&items[start..index]);
+            start = index;
+            chunk_len = 0;
+        }
+        chunk_len += item_len;
+    }
+    if start < items.len() {
+        chunks.push(&items[start..]);
+    }
+    chunks
+}
+
+/// Shuffles `items` deterministically based on `seed`.
+fn shuffle_with_seed<T>(items: &mut [T], seed: u64) {
+    items.shuffle(&mut StdRng::seed_from_u64(seed));
+}
+
+/// Returns the tests in `test_list`, shuffled with `shuffle_seed` if specified.
+///
+/// Shared by the runner and [`TestRunnerBuilder::run_order`].
+fn ordered_tests<'a>(
+    test_list: &'a TestList<'a>,
+    shuffle_seed: Option<u64>,
+) -> Vec<TestInstance<'a>> {
+    let mut tests: Vec<_> = test_list.iter_tests().collect();
+    if let Some(seed) = shuffle_seed {
+        shuffle_with_seed(&mut tests, seed);
+    }
+    tests
+}
+
 #[derive(Debug)]
 struct BackoffIter {
     policy: RetryPolicy,
@@ -106,13 +192,341 @@ impl Iterator for BackoffIter {
     }
 }
 
+/// The seed used to shuffle the order in which tests are run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShuffleSeed {
+    /// Pick a seed at random.
+    Random,
+
+    /// Use the given seed.
+    Fixed(u64),
+}
+
+impl ShuffleSeed {
+    /// Returns the seed to use, picking one at random if necessary.
+    pub fn resolve(self) -> u64 {
+        match self {
+            Self::Random => thread_rng().gen(),
+            Self::Fixed(seed) => seed,
+        }
+    }
+}
+
+impl FromStr for ShuffleSeed {
+    type Err = ShuffleSeedParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("random") {
+            return Ok(Self::Random);
+        }
+
+        match s.parse() {
+            Ok(seed) => Ok(Self::Fixed(seed)),
+            Err(_) => Err(ShuffleSeedParseError::new(s)),
+        }
+    }
+}
+
+impl fmt::Display for ShuffleSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Random => write!(f, "random"),
+            Self::Fixed(seed) => write!(f, "{seed}"),
+        }
+    }
+}
+
+/// Which environment variables test processes inherit from nextest's environment.
+///
+/// Variables that nextest sets itself, such as `NEXTEST_*`, `CARGO_*` and those from the `[env]`
+/// section of Cargo configuration, are always set. `PATH` (and `SystemRoot` on Windows) are always
+/// inherited as well, since many programs can't run without them.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum EnvInheritance {
+    /// Inherit all environment variables. This is the default.
+    #[default]
+    All,
+
+    /// Only inherit the given environment variables, if they're set.
+    Only(BTreeSet<String>),
+}
+
+/// Variables inherited even if they aren't listed in [`EnvInheritance::Only`].
+#[cfg(not(windows))]
+const MINIMAL_INHERITED_ENV: &[&str] = &["PATH"];
+/// Variables inherited even if they aren't listed in [`EnvInheritance::Only`].
+#[cfg(windows)]
+const MINIMAL_INHERITED_ENV: &[&str] = &["PATH", "SystemRoot"];
+
+impl EnvInheritance {
+    /// Inherits `name` in addition to the variables already inherited.
+    pub fn add(&mut self, name: impl Into<String>) {
+        if let Self::Only(names) = self {
+            names.insert(name.into());
+        }
+    }
+
+    fn apply(&self, cmd: &mut std::process::Command, cargo_env: &EnvironmentMap) {
+        let names = match self {
+            Self::All => return,
+            Self::Only(names) => names,
+        };
+
+        // env_clear also removes variables set on the command, so set them again afterwards.
+        let explicit: Vec<(OsString, Option<OsString>)> = cmd
+            .get_envs()
+            .map(|(name, value)| (name.to_owned(), value.map(ToOwned::to_owned)))
+            .collect();
+        cmd.env_clear();
+        // Some of the variables nextest sets are inherited rather than set explicitly: CARGO comes
+        // from the Cargo process that invoked nextest, and Cargo also sets variables from the
+        // [env] section of its configuration, in which case nextest doesn't set them again.
+        let always_inherited = std::iter::once("CARGO")
+            .chain(MINIMAL_INHERITED_ENV.iter().copied())
+            .chain(cargo_env.names());
+        for name in names.iter().map(String::as_str).chain(always_inherited) {
+            if let Some(value) = std::env::var_os(name) {
+                cmd.env(name, value);
+            }
+        }
+        for (name, value) in explicit {
+            match value {
+                Some(value) => cmd.env(name, value),
+                None => cmd.env_remove(name),
+            };
+        }
+    }
+}
+
+impl FromStr for EnvInheritance {
+    type Err = EnvInheritanceParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "none" => Ok(Self::Only(BTreeSet::new())),
+            _ => s
+                .split(',')
+                .map(|name| {
+                    if name.is_empty() || name.contains('=') {
+                        Err(EnvInheritanceParseError::new(name))
+                    } else {
+                        Ok(name.to_owned())
+                    }
+                })
+                .collect::<Result<_, _>>()
+                .map(Self::Only),
+        }
+    }
+}
+
+/// A condition under which a failed test is retried immediately, without waiting for the delay
+/// configured in its retry policy.
+///
+/// Retries triggered this way still count towards the retry limit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImmediateRetryCondition {
+    /// The test was aborted by this Unix signal.
+    Signal(i32),
+}
+
+impl ImmediateRetryCondition {
+    fn retry_reason(self, result: ExecutionResult) -> Option<RetryReason> {
+        match (self, result) {
+            #[cfg(unix)]
+            (
+                Self::Signal(expected),
+                ExecutionResult::Fail {
+                    abort_status: Some(AbortStatus::UnixSignal(signal)),
+                    ..
+                },
+            ) if signal == expected => Some(RetryReason::Signal(signal)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ImmediateRetryCondition {
+    type Err = ImmediateRetryConditionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let signal = match s.strip_prefix("signal:") {
+            Some(signal) => signal,
+            None => {
+                return Err(ImmediateRetryConditionParseError::new(
+                    s,
+                    "unknown condition type",
+                ))
+            }
+        };
+
+        cfg_if::cfg_if! {
+            if #[cfg(unix)] {
+                let parsed = match signal.parse::<i32>() {
+                    Ok(signal) => nix::sys::signal::Signal::try_from(signal).ok(),
+                    Err(_) if signal.starts_with("SIG") => signal.parse().ok(),
+                    Err(_) => format!("SIG{signal}").parse().ok(),
+                };
+                match parsed {
+                    Some(signal) => Ok(Self::Signal(signal as i32)),
+                    None => Err(ImmediateRetryConditionParseError::new(s, "unknown signal")),
+                }
+            } else {
+                let _ = signal;
+                Err(ImmediateRetryConditionParseError::new(
+                    s,
+                    "signals are only supported on Unix",
+                ))
+            }
+        }
+    }
+}
+
+/// Why a failed test is being retried.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RetryReason {
+    /// The test failed, and will be retried after the delay configured in its retry policy.
+    Failure,
+
+    /// The test was aborted by this Unix signal, which matches an [`ImmediateRetryCondition`].
+    /// It will be retried immediately.
+    Signal(i32),
+}
+
+/// How tests are isolated from each other.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TestIsolation {
+    /// Run each test in its own process. This is the default.
+    #[default]
+    Process,
+
+    /// Run all the selected tests in a binary in one process, on separate threads. Binaries are run
+    /// one at a time, each with as many threads as the run.
+    Thread,
+
+    /// Run all the selected tests in a binary in one process, one after another on a single
+    /// thread. Binaries are run one at a time.
+    None,
+}
+
+/// The CPU scheduling priority that test processes are run at.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TestProcessPriority {
+    /// Run tests at a lower priority than other processes, so they don't compete with interactive
+    /// programs. On Unix this is a niceness of 10, and on Windows the below-normal priority class.
+    Nice,
+
+    /// Run tests at the same priority as nextest. This is the default.
+    #[default]
+    Normal,
+
+    /// Run tests at a higher priority than other processes, which can starve them. On Unix this is
+    /// a niceness of -10, which usually requires elevated privileges: without them, tests are run
+    /// at normal priority. On Windows this is the above-normal priority class.
+    High,
+}
+
+/// Randomly marks passing tests as failed, to exercise failure paths while testing nextest itself.
+///
+/// Whether an attempt fails is determined by hashing the seed together with the binary ID, test
+/// name and attempt number, so the same seed always fails the same attempts, regardless of the order
+/// in which tests are run.
+#[derive(Clone, Copy, Debug)]
+pub struct SimulatedFailures {
+    rate: f64,
+    seed: u64,
+}
+
+impl SimulatedFailures {
+    /// Creates a new `SimulatedFailures`, where `rate` is the probability that a passing attempt is
+    /// marked as failed.
+    ///
+    /// Returns `None` if `rate` isn't between 0.0 and 1.0.
+    pub fn new(rate: f64, seed: u64) -> Option<Self> {
+        (0.0..=1.0).contains(&rate).then_some(Self { rate, seed })
+    }
+
+    fn should_fail(&self, binary_id: &str, test_name: &str, attempt: usize) -> bool {
+        let mut hasher = XxHash64::with_seed(self.seed);
+        (binary_id, test_name, attempt).hash(&mut hasher);
+        StdRng::seed_from_u64(hasher.finish()).gen_bool(self.rate)
+    }
+}
+
+/// The retries performed across all tests in a run, for `max_retries_global`.
+#[derive(Debug)]
+struct GlobalRetries {
+    max_retries: Option<usize>,
+    used: AtomicUsize,
+    cap_reported: AtomicBool,
+}
+
+impl GlobalRetries {
+    fn new(max_retries: Option<usize>) -> Self {
+        Self {
+            max_retries,
+            used: AtomicUsize::new(0),
+            cap_reported: AtomicBool::new(false),
+        }
+    }
+
+    /// Takes a retry from the global budget, returning false if it has been used up.
+    ///
+    /// The first time this returns false, a [`InternalTestEvent::GlobalRetryCapReached`] event is
+    /// sent.
+    fn try_take<'a>(&self, run_sender: &UnboundedSender<InternalTestEvent<'a>>) -> bool {
+        let max_retries = match self.max_retries {
+            Some(max_retries) => max_retries,
+            None => return true,
+        };
+        let taken = self
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                (used < max_retries).then_some(used + 1)
+            })
+            .is_ok();
+        if !taken && !self.cap_reported.swap(true, Ordering::AcqRel) {
+            let _ = run_sender.send(InternalTestEvent::GlobalRetryCapReached { max_retries });
+        }
+        taken
+    }
+}
+
 /// Test runner options.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct TestRunnerBuilder {
     no_capture: bool,
+    stream_output: bool,
+    stdin_passthrough: bool,
     retries: Option<RetryPolicy>,
     fail_fast: Option<bool>,
+    fail_on_stderr: Option<bool>,
     test_threads: Option<TestThreads>,
+    cancellation_token: Option<CancellationToken>,
+    detect_leaky_tests: Option<Vec<Utf8PathBuf>>,
+    shuffle_seed: Option<u64>,
+    bench: bool,
+    min_run_time: Option<Duration>,
+    failure_limit_per_binary: Option<NonZeroUsize>,
+    test_binary_timeout: Option<Duration>,
+    max_retries_global: Option<usize>,
+    timing_baseline: Option<(TimingData, f64)>,
+    timeout_grace_period: Option<Duration>,
+    max_test_time: Option<Duration>,
+    env_inheritance: EnvInheritance,
+    retry_immediately_on: Vec<ImmediateRetryCondition>,
+    retry_filter: Option<RetryFilter>,
+    test_thread_stack_size: Option<u64>,
+    runs_per_test: Option<usize>,
+    no_network_access: bool,
+    fail_on_leaked_processes: bool,
+    preload_libraries: Vec<Utf8PathBuf>,
+    read_only_exceptions: Option<Vec<Utf8PathBuf>>,
+    simulated_failures: Option<SimulatedFailures>,
+    process_priority: TestProcessPriority,
+    test_isolation: TestIsolation,
+    annotate_output_with_timestamps: bool,
+    test_wrapper: Option<CommandWrapper>,
 }
 
 impl TestRunnerBuilder {
@@ -124,24 +538,315 @@ impl TestRunnerBuilder {
         self
     }
 
+    /// Sets stream-output mode.
+    ///
+    /// In this mode, output is captured as usual, but each line a test writes to standard output or
+    /// standard error is also reported as soon as it's written, through
+    /// [`TestEvent::TestOutputLine`]. Unlike no-capture mode, tests can still be run in parallel.
+    pub fn set_stream_output(&mut self, stream_output: bool) -> &mut Self {
+        self.stream_output = stream_output;
+        self
+    }
+
+    /// Sets stdin passthrough mode.
+    ///
+    /// In this mode, tests inherit the stdin of the current process rather than reading from
+    /// `/dev/null`. Tests are always run serially, and [`Self::build`] returns an error if more
+    /// than one test thread was explicitly requested.
+    pub fn set_stdin_passthrough(&mut self, stdin_passthrough: bool) -> &mut Self {
+        self.stdin_passthrough = stdin_passthrough;
+        self
+    }
+
     /// Sets the number of retries for this test runner.
     pub fn set_retries(&mut self, retries: RetryPolicy) -> &mut Self {
         self.retries = Some(retries);
         self
     }
 
+    /// Runs each test exactly `runs` times in a row, regardless of whether it passes or fails.
+    ///
+    /// This is used to measure how flaky tests are. Attempts are run back-to-back without a delay,
+    /// and any retry policy is ignored. A test fails if any of its attempts fail, regardless of the
+    /// order they happen in.
+    pub fn set_runs_per_test(&mut self, runs: usize) -> &mut Self {
+        self.runs_per_test = Some(runs);
+        self
+    }
+
     /// Sets the fail-fast value for this test runner.
     pub fn set_fail_fast(&mut self, fail_fast: bool) -> &mut Self {
         self.fail_fast = Some(fail_fast);
         self
     }
 
+    /// Sets whether tests that pass but write to standard error are marked as failed.
+    ///
+    /// Overrides the `fail-on-stderr` setting in the profile and in per-test overrides. Has no
+    /// effect in no-capture mode, since standard error isn't captured.
+    pub fn set_fail_on_stderr(&mut self, fail_on_stderr: bool) -> &mut Self {
+        self.fail_on_stderr = Some(fail_on_stderr);
+        self
+    }
+
+    /// Sets how long to wait after sending SIGTERM to a timed-out test before sending SIGKILL.
+    ///
+    /// Overrides the `slow-timeout.grace-period` setting in the profile and in per-test overrides.
+    /// This is separate from the leak timeout, which applies to tests that have already exited.
+    pub fn set_timeout_grace_period(&mut self, grace_period: Duration) -> &mut Self {
+        self.timeout_grace_period = Some(grace_period);
+        self
+    }
+
+    /// Sets an absolute ceiling on how long each test attempt can run for.
+    ///
+    /// Tests that run for longer than this are killed immediately, without a grace period,
+    /// regardless of their slow-timeout configuration. This includes tests that are within the
+    /// grace period after being terminated for a slow timeout. With retries, the maximum time
+    /// applies to each attempt separately. A
+    /// [`TestEvent::TestExceededAbsoluteMaxTime`] event is sent for them, and they're marked as
+    /// timed out.
+    pub fn set_max_test_time(&mut self, max_test_time: Duration) -> &mut Self {
+        self.max_test_time = Some(max_test_time);
+        self
+    }
+
+    /// Sets which environment variables test processes inherit from nextest's environment.
+    pub fn set_env_inheritance(&mut self, env_inheritance: EnvInheritance) -> &mut Self {
+        self.env_inheritance = env_inheritance;
+        self
+    }
+
+    /// Sets conditions under which failed tests are retried immediately, without waiting for the
+    /// delay configured in their retry policy.
+    pub fn set_retry_immediately_on(
+        &mut self,
+        conditions: impl IntoIterator<Item = ImmediateRetryCondition>,
+    ) -> &mut Self {
+        self.retry_immediately_on = conditions.into_iter().collect();
+        self
+    }
+
+    /// Sets which failed tests are retried.
+    ///
+    /// Tests that don't match the filter aren't retried, regardless of the retry policy. Overrides
+    /// the `retry-only-exit-codes` setting in the profile.
+    pub fn set_retry_filter(&mut self, retry_filter: RetryFilter) -> &mut Self {
+        self.retry_filter = Some(retry_filter);
+        self
+    }
+
     /// Sets the number of tests to run simultaneously.
     pub fn set_test_threads(&mut self, test_threads: TestThreads) -> &mut Self {
         self.test_threads = Some(test_threads);
         self
     }
 
+    /// Sets a token that can be used to cancel the test run from another task.
+    ///
+    /// Cancelling the token is treated the same way as an interrupt signal (e.g. Ctrl-C): running
+    /// tests are terminated, and [`TestRunner::try_execute`] returns [`RunError::Cancelled`].
+    pub fn with_cancellation_token(&mut self, token: CancellationToken) -> &mut Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Enables detection of tests that mutate global state.
+    ///
+    /// The working directory of each test, along with `extra_paths`, is snapshotted before and
+    /// after the test runs. Any differences are reported through [`TestEvent::LeakyTestDetected`].
+    ///
+    /// Since the filesystem is shared between tests, changes made by concurrently running tests
+    /// may be attributed to the wrong test. For precise results, run tests serially.
+    pub fn set_detect_leaky_tests(&mut self, extra_paths: Vec<Utf8PathBuf>) -> &mut Self {
+        self.detect_leaky_tests = Some(extra_paths);
+        self
+    }
+
+    /// Runs tests in a random order determined by `seed`.
+    ///
+    /// The same seed always produces the same order for a given test list.
+    pub fn set_shuffle_seed(&mut self, seed: u64) -> &mut Self {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// Sets benchmark mode.
+    ///
+    /// In this mode, `--bench` is passed in to test binaries, so that benchmarks are run as
+    /// benchmarks rather than as tests.
+    pub fn set_bench(&mut self, bench: bool) -> &mut Self {
+        self.bench = bench;
+        self
+    }
+
+    /// Sets the time below which passing tests are reported as suspiciously fast.
+    ///
+    /// Such tests are reported through [`TestEvent::SuspiciouslyFastTest`]. Overrides the
+    /// `min-run-time` setting in the profile.
+    pub fn set_min_run_time(&mut self, min_run_time: Duration) -> &mut Self {
+        self.min_run_time = Some(min_run_time);
+        self
+    }
+
+    /// Sets the number of failures after which no more tests are run from a test binary.
+    ///
+    /// Tests from other binaries continue to run. Once the limit is reached, a
+    /// [`TestEvent::BinaryFailureLimitReached`] event is sent. Overrides the
+    /// `failure-limit-per-binary` setting in the profile.
+    pub fn set_failure_limit_per_binary(&mut self, limit: NonZeroUsize) -> &mut Self {
+        self.failure_limit_per_binary = Some(limit);
+        self
+    }
+
+    /// Sets the total time after which no more tests are started from a test binary.
+    ///
+    /// The time is measured from when the first test in the binary starts. Once the timeout is
+    /// exceeded, a [`TestEvent::BinaryTimedOut`] event is sent, tests from the binary that are
+    /// still running are terminated, and a [`TestEvent::TestSkipped`] event is sent for each test
+    /// from the binary that hasn't started yet. Tests from other binaries continue to run.
+    /// Overrides the `test-binary-timeout` setting in the profile.
+    ///
+    /// This only applies to tests run in their own process: see [`TestIsolation`].
+    pub fn set_test_binary_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.test_binary_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the total number of retries that can be performed across all tests in the run.
+    ///
+    /// Once this many retries have been performed, tests that fail aren't retried, and a
+    /// [`TestEvent::GlobalRetryCapReached`] event is sent. This doesn't limit the attempts made with
+    /// [`Self::set_runs_per_test`].
+    pub fn set_max_retries_global(&mut self, max_retries: usize) -> &mut Self {
+        self.max_retries_global = Some(max_retries);
+        self
+    }
+
+    /// Sets baseline timings to compare passing tests against.
+    ///
+    /// Passing tests that take more than `threshold_pct` percent longer than their time in
+    /// `baseline` are reported through [`TestEvent::TimingRegression`]. Tests that aren't in the
+    /// baseline are ignored.
+    pub fn set_timing_baseline(&mut self, baseline: TimingData, threshold_pct: f64) -> &mut Self {
+        self.timing_baseline = Some((baseline, threshold_pct));
+        self
+    }
+
+    /// Sets the stack size, in bytes, for threads that run tests.
+    ///
+    /// This is passed to test processes through the `RUST_MIN_STACK` environment variable.
+    /// Overrides the `test-thread-stack-size` setting in the profile.
+    pub fn set_test_thread_stack_size(&mut self, stack_size: u64) -> &mut Self {
+        self.test_thread_stack_size = Some(stack_size);
+        self
+    }
+
+    /// Sets whether tests are allowed to access the network. Network access is allowed by default.
+    ///
+    /// If set to false, tests (and any processes they spawn) are killed if they call `connect`,
+    /// `bind` or `sendto`, and are reported through [`TestEvent::NetworkAccessViolation`]. This
+    /// is only supported on Linux on x86_64 and aarch64: on other platforms, [`Self::build`]
+    /// returns an error.
+    pub fn set_network_access(&mut self, network_access: bool) -> &mut Self {
+        self.no_network_access = !network_access;
+        self
+    }
+
+    /// Sets whether tests that leave processes running after they exit are marked as failed.
+    ///
+    /// If set to true, processes that a test spawned and didn't wait for are looked for once the
+    /// test process exits. Tests that leave any behind are reported through
+    /// [`TestEvent::ProcessLeak`], and passing tests are marked as failed with
+    /// [`ExecutionResult::ProcessLeak`]. This only applies to tests run in their own process, and
+    /// is only supported on Linux and Windows: on other platforms, [`Self::build`] returns an
+    /// error.
+    pub fn set_fail_on_leaked_processes(&mut self, fail_on_leaked_processes: bool) -> &mut Self {
+        self.fail_on_leaked_processes = fail_on_leaked_processes;
+        self
+    }
+
+    /// Sets shared libraries to load into test processes before any others, e.g. sanitizer
+    /// runtimes or custom allocators.
+    ///
+    /// The libraries are passed to test processes through `LD_PRELOAD`, or `DYLD_INSERT_LIBRARIES`
+    /// on macOS, ahead of any libraries the variable already lists. Libraries with a `/` in their
+    /// path are resolved to absolute paths, and [`Self::build`] returns an error if any of them
+    /// don't exist; bare names are left for the dynamic linker to look up. Preloading isn't
+    /// supported on Windows: there, a warning is logged and tests are run without the libraries.
+    ///
+    /// The variable is set on the process started for each test. With a target runner or a test
+    /// wrapper, that's the runner or wrapper, which the libraries are loaded into as well.
+    pub fn set_preload_libraries(
+        &mut self,
+        libraries: impl IntoIterator<Item = Utf8PathBuf>,
+    ) -> &mut Self {
+        self.preload_libraries = libraries.into_iter().collect();
+        self
+    }
+
+    /// Makes the workspace read-only for tests, apart from the directories in `exceptions`.
+    ///
+    /// Writes to the workspace fail with `EROFS`. This is only supported on Linux, through user and
+    /// mount namespaces. If the sandbox can't be set up, for example because unprivileged user
+    /// namespaces are disabled, a warning is logged and tests are run with write access.
+    pub fn set_read_only_workspace(&mut self, exceptions: Vec<Utf8PathBuf>) -> &mut Self {
+        self.read_only_exceptions = Some(exceptions);
+        self
+    }
+
+    /// Randomly marks passing tests as failed, reporting them as
+    /// [`ExecutionResult::SimulatedFailure`].
+    ///
+    /// This is meant for testing nextest itself.
+    pub fn set_simulated_failures(&mut self, simulated_failures: SimulatedFailures) -> &mut Self {
+        self.simulated_failures = Some(simulated_failures);
+        self
+    }
+
+    /// Sets the CPU scheduling priority that test processes are run at.
+    pub fn set_process_priority(&mut self, process_priority: TestProcessPriority) -> &mut Self {
+        self.process_priority = process_priority;
+        self
+    }
+
+    /// Sets how tests are isolated from each other.
+    ///
+    /// With [`TestIsolation::Thread`] and [`TestIsolation::None`], retries, timeouts, failure limits,
+    /// timing baselines and per-test settings other than how output is displayed don't apply, and
+    /// fail-fast only takes effect between binaries. A warning is logged for settings that are
+    /// ignored.
+    pub fn set_test_isolation(&mut self, test_isolation: TestIsolation) -> &mut Self {
+        self.test_isolation = test_isolation;
+        self
+    }
+
+    /// Sets whether to prefix each line of captured output with the time elapsed since the test
+    /// process started, like `[+12.345s] `.
+    ///
+    /// This only applies to tests run in their own process, since the output of tests that share a
+    /// process is parsed to split it up.
+    pub fn set_annotate_output_with_timestamps(&mut self, annotate: bool) -> &mut Self {
+        self.annotate_output_with_timestamps = annotate;
+        self
+    }
+
+    /// Sets a command to run each test process under, such as `taskset -c 0-3`.
+    ///
+    /// The wrapper is passed the test binary and its arguments, preceded by the target runner if
+    /// there is one.
+    pub fn set_test_wrapper(&mut self, wrapper: CommandWrapper) -> &mut Self {
+        self.test_wrapper = Some(wrapper);
+        self
+    }
+
+    /// Returns the tests in `test_list`, in the order a runner built with these options starts them.
+    ///
+    /// Tests that don't match the filter are included as well.
+    pub fn run_order<'a>(&self, test_list: &'a TestList) -> Vec<TestInstance<'a>> {
+        ordered_tests(test_list, self.shuffle_seed)
+    }
+
     /// Creates a new test runner.
     pub fn build<'a>(
         self,
@@ -151,7 +856,75 @@ impl TestRunnerBuilder {
         double_spawn: DoubleSpawnInfo,
         target_runner: TargetRunner,
     ) -> Result<TestRunner<'a>, TestRunnerBuildError> {
-        let test_threads = match self.no_capture {
+        if self.no_network_access && !network_sandbox::IS_SUPPORTED {
+            return Err(TestRunnerBuildError::NetworkSandboxUnsupported);
+        }
+        if self.fail_on_leaked_processes && !process_leak::IS_SUPPORTED {
+            return Err(TestRunnerBuildError::ProcessLeakDetectionUnsupported);
+        }
+        let preload_libraries = if self.preload_libraries.is_empty() {
+            Vec::new()
+        } else if PRELOAD_ENV_VAR.is_none() {
+            log::warn!(
+                "preloading libraries into tests isn't supported on this platform, \
+                 running tests without them"
+            );
+            Vec::new()
+        } else {
+            self.preload_libraries
+                .iter()
+                .map(|library| {
+                    if !library.as_str().contains('/') {
+                        // A bare name like `libjemalloc.so` is looked up by the dynamic linker.
+                        return Ok(library.clone());
+                    }
+                    // Test processes can run in a different working directory, so use absolute
+                    // paths.
+                    match library.canonicalize_utf8() {
+                        Ok(path) if path.is_file() => Ok(path),
+                        _ => Err(TestRunnerBuildError::PreloadLibraryNotFound(
+                            library.clone(),
+                        )),
+                    }
+                })
+                .collect::<Result<_, _>>()?
+        };
+        let read_only_sandbox = match &self.read_only_exceptions {
+            Some(exceptions) => {
+                if let Some(exception) = exceptions.iter().find(|exception| !exception.is_dir()) {
+                    return Err(TestRunnerBuildError::FilesystemAccessExceptionNotFound(
+                        exception.clone(),
+                    ));
+                }
+                match ReadOnlySandbox::new(profile.workspace_root(), exceptions) {
+                    Ok(sandbox) => Some(sandbox),
+                    Err(error) => {
+                        log::warn!(
+                            "unable to make the workspace read-only for tests ({error}), \
+                             running tests with write access"
+                        );
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        if self.stdin_passthrough {
+            if self.fail_on_leaked_processes && cfg!(unix) {
+                log::warn!(
+                    "leaked processes can't be detected with stdin passthrough, \
+                     since tests aren't run in their own process group"
+                );
+            }
+            if let Some(test_threads) = self.test_threads {
+                let test_threads = test_threads.compute();
+                if test_threads > 1 {
+                    return Err(StdinPassthroughWithParallelismError::new(test_threads).into());
+                }
+            }
+        }
+
+        let test_threads = match self.no_capture || self.stdin_passthrough {
             true => 1,
             false => self
                 .test_threads
@@ -159,20 +932,69 @@ impl TestRunnerBuilder {
                 .compute(),
         };
         let fail_fast = self.fail_fast.unwrap_or_else(|| profile.fail_fast());
+        let min_run_time = self.min_run_time.or_else(|| profile.min_run_time());
+        let failure_limit_per_binary = self
+            .failure_limit_per_binary
+            .or_else(|| profile.failure_limit_per_binary());
+        let retry_filter = self.retry_filter.unwrap_or_else(|| profile.retry_filter());
+        let test_binary_timeout = self
+            .test_binary_timeout
+            .or_else(|| profile.test_binary_timeout());
+        let test_thread_stack_size = self
+            .test_thread_stack_size
+            .or_else(|| profile.test_thread_stack_size());
+        if self.test_isolation != TestIsolation::Process {
+            warn_unsupported_with_shared_process(
+                test_list,
+                &profile,
+                failure_limit_per_binary.is_some(),
+                self.timing_baseline.is_some(),
+            );
+        }
 
         let runtime = Runtime::new().map_err(TestRunnerBuildError::TokioRuntimeCreate)?;
         let _guard = runtime.enter();
 
         // This must be called from within the guard.
-        let handler = handler_kind.build()?;
+        let mut handler = handler_kind.build()?;
+        if let Some(token) = self.cancellation_token {
+            handler.set_cancellation_token(token);
+        }
 
         Ok(TestRunner {
             inner: TestRunnerInner {
                 no_capture: self.no_capture,
+                stream_output: self.stream_output,
+                stdin_passthrough: self.stdin_passthrough,
+                detect_leaky_tests: self.detect_leaky_tests,
+                shuffle_seed: self.shuffle_seed,
+                bench: self.bench,
+                min_run_time,
+                failure_limit_per_binary,
+                test_binary_timeout,
+                max_retries_global: self.max_retries_global,
+                timing_baseline: self.timing_baseline,
+                force_timeout_grace_period: self.timeout_grace_period,
+                max_test_time: self.max_test_time,
+                env_inheritance: self.env_inheritance,
+                retry_immediately_on: self.retry_immediately_on,
+                retry_filter,
+                test_thread_stack_size,
+                runs_per_test: self.runs_per_test,
+                no_network_access: self.no_network_access,
+                fail_on_leaked_processes: self.fail_on_leaked_processes,
+                preload_libraries,
+                read_only_sandbox,
+                simulated_failures: self.simulated_failures,
+                process_priority: self.process_priority,
+                test_isolation: self.test_isolation,
+                annotate_output_with_timestamps: self.annotate_output_with_timestamps,
+                test_wrapper: self.test_wrapper,
                 profile,
                 test_threads,
                 force_retries: self.retries,
                 fail_fast,
+                force_fail_on_stderr: self.fail_on_stderr,
                 test_list,
                 double_spawn,
                 target_runner,
@@ -194,25 +1016,34 @@ pub struct TestRunner<'a> {
 }
 
 impl<'a> TestRunner<'a> {
-    /// Executes the listed tests, each one in its own process.
+    /// Executes the listed tests, by default each one in its own process.
     ///
     /// The callback is called with the results of each test.
+    ///
+    /// If the run is cancelled through a cancellation token, the statistics for the tests that
+    /// finished are returned.
     pub fn execute<F>(self, mut callback: F) -> RunStats
     where
         F: FnMut(TestEvent<'a>) + Send,
     {
-        self.try_execute::<Infallible, _>(|test_event| {
+        let res = self.try_execute::<Infallible, _>(|test_event| {
             callback(test_event);
             Ok(())
-        })
-        .expect("Err branch is infallible")
+        });
+        match res {
+            Ok(run_stats) | Err(RunError::Cancelled { run_stats }) => run_stats,
+            Err(RunError::Callback(err)) => match err {},
+        }
     }
 
-    /// Executes the listed tests, each one in its own process.
+    /// Executes the listed tests, by default each one in its own process.
     ///
     /// Accepts a callback that is called with the results of each test. If the callback returns an
     /// error, the test run terminates and the callback is no longer called.
-    pub fn try_execute<E, F>(mut self, callback: F) -> Result<RunStats, E>
+    ///
+    /// If a cancellation token was set with [`TestRunnerBuilder::with_cancellation_token`] and
+    /// it is cancelled, returns [`RunError::Cancelled`].
+    pub fn try_execute<E, F>(mut self, callback: F) -> Result<RunStats, RunError<E>>
     where
         F: FnMut(TestEvent<'a>) -> Result<(), E> + Send,
         E: Send,
@@ -222,18 +1053,52 @@ impl<'a> TestRunner<'a> {
         // stuck indefinitely if it's dropped the normal way. Shut it down aggressively, being OK
         // with leaked resources.
         self.inner.runtime.shutdown_background();
-        run_stats
+        let run_stats = run_stats.map_err(RunError::Callback)?;
+        if self.handler.is_cancelled() {
+            Err(RunError::Cancelled { run_stats })
+        } else {
+            Ok(run_stats)
+        }
     }
 }
 
 #[derive(Debug)]
 struct TestRunnerInner<'a> {
     no_capture: bool,
+    stream_output: bool,
+    stdin_passthrough: bool,
+    detect_leaky_tests: Option<Vec<Utf8PathBuf>>,
+    shuffle_seed: Option<u64>,
+    bench: bool,
+    min_run_time: Option<Duration>,
+    failure_limit_per_binary: Option<NonZeroUsize>,
+    test_binary_timeout: Option<Duration>,
+    max_retries_global: Option<usize>,
+    timing_baseline: Option<(TimingData, f64)>,
+    // This is Some if the user specifies --timeout-grace-period over the command-line.
+    force_timeout_grace_period: Option<Duration>,
+    max_test_time: Option<Duration>,
+    env_inheritance: EnvInheritance,
+    retry_immediately_on: Vec<ImmediateRetryCondition>,
+    retry_filter: RetryFilter,
+    test_thread_stack_size: Option<u64>,
+    runs_per_test: Option<usize>,
+    no_network_access: bool,
+    fail_on_leaked_processes: bool,
+    preload_libraries: Vec<Utf8PathBuf>,
+    read_only_sandbox: Option<ReadOnlySandbox>,
+    simulated_failures: Option<SimulatedFailures>,
+    process_priority: TestProcessPriority,
+    test_isolation: TestIsolation,
+    annotate_output_with_timestamps: bool,
+    test_wrapper: Option<CommandWrapper>,
     profile: NextestProfile<'a>,
     test_threads: usize,
     // This is Some if the user specifies a retry policy over the command-line.
     force_retries: Option<RetryPolicy>,
     fail_fast: bool,
+    // This is Some if the user specifies --fail-on-stderr over the command-line.
+    force_fail_on_stderr: Option<bool>,
     test_list: &'a TestList<'a>,
     double_spawn: DoubleSpawnInfo,
     target_runner: TargetRunner,
@@ -259,6 +1124,26 @@ impl<'a> TestRunnerInner<'a> {
         let canceled = AtomicBool::new(false);
         let canceled_ref = &canceled;
 
+        // The number of tests that have failed in each binary, for failure_limit_per_binary.
+        let binary_failures: HashMap<_, _> = self
+            .test_list
+            .iter()
+            .map(|suite| (&suite.binary_id, AtomicUsize::new(0)))
+            .collect();
+        let binary_failures_ref = &binary_failures;
+
+        // When the first test in each binary started, and whether the binary's timeout has been
+        // reported, for test_binary_timeout.
+        let binary_start_times: HashMap<_, _> = self
+            .test_list
+            .iter()
+            .map(|suite| (&suite.binary_id, (OnceCell::new(), AtomicBool::new(false))))
+            .collect();
+        let binary_start_times_ref = &binary_start_times;
+
+        let global_retries = GlobalRetries::new(self.max_retries_global);
+        let global_retries_ref = &global_retries;
+
         let mut ctx = CallbackContext::new(
             callback,
             self.run_id,
@@ -288,10 +1173,41 @@ impl<'a> TestRunnerInner<'a> {
             tokio::sync::broadcast::channel::<SignalForwardEvent>(32);
         let forward_sender_ref = &forward_sender;
 
+        // With thread or no isolation, each binary's tests are run as a batch in one process.
+        let (process_tests, batches) = match self.test_isolation {
+            TestIsolation::Process => (self.tests_in_run_order(), Vec::new()),
+            TestIsolation::Thread | TestIsolation::None => {
+                (Vec::new(), self.batches_in_run_order())
+            }
+        };
+
         TokioScope::scope_and_block(move |scope| {
             let (run_sender, mut run_receiver) = tokio::sync::mpsc::unbounded_channel();
             let (cancellation_sender, _cancellation_receiver) = tokio::sync::broadcast::channel(1);
             {
+                let batch_run_sender = run_sender.clone();
+                let batch_fut = async move {
+                    let mut forward_receiver = forward_sender_ref.subscribe();
+                    let test_threads = match self.test_isolation {
+                        TestIsolation::None => 1,
+                        _ => self.test_threads,
+                    };
+                    for batch in batches {
+                        if canceled_ref.load(Ordering::Acquire) {
+                            break;
+                        }
+                        self.run_batch(
+                            batch,
+                            test_threads,
+                            &batch_run_sender,
+                            &mut forward_receiver,
+                        )
+                        .await;
+                        drain_forward_receiver(&mut forward_receiver).await;
+                    }
+                };
+                scope.spawn_cancellable(batch_fut, || ());
+
                 let cancellation_sender = cancellation_sender.clone();
 
                 // groups is going to be passed to future_queue_grouped.
@@ -301,7 +1217,7 @@ impl<'a> TestRunnerInner<'a> {
                     .iter()
                     .map(|(group_name, config)| (group_name, config.max_threads.compute()));
 
-                let run_fut = futures::stream::iter(self.test_list.iter_tests())
+                let run_fut = futures::stream::iter(process_tests)
                     .map(move |test_instance| {
                         let this_run_sender = run_sender.clone();
                         let mut cancellation_receiver = cancellation_sender.subscribe();
@@ -328,7 +1244,11 @@ impl<'a> TestRunnerInner<'a> {
 
                             let retry_policy =
                                 self.force_retries.unwrap_or_else(|| settings.retries());
-                            let total_attempts = retry_policy.count() + 1;
+                            // With runs_per_test, every attempt is run even if earlier ones
+                            // pass.
+                            let total_attempts = self
+                                .runs_per_test
+                                .unwrap_or_else(|| retry_policy.count() + 1);
                             let mut backoff_iter = BackoffIter::new(retry_policy);
 
                             if let FilterMatch::Mismatch { reason } =
@@ -342,20 +1262,80 @@ impl<'a> TestRunnerInner<'a> {
                                 return;
                             }
 
+                            let failure_count = binary_failures_ref
+                                .get(&test_instance.suite_info.binary_id)
+                                .expect("every binary in the test list has a failure count");
+                            if let Some(limit) = self.failure_limit_per_binary {
+                                if failure_count.load(Ordering::Acquire) >= limit.get() {
+                                    // This binary has reached its failure limit, so the test
+                                    // isn't run.
+                                    let _ = this_run_sender.send(InternalTestEvent::NotRun {
+                                        test_instance,
+                                        reason: MismatchReason::BinaryFailureLimit,
+                                    });
+                                    return;
+                                }
+                            }
+
+                            // The time by which this binary's tests must finish, for
+                            // test_binary_timeout.
+                            let binary_deadline = self.test_binary_timeout.map(|timeout| {
+                                let (start_time, _) = binary_start_times_ref
+                                    .get(&test_instance.suite_info.binary_id)
+                                    .expect("every binary in the test list has a start time");
+                                *start_time.get_or_init(Instant::now) + timeout
+                            });
+                            // Sends BinaryTimedOut if the binary's timeout has passed, the first
+                            // time that's noticed. Returns true if the timeout has passed.
+                            let check_binary_timeout = || {
+                                let timeout = match (self.test_binary_timeout, binary_deadline) {
+                                    (Some(timeout), Some(deadline))
+                                        if Instant::now() >= deadline =>
+                                    {
+                                        timeout
+                                    }
+                                    _ => return false,
+                                };
+                                let (_, timed_out) = binary_start_times_ref
+                                    .get(&test_instance.suite_info.binary_id)
+                                    .expect("every binary in the test list has a start time");
+                                if !timed_out.swap(true, Ordering::AcqRel) {
+                                    let _ =
+                                        this_run_sender.send(InternalTestEvent::BinaryTimedOut {
+                                            suite_info: test_instance.suite_info,
+                                            timeout,
+                                        });
+                                }
+                                true
+                            };
+                            if check_binary_timeout() {
+                                let _ = this_run_sender.send(InternalTestEvent::NotRun {
+                                    test_instance,
+                                    reason: MismatchReason::BinaryTimeout,
+                                });
+                                return;
+                            }
+
                             // Failure to send means the receiver was dropped.
                             let _ =
                                 this_run_sender.send(InternalTestEvent::Started { test_instance });
 
+                            let snapshot_before = self.snapshot_state(test_instance, &settings);
+
                             let mut run_statuses = vec![];
                             let mut delay = Duration::ZERO;
+                            let mut cancel_reason = None;
                             loop {
                                 let retry_data = RetryData {
                                     attempt: run_statuses.len() + 1,
                                     total_attempts,
                                 };
 
-                                if canceled_ref.load(Ordering::Acquire) {
-                                    // The test run has been canceled. Don't run any further tests.
+                                // The test run has been canceled: don't retry this test. (The
+                                // first attempt is always run, since the test has already been
+                                // reported as started. If the run was canceled by a signal,
+                                // the attempt is terminated and the test is cancelled.)
+                                if retry_data.attempt > 1 && canceled_ref.load(Ordering::Acquire) {
                                     break;
                                 }
 
@@ -374,30 +1354,56 @@ impl<'a> TestRunnerInner<'a> {
                                         &this_run_sender,
                                         &mut this_forward_receiver,
                                         delay,
+                                        binary_deadline,
                                     )
-                                    .await
-                                    .into_external(retry_data);
+                                    .await;
+                                cancel_reason = run_status.cancel_reason;
+                                let exit_code = run_status.exit_code;
+                                let run_status = run_status.into_external(retry_data);
 
-                                if run_status.result.is_success() {
+                                if cancel_reason.is_some() {
+                                    // The test was terminated because the run was canceled.
+                                    run_statuses.push(run_status);
+                                    break;
+                                } else if check_binary_timeout() {
+                                    // The binary's timeout passed while this test was running, so
+                                    // it was terminated if it was still running. Don't retry it.
+                                    run_statuses.push(run_status);
+                                    break;
+                                } else if run_status.result.is_success()
+                                    && self.runs_per_test.is_none()
+                                {
                                     // The test succeeded.
                                     run_statuses.push(run_status);
                                     break;
                                 } else if retry_data.attempt < retry_data.total_attempts
                                     && !canceled_ref.load(Ordering::Acquire)
+                                    && (self.runs_per_test.is_some()
+                                        || (self.retry_filter.matches(exit_code)
+                                            && global_retries_ref.try_take(&this_run_sender)))
                                 {
                                     // Retry this test: send a retry event, then retry the loop.
-                                    delay = backoff_iter
-                                        .next()
-                                        .expect("backoff delay must be non-empty");
-
-                                    let _ = this_run_sender.send(
-                                        InternalTestEvent::AttemptFailedWillRetry {
-                                            test_instance,
-                                            failure_output: settings.failure_output(),
-                                            run_status: run_status.clone(),
-                                            delay_before_next_attempt: delay,
-                                        },
-                                    );
+                                    let retry_reason = self.retry_reason(run_status.result);
+                                    if self.runs_per_test.is_none() {
+                                        delay = match retry_reason {
+                                            RetryReason::Failure => backoff_iter
+                                                .next()
+                                                .expect("backoff delay must be non-empty"),
+                                            RetryReason::Signal(_) => Duration::ZERO,
+                                        };
+                                    }
+
+                                    if !run_status.result.is_success() {
+                                        let _ = this_run_sender.send(
+                                            InternalTestEvent::AttemptFailedWillRetry {
+                                                test_instance,
+                                                failure_output: settings.failure_output(),
+                                                run_status: run_status.clone(),
+                                                delay_before_next_attempt: delay,
+                                                retry_reason,
+                                            },
+                                        );
+                                    }
                                     run_statuses.push(run_status);
 
                                     tokio::select! {
@@ -416,38 +1422,110 @@ impl<'a> TestRunnerInner<'a> {
                                 }
                             }
 
+                            if let Some(min_run_time) = self.min_run_time {
+                                let last_status = run_statuses.last().expect("at least one status");
+                                if last_status.result.is_success()
+                                    && last_status.time_taken < min_run_time
+                                {
+                                    let _ = this_run_sender.send(
+                                        InternalTestEvent::SuspiciouslyFastTest {
+                                            test_instance,
+                                            time_taken: last_status.time_taken,
+                                            min_run_time,
+                                        },
+                                    );
+                                }
+                            }
+
+                            if let Some((baseline, threshold_pct)) = &self.timing_baseline {
+                                let last_status = run_statuses.last().expect("at least one status");
+                                let baseline_time = baseline
+                                    .get(&test_instance.suite_info.binary_id, test_instance.name);
+                                if let (true, Some((baseline, regression_pct))) = (
+                                    last_status.result.is_success(),
+                                    timing_regression(
+                                        baseline_time,
+                                        last_status.time_taken,
+                                        *threshold_pct,
+                                    ),
+                                ) {
+                                    let _ =
+                                        this_run_sender.send(InternalTestEvent::TimingRegression {
+                                            test_instance,
+                                            baseline,
+                                            current: last_status.time_taken,
+                                            regression_pct,
+                                        });
+                                }
+                            }
+
+                            if self.no_network_access
+                                && run_statuses
+                                    .iter()
+                                    .any(|status| network_sandbox::is_violation(status.result))
+                            {
+                                let _ = this_run_sender.send(
+                                    InternalTestEvent::NetworkAccessViolation { test_instance },
+                                );
+                            }
+
+                            if let Some(snapshot_before) = snapshot_before {
+                                let snapshot_after = self
+                                    .snapshot_state(test_instance, &settings)
+                                    .expect("leaky test detection is enabled");
+                                let mutations = snapshot_before.diff(&snapshot_after);
+                                if !mutations.is_empty() {
+                                    let _ = this_run_sender.send(
+                                        InternalTestEvent::LeakyTestDetected {
+                                            test_instance,
+                                            mutations,
+                                        },
+                                    );
+                                }
+                            }
+
+                            let run_statuses =
+                                ExecutionStatuses::new(run_statuses, self.runs_per_test.is_some());
+                            if let Some(reason) = cancel_reason {
+                                let _ = this_run_sender.send(InternalTestEvent::Cancelled {
+                                    test_instance,
+                                    reason,
+                                    run_statuses,
+                                });
+                                return;
+                            }
+
                             // At this point, either:
                             // * the test has succeeded, or
                             // * the test has failed and we've run out of retries.
                             // In either case, the test is finished.
+                            let failed = !run_statuses.last_status().result.is_success();
                             let _ = this_run_sender.send(InternalTestEvent::Finished {
                                 test_instance,
                                 success_output: settings.success_output(),
                                 failure_output: settings.failure_output(),
                                 junit_store_success_output: settings.junit_store_success_output(),
                                 junit_store_failure_output: settings.junit_store_failure_output(),
-                                run_statuses: ExecutionStatuses::new(run_statuses),
+                                run_statuses,
                             });
 
-                            // Drain the forward receiver of any messages, including those that are
-                            // related to SIGTSTP.
-                            loop {
-                                let message = this_forward_receiver.try_recv();
-                                match message {
-                                    #[cfg(unix)]
-                                    Ok(SignalForwardEvent::Stop(sender)) => {
-                                        // The receiver being dead isn't really important.
-                                        let _ = sender.send(()).await;
-                                    }
-                                    Err(
-                                        tokio::sync::broadcast::error::TryRecvError::Empty
-                                        | tokio::sync::broadcast::error::TryRecvError::Closed,
-                                    ) => {
-                                        break;
+                            if failed {
+                                let failures = failure_count.fetch_add(1, Ordering::AcqRel) + 1;
+                                if let Some(failure_limit) = self.failure_limit_per_binary {
+                                    // Only report this once, for the failure that reached the
+                                    // limit.
+                                    if failures == failure_limit.get() {
+                                        let _ = this_run_sender.send(
+                                            InternalTestEvent::BinaryFailureLimitReached {
+                                                suite_info: test_instance.suite_info,
+                                                failure_limit,
+                                            },
+                                        );
                                     }
-                                    _ => {}
                                 }
                             }
+
+                            drain_forward_receiver(&mut this_forward_receiver).await;
                         };
                         (threads_required, test_group, fut)
                     })
@@ -601,7 +1679,54 @@ impl<'a> TestRunnerInner<'a> {
     // Helper methods
     // ---
 
+    /// Returns why a failed attempt with this result is being retried.
+    fn retry_reason(&self, result: ExecutionResult) -> RetryReason {
+        self.retry_immediately_on
+            .iter()
+            .find_map(|condition| condition.retry_reason(result))
+            .unwrap_or(RetryReason::Failure)
+    }
+
+    /// Returns the tests in the order they should be run, shuffling them if a seed was set.
+    fn tests_in_run_order(&self) -> Vec<TestInstance<'a>> {
+        ordered_tests(self.test_list, self.shuffle_seed)
+    }
+
+    /// Returns the tests grouped by binary, for running each binary's tests in one process.
+    /// Binaries are in the order their first test would be run in.
+    fn batches_in_run_order(&self) -> Vec<Vec<TestInstance<'a>>> {
+        let mut batches: Vec<Vec<TestInstance<'a>>> = Vec::new();
+        let mut batch_indexes = HashMap::new();
+        for test_instance in self.tests_in_run_order() {
+            let index = *batch_indexes
+                .entry(&test_instance.suite_info.binary_id)
+                .or_insert_with(|| {
+                    batches.push(Vec::new());
+                    batches.len() - 1
+                });
+            batches[index].push(test_instance);
+        }
+        batches
+    }
+
+    /// Snapshots global state for leaky test detection, if enabled.
+    fn snapshot_state(
+        &self,
+        test_instance: TestInstance<'a>,
+        settings: &TestSettings,
+    ) -> Option<StateSnapshot> {
+        self.detect_leaky_tests.as_ref().map(|extra_paths| {
+            let working_dir = settings
+                .working_dir()
+                .unwrap_or(&test_instance.suite_info.cwd);
+            StateSnapshot::take(
+                std::iter::once(working_dir).chain(extra_paths.iter().map(|path| path.as_path())),
+            )
+        })
+    }
+
     /// Run an individual test in its own process.
+    #[allow(clippy::too_many_arguments)]
     async fn run_test(
         &self,
         test: TestInstance<'a>,
@@ -610,18 +1735,53 @@ impl<'a> TestRunnerInner<'a> {
         run_sender: &UnboundedSender<InternalTestEvent<'a>>,
         forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
         delay_before_start: Duration,
+        binary_deadline: Option<Instant>,
     ) -> InternalExecuteStatus {
         let mut stopwatch = crate::time::stopwatch();
 
+        if let Some(working_dir) = settings.working_dir() {
+            if !working_dir.is_dir() {
+                let error = InvalidWorkingDirError::new(working_dir);
+                return InternalExecuteStatus {
+                    stdout: Bytes::new(),
+                    stderr: Bytes::from(format!("error: {error}\n")),
+                    result: ExecutionResult::ExecFail,
+                    stopwatch_end: stopwatch.end(),
+                    is_slow: false,
+                    delay_before_start,
+                    cancel_reason: None,
+                    exit_code: None,
+                };
+            }
+        }
+
+        let env_vars = match settings.env_file().map(env_file::read_env_file).transpose() {
+            Ok(env_vars) => env_vars.unwrap_or_default(),
+            Err(error) => {
+                return InternalExecuteStatus {
+                    stdout: Bytes::new(),
+                    stderr: Bytes::from(format!("error: {error}\n")),
+                    result: ExecutionResult::ExecFail,
+                    stopwatch_end: stopwatch.end(),
+                    is_slow: false,
+                    delay_before_start,
+                    cancel_reason: None,
+                    exit_code: None,
+                };
+            }
+        };
+
         match self
             .run_test_inner(
                 test,
                 retry_data,
                 &mut stopwatch,
                 settings,
+                &env_vars,
                 run_sender,
                 forward_receiver,
                 delay_before_start,
+                binary_deadline,
             )
             .await
         {
@@ -634,10 +1794,85 @@ impl<'a> TestRunnerInner<'a> {
                 stopwatch_end: stopwatch.end(),
                 is_slow: false,
                 delay_before_start,
+                cancel_reason: None,
+                exit_code: None,
             },
         }
     }
 
+    /// Wraps a pipe the output of a test process is read from, adding timestamps if requested.
+    fn wrap_output_pipe(
+        &self,
+        pipe: impl AsyncRead + Unpin + Send + 'static,
+        process_start: Instant,
+    ) -> Box<dyn AsyncRead + Unpin + Send> {
+        if self.annotate_output_with_timestamps {
+            Box::new(TimestampedReader::new(pipe, process_start))
+        } else {
+            Box::new(pipe)
+        }
+    }
+
+    /// Sets up the environment, stdin and sandboxing for a test process.
+    fn configure_command(
+        &self,
+        command_mut: &mut std::process::Command,
+        env_vars: &[(String, String)],
+        attempt: usize,
+    ) {
+        self.env_inheritance
+            .apply(command_mut, self.test_list.cargo_env());
+        // Variables from the env file take precedence over inherited ones.
+        command_mut.envs(env_vars.iter().map(|(key, value)| (key, value)));
+
+        // Debug environment variable for testing.
+        command_mut.env("__NEXTEST_ATTEMPT", format!("{attempt}"));
+        command_mut.env("NEXTEST_RUN_ID", format!("{}", self.run_id));
+        if let Some(stack_size) = self.test_thread_stack_size {
+            // libtest runs each test on a thread spawned through std, which reads this variable.
+            command_mut.env("RUST_MIN_STACK", stack_size.to_string());
+        }
+        if let (Some(var), false) = (PRELOAD_ENV_VAR, self.preload_libraries.is_empty()) {
+            self.set_preload_env(command_mut, var);
+        }
+        if self.stdin_passthrough {
+            // Tests that share nextest's stdin stay in nextest's process group: a new group would
+            // be in the background, so reading from a terminal would stop the test with SIGTTIN.
+            command_mut.stdin(Stdio::inherit());
+        } else {
+            command_mut.stdin(Stdio::null());
+            imp::set_process_group(command_mut);
+        }
+        imp::set_process_priority(command_mut, self.process_priority);
+        if self.no_network_access {
+            network_sandbox::block_network_access(command_mut);
+        }
+        if let Some(sandbox) = &self.read_only_sandbox {
+            sandbox.apply(command_mut);
+        }
+    }
+
+    /// Sets `var` to the libraries to preload, followed by any libraries it already lists for the
+    /// test process.
+    ///
+    /// With double-spawning, the value is passed on to the double-spawn child, which sets `var`
+    /// just before it executes the test.
+    fn set_preload_env(&self, command_mut: &mut std::process::Command, var: &str) {
+        let existing = match command_mut.get_envs().find(|(name, _)| *name == var) {
+            // Set by the env file, or inherited with a restricted environment.
+            Some((_, value)) => value.map(ToOwned::to_owned),
+            None if self.env_inheritance == EnvInheritance::All => std::env::var_os(var),
+            None => None,
+        };
+        let value = preload_env_value(&self.preload_libraries, existing);
+        if self.double_spawn.current_exe().is_some() {
+            command_mut.env_remove(var);
+            command_mut.env(DoubleSpawnInfo::PRELOAD_ENV, value);
+        } else {
+            command_mut.env(var, value);
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn run_test_inner(
         &self,
@@ -645,22 +1880,24 @@ impl<'a> TestRunnerInner<'a> {
         retry_data: RetryData,
         stopwatch: &mut StopwatchStart,
         settings: &TestSettings,
+        env_vars: &[(String, String)],
         run_sender: &UnboundedSender<InternalTestEvent<'a>>,
         forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
         delay_before_start: Duration,
+        binary_deadline: Option<Instant>,
     ) -> std::io::Result<InternalExecuteStatus> {
         let ctx = TestExecuteContext {
             double_spawn: &self.double_spawn,
             target_runner: &self.target_runner,
+            extra_args: self.test_list.extra_args(),
         };
-        let mut cmd = test.make_command(&ctx, self.test_list);
+        let mut cmd =
+            test.make_command(&ctx, self.test_list, self.bench, self.test_wrapper.as_ref());
         let command_mut = cmd.command_mut();
-
-        // Debug environment variable for testing.
-        command_mut.env("__NEXTEST_ATTEMPT", format!("{}", retry_data.attempt));
-        command_mut.env("NEXTEST_RUN_ID", format!("{}", self.run_id));
-        command_mut.stdin(Stdio::null());
-        imp::set_process_group(command_mut);
+        if let Some(working_dir) = settings.working_dir() {
+            command_mut.current_dir(working_dir);
+        }
+        self.configure_command(command_mut, env_vars, retry_data.attempt);
 
         // If creating a job fails, we might be on an old system. Ignore this -- job objects are a
         // best-effort thing.
@@ -673,26 +1910,47 @@ impl<'a> TestRunnerInner<'a> {
                 .stderr(std::process::Stdio::piped());
         };
 
+        let process_start = Instant::now();
         let mut child = cmd.spawn()?;
+        // The ID is no longer available once the child has been waited for.
+        let child_pid = child.id();
 
         // If assigning the child to the job fails, ignore this. This can happen if the process has
         // exited.
         let _ = imp::assign_process_to_job(&child, job.as_ref());
 
         let mut status: Option<ExecutionResult> = None;
-        let slow_timeout = settings.slow_timeout();
+        let mut slow_timeout = settings.slow_timeout();
+        if let Some(grace_period) = self.force_timeout_grace_period {
+            slow_timeout.grace_period = grace_period;
+        }
         let leak_timeout = settings.leak_timeout();
         let mut is_slow = false;
+        let mut cancel_reason = None;
 
         // Use a pausable_sleep rather than an interval here because it's much harder to pause and
         // resume an interval.
         let interval_sleep = crate::time::pausable_sleep(slow_timeout.period);
         tokio::pin!(interval_sleep);
+        // This is only polled if a maximum test time is set.
+        let max_time_sleep =
+            crate::time::pausable_sleep(self.max_test_time.unwrap_or(Duration::ZERO));
+        tokio::pin!(max_time_sleep);
+        // This is only polled if the test binary has a timeout.
+        let binary_deadline_sleep =
+            tokio::time::sleep_until(binary_deadline.unwrap_or_else(Instant::now).into());
+        tokio::pin!(binary_deadline_sleep);
 
         let mut timeout_hit = 0;
 
-        let child_stdout = child.stdout.take();
-        let child_stderr = child.stderr.take();
+        let child_stdout = child
+            .stdout
+            .take()
+            .map(|pipe| self.wrap_output_pipe(pipe, process_start));
+        let child_stderr = child
+            .stderr
+            .take()
+            .map(|pipe| self.wrap_output_pipe(pipe, process_start));
         let mut stdout = bytes::BytesMut::new();
         let mut stderr = bytes::BytesMut::new();
 
@@ -700,24 +1958,49 @@ impl<'a> TestRunnerInner<'a> {
             async fn read_all_to_bytes(
                 bytes: &mut bytes::BytesMut,
                 mut input: &mut (dyn AsyncRead + Unpin + Send),
+                mut line_sink: Option<&mut (dyn FnMut(Bytes) + Send)>,
             ) -> std::io::Result<()> {
                 // Reborrow it as AsyncReadExt::read_buf expects
                 // Sized self.
                 let input = &mut input;
+                let mut splitter = LineSplitter::default();
 
                 loop {
                     bytes.reserve(4096);
                     let bytes_read = input.read_buf(bytes).await?;
+                    if let Some(line_sink) = &mut line_sink {
+                        if bytes_read == 0 {
+                            splitter.finish(bytes, &mut **line_sink);
+                        } else {
+                            splitter.split(bytes, &mut **line_sink);
+                        }
+                    }
                     if bytes_read == 0 {
                         break Ok(());
                     }
                 }
             }
 
+            // In stream-output mode, send lines to the reporter as they're read.
+            let line_sender = |stream: TestOutputStream| {
+                move |line: Bytes| {
+                    let _ = run_sender.send(InternalTestEvent::OutputLine {
+                        test_instance: test,
+                        stream,
+                        line,
+                    });
+                }
+            };
+            let mut stdout_line_sink = line_sender(TestOutputStream::Stdout);
+            let mut stderr_line_sink = line_sender(TestOutputStream::Stderr);
+            let stream_output = self.stream_output;
+
             // Set up futures for reading from stdout and stderr.
             let stdout_fut = async {
                 if let Some(mut child_stdout) = child_stdout {
-                    read_all_to_bytes(&mut stdout, &mut child_stdout).await
+                    let line_sink = stream_output
+                        .then_some(&mut stdout_line_sink as &mut (dyn FnMut(Bytes) + Send));
+                    read_all_to_bytes(&mut stdout, &mut child_stdout, line_sink).await
                 } else {
                     Ok(())
                 }
@@ -725,7 +2008,9 @@ impl<'a> TestRunnerInner<'a> {
 
             let stderr_fut = async {
                 if let Some(mut child_stderr) = child_stderr {
-                    read_all_to_bytes(&mut stderr, &mut child_stderr).await
+                    let line_sink = stream_output
+                        .then_some(&mut stderr_line_sink as &mut (dyn FnMut(Bytes) + Send));
+                    read_all_to_bytes(&mut stderr, &mut child_stderr, line_sink).await
                 } else {
                     Ok(())
                 }
@@ -767,122 +2052,501 @@ impl<'a> TestRunnerInner<'a> {
                             });
                         }
 
-                        if will_terminate {
-                            // attempt to terminate the slow test.
-                            // as there is a race between shutting down a slow test and its own completion
-                            // we silently ignore errors to avoid printing false warnings.
-                            imp::terminate_child(&mut child, TerminateMode::Timeout(slow_timeout.grace_period), forward_receiver, job.as_ref()).await;
-                            status = Some(ExecutionResult::Timeout);
-                            if slow_timeout.grace_period.is_zero() {
-                                break child.wait().await;
-                            }
-                            // Don't break here to give the wait task a chance to finish.
-                        } else {
-                            interval_sleep.as_mut().reset_original_duration();
-                        }
+                        if will_terminate {
+                            // attempt to terminate the slow test.
+                            // as there is a race between shutting down a slow test and its own completion
+                            // we silently ignore errors to avoid printing false warnings.
+                            // The grace period is waited for within terminate_child, so cap it to
+                            // keep enforcing the maximum test time.
+                            let grace_period = capped_grace_period(
+                                slow_timeout.grace_period,
+                                self.max_test_time,
+                                stopwatch.end().duration,
+                            );
+                            imp::terminate_child(&mut child, TerminateMode::Timeout(grace_period), forward_receiver, job.as_ref()).await;
+                            status = Some(ExecutionResult::Timeout);
+                            if grace_period.is_zero() {
+                                break child.wait().await;
+                            }
+                            // Don't break here to give the wait task a chance to finish.
+                        } else {
+                            interval_sleep.as_mut().reset_original_duration();
+                        }
+                    }
+                    _ = &mut max_time_sleep, if self.max_test_time.is_some() && status.is_none() => {
+                        let max_test_time = self.max_test_time.expect("checked in the guard above");
+                        let _ = run_sender.send(InternalTestEvent::ExceededAbsoluteMaxTime {
+                            test_instance: test,
+                            retry_data,
+                            max_test_time,
+                        });
+                        // This is a safety valve for pathological tests, so skip the grace period.
+                        imp::terminate_child(&mut child, TerminateMode::Timeout(Duration::ZERO), forward_receiver, job.as_ref()).await;
+                        status = Some(ExecutionResult::Timeout);
+                        break child.wait().await;
+                    }
+                    _ = &mut binary_deadline_sleep, if binary_deadline.is_some() && status.is_none() => {
+                        // The test binary's timeout has passed. The runner reports this once the
+                        // test finishes.
+                        imp::terminate_child(&mut child, TerminateMode::Timeout(Duration::ZERO), forward_receiver, job.as_ref()).await;
+                        status = Some(ExecutionResult::Timeout);
+                        break child.wait().await;
+                    }
+                    recv = forward_receiver.recv() => {
+                        // The sender stays open longer than the whole loop, and the buffer is big
+                        // enough for all messages ever sent through this channel, so a RecvError
+                        // should never happen.
+                        let forward_event = recv.expect("a RecvError should never happen here");
+
+                        match forward_event {
+                            #[cfg(unix)]
+                            SignalForwardEvent::Stop(sender) => {
+                                // It isn't possible to receive a stop event twice since it gets
+                                // debounced in the main signal handler.
+                                stopwatch.pause();
+                                interval_sleep.as_mut().pause();
+                                max_time_sleep.as_mut().pause();
+                                imp::job_control_child(&child, JobControlEvent::Stop);
+                                // The receiver being dead probably means the main thread panicked
+                                // or similar.
+                                let _ = sender.send(()).await;
+                            }
+                            #[cfg(unix)]
+                            SignalForwardEvent::Continue => {
+                                // It's possible to receive a resume event right at the beginning of
+                                // test execution, so debounce it.
+                                if stopwatch.is_paused() {
+                                    stopwatch.resume();
+                                    interval_sleep.as_mut().resume();
+                                    max_time_sleep.as_mut().resume();
+                                    imp::job_control_child(&child, JobControlEvent::Continue);
+                                }
+                            }
+                            SignalForwardEvent::Shutdown(event) => {
+                                // A second signal is only forwarded after the first one.
+                                if let ShutdownForwardEvent::Once(event) = event {
+                                    cancel_reason.get_or_insert(shutdown_cancel_reason(event));
+                                }
+                                imp::terminate_child(&mut child, TerminateMode::Signal(event), forward_receiver, job.as_ref()).await;
+                            }
+                        }
+
+                    }
+                };
+            };
+
+            // Once the process is done executing, wait up to leak_timeout for the pipes to shut down.
+            // Previously, this used to hang if spawned grandchildren inherited stdout/stderr but
+            // didn't shut down properly. Now, this detects those cases and marks them as leaked.
+            let leaked = loop {
+                // Ignore stop and continue events here since the leak timeout should be very small.
+                // TODO: we may want to consider them.
+                let sleep = tokio::time::sleep(leak_timeout);
+
+                tokio::select! {
+                    res = &mut collect_output_fut, if !collect_output_done => {
+                        collect_output_done = true;
+                        res?;
+                    }
+                    () = sleep, if !collect_output_done => {
+                        break true;
+                    }
+                    else => {
+                        break false;
+                    }
+                }
+            };
+
+            (res, leaked)
+        };
+
+        let output = res?;
+        let exit_status = output;
+
+        let status = status.unwrap_or_else(|| {
+            if exit_status.success() {
+                if leaked {
+                    ExecutionResult::Leak
+                } else {
+                    ExecutionResult::Pass
+                }
+            } else {
+                ExecutionResult::Fail {
+                    abort_status: abort_status(exit_status),
+                    leaked,
+                }
+            }
+        });
+        let fail_on_stderr = self
+            .force_fail_on_stderr
+            .unwrap_or_else(|| settings.fail_on_stderr());
+        let status = if fail_on_stderr && status.is_success() && !stderr.is_empty() {
+            ExecutionResult::StderrWritten {
+                leaked: status == ExecutionResult::Leak,
+            }
+        } else {
+            status
+        };
+        let leaked_pids = match child_pid {
+            Some(pid) if self.fail_on_leaked_processes => imp::leaked_processes(pid, job.as_ref()),
+            _ => Vec::new(),
+        };
+        let status = if leaked_pids.is_empty() {
+            status
+        } else {
+            let _ = run_sender.send(InternalTestEvent::ProcessLeak {
+                test_instance: test,
+                retry_data,
+                leaked_pids,
+            });
+            if status.is_success() {
+                ExecutionResult::ProcessLeak
+            } else {
+                status
+            }
+        };
+        let status = match &self.simulated_failures {
+            Some(simulated_failures)
+                if status.is_success()
+                    && simulated_failures.should_fail(
+                        test.suite_info.binary_id.as_str(),
+                        test.name,
+                        retry_data.attempt,
+                    ) =>
+            {
+                ExecutionResult::SimulatedFailure
+            }
+            _ => status,
+        };
+        let cancel_reason = cancel_reason.filter(|_| !status.is_success());
+        let exit_code = match status {
+            ExecutionResult::Fail {
+                abort_status: None, ..
+            } => exit_status.code(),
+            _ => None,
+        };
+
+        Ok(InternalExecuteStatus {
+            stdout: stdout.freeze(),
+            stderr: stderr.freeze(),
+            result: status,
+            stopwatch_end: stopwatch.end(),
+            is_slow,
+            delay_before_start,
+            cancel_reason,
+            exit_code,
+        })
+    }
+
+    /// Runs `tests`, which are all from the same binary, in as few processes as possible with
+    /// `test_threads` threads each.
+    ///
+    /// Tests are split across processes so that their names fit on the command line. The time
+    /// taken by each test isn't known, since only the whole process is timed. Tests that didn't
+    /// finish because the process crashed are reported as failed, with all of the process's
+    /// output.
+    async fn run_batch(
+        &self,
+        tests: Vec<TestInstance<'a>>,
+        test_threads: usize,
+        run_sender: &UnboundedSender<InternalTestEvent<'a>>,
+        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+    ) {
+        let mut to_run = Vec::with_capacity(tests.len());
+        for test_instance in tests {
+            match test_instance.test_info.filter_match {
+                FilterMatch::Matches => to_run.push(test_instance),
+                FilterMatch::Mismatch { reason } => {
+                    // Failure to send means the receiver was dropped.
+                    let _ = run_sender.send(InternalTestEvent::Skipped {
+                        test_instance,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        // Each name is passed in as a separate argument, so count the space before it as well.
+        for chunk in split_by_total_len(&to_run, MAX_BATCH_NAMES_LEN, |test| test.name.len() + 1) {
+            let cancel_reason = self
+                .run_batch_chunk(chunk, test_threads, run_sender, forward_receiver)
+                .await;
+            if cancel_reason.is_some() {
+                // The remaining tests aren't started.
+                break;
+            }
+        }
+    }
+
+    /// Runs `tests` in one process for [`Self::run_batch`], returning the reason the process was
+    /// terminated if the run was canceled.
+    async fn run_batch_chunk(
+        &self,
+        tests: &[TestInstance<'a>],
+        test_threads: usize,
+        run_sender: &UnboundedSender<InternalTestEvent<'a>>,
+        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+    ) -> Option<CancelReason> {
+        for &test_instance in tests {
+            let _ = run_sender.send(InternalTestEvent::Started { test_instance });
+        }
+
+        let mut stopwatch = crate::time::stopwatch();
+        let res = self
+            .run_batch_process(tests, test_threads, &mut stopwatch, forward_receiver)
+            .await;
+        let start_time = stopwatch.end().start_time;
+        let (stdout, stderr, exit_status, cancel_reason) = match res {
+            Ok(output) => (
+                output.stdout,
+                output.stderr,
+                Some(output.exit_status),
+                output.cancel_reason,
+            ),
+            Err(error) => {
+                let stderr = Bytes::from(format!("error: failed to run test binary: {error}\n"));
+                (Bytes::new(), stderr, None, None)
+            }
+        };
+        let stdout_str = String::from_utf8_lossy(&stdout);
+        let test_outputs = libtest_output::parse_libtest_output(&stdout_str);
+
+        let retry_data = RetryData {
+            attempt: 1,
+            total_attempts: 1,
+        };
+        for &test_instance in tests {
+            let test_output = test_outputs
+                .get(test_instance.name)
+                .and_then(|output| output.result.map(|result| (result, &output.output)));
+            let (result, test_stdout, test_stderr) = match (test_output, exit_status) {
+                (Some((LibtestResult::Ignored, _)), _) => {
+                    // The test harness didn't run this test, for example because it isn't a
+                    // benchmark and this is a benchmark run.
+                    let _ = run_sender.send(InternalTestEvent::SkippedAfterStart {
+                        test_instance,
+                        reason: MismatchReason::Ignored,
+                    });
+                    continue;
+                }
+                (Some((LibtestResult::Passed, output)), _) => (
+                    ExecutionResult::Pass,
+                    Bytes::from(output.clone()),
+                    Bytes::new(),
+                ),
+                (Some((LibtestResult::Failed, output)), _) => (
+                    ExecutionResult::Fail {
+                        abort_status: None,
+                        leaked: false,
+                    },
+                    Bytes::from(output.clone()),
+                    Bytes::new(),
+                ),
+                // The test didn't finish, so all of the output might be relevant.
+                (None, Some(exit_status)) => (
+                    ExecutionResult::Fail {
+                        abort_status: abort_status(exit_status),
+                        leaked: false,
+                    },
+                    stdout.clone(),
+                    stderr.clone(),
+                ),
+                (None, None) => (ExecutionResult::ExecFail, stdout.clone(), stderr.clone()),
+            };
+            let run_statuses = ExecutionStatuses::new(
+                vec![ExecuteStatus {
+                    retry_data,
+                    stdout: test_stdout,
+                    stderr: test_stderr,
+                    result,
+                    start_time,
+                    // Tests ran alongside each other in the same process, so only the time taken by
+                    // the whole process is known.
+                    time_taken: Duration::ZERO,
+                    time_taken_known: false,
+                    is_slow: false,
+                    delay_before_start: Duration::ZERO,
+                }],
+                false,
+            );
+
+            match cancel_reason {
+                Some(reason) if !result.is_success() => {
+                    let _ = run_sender.send(InternalTestEvent::Cancelled {
+                        test_instance,
+                        reason,
+                        run_statuses,
+                    });
+                }
+                _ => {
+                    let settings = self.profile.settings_for(&test_instance.to_test_query());
+                    let _ = run_sender.send(InternalTestEvent::Finished {
+                        test_instance,
+                        success_output: settings.success_output(),
+                        failure_output: settings.failure_output(),
+                        junit_store_success_output: settings.junit_store_success_output(),
+                        junit_store_failure_output: settings.junit_store_failure_output(),
+                        run_statuses,
+                    });
+                }
+            }
+        }
+
+        cancel_reason
+    }
+
+    /// Runs the process for [`Self::run_batch_chunk`].
+    async fn run_batch_process(
+        &self,
+        tests: &[TestInstance<'a>],
+        test_threads: usize,
+        stopwatch: &mut StopwatchStart,
+        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+    ) -> std::io::Result<BatchOutput> {
+        let ctx = TestExecuteContext {
+            double_spawn: &self.double_spawn,
+            target_runner: &self.target_runner,
+            extra_args: self.test_list.extra_args(),
+        };
+        let mut cmd = TestInstance::make_batch_command(
+            tests,
+            test_threads,
+            &ctx,
+            self.test_list,
+            self.bench,
+            self.test_wrapper.as_ref(),
+        );
+        let command_mut = cmd.command_mut();
+        self.configure_command(command_mut, &[], 1);
+        command_mut
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+
+        // Job objects are best-effort, as in run_test_inner.
+        let job = imp::Job::create().ok();
+        let mut child = cmd.spawn()?;
+        let _ = imp::assign_process_to_job(&child, job.as_ref());
+
+        let mut child_stdout = child.stdout.take().expect("stdout is piped");
+        let mut child_stderr = child.stderr.take().expect("stderr is piped");
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut cancel_reason = None;
+
+        let exit_status = {
+            let collect_output_fut = try_join(
+                child_stdout.read_to_end(&mut stdout),
+                child_stderr.read_to_end(&mut stderr),
+            );
+            tokio::pin!(collect_output_fut);
+            let mut collect_output_done = false;
+
+            let exit_status = loop {
+                tokio::select! {
+                    res = &mut collect_output_fut, if !collect_output_done => {
+                        collect_output_done = true;
+                        res?;
+                    }
+                    res = child.wait() => {
+                        break res?;
                     }
                     recv = forward_receiver.recv() => {
-                        // The sender stays open longer than the whole loop, and the buffer is big
-                        // enough for all messages ever sent through this channel, so a RecvError
-                        // should never happen.
+                        // See run_test_inner for why a RecvError should never happen here.
                         let forward_event = recv.expect("a RecvError should never happen here");
-
                         match forward_event {
                             #[cfg(unix)]
                             SignalForwardEvent::Stop(sender) => {
-                                // It isn't possible to receive a stop event twice since it gets
-                                // debounced in the main signal handler.
                                 stopwatch.pause();
-                                interval_sleep.as_mut().pause();
                                 imp::job_control_child(&child, JobControlEvent::Stop);
-                                // The receiver being dead probably means the main thread panicked
-                                // or similar.
                                 let _ = sender.send(()).await;
                             }
                             #[cfg(unix)]
                             SignalForwardEvent::Continue => {
-                                // It's possible to receive a resume event right at the beginning of
-                                // test execution, so debounce it.
                                 if stopwatch.is_paused() {
                                     stopwatch.resume();
-                                    interval_sleep.as_mut().resume();
                                     imp::job_control_child(&child, JobControlEvent::Continue);
                                 }
                             }
                             SignalForwardEvent::Shutdown(event) => {
+                                if let ShutdownForwardEvent::Once(event) = event {
+                                    cancel_reason.get_or_insert(shutdown_cancel_reason(event));
+                                }
                                 imp::terminate_child(&mut child, TerminateMode::Signal(event), forward_receiver, job.as_ref()).await;
                             }
                         }
-
-                    }
-                };
-            };
-
-            // Once the process is done executing, wait up to leak_timeout for the pipes to shut down.
-            // Previously, this used to hang if spawned grandchildren inherited stdout/stderr but
-            // didn't shut down properly. Now, this detects those cases and marks them as leaked.
-            let leaked = loop {
-                // Ignore stop and continue events here since the leak timeout should be very small.
-                // TODO: we may want to consider them.
-                let sleep = tokio::time::sleep(leak_timeout);
-
-                tokio::select! {
-                    res = &mut collect_output_fut, if !collect_output_done => {
-                        collect_output_done = true;
-                        res?;
-                    }
-                    () = sleep, if !collect_output_done => {
-                        break true;
-                    }
-                    else => {
-                        break false;
                     }
                 }
             };
 
-            (res, leaked)
+            // As with individual tests, don't wait on leaked pipes for longer than the leak
+            // timeout. Whatever output was read by then is used.
+            if !collect_output_done {
+                if let Ok(res) =
+                    tokio::time::timeout(self.profile.leak_timeout(), collect_output_fut).await
+                {
+                    res?;
+                }
+            }
+            exit_status
         };
 
-        let output = res?;
-        let exit_status = output;
+        Ok(BatchOutput {
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+            exit_status,
+            cancel_reason,
+        })
+    }
+}
 
-        let status = status.unwrap_or_else(|| {
-            if exit_status.success() {
-                if leaked {
-                    ExecutionResult::Leak
-                } else {
-                    ExecutionResult::Pass
-                }
-            } else {
-                cfg_if::cfg_if! {
-                    if #[cfg(unix)] {
-                        // On Unix, extract the signal if it's found.
-                        use std::os::unix::process::ExitStatusExt;
-                        let abort_status = exit_status.signal().map(AbortStatus::UnixSignal);
-                    } else if #[cfg(windows)] {
-                        let abort_status = exit_status.code().and_then(|code| {
-                            let exception = windows::Win32::Foundation::NTSTATUS(code);
-                            exception.is_err().then(|| AbortStatus::WindowsNtStatus(exception))
-                        });
-                    } else {
-                        let abort_status = None;
-                    }
-                }
-                ExecutionResult::Fail {
-                    abort_status,
-                    leaked,
-                }
+/// The output of a process that ran several tests.
+struct BatchOutput {
+    stdout: Bytes,
+    stderr: Bytes,
+    exit_status: std::process::ExitStatus,
+    // Set if the process was terminated because the run was canceled.
+    cancel_reason: Option<CancelReason>,
+}
+
+/// Drains `forward_receiver` of any messages, including those that are related to SIGTSTP.
+async fn drain_forward_receiver(
+    forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+) {
+    loop {
+        let message = forward_receiver.try_recv();
+        match message {
+            #[cfg(unix)]
+            Ok(SignalForwardEvent::Stop(sender)) => {
+                // The receiver being dead isn't really important.
+                let _ = sender.send(()).await;
             }
-        });
+            Err(
+                tokio::sync::broadcast::error::TryRecvError::Empty
+                | tokio::sync::broadcast::error::TryRecvError::Closed,
+            ) => {
+                break;
+            }
+            _ => {}
+        }
+    }
+}
 
-        Ok(InternalExecuteStatus {
-            stdout: stdout.freeze(),
-            stderr: stderr.freeze(),
-            result: status,
-            stopwatch_end: stopwatch.end(),
-            is_slow,
-            delay_before_start,
-        })
+/// Returns how a process that exited unsuccessfully was aborted, if it was.
+fn abort_status(exit_status: std::process::ExitStatus) -> Option<AbortStatus> {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            // On Unix, extract the signal if it's found.
+            use std::os::unix::process::ExitStatusExt;
+            exit_status.signal().map(AbortStatus::UnixSignal)
+        } else if #[cfg(windows)] {
+            exit_status.code().and_then(|code| {
+                let exception = windows::Win32::Foundation::NTSTATUS(code);
+                exception.is_err().then(|| AbortStatus::WindowsNtStatus(exception))
+            })
+        } else {
+            let _ = exit_status;
+            None
+        }
     }
 }
 
@@ -908,18 +2572,36 @@ impl RetryData {
 pub struct ExecutionStatuses {
     /// This is guaranteed to be non-empty.
     statuses: Vec<ExecuteStatus>,
+    // Whether every attempt was run regardless of the results of earlier ones, with
+    // `TestRunnerBuilder::set_runs_per_test`. If so, any failed attempt fails the test.
+    runs_per_test: bool,
 }
 
 #[allow(clippy::len_without_is_empty)] // RunStatuses is never empty
 impl ExecutionStatuses {
-    fn new(statuses: Vec<ExecuteStatus>) -> Self {
-        Self { statuses }
+    pub(crate) fn new(statuses: Vec<ExecuteStatus>, runs_per_test: bool) -> Self {
+        Self {
+            statuses,
+            runs_per_test,
+        }
     }
 
-    /// Returns the last execution status.
+    /// Returns the status that determines the final result.
     ///
-    /// This status is typically used as the final result.
+    /// This is the last execution status. With
+    /// [`TestRunnerBuilder::set_runs_per_test`], it's the last failed status instead, if any
+    /// attempts failed.
     pub fn last_status(&self) -> &ExecuteStatus {
+        if self.runs_per_test {
+            if let Some(status) = self
+                .statuses
+                .iter()
+                .rev()
+                .find(|status| !status.result.is_success())
+            {
+                return status;
+            }
+        }
         self.statuses
             .last()
             .expect("execution statuses is non-empty")
@@ -939,26 +2621,30 @@ impl ExecutionStatuses {
     pub fn describe(&self) -> ExecutionDescription<'_> {
         let last_status = self.last_status();
         if last_status.result.is_success() {
-            if self.statuses.len() > 1 {
-                ExecutionDescription::Flaky {
-                    last_status,
-                    prior_statuses: &self.statuses[..self.statuses.len() - 1],
-                }
-            } else {
+            // With runs_per_test, a successful last status means that every attempt passed.
+            // Otherwise, the test only ran again if earlier attempts failed.
+            let prior_statuses = &self.statuses[..self.statuses.len() - 1];
+            if prior_statuses.is_empty() || self.runs_per_test {
                 ExecutionDescription::Success {
                     single_status: last_status,
                 }
+            } else {
+                ExecutionDescription::Flaky {
+                    last_status,
+                    prior_statuses,
+                }
             }
         } else {
-            let first_status = self
+            // With runs_per_test, attempts before the first failure may have passed.
+            let first_index = self
                 .statuses
-                .first()
-                .expect("execution statuses is non-empty");
-            let retries = &self.statuses[1..];
+                .iter()
+                .position(|status| !status.result.is_success())
+                .expect("a status failed");
             ExecutionDescription::Failure {
-                first_status,
+                first_status: &self.statuses[first_index],
                 last_status,
-                retries,
+                retries: &self.statuses[first_index + 1..],
             }
         }
     }
@@ -984,7 +2670,8 @@ pub enum ExecutionDescription<'a> {
         prior_statuses: &'a [ExecuteStatus],
     },
 
-    /// The test was run once, or possibly multiple times. All runs failed.
+    /// The test was run once, or possibly multiple times. All runs failed, or with
+    /// [`TestRunnerBuilder::set_runs_per_test`], at least one run failed.
     Failure {
         /// The first, failing status.
         first_status: &'a ExecuteStatus,
@@ -992,7 +2679,9 @@ pub enum ExecutionDescription<'a> {
         /// The last, failing status. Same as the first status if no retries were performed.
         last_status: &'a ExecuteStatus,
 
-        /// Any retries that were performed. All of these runs failed.
+        /// Any retries that were performed. All of these runs failed, except with
+        /// [`TestRunnerBuilder::set_runs_per_test`], where attempts after the first failure are
+        /// included whether they passed or not.
         ///
         /// May be empty.
         retries: &'a [ExecuteStatus],
@@ -1061,7 +2750,14 @@ pub struct ExecuteStatus {
     /// The time at which the test started.
     pub start_time: SystemTime,
     /// The time it took for the test to run.
+    ///
+    /// This is zero if the time isn't known, as indicated by `time_taken_known`.
     pub time_taken: Duration,
+    /// Whether the time taken by this test is known.
+    ///
+    /// This is false for tests that ran in the same process as other tests, with
+    /// `--test-isolation thread` or `none`.
+    pub time_taken_known: bool,
     /// Whether this test counts as slow.
     pub is_slow: bool,
     /// The delay will be non-zero if this is a retry and delay was specified.
@@ -1075,6 +2771,10 @@ struct InternalExecuteStatus {
     stopwatch_end: StopwatchEnd,
     is_slow: bool,
     delay_before_start: Duration,
+    // Set if the test was terminated because the run was canceled, and didn't pass.
+    cancel_reason: Option<CancelReason>,
+    // Set if the test failed by exiting with a non-zero exit code, rather than being aborted.
+    exit_code: Option<i32>,
 }
 
 impl InternalExecuteStatus {
@@ -1086,6 +2786,7 @@ impl InternalExecuteStatus {
             result: self.result,
             start_time: self.stopwatch_end.start_time,
             time_taken: self.stopwatch_end.duration,
+            time_taken_known: true,
             is_slow: self.is_slow,
             delay_before_start: self.delay_before_start,
         }
@@ -1093,7 +2794,8 @@ impl InternalExecuteStatus {
 }
 
 /// Statistics for a test run.
-#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct RunStats {
     /// The total number of tests that were expected to be run at the beginning.
     ///
@@ -1129,6 +2831,12 @@ pub struct RunStats {
 
     /// The number of tests that were skipped.
     pub skipped: usize,
+
+    /// The number of tests that were terminated while running because the run was canceled.
+    ///
+    /// These tests aren't included in `finished_count`.
+    #[serde(default)]
+    pub cancelled: usize,
 }
 
 impl RunStats {
@@ -1171,7 +2879,7 @@ impl RunStats {
                 if last_status.is_slow {
                     self.passed_slow += 1;
                 }
-                if run_statuses.len() > 1 {
+                if matches!(run_statuses.describe(), ExecutionDescription::Flaky { .. }) {
                     self.flaky += 1;
                 }
             }
@@ -1181,11 +2889,14 @@ impl RunStats {
                 if last_status.is_slow {
                     self.passed_slow += 1;
                 }
-                if run_statuses.len() > 1 {
+                if matches!(run_statuses.describe(), ExecutionDescription::Flaky { .. }) {
                     self.flaky += 1;
                 }
             }
-            ExecutionResult::Fail { .. } => {
+            ExecutionResult::Fail { .. }
+            | ExecutionResult::StderrWritten { .. }
+            | ExecutionResult::SimulatedFailure
+            | ExecutionResult::ProcessLeak => {
                 self.failed += 1;
                 if last_status.is_slow {
                     self.failed_slow += 1;
@@ -1301,16 +3012,27 @@ where
                 elapsed,
                 will_terminate,
             }),
+            InternalEvent::Test(InternalTestEvent::ExceededAbsoluteMaxTime {
+                test_instance,
+                retry_data,
+                max_test_time,
+            }) => self.callback(TestEvent::TestExceededAbsoluteMaxTime {
+                test_instance,
+                retry_data,
+                max_test_time,
+            }),
             InternalEvent::Test(InternalTestEvent::AttemptFailedWillRetry {
                 test_instance,
                 failure_output,
                 run_status,
                 delay_before_next_attempt,
+                retry_reason,
             }) => self.callback(TestEvent::TestAttemptFailedWillRetry {
                 test_instance,
                 failure_output,
                 run_status,
                 delay_before_next_attempt,
+                retry_reason,
             }),
             InternalEvent::Test(InternalTestEvent::RetryStarted {
                 test_instance,
@@ -1354,6 +3076,21 @@ where
                     Ok(None)
                 }
             }
+            InternalEvent::Test(InternalTestEvent::Cancelled {
+                test_instance,
+                reason,
+                run_statuses,
+            }) => {
+                self.running -= 1;
+                self.run_stats.cancelled += 1;
+                self.callback(TestEvent::TestCancelled {
+                    test_instance,
+                    reason,
+                    run_statuses,
+                    current_stats: self.run_stats,
+                    running: self.running,
+                })
+            }
             InternalEvent::Test(InternalTestEvent::Skipped {
                 test_instance,
                 reason,
@@ -1364,19 +3101,103 @@ where
                     reason,
                 })
             }
+            InternalEvent::Test(InternalTestEvent::NotRun {
+                test_instance,
+                reason,
+            }) => {
+                // The test was counted as expected to finish.
+                self.run_stats.initial_run_count -= 1;
+                self.run_stats.skipped += 1;
+                self.callback(TestEvent::TestSkipped {
+                    test_instance,
+                    reason,
+                })
+            }
+            InternalEvent::Test(InternalTestEvent::SkippedAfterStart {
+                test_instance,
+                reason,
+            }) => {
+                // The test was counted as running, and as expected to finish.
+                self.running -= 1;
+                self.run_stats.initial_run_count -= 1;
+                self.run_stats.skipped += 1;
+                self.callback(TestEvent::TestSkipped {
+                    test_instance,
+                    reason,
+                })
+            }
+            InternalEvent::Test(InternalTestEvent::LeakyTestDetected {
+                test_instance,
+                mutations,
+            }) => self.callback(TestEvent::LeakyTestDetected {
+                test_instance,
+                mutations,
+            }),
+            InternalEvent::Test(InternalTestEvent::SuspiciouslyFastTest {
+                test_instance,
+                time_taken,
+                min_run_time,
+            }) => self.callback(TestEvent::SuspiciouslyFastTest {
+                test_instance,
+                time_taken,
+                min_run_time,
+            }),
+            InternalEvent::Test(InternalTestEvent::TimingRegression {
+                test_instance,
+                baseline,
+                current,
+                regression_pct,
+            }) => self.callback(TestEvent::TimingRegression {
+                test_instance,
+                baseline,
+                current,
+                regression_pct,
+            }),
+            InternalEvent::Test(InternalTestEvent::NetworkAccessViolation { test_instance }) => {
+                self.callback(TestEvent::NetworkAccessViolation { test_instance })
+            }
+            InternalEvent::Test(InternalTestEvent::ProcessLeak {
+                test_instance,
+                retry_data,
+                leaked_pids,
+            }) => self.callback(TestEvent::ProcessLeak {
+                test_instance,
+                retry_data,
+                leaked_pids,
+            }),
+            InternalEvent::Test(InternalTestEvent::OutputLine {
+                test_instance,
+                stream,
+                line,
+            }) => self.callback(TestEvent::TestOutputLine {
+                test_instance,
+                stream,
+                line,
+            }),
+            InternalEvent::Test(InternalTestEvent::BinaryFailureLimitReached {
+                suite_info,
+                failure_limit,
+            }) => self.callback(TestEvent::BinaryFailureLimitReached {
+                suite_info,
+                failure_limit,
+            }),
+            InternalEvent::Test(InternalTestEvent::BinaryTimedOut {
+                suite_info,
+                timeout,
+            }) => self.callback(TestEvent::BinaryTimedOut {
+                suite_info,
+                timeout,
+            }),
+            InternalEvent::Test(InternalTestEvent::GlobalRetryCapReached { max_retries }) => {
+                self.callback(TestEvent::GlobalRetryCapReached { max_retries })
+            }
             InternalEvent::Signal(SignalEvent::Shutdown(event)) => {
                 let signal_count = self.increment_signal_count();
                 let forward_event = signal_count.to_forward_event(event);
 
-                let cancel_reason = match event {
-                    #[cfg(unix)]
-                    ShutdownEvent::Hangup | ShutdownEvent::Term => CancelReason::Signal,
-                    ShutdownEvent::Interrupt => CancelReason::Interrupt,
-                };
-
                 Err(InternalError::SignalCanceled(
                     forward_event,
-                    self.begin_cancel(cancel_reason).err(),
+                    self.begin_cancel(shutdown_cancel_reason(event)).err(),
                 ))
             }
             #[cfg(unix)]
@@ -1463,11 +3284,17 @@ enum InternalTestEvent<'a> {
         elapsed: Duration,
         will_terminate: bool,
     },
+    ExceededAbsoluteMaxTime {
+        test_instance: TestInstance<'a>,
+        retry_data: RetryData,
+        max_test_time: Duration,
+    },
     AttemptFailedWillRetry {
         test_instance: TestInstance<'a>,
         failure_output: TestOutputDisplay,
         run_status: ExecuteStatus,
         delay_before_next_attempt: Duration,
+        retry_reason: RetryReason,
     },
     RetryStarted {
         test_instance: TestInstance<'a>,
@@ -1481,10 +3308,171 @@ enum InternalTestEvent<'a> {
         junit_store_failure_output: bool,
         run_statuses: ExecutionStatuses,
     },
+    Cancelled {
+        test_instance: TestInstance<'a>,
+        reason: CancelReason,
+        run_statuses: ExecutionStatuses,
+    },
     Skipped {
         test_instance: TestInstance<'a>,
         reason: MismatchReason,
     },
+    // A test that was reported as started, but that the test harness didn't run.
+    SkippedAfterStart {
+        test_instance: TestInstance<'a>,
+        reason: MismatchReason,
+    },
+    // A test that was expected to run, but that wasn't started.
+    NotRun {
+        test_instance: TestInstance<'a>,
+        reason: MismatchReason,
+    },
+    LeakyTestDetected {
+        test_instance: TestInstance<'a>,
+        mutations: Vec<StateMutation>,
+    },
+    SuspiciouslyFastTest {
+        test_instance: TestInstance<'a>,
+        time_taken: Duration,
+        min_run_time: Duration,
+    },
+    TimingRegression {
+        test_instance: TestInstance<'a>,
+        baseline: Duration,
+        current: Duration,
+        regression_pct: f64,
+    },
+    NetworkAccessViolation {
+        test_instance: TestInstance<'a>,
+    },
+    ProcessLeak {
+        test_instance: TestInstance<'a>,
+        retry_data: RetryData,
+        leaked_pids: Vec<u32>,
+    },
+    OutputLine {
+        test_instance: TestInstance<'a>,
+        stream: TestOutputStream,
+        line: Bytes,
+    },
+    BinaryFailureLimitReached {
+        suite_info: &'a RustTestSuite<'a>,
+        failure_limit: NonZeroUsize,
+    },
+    BinaryTimedOut {
+        suite_info: &'a RustTestSuite<'a>,
+        timeout: Duration,
+    },
+    GlobalRetryCapReached {
+        max_retries: usize,
+    },
+}
+
+fn shutdown_cancel_reason(event: ShutdownEvent) -> CancelReason {
+    match event {
+        #[cfg(unix)]
+        ShutdownEvent::Hangup | ShutdownEvent::Term => CancelReason::Signal,
+        ShutdownEvent::Interrupt => CancelReason::Interrupt,
+    }
+}
+
+/// Compares the time a test took against its baseline, returning the baseline and the percentage
+/// by which the test was slower if that's more than `threshold_pct`.
+///
+/// Tests without a baseline, or with a baseline of zero, are never considered regressions.
+fn timing_regression(
+    baseline: Option<Duration>,
+    current: Duration,
+    threshold_pct: f64,
+) -> Option<(Duration, f64)> {
+    let baseline = baseline.filter(|time| !time.is_zero())?;
+    let regression_pct = (current.as_secs_f64() / baseline.as_secs_f64() - 1.0) * 100.0;
+    (regression_pct > threshold_pct).then_some((baseline, regression_pct))
+}
+
+/// Returns the grace period for a test attempt that's being terminated for timing out, after it has
+/// run for `elapsed`. The grace period is cut short so that the attempt doesn't run for longer than
+/// `max_test_time`.
+fn capped_grace_period(
+    grace_period: Duration,
+    max_test_time: Option<Duration>,
+    elapsed: Duration,
+) -> Duration {
+    match max_test_time {
+        Some(max_test_time) => grace_period.min(max_test_time.saturating_sub(elapsed)),
+        None => grace_period,
+    }
+}
+
+/// Warns about settings that don't apply when each binary's tests share a process, since they rely
+/// on each test being run and timed in its own process.
+fn warn_unsupported_with_shared_process(
+    test_list: &TestList<'_>,
+    profile: &NextestProfile<'_>,
+    has_failure_limit: bool,
+    has_timing_baseline: bool,
+) {
+    if has_failure_limit {
+        log::warn!(
+            "failure-limit-per-binary is ignored when tests share a process, \
+             since all of a binary's tests are started at once"
+        );
+    }
+    if has_timing_baseline {
+        log::warn!(
+            "timing regressions aren't detected when tests share a process, \
+             since the time taken by each test isn't known"
+        );
+    }
+    for test_instance in test_list.iter_tests() {
+        if !test_instance.test_info.filter_match.is_match() {
+            continue;
+        }
+        let settings = profile.settings_for(&test_instance.to_test_query());
+        if settings.working_dir().is_some() || settings.env_file().is_some() {
+            log::warn!(
+                "working-dir and env-file overrides for {}::{} are ignored \
+                 when tests share a process",
+                test_instance.suite_info.binary_id,
+                test_instance.name,
+            );
+        }
+    }
+}
+
+/// Splits output into lines as it's read, for stream-output mode.
+#[derive(Debug, Default)]
+struct LineSplitter {
+    // The start of the first line that hasn't been sent yet.
+    line_start: usize,
+    // The offset up to which the buffer has been scanned for newlines, so that a long partial
+    // line isn't rescanned every time more output is read.
+    scanned: usize,
+}
+
+impl LineSplitter {
+    /// Sends each complete line in `buf` that hasn't been sent yet to `sink`, including its
+    /// trailing newline.
+    ///
+    /// `buf` must contain everything passed in to previous calls as a prefix.
+    fn split(&mut self, buf: &[u8], sink: &mut dyn FnMut(Bytes)) {
+        while let Some(pos) = buf[self.scanned..].iter().position(|&b| b == b'\n') {
+            let line_end = self.scanned + pos + 1;
+            sink(Bytes::copy_from_slice(&buf[self.line_start..line_end]));
+            self.line_start = line_end;
+            self.scanned = line_end;
+        }
+        self.scanned = buf.len();
+    }
+
+    /// Sends the last line in `buf` to `sink` if it doesn't end with a newline.
+    fn finish(&mut self, buf: &[u8], sink: &mut dyn FnMut(Bytes)) {
+        self.split(buf, sink);
+        if self.line_start < buf.len() {
+            sink(Bytes::copy_from_slice(&buf[self.line_start..]));
+            self.line_start = buf.len();
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -1519,6 +3507,24 @@ pub enum ExecutionResult {
     ExecFail,
     /// The test was terminated due to timeout.
     Timeout,
+    /// The test exited successfully, but wrote to standard error with `fail-on-stderr` set.
+    ///
+    /// This is treated as a failure.
+    StderrWritten {
+        /// Whether the test also leaked handles. If set to true, this usually indicates that
+        /// a subprocess that inherit standard IO was created, but it didn't shut down when
+        /// the test exited.
+        leaked: bool,
+    },
+    /// The test passed, but was marked as failed through [`TestRunnerBuilder::set_simulated_failures`].
+    ///
+    /// This is treated as a failure.
+    SimulatedFailure,
+    /// The test passed, but left processes running after it exited, with
+    /// [`TestRunnerBuilder::set_fail_on_leaked_processes`] set.
+    ///
+    /// This is treated as a failure.
+    ProcessLeak,
 }
 
 impl ExecutionResult {
@@ -1526,9 +3532,12 @@ impl ExecutionResult {
     pub fn is_success(self) -> bool {
         match self {
             ExecutionResult::Pass | ExecutionResult::Leak => true,
-            ExecutionResult::Fail { .. } | ExecutionResult::ExecFail | ExecutionResult::Timeout => {
-                false
-            }
+            ExecutionResult::Fail { .. }
+            | ExecutionResult::ExecFail
+            | ExecutionResult::Timeout
+            | ExecutionResult::StderrWritten { .. }
+            | ExecutionResult::SimulatedFailure
+            | ExecutionResult::ProcessLeak => false,
         }
     }
 }
@@ -1614,6 +3623,28 @@ mod imp {
         // TODO: set process group on Windows for better ctrl-C handling.
     }
 
+    pub(super) fn set_process_priority(
+        cmd: &mut std::process::Command,
+        priority: TestProcessPriority,
+    ) {
+        use std::os::windows::process::CommandExt;
+
+        // Priority classes passed to CreateProcess, which has the same effect as calling
+        // SetPriorityClass right after the process is created.
+        const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+        const ABOVE_NORMAL_PRIORITY_CLASS: u32 = 0x0000_8000;
+
+        match priority {
+            TestProcessPriority::Nice => {
+                cmd.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+            }
+            TestProcessPriority::Normal => {}
+            TestProcessPriority::High => {
+                cmd.creation_flags(ABOVE_NORMAL_PRIORITY_CLASS);
+            }
+        }
+    }
+
     pub(super) fn assign_process_to_job(
         child: &tokio::process::Child,
         job: Option<&Job>,
@@ -1657,6 +3688,11 @@ mod imp {
         // Start killing the process directly for good measure.
         let _ = child.start_kill();
     }
+
+    pub(super) fn leaked_processes(_pid: u32, job: Option<&Job>) -> Vec<u32> {
+        // The test process has exited, so anything left in its job was spawned by it.
+        job.map_or_else(Vec::new, crate::process_leak::processes_in_job)
+    }
 }
 
 #[cfg(unix)]
@@ -1680,6 +3716,25 @@ mod imp {
         cmd.process_group(0);
     }
 
+    pub(super) fn set_process_priority(
+        cmd: &mut std::process::Command,
+        priority: TestProcessPriority,
+    ) {
+        let niceness = match priority {
+            TestProcessPriority::Nice => 10,
+            TestProcessPriority::Normal => return,
+            TestProcessPriority::High => -10,
+        };
+        unsafe {
+            cmd.pre_exec(move || {
+                // This is best-effort: raising the priority requires privileges, and if nextest is
+                // already running at a lower priority, it can't be raised back to 10 either.
+                libc::setpriority(libc::PRIO_PROCESS, 0, niceness);
+                Ok(())
+            });
+        }
+    }
+
     #[derive(Debug)]
     pub(super) struct Job(());
 
@@ -1696,6 +3751,19 @@ mod imp {
         Ok(())
     }
 
+    pub(super) fn leaked_processes(pid: u32, _job: Option<&Job>) -> Vec<u32> {
+        // The test was started in its own process group, whose ID is the test's process ID. The
+        // group outlives the test process if anything it spawned is still running.
+        cfg_if::cfg_if! {
+            if #[cfg(target_os = "linux")] {
+                crate::process_leak::processes_in_group(pid)
+            } else {
+                let _ = pid;
+                Vec::new()
+            }
+        }
+    }
+
     pub(super) fn job_control_child(child: &Child, event: JobControlEvent) {
         if let Some(pid) = child.id() {
             let pid = pid as i32;
@@ -1704,16 +3772,26 @@ mod imp {
                 JobControlEvent::Stop => SIGTSTP,
                 JobControlEvent::Continue => SIGCONT,
             };
-            unsafe {
-                // We set up a process group while starting the test -- now send a signal to that
-                // group.
-                libc::kill(-pid, signal);
-            }
+            signal_test(pid, signal);
         } else {
             // The child exited already -- don't send a signal.
         }
     }
 
+    /// Sends `signal` to the process group that the test with ID `pid` was started in.
+    ///
+    /// Tests run with stdin passthrough aren't started in their own process group, so the signal is
+    /// sent to just the test in that case.
+    fn signal_test(pid: i32, signal: libc::c_int) {
+        unsafe {
+            if libc::kill(-pid, signal) == -1
+                && std::io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH)
+            {
+                libc::kill(pid, signal);
+            }
+        }
+    }
+
     // Note this is SIGSTOP rather than SIGTSTP to avoid triggering our signal handler.
     pub(super) fn raise_stop() {
         // This can never error out because SIGSTOP is a valid signal.
@@ -1745,11 +3823,7 @@ mod imp {
                 }
                 TerminateMode::Signal(ShutdownForwardEvent::Twice) => SIGKILL,
             };
-            unsafe {
-                // We set up a process group while starting the test -- now send a signal to that
-                // group.
-                libc::kill(-pid, term_signal)
-            };
+            signal_test(pid, term_signal);
 
             if term_signal == SIGKILL {
                 // SIGKILL guarantees the process group is dead.
@@ -1786,20 +3860,16 @@ mod imp {
                             SignalForwardEvent::Shutdown(_) => {
                                 // Receiving a shutdown signal while in this state always means kill
                                 // immediately.
-                                unsafe {
-                                    // Send SIGKILL to the entire process group.
-                                    libc::kill(-pid, SIGKILL);
-                                }
+                                // Send SIGKILL to the entire process group.
+                                signal_test(pid, SIGKILL);
                                 break;
                             }
                         }
                     }
                     _ = &mut sleep => {
                         // The process didn't exit -- need to do a hard shutdown.
-                        unsafe {
-                            // Send SIGKILL to the entire process group.
-                            libc::kill(-pid, SIGKILL);
-                        }
+                        // Send SIGKILL to the entire process group.
+                        signal_test(pid, SIGKILL);
                         break;
                     }
                 }
@@ -1817,7 +3887,22 @@ enum TerminateMode {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config::NextestConfig, platform::BuildPlatforms};
+    use crate::{
+        cargo_config::EnvironmentMap,
+        config::NextestConfig,
+        list::{
+            test_helpers::{package_metadata, HELPER_PACKAGE_ID},
+            RustBuildMeta, RustTestArtifact,
+        },
+        platform::BuildPlatforms,
+        reuse_build::PathMapper,
+        test_filter::{RunIgnored, TestFilterBuilder},
+    };
+    use nextest_metadata::{BuildPlatform, RustBinaryId, RustTestBinaryKind};
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        ffi::OsStr,
+    };
 
     #[test]
     fn no_capture_settings() {
@@ -1844,6 +3929,591 @@ mod tests {
         assert_eq!(runner.inner.test_threads, 1, "tests run serially");
     }
 
+    #[test]
+    fn test_split_by_total_len() {
+        let names = ["aaaa", "bb", "cc", "dddddddd", "e"];
+        assert_eq!(
+            split_by_total_len(&names, 6, |name| name.len()),
+            [&["aaaa", "bb"][..], &["cc"], &["dddddddd"], &["e"]],
+            "items that don't fit on their own get a chunk to themselves",
+        );
+        assert_eq!(
+            split_by_total_len(&names, 100, |name| name.len()),
+            [&names[..]],
+        );
+        assert!(split_by_total_len(&[] as &[&str], 6, |name| name.len()).is_empty());
+    }
+
+    #[test]
+    fn test_preload_env_value() {
+        let libraries = [
+            Utf8PathBuf::from("/opt/libcheck.so"),
+            Utf8PathBuf::from("libjemalloc.so"),
+        ];
+        assert_eq!(
+            preload_env_value(&libraries, None),
+            "/opt/libcheck.so:libjemalloc.so",
+        );
+        assert_eq!(
+            preload_env_value(&libraries, Some("".into())),
+            "/opt/libcheck.so:libjemalloc.so",
+        );
+        assert_eq!(
+            preload_env_value(&libraries, Some("libasan.so:/usr/lib/libfoo.so".into())),
+            "/opt/libcheck.so:libjemalloc.so:libasan.so:/usr/lib/libfoo.so",
+            "existing libraries are loaded after the ones passed in",
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preload_libraries_lookup() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let build = |library: &str| {
+            let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+            let mut builder = TestRunnerBuilder::default();
+            builder.set_preload_libraries([Utf8PathBuf::from(library)]);
+            builder
+                .build(
+                    &test_list,
+                    profile.apply_build_platforms(&build_platforms),
+                    SignalHandlerKind::Noop,
+                    DoubleSpawnInfo::disabled(),
+                    TargetRunner::empty(),
+                )
+                .map(|runner| runner.inner.preload_libraries)
+        };
+
+        // Bare names are left to the dynamic linker to find.
+        assert_eq!(
+            build("libjemalloc.so").unwrap(),
+            [Utf8PathBuf::from("libjemalloc.so")],
+        );
+        assert!(matches!(
+            build("./does-not-exist/libfoo.so"),
+            Err(TestRunnerBuildError::PreloadLibraryNotFound(_)),
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preload_libraries_with_test_wrapper() {
+        let test_filter = TestFilterBuilder::any(RunIgnored::Default);
+        let test_binary = RustTestArtifact {
+            binary_path: "/fake/binary".into(),
+            cwd: "/fake/cwd".into(),
+            package: package_metadata(HELPER_PACKAGE_ID),
+            binary_name: "fake-binary".to_owned(),
+            binary_id: RustBinaryId::new("metadata-helper::fake-binary"),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let rust_build_meta = RustBuildMeta::new("/fake", None).map_paths(&PathMapper::noop());
+        let test_list = TestList::new_with_outputs(
+            [(test_binary, "tests::test_foo: test\n", "")],
+            rust_build_meta,
+            &test_filter,
+            EnvironmentMap::empty(),
+        )
+        .expect("valid output");
+        let test_instance = test_list.iter_tests().next().expect("test list has a test");
+        let config = NextestConfig::default_config("/fake/dir");
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let preload_var = PRELOAD_ENV_VAR.expect("preloading is supported on Unix");
+
+        // Returns the program, arguments and environment nextest starts for the test.
+        let make_command = |double_spawn: DoubleSpawnInfo| {
+            let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+            let mut builder = TestRunnerBuilder::default();
+            builder
+                .set_env_inheritance(EnvInheritance::Only(BTreeSet::new()))
+                .set_preload_libraries([Utf8PathBuf::from("libfoo.so")])
+                .set_test_wrapper(CommandWrapper::new("my-wrapper", []));
+            let runner = builder
+                .build(
+                    &test_list,
+                    profile.apply_build_platforms(&build_platforms),
+                    SignalHandlerKind::Noop,
+                    double_spawn,
+                    TargetRunner::empty(),
+                )
+                .unwrap();
+            let ctx = TestExecuteContext {
+                double_spawn: &runner.inner.double_spawn,
+                target_runner: &runner.inner.target_runner,
+                extra_args: &[],
+            };
+            let mut cmd = test_instance.make_command(
+                &ctx,
+                &test_list,
+                false,
+                runner.inner.test_wrapper.as_ref(),
+            );
+            runner.inner.configure_command(cmd.command_mut(), &[], 1);
+            let command = cmd.command_mut();
+            let env: BTreeMap<_, _> = command
+                .get_envs()
+                .filter_map(|(name, value)| Some((name.to_owned(), value?.to_owned())))
+                .collect();
+            (
+                command.get_program().to_owned(),
+                command
+                    .get_args()
+                    .map(ToOwned::to_owned)
+                    .collect::<Vec<_>>(),
+                env,
+            )
+        };
+
+        // Without double-spawning, the variable is set on the wrapper, which passes its
+        // environment on to the test binary.
+        let (program, args, env) = make_command(DoubleSpawnInfo::disabled());
+        assert_eq!(program, "my-wrapper");
+        assert_eq!(args[0], "/fake/binary", "the wrapper runs the test binary");
+        assert_eq!(
+            env.get(OsStr::new(preload_var))
+                .map(|value| value.as_os_str()),
+            Some(OsStr::new("libfoo.so")),
+            "the preload variable is set for the wrapper"
+        );
+
+        // With double-spawning, the double-spawn child sets the variable just before it executes
+        // the wrapper, so the libraries aren't loaded into the child itself.
+        let double_spawn = DoubleSpawnInfo::try_enable();
+        if let Some(current_exe) = double_spawn.current_exe() {
+            let current_exe = current_exe.to_owned();
+            let (program, args, env) = make_command(double_spawn);
+            assert_eq!(program, current_exe);
+            assert_eq!(
+                args[..3],
+                [DoubleSpawnInfo::SUBCOMMAND_NAME, "--", "my-wrapper"],
+                "the double-spawn child executes the wrapper"
+            );
+            assert_eq!(env.get(OsStr::new(preload_var)), None);
+            assert_eq!(
+                env.get(OsStr::new(DoubleSpawnInfo::PRELOAD_ENV))
+                    .map(|value| value.as_os_str()),
+                Some(OsStr::new("libfoo.so")),
+                "the preload variable is passed to the double-spawn child"
+            );
+        }
+    }
+
+    #[test]
+    fn shuffle_seed() {
+        assert_eq!(
+            "random".parse::<ShuffleSeed>().unwrap(),
+            ShuffleSeed::Random
+        );
+        assert_eq!(
+            "RANDOM".parse::<ShuffleSeed>().unwrap(),
+            ShuffleSeed::Random
+        );
+        assert_eq!("42".parse::<ShuffleSeed>().unwrap(), ShuffleSeed::Fixed(42));
+        assert_eq!(ShuffleSeed::Fixed(42).resolve(), 42);
+        "foo".parse::<ShuffleSeed>().unwrap_err();
+        "-1".parse::<ShuffleSeed>().unwrap_err();
+
+        let original: Vec<_> = (0..100).collect();
+        let mut first = original.clone();
+        shuffle_with_seed(&mut first, 42);
+        let mut second = original.clone();
+        shuffle_with_seed(&mut second, 42);
+        assert_eq!(first, second, "same seed produces the same order");
+        assert_ne!(first, original, "order was shuffled");
+
+        let mut third = original;
+        shuffle_with_seed(&mut third, 43);
+        assert_ne!(first, third, "different seeds produce different orders");
+    }
+
+    #[test]
+    fn env_inheritance() {
+        assert_eq!(
+            "all".parse::<EnvInheritance>().unwrap(),
+            EnvInheritance::All
+        );
+        assert_eq!(
+            "none".parse::<EnvInheritance>().unwrap(),
+            EnvInheritance::Only(BTreeSet::new())
+        );
+        let mut inheritance = "PATH,HOME".parse::<EnvInheritance>().unwrap();
+        inheritance.add("TERM");
+        assert_eq!(
+            inheritance,
+            EnvInheritance::Only(["HOME", "PATH", "TERM"].map(String::from).into())
+        );
+        "PATH,".parse::<EnvInheritance>().unwrap_err();
+        "A=B".parse::<EnvInheritance>().unwrap_err();
+
+        let mut cmd = std::process::Command::new("true");
+        cmd.env("NEXTEST", "1");
+        EnvInheritance::Only(BTreeSet::new()).apply(&mut cmd, &EnvironmentMap::empty());
+        let envs: BTreeMap<_, _> = cmd.get_envs().collect();
+        assert_eq!(
+            envs.get(OsStr::new("NEXTEST")),
+            Some(&Some(OsStr::new("1"))),
+            "explicitly set variables are kept"
+        );
+        for (name, value) in std::env::vars_os() {
+            if MINIMAL_INHERITED_ENV.contains(&name.to_str().unwrap_or_default()) {
+                assert_eq!(
+                    envs.get(name.as_os_str()),
+                    Some(&Some(value.as_os_str())),
+                    "{name:?} is always inherited"
+                );
+            } else if name != "CARGO" && name != "NEXTEST" {
+                assert!(
+                    !envs.contains_key(name.as_os_str()),
+                    "{name:?} isn't inherited"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn immediate_retry_condition() {
+        "SIGSEGV".parse::<ImmediateRetryCondition>().unwrap_err();
+        "signal:NOTASIGNAL"
+            .parse::<ImmediateRetryCondition>()
+            .unwrap_err();
+
+        cfg_if::cfg_if! {
+            if #[cfg(unix)] {
+                for input in ["signal:SIGSEGV", "signal:SEGV", "signal:11"] {
+                    assert_eq!(
+                        input.parse::<ImmediateRetryCondition>().unwrap(),
+                        ImmediateRetryCondition::Signal(libc::SIGSEGV),
+                        "{input} parsed correctly"
+                    );
+                }
+
+                let condition = ImmediateRetryCondition::Signal(libc::SIGSEGV);
+                let aborted = |signal| ExecutionResult::Fail {
+                    abort_status: Some(AbortStatus::UnixSignal(signal)),
+                    leaked: false,
+                };
+                assert_eq!(
+                    condition.retry_reason(aborted(libc::SIGSEGV)),
+                    Some(RetryReason::Signal(libc::SIGSEGV))
+                );
+                assert_eq!(condition.retry_reason(aborted(libc::SIGABRT)), None);
+                assert_eq!(condition.retry_reason(ExecutionResult::Timeout), None);
+            } else {
+                "signal:SIGSEGV".parse::<ImmediateRetryCondition>().unwrap_err();
+            }
+        }
+    }
+
+    #[test]
+    fn simulated_failures() {
+        assert!(SimulatedFailures::new(-0.1, 0).is_none());
+        assert!(SimulatedFailures::new(1.5, 0).is_none());
+
+        let failures = |rate, seed| {
+            let simulated = SimulatedFailures::new(rate, seed).expect("rate is valid");
+            (0..1000)
+                .filter(|n| simulated.should_fail("my-crate", &format!("test_{n}"), 1))
+                .collect::<Vec<_>>()
+        };
+        assert!(failures(0.0, 0).is_empty(), "rate 0.0 never fails");
+        assert_eq!(failures(1.0, 0).len(), 1000, "rate 1.0 always fails");
+
+        let first = failures(0.5, 42);
+        assert!(
+            (400..600).contains(&first.len()),
+            "about half of tests fail: {}",
+            first.len()
+        );
+        assert_eq!(first, failures(0.5, 42), "same seed fails the same tests");
+        assert_ne!(
+            first,
+            failures(0.5, 43),
+            "different seeds fail different tests"
+        );
+    }
+
+    #[test]
+    fn min_run_time_settings() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+
+        // The default profile doesn't set a minimum run time.
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let runner = TestRunnerBuilder::default()
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+        assert_eq!(runner.inner.min_run_time, None, "min_run_time is unset");
+
+        let mut builder = TestRunnerBuilder::default();
+        builder.set_min_run_time(Duration::from_micros(1));
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let runner = builder
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+        assert_eq!(
+            runner.inner.min_run_time,
+            Some(Duration::from_micros(1)),
+            "min_run_time is set from the builder"
+        );
+    }
+
+    #[test]
+    fn test_global_retries() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        let unlimited = GlobalRetries::new(None);
+        for _ in 0..100 {
+            assert!(unlimited.try_take(&sender), "retries are unlimited");
+        }
+        assert!(receiver.try_recv().is_err(), "no event is sent");
+
+        let limited = GlobalRetries::new(Some(2));
+        assert!(limited.try_take(&sender), "first retry is taken");
+        assert!(limited.try_take(&sender), "second retry is taken");
+        assert!(!limited.try_take(&sender), "cap is reached");
+        assert!(!limited.try_take(&sender), "cap is still reached");
+        match receiver.try_recv() {
+            Ok(InternalTestEvent::GlobalRetryCapReached { max_retries }) => {
+                assert_eq!(max_retries, 2)
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert!(receiver.try_recv().is_err(), "cap is only reported once");
+    }
+
+    #[test]
+    fn test_thread_stack_size_settings() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+
+        // The default profile doesn't set a stack size.
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let runner = TestRunnerBuilder::default()
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+        assert_eq!(
+            runner.inner.test_thread_stack_size, None,
+            "test_thread_stack_size is unset"
+        );
+
+        let mut builder = TestRunnerBuilder::default();
+        builder.set_test_thread_stack_size(16 * 1024 * 1024);
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let runner = builder
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+        assert_eq!(
+            runner.inner.test_thread_stack_size,
+            Some(16 * 1024 * 1024),
+            "test_thread_stack_size is set from the builder"
+        );
+    }
+
+    #[test]
+    fn stdin_passthrough_settings() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+
+        // Without an explicit thread count, tests are run serially.
+        let mut builder = TestRunnerBuilder::default();
+        builder.set_stdin_passthrough(true);
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let runner = builder
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+        assert!(runner.inner.stdin_passthrough, "stdin_passthrough is true");
+        assert_eq!(runner.inner.test_threads, 1, "tests run serially");
+
+        // An explicit thread count greater than 1 is an error.
+        let mut builder = TestRunnerBuilder::default();
+        builder
+            .set_stdin_passthrough(true)
+            .set_test_threads(TestThreads::Count(4));
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let error = builder
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .expect_err("stdin passthrough with 4 threads fails");
+        match error {
+            TestRunnerBuildError::StdinPassthroughWithParallelism(error) => {
+                assert_eq!(error.test_threads(), 4);
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    /// With stdin passthrough, tests must be able to read from the terminal that nextest is in the
+    /// foreground of, rather than being stopped with SIGTTIN.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stdin_passthrough_from_terminal() {
+        use std::{
+            fs::File,
+            io::{Read, Write},
+            os::unix::{io::FromRawFd, process::CommandExt},
+            process::Command,
+        };
+
+        const HELPER_ENV: &str = "__NEXTEST_STDIN_PASSTHROUGH_HELPER";
+
+        if std::env::var_os(HELPER_ENV).is_some() {
+            // This is the copy of the test process started below, in the foreground of the
+            // terminal. Run a "test" that reads a line from it, the way the runner would.
+            let test_list = TestList::empty();
+            let config = NextestConfig::default_config("/fake/dir");
+            let build_platforms = BuildPlatforms::new(None).unwrap();
+            let mut builder = TestRunnerBuilder::default();
+            builder.set_stdin_passthrough(true);
+            let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+            let runner = builder
+                .build(
+                    &test_list,
+                    profile.apply_build_platforms(&build_platforms),
+                    SignalHandlerKind::Noop,
+                    DoubleSpawnInfo::disabled(),
+                    TargetRunner::empty(),
+                )
+                .unwrap();
+
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", r#"read line && [ "$line" = hello ]"#]);
+            runner.inner.configure_command(&mut cmd, &[], 1);
+            let status = cmd.status().expect("sh spawned");
+            std::process::exit(if status.success() { 0 } else { 1 });
+        }
+
+        let mut master = 0;
+        let mut slave = 0;
+        let res = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(res, 0, "openpty succeeded");
+
+        let mut helper = Command::new(std::env::current_exe().unwrap());
+        helper
+            .args([
+                "--exact",
+                "runner::tests::stdin_passthrough_from_terminal",
+                "--nocapture",
+            ])
+            .env(HELPER_ENV, "1");
+        unsafe {
+            helper.pre_exec(move || {
+                // Start a new session with the pty as its controlling terminal, as a shell would.
+                if libc::setsid() == -1 || libc::ioctl(slave, libc::TIOCSCTTY, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                for fd in 0..3 {
+                    if libc::dup2(slave, fd) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+        let mut helper = helper.spawn().expect("helper spawned");
+        unsafe { libc::close(slave) };
+
+        let mut master = unsafe { File::from_raw_fd(master) };
+        master.write_all(b"hello\n").unwrap();
+        // Drain the terminal so that the helper never blocks writing to it. This stops once the
+        // helper exits, since reads then fail with EIO.
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            while matches!(master.read(&mut buf), Ok(n) if n > 0) {}
+        });
+
+        // A test in a background process group is stopped when it reads, so it never exits.
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let status = loop {
+            if let Some(status) = helper.try_wait().unwrap() {
+                break status;
+            }
+            if Instant::now() > deadline {
+                _ = helper.kill();
+                panic!("test reading from the terminal didn't exit");
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        assert!(status.success(), "test read a line from the terminal");
+    }
+
+    #[test]
+    fn cancellation_token() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+
+        let token = CancellationToken::new();
+        let mut builder = TestRunnerBuilder::default();
+        builder.with_cancellation_token(token.clone());
+        let runner = builder
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+
+        token.cancel();
+        let res = runner.try_execute::<Infallible, _>(|_| Ok(()));
+        assert!(
+            matches!(res, Err(RunError::Cancelled { .. })),
+            "cancelled token => RunError::Cancelled, actual: {res:?}"
+        );
+    }
+
     #[test]
     fn test_is_success() {
         assert!(RunStats::default().is_success(), "empty run => success");
@@ -1963,4 +4633,141 @@ mod tests {
             "skipped => not considered a failure"
         );
     }
+
+    #[test]
+    fn line_splitter_splits_lines() {
+        let mut lines = Vec::new();
+        let mut sink = |line: Bytes| lines.push(line);
+        let mut splitter = LineSplitter::default();
+
+        // Reads can end anywhere, including in the middle of a line.
+        let mut buf = b"first\nsec".to_vec();
+        splitter.split(&buf, &mut sink);
+        buf.extend_from_slice(b"ond\nthird\n\nfou");
+        splitter.split(&buf, &mut sink);
+        buf.extend_from_slice(b"rth");
+        splitter.split(&buf, &mut sink);
+        splitter.finish(&buf, &mut sink);
+
+        assert_eq!(
+            lines,
+            vec![
+                Bytes::from_static(b"first\n"),
+                Bytes::from_static(b"second\n"),
+                Bytes::from_static(b"third\n"),
+                Bytes::from_static(b"\n"),
+                Bytes::from_static(b"fourth"),
+            ]
+        );
+    }
+
+    #[test]
+    fn line_splitter_finish() {
+        // A partial line is only sent once the output ends.
+        let mut lines = Vec::new();
+        let mut splitter = LineSplitter::default();
+        splitter.split(b"no newline", &mut |line| lines.push(line));
+        assert_eq!(lines, Vec::<Bytes>::new());
+        splitter.finish(b"no newline", &mut |line| lines.push(line));
+        assert_eq!(lines, vec![Bytes::from_static(b"no newline")]);
+
+        // If the output ends with a newline, nothing more is sent.
+        let mut lines = Vec::new();
+        let mut sink = |line: Bytes| lines.push(line);
+        let mut splitter = LineSplitter::default();
+        splitter.split(b"line\n", &mut sink);
+        splitter.finish(b"line\n", &mut sink);
+        assert_eq!(lines, vec![Bytes::from_static(b"line\n")]);
+    }
+
+    #[test]
+    fn timing_regression_threshold() {
+        // Durations that are exactly representable as f64 seconds keep the percentages exact.
+        let baseline = Some(Duration::from_secs(4));
+        assert_eq!(
+            timing_regression(baseline, Duration::from_secs(6), 20.0),
+            Some((Duration::from_secs(4), 50.0)),
+            "50% slower than the baseline is a regression"
+        );
+        assert_eq!(
+            timing_regression(baseline, Duration::from_millis(4500), 20.0),
+            None,
+            "12.5% slower than the baseline is within the threshold"
+        );
+        assert_eq!(
+            timing_regression(baseline, Duration::from_secs(5), 25.0),
+            None,
+            "exactly the threshold isn't a regression"
+        );
+        assert_eq!(
+            timing_regression(baseline, Duration::from_secs(5), 0.0),
+            Some((Duration::from_secs(4), 25.0)),
+            "any slowdown is a regression with a threshold of 0"
+        );
+    }
+
+    #[test]
+    fn timing_regression_missing_baseline() {
+        assert_eq!(
+            timing_regression(None, Duration::from_secs(10), 20.0),
+            None,
+            "tests without a baseline aren't regressions"
+        );
+        assert_eq!(
+            timing_regression(Some(Duration::ZERO), Duration::from_secs(10), 20.0),
+            None,
+            "tests with a zero baseline aren't regressions"
+        );
+    }
+
+    #[test]
+    fn timing_regression_faster_than_baseline() {
+        assert_eq!(
+            timing_regression(Some(Duration::from_secs(4)), Duration::from_secs(2), 20.0),
+            None,
+            "tests that are faster than their baseline aren't regressions"
+        );
+        assert_eq!(
+            timing_regression(Some(Duration::from_secs(4)), Duration::from_secs(2), 0.0),
+            None,
+            "tests that are faster than their baseline aren't regressions with a threshold of 0"
+        );
+    }
+
+    #[test]
+    fn capped_grace_period_by_max_test_time() {
+        let grace_period = Duration::from_secs(10);
+        assert_eq!(
+            capped_grace_period(grace_period, None, Duration::from_secs(60)),
+            grace_period,
+            "grace period isn't capped without a maximum test time"
+        );
+        assert_eq!(
+            capped_grace_period(
+                grace_period,
+                Some(Duration::from_secs(60)),
+                Duration::from_secs(5)
+            ),
+            grace_period,
+            "grace period ends before the maximum test time"
+        );
+        assert_eq!(
+            capped_grace_period(
+                grace_period,
+                Some(Duration::from_secs(60)),
+                Duration::from_secs(55)
+            ),
+            Duration::from_secs(5),
+            "grace period is cut short at the maximum test time"
+        );
+        assert_eq!(
+            capped_grace_period(
+                grace_period,
+                Some(Duration::from_secs(60)),
+                Duration::from_secs(61)
+            ),
+            Duration::ZERO,
+            "test is killed immediately once past the maximum test time"
+        );
+    }
 }
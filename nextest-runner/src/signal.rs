@@ -4,6 +4,7 @@
 //! Support for handling signals in nextest.
 
 use crate::errors::SignalHandlerSetupError;
+use tokio_util::sync::CancellationToken;
 
 /// The kind of signal handling to set up for a test run.
 ///
@@ -32,6 +33,9 @@ impl SignalHandlerKind {
 #[derive(Debug)]
 pub(crate) struct SignalHandler {
     signals: Option<imp::Signals>,
+    // If this token is cancelled, the handler produces an interrupt event.
+    cancellation_token: Option<CancellationToken>,
+    cancellation_done: bool,
 }
 
 impl SignalHandler {
@@ -41,18 +45,54 @@ impl SignalHandler {
         let signals = imp::Signals::new()?;
         Ok(Self {
             signals: Some(signals),
+            cancellation_token: None,
+            cancellation_done: false,
         })
     }
 
     /// Creates a new `SignalReceiver` that does nothing.
     pub(crate) fn noop() -> Self {
-        Self { signals: None }
+        Self {
+            signals: None,
+            cancellation_token: None,
+            cancellation_done: false,
+        }
+    }
+
+    /// Sets a cancellation token. Cancelling the token is treated the same way as an interrupt
+    /// signal.
+    pub(crate) fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation_token = Some(token);
+    }
+
+    /// Returns true if the cancellation token, if any, was cancelled.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .map_or(false, |token| token.is_cancelled())
     }
 
     pub(crate) async fn recv(&mut self) -> Option<SignalEvent> {
-        match &mut self.signals {
-            Some(signals) => signals.recv().await,
-            None => None,
+        loop {
+            let token = self.cancellation_token.as_ref();
+            tokio::select! {
+                () = async { token.expect("token is Some").cancelled().await },
+                    if token.is_some() && !self.cancellation_done =>
+                {
+                    // Only produce the cancellation event once.
+                    self.cancellation_done = true;
+                    break Some(SignalEvent::Shutdown(ShutdownEvent::Interrupt));
+                }
+                recv = async { self.signals.as_mut().expect("signals is Some").recv().await },
+                    if self.signals.is_some() =>
+                {
+                    match recv {
+                        Some(event) => break Some(event),
+                        None => self.signals = None,
+                    }
+                }
+                else => break None,
+            }
         }
     }
 }
@@ -0,0 +1,171 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Detection of tests that mutate global state.
+//!
+//! Each test runs in its own process, so changes to environment variables or the working directory
+//! cannot leak from one test to another. The filesystem is shared, though: a test that creates,
+//! removes or modifies files can affect tests that run after it.
+//!
+//! With leaky test detection enabled, nextest snapshots a set of paths before and after each test
+//! and reports any differences as [`StateMutation`]s.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use std::{collections::BTreeMap, fmt, time::SystemTime};
+
+/// A change to global state observed across the run of a single test.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[non_exhaustive]
+pub enum StateMutation {
+    /// A path was created.
+    Created {
+        /// The path that was created.
+        path: Utf8PathBuf,
+    },
+
+    /// A path was removed.
+    Removed {
+        /// The path that was removed.
+        path: Utf8PathBuf,
+    },
+
+    /// The size or modification time of a path changed.
+    Modified {
+        /// The path that was modified.
+        path: Utf8PathBuf,
+    },
+}
+
+impl StateMutation {
+    /// Returns the path affected by this mutation.
+    pub fn path(&self) -> &Utf8Path {
+        match self {
+            Self::Created { path } | Self::Removed { path } | Self::Modified { path } => path,
+        }
+    }
+}
+
+impl fmt::Display for StateMutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Created { path } => write!(f, "created `{path}`"),
+            Self::Removed { path } => write!(f, "removed `{path}`"),
+            Self::Modified { path } => write!(f, "modified `{path}`"),
+        }
+    }
+}
+
+/// A snapshot of the state of a set of paths.
+///
+/// For each path that is a directory, its immediate children are recorded as well. Directories are
+/// not traversed recursively, to keep snapshots cheap.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct StateSnapshot {
+    entries: BTreeMap<Utf8PathBuf, EntryState>,
+}
+
+impl StateSnapshot {
+    /// Takes a snapshot of the given paths.
+    ///
+    /// Paths that cannot be read are treated as missing.
+    pub(crate) fn take<'a>(paths: impl IntoIterator<Item = &'a Utf8Path>) -> Self {
+        let mut entries = BTreeMap::new();
+        for path in paths {
+            let state = match EntryState::read(path) {
+                Some(state) => state,
+                None => continue,
+            };
+            if state.is_dir {
+                if let Ok(read_dir) = path.read_dir_utf8() {
+                    for entry in read_dir.flatten() {
+                        if let Some(child_state) = EntryState::read(entry.path()) {
+                            entries.insert(entry.path().to_owned(), child_state);
+                        }
+                    }
+                }
+            }
+            entries.insert(path.to_owned(), state);
+        }
+
+        Self { entries }
+    }
+
+    /// Returns the mutations needed to go from `self` to `after`, sorted by path.
+    pub(crate) fn diff(&self, after: &Self) -> Vec<StateMutation> {
+        let mut mutations = Vec::new();
+        for (path, before_state) in &self.entries {
+            match after.entries.get(path) {
+                Some(after_state) => {
+                    if before_state != after_state && !before_state.is_dir {
+                        mutations.push(StateMutation::Modified { path: path.clone() });
+                    }
+                }
+                None => mutations.push(StateMutation::Removed { path: path.clone() }),
+            }
+        }
+        for path in after.entries.keys() {
+            if !self.entries.contains_key(path) {
+                mutations.push(StateMutation::Created { path: path.clone() });
+            }
+        }
+
+        mutations.sort_by(|a, b| a.path().cmp(b.path()));
+        mutations
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct EntryState {
+    is_dir: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl EntryState {
+    fn read(path: &Utf8Path) -> Option<Self> {
+        // Use symlink_metadata so that symlinks themselves are tracked, not their targets.
+        let metadata = path.symlink_metadata().ok()?;
+        Some(Self {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).expect("temp dir is valid UTF-8");
+        std::fs::write(root.join("unchanged"), "foo").unwrap();
+        std::fs::write(root.join("modified"), "foo").unwrap();
+        std::fs::write(root.join("removed"), "foo").unwrap();
+
+        let before = StateSnapshot::take([root]);
+        assert_eq!(before.diff(&before), vec![], "no mutations against itself");
+
+        std::fs::write(root.join("modified"), "foobar").unwrap();
+        std::fs::remove_file(root.join("removed")).unwrap();
+        std::fs::write(root.join("created"), "foo").unwrap();
+
+        let after = StateSnapshot::take([root]);
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                StateMutation::Created {
+                    path: root.join("created")
+                },
+                StateMutation::Modified {
+                    path: root.join("modified")
+                },
+                StateMutation::Removed {
+                    path: root.join("removed")
+                },
+            ]
+        );
+    }
+}
@@ -10,7 +10,8 @@ use crate::{
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use nextest_metadata::BuildPlatform;
-use std::fmt;
+use object::{Architecture, Object};
+use std::{ffi::OsStr, fmt};
 use target_spec::Platform;
 
 /// A [target runner](https://doc.rust-lang.org/cargo/reference/config.html#targettriplerunner)
@@ -75,6 +76,38 @@ impl TargetRunner {
             (BuildPlatform::Host, self.host()),
         ]
     }
+
+    /// Checks that runner binaries are able to run test binaries for their platforms.
+    ///
+    /// Returns [`TargetRunnerError::IncompatibleRunnerArch`] if a runner's architecture could be
+    /// determined and doesn't match the architecture it runs test binaries for.
+    pub fn check_arch(&self, build_platforms: &BuildPlatforms) -> Result<(), TargetRunnerError> {
+        let target_platform = match &build_platforms.target {
+            Some(target) => &target.platform,
+            None => &build_platforms.host,
+        };
+        if let Some(runner) = &self.target {
+            runner.check_arch(target_platform, &build_platforms.host)?;
+        }
+        if let Some(runner) = &self.host {
+            runner.check_arch(&build_platforms.host, &build_platforms.host)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that runner binaries exist and are executable.
+    ///
+    /// Returns [`TargetRunnerError::BinaryNotFound`] or
+    /// [`TargetRunnerError::BinaryNotExecutable`] for the first runner that fails these checks.
+    pub fn check_binaries(&self) -> Result<(), TargetRunnerError> {
+        if let Some(runner) = &self.target {
+            runner.check_binary()?;
+        }
+        if let Some(runner) = &self.host {
+            runner.check_binary()?;
+        }
+        Ok(())
+    }
 }
 
 /// A target runner scoped to a specific platform (host or target).
@@ -286,6 +319,176 @@ impl PlatformRunner {
     pub fn source(&self) -> &PlatformRunnerSource {
         &self.source
     }
+
+    fn check_arch(&self, platform: &Platform, host: &Platform) -> Result<(), TargetRunnerError> {
+        // Architectures that aren't recognized aren't checked, to avoid false mismatches.
+        let (target_arch, host_arch) = match (triple_arch(platform), triple_arch(host)) {
+            (Some(target_arch), Some(host_arch)) => (target_arch, host_arch),
+            _ => return Ok(()),
+        };
+        let runner_arch = match self.runner_arch(host_arch) {
+            Some(runner_arch) => runner_arch,
+            None => return Ok(()),
+        };
+
+        if runner_arch == target_arch {
+            Ok(())
+        } else {
+            Err(TargetRunnerError::IncompatibleRunnerArch {
+                runner: self.runner_binary.clone(),
+                runner_arch: runner_arch.to_owned(),
+                target_arch: target_arch.to_owned(),
+            })
+        }
+    }
+
+    fn check_binary(&self) -> Result<(), TargetRunnerError> {
+        let path = self
+            .resolve_binary()
+            .filter(|path| path.is_file())
+            .ok_or_else(|| TargetRunnerError::BinaryNotFound {
+                runner: self.runner_binary.clone(),
+                key: self.source.clone(),
+            })?;
+        if is_executable(&path) {
+            Ok(())
+        } else {
+            Err(TargetRunnerError::BinaryNotExecutable {
+                runner: path,
+                key: self.source.clone(),
+            })
+        }
+    }
+
+    /// Returns the architecture this runner runs binaries for, or `None` if it couldn't be
+    /// determined.
+    fn runner_arch(&self, host_arch: &str) -> Option<&'static str> {
+        // QEMU user-mode emulators are named after the architecture they emulate.
+        if let Some(arch) = qemu_user_arch(&self.runner_binary) {
+            return Some(arch);
+        }
+
+        let path = self.resolve_binary()?;
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(error) => {
+                log::debug!("unable to read runner binary `{path}`: {error}");
+                return None;
+            }
+        };
+        let arch = match object::File::parse(&*data) {
+            Ok(file) => object_arch(file.architecture())?,
+            Err(error) => {
+                log::debug!("unable to parse runner binary `{path}`: {error}");
+                return None;
+            }
+        };
+
+        // Runners built for the host are assumed to be emulators or wrappers (e.g. wine or
+        // probe-rs), which can run binaries for any architecture.
+        (arch != host_arch).then_some(arch)
+    }
+
+    fn resolve_binary(&self) -> Option<Utf8PathBuf> {
+        if self.runner_binary.components().count() > 1 {
+            return Some(self.runner_binary.clone());
+        }
+
+        // A pathless name: look it up in PATH.
+        let paths = std::env::var_os("PATH")?;
+        find_in_path(&self.runner_binary, &paths)
+    }
+}
+
+/// Looks up a pathless binary name in `paths`, formatted like the `PATH` environment variable.
+///
+/// Like shells do, files that aren't executable are skipped.
+fn find_in_path(name: &Utf8Path, paths: &OsStr) -> Option<Utf8PathBuf> {
+    std::env::split_paths(paths)
+        .filter_map(|dir| Utf8PathBuf::try_from(dir).ok())
+        .flat_map(|dir| {
+            let path = dir.join(name);
+            let exe_path = path.with_extension(std::env::consts::EXE_EXTENSION);
+            [path, exe_path]
+        })
+        .find(|path| path.is_file() && is_executable(path))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Utf8Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    match path.metadata() {
+        Ok(metadata) => metadata.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Utf8Path) -> bool {
+    // Windows has no executable bit: any file that exists can be attempted.
+    true
+}
+
+/// Returns the architecture emulated by a QEMU user-mode runner, e.g. `qemu-aarch64-static`.
+fn qemu_user_arch(runner_binary: &Utf8Path) -> Option<&'static str> {
+    let name = runner_binary.file_stem()?;
+    let arch = name.strip_prefix("qemu-")?;
+    let arch = arch.strip_suffix("-static").unwrap_or(arch);
+    if arch.starts_with("system-") {
+        // Full-system emulators don't run binaries directly.
+        return None;
+    }
+    normalize_arch(arch)
+}
+
+fn triple_arch(platform: &Platform) -> Option<&'static str> {
+    let arch = platform.triple_str().split('-').next().unwrap_or_default();
+    normalize_arch(arch)
+}
+
+/// Maps architecture names in target triples and QEMU binaries to a common set of names, or
+/// returns `None` if the architecture isn't recognized.
+fn normalize_arch(arch: &str) -> Option<&'static str> {
+    let arch = match arch {
+        "x86_64" | "amd64" => "x86_64",
+        "i386" | "i486" | "i586" | "i686" | "x86" => "x86",
+        "aarch64" | "aarch64_be" | "arm64" | "arm64e" => "aarch64",
+        "powerpc64" | "powerpc64le" | "ppc64" | "ppc64le" => "powerpc64",
+        "powerpc" | "ppc" => "powerpc",
+        "s390x" => "s390x",
+        "loongarch64" => "loongarch64",
+        "sparc64" | "sparcv9" => "sparc64",
+        "wasm32" => "wasm32",
+        other if other.starts_with("arm") || other.starts_with("thumb") => "arm",
+        other if other.starts_with("riscv64") => "riscv64",
+        other if other.starts_with("riscv32") => "riscv32",
+        other if other.starts_with("mips64") => "mips64",
+        other if other.starts_with("mips") => "mips",
+        _ => return None,
+    };
+    Some(arch)
+}
+
+fn object_arch(arch: Architecture) -> Option<&'static str> {
+    let arch = match arch {
+        Architecture::X86_64 | Architecture::X86_64_X32 => "x86_64",
+        Architecture::I386 => "x86",
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => "aarch64",
+        Architecture::Arm => "arm",
+        Architecture::PowerPc64 => "powerpc64",
+        Architecture::PowerPc => "powerpc",
+        Architecture::S390x => "s390x",
+        Architecture::LoongArch64 => "loongarch64",
+        Architecture::Sparc64 => "sparc64",
+        Architecture::Wasm32 => "wasm32",
+        Architecture::Riscv64 => "riscv64",
+        Architecture::Riscv32 => "riscv32",
+        Architecture::Mips64 => "mips64",
+        Architecture::Mips => "mips",
+        _ => return None,
+    };
+    Some(arch)
 }
 
 /// The place where a platform runner's configuration was picked up from.
@@ -595,6 +798,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_arch() {
+        assert_eq!(qemu_user_arch("qemu-aarch64".into()), Some("aarch64"));
+        assert_eq!(
+            qemu_user_arch("/usr/bin/qemu-arm-static".into()),
+            Some("arm")
+        );
+        assert_eq!(qemu_user_arch("qemu-riscv64".into()), Some("riscv64"));
+        assert_eq!(qemu_user_arch("qemu-system-x86_64".into()), None);
+        assert_eq!(qemu_user_arch("wine".into()), None);
+        assert_eq!(qemu_user_arch("qemu-sparc64".into()), Some("sparc64"));
+        assert_eq!(qemu_user_arch("qemu-hexagon".into()), None);
+
+        let host = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        let runner = |runner_binary: &str| PlatformRunner {
+            runner_binary: runner_binary.into(),
+            args: vec![],
+            source: PlatformRunnerSource::Env("CARGO_TARGET_RUNNER".into()),
+        };
+
+        let target = Platform::new("aarch64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        runner("qemu-aarch64")
+            .check_arch(&target, &host)
+            .expect("runner matches target");
+        let target =
+            Platform::new("armv7-unknown-linux-gnueabihf", TargetFeatures::Unknown).unwrap();
+        runner("qemu-arm")
+            .check_arch(&target, &host)
+            .expect("runner matches target");
+
+        let err = runner("qemu-aarch64")
+            .check_arch(&host, &host)
+            .expect_err("runner doesn't match target");
+        match err {
+            TargetRunnerError::IncompatibleRunnerArch {
+                runner,
+                runner_arch,
+                target_arch,
+            } => {
+                assert_eq!(runner, "qemu-aarch64");
+                assert_eq!(runner_arch, "aarch64");
+                assert_eq!(target_arch, "x86_64");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+
+        // Runners built for the host are assumed to be emulators, and aren't checked.
+        let current_exe: Utf8PathBuf = std::env::current_exe().unwrap().try_into().unwrap();
+        runner(current_exe.as_str())
+            .check_arch(&target, &Platform::current().unwrap())
+            .expect("host runners are skipped");
+
+        // Runners whose architecture can't be determined aren't checked.
+        runner("nonexistent-runner")
+            .check_arch(&target, &host)
+            .expect("unknown runners are skipped");
+
+        // Neither are targets with unrecognized architectures.
+        let target = Platform::new("sparcv9-sun-solaris", TargetFeatures::Unknown).unwrap();
+        runner("qemu-sparc64")
+            .check_arch(&target, &host)
+            .expect("sparcv9 is sparc64");
+        let target = Platform::new("hexagon-unknown-linux-musl", TargetFeatures::Unknown).unwrap();
+        runner("qemu-aarch64")
+            .check_arch(&target, &host)
+            .expect("unknown target architectures are skipped");
+    }
+
+    #[test]
+    fn test_check_binary() {
+        let runner = |runner_binary: &str| PlatformRunner {
+            runner_binary: runner_binary.into(),
+            args: vec![],
+            source: PlatformRunnerSource::Env("CARGO_TARGET_RUNNER".into()),
+        };
+
+        let current_exe: Utf8PathBuf = std::env::current_exe().unwrap().try_into().unwrap();
+        runner(current_exe.as_str())
+            .check_binary()
+            .expect("current exe is a valid runner");
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir = Utf8Path::from_path(dir.path()).unwrap();
+        let missing = dir.join("missing-runner");
+        match runner(missing.as_str()).check_binary() {
+            Err(TargetRunnerError::BinaryNotFound { runner, .. }) => assert_eq!(runner, missing),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        match runner("nextest-nonexistent-runner").check_binary() {
+            Err(TargetRunnerError::BinaryNotFound { .. }) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        if cfg!(unix) {
+            let not_executable = dir.join("not-executable");
+            std::fs::write(&not_executable, "").unwrap();
+            match runner(not_executable.as_str()).check_binary() {
+                Err(TargetRunnerError::BinaryNotExecutable { runner, .. }) => {
+                    assert_eq!(runner, not_executable)
+                }
+                other => panic!("unexpected result: {other:?}"),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_in_path_skips_non_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir = Utf8Path::from_path(dir.path()).unwrap();
+        let first_dir = dir.join("first");
+        let second_dir = dir.join("second");
+        std::fs::create_dir(&first_dir).unwrap();
+        std::fs::create_dir(&second_dir).unwrap();
+
+        // The runner in the first directory isn't executable, so the one in the second directory
+        // is used.
+        std::fs::write(first_dir.join("my-runner"), "").unwrap();
+        let executable = second_dir.join("my-runner");
+        std::fs::write(&executable, "").unwrap();
+        std::fs::set_permissions(&executable, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let paths = std::env::join_paths([&first_dir, &second_dir]).unwrap();
+        assert_eq!(
+            find_in_path(Utf8Path::new("my-runner"), &paths),
+            Some(executable),
+            "non-executable file in PATH is skipped"
+        );
+
+        let paths = std::env::join_paths([&first_dir]).unwrap();
+        assert_eq!(
+            find_in_path(Utf8Path::new("my-runner"), &paths),
+            None,
+            "no executable file in PATH"
+        );
+    }
+
     fn setup_temp_dir() -> Result<TempDir> {
         let dir = tempfile::Builder::new()
             .tempdir()
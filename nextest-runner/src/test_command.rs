@@ -21,6 +21,7 @@ pub(crate) struct LocalExecuteContext<'a> {
     pub(crate) runner: &'a TargetRunner,
     pub(crate) dylib_path: &'a OsStr,
     pub(crate) env: &'a EnvironmentMap,
+    pub(crate) extra_args: &'a [String],
 }
 
 /// Represents a to-be-run test command for a test binary with a certain set of arguments.
@@ -10,16 +10,26 @@
 // result
 
 use crate::{
-    errors::TestFilterBuilderError,
+    errors::{
+        BinaryNameGlobParseError, TestFilterBuilderError, WorkspaceFilterParseError,
+        WorkspacePathGlobParseError,
+    },
     helpers::convert_build_platform,
     list::RustTestArtifact,
     partition::{Partitioner, PartitionerBuilder},
 };
 use aho_corasick::AhoCorasick;
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use guppy::graph::{PackageGraph, PackageMetadata};
 use nextest_filtering::{BinaryQuery, FilteringExpr, TestQuery};
 use nextest_metadata::{FilterMatch, MismatchReason};
+use std::str::FromStr;
 
 /// Whether to run ignored tests.
+///
+/// This is applied while filtering the test list, before any shuffling of the run order (see
+/// [`TestRunnerBuilder::set_shuffle_seed`](crate::runner::TestRunnerBuilder::set_shuffle_seed)),
+/// so the two compose: a shuffled run selects the same tests as an unshuffled one.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
 pub enum RunIgnored {
     /// Only run tests that aren't ignored.
@@ -305,6 +315,246 @@ impl FilterNameMatch {
     }
 }
 
+/// A glob pattern matched against the names of test binaries.
+///
+/// Used to construct a [`BinaryNameFilter`].
+#[derive(Clone, Debug)]
+pub struct BinaryNameGlob {
+    matcher: GlobMatcher,
+}
+
+impl BinaryNameGlob {
+    /// Returns the glob pattern as a string.
+    pub fn as_str(&self) -> &str {
+        self.matcher.glob().glob()
+    }
+}
+
+impl FromStr for BinaryNameGlob {
+    type Err = BinaryNameGlobParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let glob = Glob::new(s).map_err(|error| BinaryNameGlobParseError::new(s, error))?;
+        Ok(Self {
+            matcher: glob.compile_matcher(),
+        })
+    }
+}
+
+/// Filters test binaries by name, before tests are listed from them.
+///
+/// A binary matches if its name matches any of the globs. If no globs are specified, all binaries
+/// match.
+#[derive(Clone, Debug, Default)]
+pub struct BinaryNameFilter {
+    globs: Vec<BinaryNameGlob>,
+}
+
+impl BinaryNameFilter {
+    /// Creates a new `BinaryNameFilter` from the given globs.
+    pub fn new(globs: impl IntoIterator<Item = BinaryNameGlob>) -> Self {
+        Self {
+            globs: globs.into_iter().collect(),
+        }
+    }
+
+    /// Returns true if the given test binary matches this filter.
+    pub fn is_match(&self, artifact: &RustTestArtifact<'_>) -> bool {
+        self.is_name_match(&artifact.binary_name)
+    }
+
+    fn is_name_match(&self, binary_name: &str) -> bool {
+        self.globs.is_empty()
+            || self
+                .globs
+                .iter()
+                .any(|glob| glob.matcher.is_match(binary_name))
+    }
+}
+
+/// An expression matched against the `[package.metadata]` table of workspace packages.
+///
+/// Two forms are supported:
+/// * `metadata.key = "value"` matches if `key` is set to the string `value`. Booleans and numbers
+///   are compared through their string representations.
+/// * `metadata.key exists` matches if `key` is set at all.
+///
+/// Keys may be nested with `.`, e.g. `metadata.ci.category = "backend"`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WorkspaceFilterExpr {
+    key_path: Vec<String>,
+    value: Option<String>,
+}
+
+impl WorkspaceFilterExpr {
+    /// Returns true if the given package's metadata matches this expression.
+    pub fn is_match(&self, package: &PackageMetadata<'_>) -> bool {
+        self.is_metadata_match(package.metadata_table())
+    }
+
+    fn is_metadata_match(&self, metadata: &serde_json::Value) -> bool {
+        let value = self
+            .key_path
+            .iter()
+            .try_fold(metadata, |value, key| value.get(key));
+        match (value, &self.value) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(serde_json::Value::String(actual)), Some(expected)) => actual == expected,
+            (Some(serde_json::Value::Bool(actual)), Some(expected)) => {
+                expected.parse::<bool>() == Ok(*actual)
+            }
+            (Some(serde_json::Value::Number(actual)), Some(expected)) => {
+                expected.parse::<serde_json::Number>().ok().as_ref() == Some(actual)
+            }
+            (Some(_), Some(_)) => false,
+        }
+    }
+}
+
+impl FromStr for WorkspaceFilterExpr {
+    type Err = WorkspaceFilterParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let err = |message| WorkspaceFilterParseError::new(input, message);
+
+        let (key_path, value) = if let Some(key_path) = input.trim().strip_suffix(" exists") {
+            (key_path, None)
+        } else if let Some((key_path, value)) = input.split_once('=') {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .filter(|value| !value.contains('"'))
+                .ok_or_else(|| err("value must be a double-quoted string"))?;
+            (key_path, Some(value.to_owned()))
+        } else {
+            return Err(err("expected `= \"value\"` or `exists`"));
+        };
+
+        let key_path = key_path
+            .trim()
+            .strip_prefix("metadata.")
+            .ok_or_else(|| err("key must start with `metadata.`"))?;
+        let key_path = key_path
+            .split('.')
+            .map(|key| {
+                let is_valid = !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                is_valid
+                    .then(|| key.to_owned())
+                    .ok_or_else(|| err("keys must be non-empty and only contain `A-Za-z0-9_-`"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { key_path, value })
+    }
+}
+
+/// Filters test binaries by the metadata of the workspace package they belong to.
+///
+/// A binary matches if its package matches any of the expressions. If no expressions are
+/// specified, all binaries match.
+#[derive(Clone, Debug, Default)]
+pub struct WorkspaceFilter {
+    exprs: Vec<WorkspaceFilterExpr>,
+}
+
+impl WorkspaceFilter {
+    /// Creates a new `WorkspaceFilter` from the given expressions.
+    pub fn new(exprs: impl IntoIterator<Item = WorkspaceFilterExpr>) -> Self {
+        Self {
+            exprs: exprs.into_iter().collect(),
+        }
+    }
+
+    /// Returns true if the given test binary matches this filter.
+    pub fn is_match(&self, artifact: &RustTestArtifact<'_>) -> bool {
+        self.exprs.is_empty()
+            || self
+                .exprs
+                .iter()
+                .any(|expr| expr.is_match(&artifact.package))
+    }
+}
+
+/// A glob pattern matched against the paths of workspace packages, relative to the workspace root.
+///
+/// `*` doesn't match `/`, so `vendor/*` matches packages directly within `vendor`, while
+/// `vendor/**` matches packages at any depth.
+#[derive(Clone, Debug)]
+pub struct WorkspacePathGlob {
+    matcher: GlobMatcher,
+}
+
+impl WorkspacePathGlob {
+    /// Returns the glob pattern as a string.
+    pub fn as_str(&self) -> &str {
+        self.matcher.glob().glob()
+    }
+}
+
+impl FromStr for WorkspacePathGlob {
+    type Err = WorkspacePathGlobParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let glob = GlobBuilder::new(s)
+            .literal_separator(true)
+            .build()
+            .map_err(|error| WorkspacePathGlobParseError::new(s, error))?;
+        Ok(Self {
+            matcher: glob.compile_matcher(),
+        })
+    }
+}
+
+/// Filters out workspace packages whose paths match any of a set of globs.
+///
+/// If no globs are specified, no packages are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct WorkspaceIgnoreFilter {
+    globs: Vec<WorkspacePathGlob>,
+}
+
+impl WorkspaceIgnoreFilter {
+    /// Creates a new `WorkspaceIgnoreFilter` from the given globs.
+    pub fn new(globs: impl IntoIterator<Item = WorkspacePathGlob>) -> Self {
+        Self {
+            globs: globs.into_iter().collect(),
+        }
+    }
+
+    /// Returns true if the given test binary's package isn't ignored.
+    pub fn is_match(&self, artifact: &RustTestArtifact<'_>) -> bool {
+        !self.is_ignored(&artifact.package)
+    }
+
+    /// Returns true if the given package is in the workspace and its path matches a glob.
+    pub fn is_ignored(&self, package: &PackageMetadata<'_>) -> bool {
+        match package.source().workspace_path() {
+            Some(path) => self.globs.iter().any(|glob| glob.matcher.is_match(path)),
+            None => false,
+        }
+    }
+
+    /// Returns the names of the workspace packages that are ignored, in sorted order.
+    pub fn ignored_packages(&self, graph: &PackageGraph) -> Vec<String> {
+        if self.globs.is_empty() {
+            return Vec::new();
+        }
+        let mut names: Vec<_> = graph
+            .workspace()
+            .iter()
+            .filter(|package| self.is_ignored(package))
+            .map(|package| package.name().to_owned())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -375,4 +625,89 @@ mod tests {
     //         cwd: "/fake".into(),
     //     }
     // }
+
+    #[test]
+    fn binary_name_filter() {
+        let filter = BinaryNameFilter::default();
+        assert!(
+            filter.is_name_match("anything"),
+            "empty filter matches everything"
+        );
+
+        let filter = BinaryNameFilter::new(
+            ["*_integration", "basic"]
+                .into_iter()
+                .map(|glob| glob.parse().expect("valid glob")),
+        );
+        assert!(filter.is_name_match("foo_integration"));
+        assert!(filter.is_name_match("basic"));
+        assert!(!filter.is_name_match("basic_unit"));
+        assert!(!filter.is_name_match("foo_integration_2"));
+
+        "foo[".parse::<BinaryNameGlob>().expect_err("invalid glob");
+    }
+
+    #[test]
+    fn workspace_filter_expr() {
+        let metadata = serde_json::json!({
+            "category": "backend",
+            "ci": { "slow": true, "shard": 2 },
+        });
+        let is_match = |input: &str| {
+            input
+                .parse::<WorkspaceFilterExpr>()
+                .expect("valid expression")
+                .is_metadata_match(&metadata)
+        };
+
+        assert!(is_match(r#"metadata.category = "backend""#));
+        assert!(is_match(r#"metadata.category="backend""#));
+        assert!(!is_match(r#"metadata.category = "frontend""#));
+        assert!(is_match("metadata.category exists"));
+        assert!(!is_match("metadata.owner exists"));
+        assert!(is_match("metadata.ci exists"));
+        assert!(is_match(r#"metadata.ci.slow = "true""#));
+        assert!(is_match(r#"metadata.ci.shard = "2""#));
+        assert!(
+            !is_match(r#"metadata.ci = "backend""#),
+            "tables don't match values"
+        );
+        assert!(!is_match(r#"metadata.category.nested = "backend""#));
+
+        for invalid in [
+            "",
+            "category exists",
+            r#"metadata.category = backend"#,
+            r#"metadata..category exists"#,
+            r#"metadata.category"#,
+            r#"metadata.cat egory exists"#,
+        ] {
+            invalid
+                .parse::<WorkspaceFilterExpr>()
+                .expect_err(&format!("{invalid:?} is invalid"));
+        }
+    }
+
+    #[test]
+    fn workspace_path_glob() {
+        let is_match = |glob: &str, path: &str| {
+            glob.parse::<WorkspacePathGlob>()
+                .expect("glob is valid")
+                .matcher
+                .is_match(path)
+        };
+
+        assert!(is_match("vendor/*", "vendor/foo"));
+        assert!(
+            !is_match("vendor/*", "vendor/foo/bar"),
+            "* doesn't match path separators"
+        );
+        assert!(is_match("vendor/**", "vendor/foo/bar"));
+        assert!(!is_match("vendor/**", "crates/vendor"));
+        assert!(is_match("**/generated-*", "crates/api/generated-types"));
+
+        "vendor/["
+            .parse::<WorkspacePathGlob>()
+            .expect_err("unclosed class is invalid");
+    }
 }
@@ -0,0 +1,143 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Prefixing captured test output with timestamps, for `--annotate-output-with-timestamps`.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    borrow::Cow,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tokio::io::{AsyncRead, ReadBuf};
+
+static TIMESTAMP_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\[\+[0-9]+\.[0-9]{3}s\] ").unwrap());
+
+/// Removes the timestamps added by [`TimestampedReader`] from the start of each line in `text`.
+///
+/// Heuristics that look for patterns at the start of a line, like panic messages, run on the
+/// output with timestamps removed.
+pub(crate) fn strip_timestamps(text: &str) -> Cow<'_, str> {
+    TIMESTAMP_REGEX.replace_all(text, "")
+}
+
+/// An [`AsyncRead`] wrapper that prefixes each line read from `inner` with the time elapsed since
+/// `start`, formatted like `[+12.345s] `.
+///
+/// The time for each line is taken when the first chunk of it is read, which is as close to when
+/// the test wrote it as a pipe allows.
+#[derive(Debug)]
+pub(crate) struct TimestampedReader<R> {
+    inner: R,
+    start: Instant,
+    at_line_start: bool,
+    // Annotated output that hasn't been returned to the caller yet.
+    pending: Vec<u8>,
+    pending_pos: usize,
+}
+
+impl<R> TimestampedReader<R> {
+    pub(crate) fn new(inner: R, start: Instant) -> Self {
+        Self {
+            inner,
+            start,
+            at_line_start: true,
+            pending: Vec::new(),
+            pending_pos: 0,
+        }
+    }
+
+    fn annotate(&mut self, data: &[u8]) {
+        self.pending.clear();
+        self.pending_pos = 0;
+        let elapsed = self.start.elapsed();
+        for &byte in data {
+            if self.at_line_start {
+                self.pending
+                    .extend_from_slice(format!("[+{:.3}s] ", elapsed.as_secs_f64()).as_bytes());
+            }
+            self.pending.push(byte);
+            self.at_line_start = byte == b'\n';
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for TimestampedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.pending_pos == this.pending.len() {
+            let mut chunk = [0; 4096];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut this.inner).poll_read(cx, &mut chunk_buf) {
+                Poll::Ready(Ok(())) => {}
+                other => return other,
+            }
+            if chunk_buf.filled().is_empty() {
+                // End of file.
+                return Poll::Ready(Ok(()));
+            }
+            this.annotate(chunk_buf.filled());
+        }
+
+        let pending = &this.pending[this.pending_pos..];
+        let len = pending.len().min(buf.remaining());
+        buf.put_slice(&pending[..len]);
+        this.pending_pos += len;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_timestamped_reader() {
+        let input: &[u8] = b"first line\nsecond line\n\nno newline";
+        let mut reader = TimestampedReader::new(input, Instant::now());
+        let mut output = String::new();
+        reader.read_to_string(&mut output).await.unwrap();
+
+        let lines: Vec<_> = output.split('\n').collect();
+        assert_eq!(lines.len(), 4);
+        for (line, expected) in lines
+            .iter()
+            .zip(["first line", "second line", "", "no newline"])
+        {
+            let (timestamp, rest) = line.split_once("] ").expect("line has a timestamp");
+            assert!(
+                timestamp.starts_with("[+") && timestamp.ends_with('s'),
+                "timestamp is formatted correctly: {timestamp}"
+            );
+            assert_eq!(rest, expected);
+        }
+
+        // Small reads see the same output.
+        let mut reader = TimestampedReader::new(input, Instant::now());
+        let mut small_output = Vec::new();
+        let mut buf = [0; 3];
+        loop {
+            let len = reader.read(&mut buf).await.unwrap();
+            if len == 0 {
+                break;
+            }
+            small_output.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(small_output.len(), output.len());
+
+        assert_eq!(
+            strip_timestamps(&output),
+            String::from_utf8_lossy(input),
+            "timestamps are stripped"
+        );
+    }
+}
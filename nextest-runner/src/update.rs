@@ -8,10 +8,12 @@ use camino::{Utf8Path, Utf8PathBuf};
 use mukti_metadata::{MuktiProject, MuktiReleasesJson, ReleaseLocation, ReleaseVersionData};
 use self_update::{ArchiveKind, Compression, Download, Extract};
 use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
 use std::{
     fs,
     io::{self, BufWriter},
     str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use target_spec::Platform;
 
@@ -29,6 +31,25 @@ impl MuktiBackend {
     /// Fetch releases.
     pub fn fetch_releases(&self, current_version: Version) -> Result<NextestReleases, UpdateError> {
         log::info!(target: "nextest-runner::update", "checking for self-updates");
+        let project = self.fetch_project()?;
+        NextestReleases::new(&self.package_name, project, current_version)
+    }
+
+    /// Fetches the latest non-pre-release, non-yanked version.
+    ///
+    /// Unlike [`Self::fetch_releases`], this doesn't log anything, so it's suitable for checks
+    /// that run in the background.
+    pub fn fetch_latest_version(&self) -> Result<Version, UpdateError> {
+        let project = self.fetch_project()?;
+        project
+            .get_latest_matching(&VersionReq::STAR)
+            .map(|(version, _)| version.clone())
+            .ok_or(UpdateError::NoMatchForVersionReq {
+                req: VersionReq::STAR,
+            })
+    }
+
+    fn fetch_project(&self) -> Result<MuktiProject, UpdateError> {
         let mut releases_buf: Vec<u8> = Vec::new();
         Download::from_url(&self.url)
             .download_to(&mut releases_buf)
@@ -36,20 +57,90 @@ impl MuktiBackend {
         let mut releases_json: MuktiReleasesJson =
             serde_json::from_slice(&releases_buf).map_err(UpdateError::ReleaseMetadataDe)?;
 
-        let project = match releases_json.projects.remove(&self.package_name) {
-            Some(project) => project,
-            None => {
-                return Err(UpdateError::MuktiProjectNotFound {
-                    not_found: self.package_name.clone(),
-                    known: releases_json.projects.keys().cloned().collect(),
-                })
-            }
-        };
+        match releases_json.projects.remove(&self.package_name) {
+            Some(project) => Ok(project),
+            None => Err(UpdateError::MuktiProjectNotFound {
+                not_found: self.package_name.clone(),
+                known: releases_json.projects.keys().cloned().collect(),
+            }),
+        }
+    }
+}
 
-        NextestReleases::new(&self.package_name, project, current_version)
+/// How long the result of a version check is cached for.
+pub const VERSION_CHECK_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// An on-disk cache for the latest known version of nextest.
+///
+/// Used by `cargo nextest run --check-for-updates` to avoid hitting the network on every run.
+#[derive(Clone, Debug)]
+pub struct VersionCheckCache {
+    path: Utf8PathBuf,
+}
+
+impl VersionCheckCache {
+    /// Creates a new cache stored at `path`.
+    pub fn new(path: impl Into<Utf8PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Creates a new cache within nextest's data directory, `$CARGO_HOME/nextest`.
+    ///
+    /// Returns `None` if the Cargo home directory couldn't be determined, or isn't valid UTF-8.
+    pub fn in_data_dir() -> Option<Self> {
+        let cargo_home = home::cargo_home().ok()?;
+        let cargo_home = Utf8PathBuf::try_from(cargo_home).ok()?;
+        Some(Self::new(
+            cargo_home.join("nextest").join(VERSION_CHECK_FILE_NAME),
+        ))
+    }
+
+    /// Returns the path to the cache file.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Returns the latest version, if it was recorded less than [`VERSION_CHECK_TTL`] before
+    /// `now`.
+    ///
+    /// Returns `None` if the cache is missing, stale or unreadable.
+    pub fn read(&self, now: SystemTime) -> Option<Version> {
+        let contents = fs::read(&self.path).ok()?;
+        let data: VersionCheckData = serde_json::from_slice(&contents).ok()?;
+        let checked_at = UNIX_EPOCH + Duration::from_secs(data.checked_at);
+        match now.duration_since(checked_at) {
+            Ok(age) if age < VERSION_CHECK_TTL => data.latest_version.parse().ok(),
+            // Either the cache is stale, or it was written in the future (e.g. because the clock
+            // changed). Treat both as stale.
+            _ => None,
+        }
+    }
+
+    /// Records `latest_version` as having been checked at `now`.
+    pub fn write(&self, latest_version: &Version, now: SystemTime) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let data = VersionCheckData {
+            checked_at: now
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            latest_version: latest_version.to_string(),
+        };
+        let contents = serde_json::to_vec(&data)?;
+        fs::write(&self.path, contents)
     }
 }
 
+const VERSION_CHECK_FILE_NAME: &str = "version-check.json";
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct VersionCheckData {
+    checked_at: u64,
+    latest_version: String,
+}
+
 /// Release info for nextest.
 ///
 /// Returned by [`MuktiBackend::fetch_releases`].
@@ -503,3 +594,40 @@ impl FromStr for UpdateVersion {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_check_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path: &Utf8Path = dir.path().try_into().unwrap();
+        let cache = VersionCheckCache::new(dir_path.join("nextest").join("version-check.json"));
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let version: Version = "0.9.60".parse().unwrap();
+
+        assert_eq!(cache.read(now), None, "cache is initially missing");
+
+        cache.write(&version, now).unwrap();
+        assert_eq!(cache.read(now), Some(version.clone()), "cache is fresh");
+        assert_eq!(
+            cache.read(now + VERSION_CHECK_TTL - Duration::from_secs(1)),
+            Some(version),
+            "cache is fresh just before the TTL"
+        );
+        assert_eq!(
+            cache.read(now + VERSION_CHECK_TTL),
+            None,
+            "cache is stale after the TTL"
+        );
+        assert_eq!(
+            cache.read(now - Duration::from_secs(60)),
+            None,
+            "cache written in the future is stale"
+        );
+
+        fs::write(cache.path(), "not json").unwrap();
+        assert_eq!(cache.read(now), None, "cache is unreadable");
+    }
+}
@@ -7,19 +7,26 @@ use color_eyre::eyre::Result;
 use nextest_filtering::FilteringExpr;
 use nextest_metadata::{BuildPlatform, FilterMatch, MismatchReason};
 use nextest_runner::{
-    config::{NextestConfig, RetryPolicy},
+    config::{NextestConfig, RetryFilter, RetryPolicy, TestThreads},
     double_spawn::DoubleSpawnInfo,
     list::BinaryList,
     platform::BuildPlatforms,
-    reporter::heuristic_extract_description,
-    runner::{ExecutionDescription, ExecutionResult, TestRunnerBuilder},
+    reporter::{
+        heuristic_extract_description, CancelReason, ReporterStderr, TestEvent, TestOutputDisplay,
+        TestReporterBuilder,
+    },
+    runner::{
+        configure_handle_inheritance, ExecutionDescription, ExecutionResult, TestIsolation,
+        TestRunnerBuilder,
+    },
     signal::SignalHandlerKind,
     target_runner::TargetRunner,
     test_filter::{RunIgnored, TestFilterBuilder},
 };
 use pretty_assertions::assert_eq;
-use std::{io::Cursor, time::Duration};
+use std::{io::Cursor, num::NonZeroUsize, time::Duration};
 use test_case::test_case;
+use tokio_util::sync::CancellationToken;
 
 #[test]
 fn test_list_binaries() -> Result<()> {
@@ -523,6 +530,306 @@ fn test_retries(retries: Option<RetryPolicy>) -> Result<()> {
     Ok(())
 }
 
+#[test_case(vec![101], 3 ; "matching exit code is retried")]
+#[test_case(vec![2], 1 ; "non-matching exit code is not retried")]
+fn test_retry_filter(exit_codes: Vec<i32>, expected_attempts: usize) -> Result<()> {
+    set_env_vars();
+
+    // test_failure_assert panics, so it exits with code 101.
+    let expr = FilteringExpr::parse(
+        "binary(basic) & test(=test_failure_assert)".to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_retries(RetryPolicy::new_without_delay(2))
+        .set_retry_filter(RetryFilter::ExitCodes(exit_codes));
+    let runner = builder
+        .build(
+            &test_list,
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    let instance_value = instance_statuses
+        .iter()
+        .find(|(&(_, name), _)| name == "test_failure_assert")
+        .expect("test_failure_assert should be present")
+        .1;
+    match &instance_value.status {
+        InstanceStatus::Finished(run_statuses) => {
+            assert_eq!(
+                run_statuses.len(),
+                expected_attempts,
+                "test_failure_assert should be run {expected_attempts} times"
+            );
+            assert!(
+                matches!(
+                    run_statuses.last_status().result,
+                    ExecutionResult::Fail { .. }
+                ),
+                "test_failure_assert failed on its last attempt"
+            );
+        }
+        other => panic!("test_failure_assert should have been run, found {other:?}"),
+    }
+    assert_eq!(run_stats.failed, 1, "1 test failed");
+
+    Ok(())
+}
+
+#[test]
+fn test_runs_per_test() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse(
+        "binary(basic) & (test(=test_flaky_mod_4) | test(=test_success))".to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile("with-junit")
+        .expect("with-junit config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+    let junit_path = profile.store_dir().join("junit.xml");
+
+    // test_flaky_mod_4 only passes on attempt 4. Check both the case where its last attempt passes
+    // and the one where a passing attempt is followed by a failing one.
+    for (runs, failed_attempts) in [(4, 3), (5, 4)] {
+        let mut builder = TestRunnerBuilder::default();
+        builder.set_runs_per_test(runs);
+        let runner = builder
+            .build(
+                &test_list,
+                profile.clone(),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+
+        let mut stderr = Vec::new();
+        let mut reporter = TestReporterBuilder::default().build(
+            &test_list,
+            &profile,
+            ReporterStderr::Buffer(&mut stderr),
+        );
+        let mut flaky_statuses = None;
+        configure_handle_inheritance(false)?;
+        let run_stats = runner.try_execute(|event| {
+            if let TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } = &event
+            {
+                if test_instance.name == "test_flaky_mod_4" {
+                    let results: Vec<_> = run_statuses
+                        .iter()
+                        .map(|status| status.result.is_success())
+                        .collect();
+                    let is_failure = matches!(
+                        run_statuses.describe(),
+                        ExecutionDescription::Failure { .. }
+                    );
+                    flaky_statuses = Some((results, is_failure));
+                }
+            }
+            reporter.report_event(event)
+        })?;
+        drop(reporter);
+
+        let mut expected_results = vec![false; runs];
+        expected_results[3] = true;
+        assert_eq!(
+            flaky_statuses,
+            Some((expected_results, true)),
+            "with {runs} runs, test_flaky_mod_4 was run {runs} times and failed"
+        );
+        assert_eq!(run_stats.passed, 1, "with {runs} runs, test_success passed");
+        assert_eq!(run_stats.failed, 1, "with {runs} runs, 1 test failed");
+        assert_eq!(run_stats.flaky, 0, "with {runs} runs, no tests are flaky");
+
+        // Passing attempts aren't recorded as reruns in the JUnit report.
+        let junit = std::fs::read_to_string(&junit_path)?;
+        assert_eq!(
+            junit.matches("<failure").count(),
+            1,
+            "with {runs} runs, one failure in JUnit report:\n{junit}"
+        );
+        assert_eq!(
+            junit.matches("<rerunFailure").count(),
+            failed_attempts - 1,
+            "with {runs} runs, failed reruns in JUnit report:\n{junit}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_annotate_output_with_timestamps() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse(
+        "binary(basic) & test(=test_failure_assert)".to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile("with-junit")
+        .expect("with-junit config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_annotate_output_with_timestamps(true);
+    let runner = builder
+        .build(
+            &test_list,
+            profile.clone(),
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut stderr = Vec::new();
+    let mut reporter = TestReporterBuilder::default().build(
+        &test_list,
+        &profile,
+        ReporterStderr::Buffer(&mut stderr),
+    );
+    let mut test_stderr = None;
+    configure_handle_inheritance(false)?;
+    runner.try_execute(|event| {
+        if let TestEvent::TestFinished { run_statuses, .. } = &event {
+            test_stderr = Some(run_statuses.last_status().stderr.clone());
+        }
+        reporter.report_event(event)
+    })?;
+    drop(reporter);
+
+    let test_stderr = String::from_utf8(test_stderr.expect("test finished").to_vec())?;
+    assert!(
+        test_stderr.starts_with("[+"),
+        "captured output is annotated with timestamps: {test_stderr}"
+    );
+
+    // The failure message is still extracted from the annotated output.
+    let junit = std::fs::read_to_string(profile.store_dir().join("junit.xml"))?;
+    let failure_start = junit.find("<failure").expect("JUnit report has a failure");
+    let failure_end = junit[failure_start..]
+        .find("</failure>")
+        .expect("failure has a description");
+    let failure = &junit[failure_start..failure_start + failure_end];
+    assert!(
+        failure.contains(">thread &apos;test_failure_assert&apos;")
+            && failure.contains("this is an assertion")
+            && !failure.contains("[+"),
+        "failure description is extracted without timestamps: {failure}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_output() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse(
+        "binary(basic) & test(=test_failure_assert)".to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_stream_output(true);
+    let runner = builder
+        .build(
+            &test_list,
+            profile.clone(),
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut stderr = Vec::new();
+    let mut reporter_builder = TestReporterBuilder::default();
+    reporter_builder
+        .set_stream_output(true)
+        .set_failure_output(TestOutputDisplay::Immediate);
+    let mut reporter =
+        reporter_builder.build(&test_list, &profile, ReporterStderr::Buffer(&mut stderr));
+    let mut streamed_lines = 0;
+    configure_handle_inheritance(false)?;
+    runner.try_execute(|event| {
+        if let TestEvent::TestOutputLine { line, .. } = &event {
+            assert!(
+                line.iter().filter(|&&b| b == b'\n').count() <= 1,
+                "each event is a single line: {line:?}"
+            );
+            streamed_lines += 1;
+        }
+        reporter.report_event(event)
+    })?;
+    drop(reporter);
+
+    assert!(streamed_lines > 0, "output lines were streamed");
+    let stderr = String::from_utf8(strip_ansi_escapes::strip(&stderr)?)?;
+    assert!(
+        stderr.lines().any(
+            |line| line.starts_with("[nextest-tests::basic::test_failure_assert] ")
+                && line.contains("this is an assertion")
+        ),
+        "output lines are written as they're read, prefixed with the test name: {stderr}"
+    );
+    assert!(
+        !stderr.contains("STDERR:"),
+        "streamed output isn't displayed again when the test fails: {stderr}"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_termination() -> Result<()> {
     set_env_vars();
@@ -597,3 +904,400 @@ fn test_termination() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_binary_timeout() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse(
+        "binary(basic) & (test(=test_failure_assert) | test(=test_slow_timeout) | test(=test_success))"
+            .to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::All, None, Vec::<String>::new(), vec![expr]).unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    // Run tests one at a time, in name order: test_failure_assert finishes quickly,
+    // test_slow_timeout sleeps for 4 seconds and is terminated once the binary's timeout passes,
+    // and test_success is never started.
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_test_threads(TestThreads::Count(1))
+        .set_test_binary_timeout(Duration::from_secs(1));
+    let runner = builder
+        .build(
+            &test_list,
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    let status = |test_name: &str| {
+        &instance_statuses
+            .iter()
+            .find(|(&(_, name), _)| name == test_name)
+            .unwrap_or_else(|| panic!("{test_name} should be present"))
+            .1
+            .status
+    };
+
+    match status("test_failure_assert") {
+        InstanceStatus::Finished(run_statuses) => assert!(
+            matches!(
+                run_statuses.last_status().result,
+                ExecutionResult::Fail { .. }
+            ),
+            "test_failure_assert failed normally"
+        ),
+        other => panic!("test_failure_assert should have been run, found {other:?}"),
+    }
+    match status("test_slow_timeout") {
+        InstanceStatus::Finished(run_statuses) => {
+            let run_status = run_statuses.last_status();
+            assert_eq!(run_status.result, ExecutionResult::Timeout);
+            assert!(
+                run_status.time_taken < Duration::from_secs(3),
+                "test_slow_timeout was terminated early, actually took {:?}",
+                run_status.time_taken
+            );
+        }
+        other => panic!("test_slow_timeout should have been run, found {other:?}"),
+    }
+    match status("test_success") {
+        InstanceStatus::Skipped(reason) => assert_eq!(*reason, MismatchReason::BinaryTimeout),
+        other => panic!("test_success should have been skipped, found {other:?}"),
+    }
+
+    assert_eq!(
+        run_stats.initial_run_count, 2,
+        "2 tests were expected to run"
+    );
+    assert_eq!(run_stats.finished_count, 2, "2 tests were run");
+    assert_eq!(run_stats.timed_out, 1, "1 test timed out");
+    let timeout_skips = instance_statuses
+        .values()
+        .filter(|value| {
+            matches!(
+                value.status,
+                InstanceStatus::Skipped(MismatchReason::BinaryTimeout)
+            )
+        })
+        .count();
+    assert_eq!(
+        timeout_skips, 1,
+        "1 test was skipped because of the timeout"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_max_test_time() -> Result<()> {
+    set_env_vars();
+
+    // test_slow_timeout sleeps for 4 seconds.
+    let expr = FilteringExpr::parse(
+        "binary(basic) & test(=test_slow_timeout)".to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::All, None, Vec::<String>::new(), vec![expr]).unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_retries(RetryPolicy::new_without_delay(1))
+        .set_max_test_time(Duration::from_secs(1));
+    let runner = builder
+        .build(
+            &test_list,
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    let instance_value = instance_statuses
+        .iter()
+        .find(|(&(_, name), _)| name == "test_slow_timeout")
+        .expect("test_slow_timeout should be present")
+        .1;
+    match &instance_value.status {
+        InstanceStatus::Finished(run_statuses) => {
+            // The maximum time applies to each attempt separately.
+            assert_eq!(run_statuses.len(), 2, "test_slow_timeout was retried once");
+            for run_status in run_statuses.iter() {
+                assert_eq!(run_status.result, ExecutionResult::Timeout);
+                assert!(
+                    run_status.time_taken < Duration::from_secs(3),
+                    "attempt {} was killed early, actually took {:?}",
+                    run_status.retry_data.attempt,
+                    run_status.time_taken
+                );
+            }
+        }
+        other => panic!("test_slow_timeout should have been run, found {other:?}"),
+    }
+    assert_eq!(run_stats.timed_out, 1, "1 test timed out");
+
+    Ok(())
+}
+
+#[test]
+fn test_failure_limit_per_binary() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse(
+        "(binary(basic) & (test(=test_failure_assert) | test(=test_failure_error) | test(=test_success))) | (binary(other) & test(=other_test_success))"
+            .to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    // Run tests one at a time, in binary and name order: test_failure_assert fails and reaches
+    // the limit for the basic binary, so test_failure_error and test_success are never started.
+    // The other binary isn't affected.
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_test_threads(TestThreads::Count(1))
+        .set_fail_fast(false)
+        .set_failure_limit_per_binary(NonZeroUsize::new(1).unwrap());
+    let runner = builder
+        .build(
+            &test_list,
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    let status = |test_name: &str| {
+        &instance_statuses
+            .iter()
+            .find(|(&(_, name), _)| name == test_name)
+            .unwrap_or_else(|| panic!("{test_name} should be present"))
+            .1
+            .status
+    };
+
+    match status("test_failure_assert") {
+        InstanceStatus::Finished(run_statuses) => assert!(
+            matches!(
+                run_statuses.last_status().result,
+                ExecutionResult::Fail { .. }
+            ),
+            "test_failure_assert failed normally"
+        ),
+        other => panic!("test_failure_assert should have been run, found {other:?}"),
+    }
+    for test_name in ["test_failure_error", "test_success"] {
+        match status(test_name) {
+            InstanceStatus::Skipped(reason) => {
+                assert_eq!(*reason, MismatchReason::BinaryFailureLimit)
+            }
+            other => panic!("{test_name} should have been skipped, found {other:?}"),
+        }
+    }
+    match status("other_test_success") {
+        InstanceStatus::Finished(run_statuses) => assert!(
+            run_statuses.last_status().result.is_success(),
+            "other_test_success passed"
+        ),
+        other => panic!("other_test_success should have been run, found {other:?}"),
+    }
+
+    assert_eq!(
+        run_stats.initial_run_count, 2,
+        "2 tests were expected to run"
+    );
+    assert_eq!(run_stats.finished_count, 2, "2 tests were run");
+    assert_eq!(run_stats.failed, 1, "1 test failed");
+    assert_eq!(run_stats.passed, 1, "1 test passed");
+
+    Ok(())
+}
+
+#[test_case(TestIsolation::Thread ; "thread")]
+#[test_case(TestIsolation::None ; "none")]
+fn test_shared_process_isolation(test_isolation: TestIsolation) -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse(
+        "binary(basic) & (test(=test_success) | test(=test_failure_assert) | test(=test_flaky_mod_4))"
+            .to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile("with-retries")
+        .expect("with-retries config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_test_isolation(test_isolation)
+        .set_fail_fast(false);
+    let runner = builder
+        .build(
+            &test_list,
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    let run_statuses = |test_name: &str| match &instance_statuses
+        .iter()
+        .find(|(&(_, name), value)| value.binary_id == "nextest-tests::basic" && name == test_name)
+        .unwrap_or_else(|| panic!("{test_name} should be present"))
+        .1
+        .status
+    {
+        InstanceStatus::Finished(run_statuses) => run_statuses,
+        other => panic!("{test_name} should have been run, found {other:?}"),
+    };
+
+    let success = run_statuses("test_success");
+    assert_eq!(success.len(), 1, "test_success was run once");
+    assert_eq!(success.last_status().result, ExecutionResult::Pass);
+    assert!(
+        !success.last_status().time_taken_known,
+        "time taken by a test in a shared process isn't known"
+    );
+
+    let failure = run_statuses("test_failure_assert");
+    assert_eq!(failure.len(), 1, "test_failure_assert isn't retried");
+    let failure_status = failure.last_status();
+    assert!(
+        matches!(failure_status.result, ExecutionResult::Fail { .. }),
+        "test_failure_assert failed, found {:?}",
+        failure_status.result
+    );
+    let failure_output = String::from_utf8_lossy(&failure_status.stdout);
+    assert!(
+        failure_output.contains("this is an assertion")
+            && !failure_output.contains("Failed because attempt"),
+        "only test_failure_assert's output is attributed to it: {failure_output}"
+    );
+
+    let flaky = run_statuses("test_flaky_mod_4");
+    assert_eq!(flaky.len(), 1, "test_flaky_mod_4 isn't retried");
+    let flaky_output = String::from_utf8_lossy(&flaky.last_status().stdout);
+    assert!(
+        matches!(flaky.last_status().result, ExecutionResult::Fail { .. })
+            && flaky_output.contains("Failed because attempt 1 % 4 != 0"),
+        "test_flaky_mod_4 failed on its first attempt: {flaky_output}"
+    );
+
+    assert_eq!(
+        run_stats.initial_run_count, 3,
+        "3 tests were expected to run"
+    );
+    assert_eq!(run_stats.finished_count, 3, "3 tests were run");
+    assert_eq!(run_stats.passed, 1, "1 test passed");
+    assert_eq!(run_stats.failed, 2, "2 tests failed");
+
+    Ok(())
+}
+
+#[test]
+fn test_cancellation() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse("test(=test_slow_timeout)".to_owned(), &PACKAGE_GRAPH).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::IgnoredOnly,
+        None,
+        Vec::<String>::new(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new(None).unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let token = CancellationToken::new();
+    let mut builder = TestRunnerBuilder::default();
+    builder.with_cancellation_token(token.clone());
+    let runner = builder
+        .build(
+            &test_list,
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    configure_handle_inheritance(false)?;
+    let mut cancelled = Vec::new();
+    let run_stats = runner.execute(|event| match event {
+        // Cancel the run once the test has started, so that it's terminated while running.
+        TestEvent::TestStarted { .. } => token.cancel(),
+        TestEvent::TestCancelled {
+            test_instance,
+            reason,
+            ..
+        } => cancelled.push((test_instance.name, reason)),
+        TestEvent::TestFinished { test_instance, .. } => {
+            panic!("{} should have been cancelled", test_instance.name)
+        }
+        _ => {}
+    });
+
+    assert_eq!(
+        cancelled,
+        vec![("test_slow_timeout", CancelReason::Interrupt)],
+        "the running test was cancelled"
+    );
+    assert_eq!(run_stats.cancelled, 1, "1 test was cancelled");
+    assert_eq!(run_stats.failed, 0, "cancelled tests aren't failures");
+    assert!(!run_stats.is_success(), "a cancelled run isn't a success");
+
+    Ok(())
+}
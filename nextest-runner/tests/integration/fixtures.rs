@@ -356,6 +356,7 @@ impl FixtureTargets {
         let ctx = TestExecuteContext {
             double_spawn: &double_spawn,
             target_runner,
+            extra_args: &[],
         };
 
         TestList::new(
@@ -9,6 +9,7 @@ use uuid::Uuid;
 
 /// The root element of a JUnit report.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct Report {
     /// The name of this report.
     pub name: String,
@@ -23,6 +24,17 @@ pub struct Report {
     /// This is not part of the JUnit spec, but may be useful for some tools.
     pub timestamp: Option<DateTime<FixedOffset>>,
 
+    /// The format in which timestamps in this report are serialized.
+    ///
+    /// This applies to all timestamps in the report, including those of test suites, test cases
+    /// and reruns.
+    pub timestamp_format: TimestampFormat,
+
+    /// The number of digits after the decimal point that times in this report are rounded to.
+    ///
+    /// This applies to all times in the report. Defaults to 3, i.e. millisecond precision.
+    pub time_precision: usize,
+
     /// The overall time taken by the test suite.
     ///
     /// This is serialized as the number of seconds.
@@ -37,6 +49,11 @@ pub struct Report {
     /// The total number of errors from all TestSuites.
     pub errors: usize,
 
+    /// Custom properties for the whole report, e.g. build metadata.
+    ///
+    /// This is not part of the JUnit spec, but is supported by some consumers.
+    pub properties: Vec<Property>,
+
     /// The test suites contained in this report.
     pub test_suites: Vec<TestSuite>,
 }
@@ -48,10 +65,13 @@ impl Report {
             name: name.into(),
             uuid: None,
             timestamp: None,
+            timestamp_format: TimestampFormat::default(),
+            time_precision: 3,
             time: None,
             tests: 0,
             failures: 0,
             errors: 0,
+            properties: vec![],
             test_suites: vec![],
         }
     }
@@ -70,12 +90,41 @@ impl Report {
         self
     }
 
+    /// Sets the format in which timestamps are serialized.
+    pub fn set_timestamp_format(&mut self, timestamp_format: TimestampFormat) -> &mut Self {
+        self.timestamp_format = timestamp_format;
+        self
+    }
+
+    /// Sets the number of digits after the decimal point that times are rounded to.
+    pub fn set_time_precision(&mut self, time_precision: usize) -> &mut Self {
+        self.time_precision = time_precision;
+        self
+    }
+
     /// Sets the time taken for overall execution.
     pub fn set_time(&mut self, time: Duration) -> &mut Self {
         self.time = Some(time);
         self
     }
 
+    /// Adds a property to this report.
+    pub fn add_property(&mut self, property: impl Into<Property>) -> &mut Self {
+        self.properties.push(property.into());
+        self
+    }
+
+    /// Adds several properties to this report.
+    pub fn add_properties(
+        &mut self,
+        properties: impl IntoIterator<Item = impl Into<Property>>,
+    ) -> &mut Self {
+        for property in properties {
+            self.add_property(property);
+        }
+        self
+    }
+
     /// Adds a new TestSuite and updates the `tests`, `failures` and `errors` counts.
     ///
     /// When generating a new report, use of this method is recommended over adding to
@@ -115,6 +164,26 @@ impl Report {
     }
 }
 
+/// The format in which timestamps are serialized.
+///
+/// Set through [`Report::set_timestamp_format`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TimestampFormat {
+    /// RFC 3339 with millisecond precision, e.g. `2021-04-01T10:52:37.000-08:00`. This is the
+    /// default.
+    #[default]
+    Rfc3339,
+
+    /// `yyyy-MM-dd'T'HH:mm:ss` in the timestamp's own offset, e.g. `2021-04-01T10:52:37`.
+    ///
+    /// Some older JUnit consumers require this format.
+    Simple,
+
+    /// Seconds since the Unix epoch with millisecond precision, e.g. `1617303157.000`.
+    Epoch,
+}
+
 /// Represents a single TestSuite.
 ///
 /// A `TestSuite` groups together several `TestCase` instances.
@@ -149,6 +218,12 @@ pub struct TestSuite {
     /// The test cases that form this TestSuite.
     pub test_cases: Vec<TestCase>,
 
+    /// TestSuites nested within this TestSuite.
+    ///
+    /// Nested TestSuites aren't part of the JUnit spec, but are supported by some consumers such
+    /// as Azure DevOps.
+    pub test_suites: Vec<TestSuite>,
+
     /// Custom properties set during test execution, e.g. environment variables.
     pub properties: Vec<Property>,
 
@@ -174,6 +249,7 @@ impl TestSuite {
             errors: 0,
             failures: 0,
             test_cases: vec![],
+            test_suites: vec![],
             properties: vec![],
             system_out: None,
             system_err: None,
@@ -239,6 +315,33 @@ impl TestSuite {
         self
     }
 
+    /// Nests a [`TestSuite`] within this TestSuite and updates counts.
+    ///
+    /// When generating a new report, use of this method is recommended over adding to
+    /// `self.test_suites` directly.
+    pub fn add_test_suite(&mut self, test_suite: TestSuite) -> &mut Self {
+        self.tests += test_suite.tests;
+        self.disabled += test_suite.disabled;
+        self.errors += test_suite.errors;
+        self.failures += test_suite.failures;
+        self.test_suites.push(test_suite);
+        self
+    }
+
+    /// Nests several [`TestSuite`]s within this TestSuite and updates counts.
+    ///
+    /// When generating a new report, use of this method is recommended over adding to
+    /// `self.test_suites` directly.
+    pub fn add_test_suites(
+        &mut self,
+        test_suites: impl IntoIterator<Item = TestSuite>,
+    ) -> &mut Self {
+        for test_suite in test_suites {
+            self.add_test_suite(test_suite);
+        }
+        self
+    }
+
     /// Sets standard output.
     pub fn set_system_out(&mut self, system_out: impl AsRef<str>) -> &mut Self {
         self.system_out = Some(Output::new(system_out.as_ref()));
@@ -293,6 +396,11 @@ pub struct TestCase {
     /// The status of this test.
     pub status: TestCaseStatus,
 
+    /// Custom properties for this test case.
+    ///
+    /// This is not part of the JUnit spec, but is supported by some consumers.
+    pub properties: Vec<Property>,
+
     /// Data written to standard output while the test case was executed.
     pub system_out: Option<Output>,
 
@@ -313,6 +421,7 @@ impl TestCase {
             timestamp: None,
             time: None,
             status,
+            properties: vec![],
             system_out: None,
             system_err: None,
             extra: IndexMap::new(),
@@ -343,6 +452,12 @@ impl TestCase {
         self
     }
 
+    /// Adds a property to this test case.
+    pub fn add_property(&mut self, property: impl Into<Property>) -> &mut Self {
+        self.properties.push(property.into());
+        self
+    }
+
     /// Sets standard output.
     pub fn set_system_out(&mut self, system_out: impl AsRef<str>) -> &mut Self {
         self.system_out = Some(Output::new(system_out.as_ref()));
@@ -5,7 +5,7 @@
 
 use crate::{
     NonSuccessKind, Output, Property, Report, SerializeError, TestCase, TestCaseStatus, TestRerun,
-    TestSuite,
+    TestSuite, TimestampFormat,
 };
 use chrono::{DateTime, FixedOffset};
 use quick_xml::{
@@ -54,10 +54,13 @@ pub(crate) fn serialize_report_impl(
         name,
         uuid,
         timestamp,
+        timestamp_format,
+        time_precision,
         time,
         tests,
         failures,
         errors,
+        properties,
         test_suites,
     } = report;
 
@@ -72,15 +75,23 @@ pub(crate) fn serialize_report_impl(
         testsuites_tag.push_attribute(("uuid", uuid.to_string().as_str()));
     }
     if let Some(timestamp) = timestamp {
-        serialize_timestamp(&mut testsuites_tag, timestamp);
+        serialize_timestamp(&mut testsuites_tag, timestamp, *timestamp_format);
     }
     if let Some(time) = time {
-        serialize_time(&mut testsuites_tag, time);
+        serialize_time(&mut testsuites_tag, time, *time_precision);
     }
     writer.write_event(Event::Start(testsuites_tag))?;
 
+    if !properties.is_empty() {
+        serialize_empty_start_tag(PROPERTIES_TAG, writer)?;
+        for property in properties {
+            serialize_property(property, writer)?;
+        }
+        serialize_end_tag(PROPERTIES_TAG, writer)?;
+    }
+
     for test_suite in test_suites {
-        serialize_test_suite(test_suite, writer)?;
+        serialize_test_suite(test_suite, *timestamp_format, *time_precision, writer)?;
     }
 
     serialize_end_tag(TESTSUITES_TAG, writer)?;
@@ -91,6 +102,8 @@ pub(crate) fn serialize_report_impl(
 
 pub(crate) fn serialize_test_suite(
     test_suite: &TestSuite,
+    timestamp_format: TimestampFormat,
+    time_precision: usize,
     writer: &mut Writer<impl io::Write>,
 ) -> quick_xml::Result<()> {
     // Use the destructuring syntax to ensure that all fields are handled.
@@ -103,6 +116,7 @@ pub(crate) fn serialize_test_suite(
         time,
         timestamp,
         test_cases,
+        test_suites,
         properties,
         system_out,
         system_err,
@@ -119,10 +133,10 @@ pub(crate) fn serialize_test_suite(
     ]);
 
     if let Some(timestamp) = timestamp {
-        serialize_timestamp(&mut test_suite_tag, timestamp);
+        serialize_timestamp(&mut test_suite_tag, timestamp, timestamp_format);
     }
     if let Some(time) = time {
-        serialize_time(&mut test_suite_tag, time);
+        serialize_time(&mut test_suite_tag, time, time_precision);
     }
 
     for (k, v) in extra {
@@ -140,7 +154,11 @@ pub(crate) fn serialize_test_suite(
     }
 
     for test_case in test_cases {
-        serialize_test_case(test_case, writer)?;
+        serialize_test_case(test_case, timestamp_format, time_precision, writer)?;
+    }
+
+    for test_suite in test_suites {
+        serialize_test_suite(test_suite, timestamp_format, time_precision, writer)?;
     }
 
     if let Some(system_out) = system_out {
@@ -169,6 +187,8 @@ fn serialize_property(
 
 fn serialize_test_case(
     test_case: &TestCase,
+    timestamp_format: TimestampFormat,
+    time_precision: usize,
     writer: &mut Writer<impl io::Write>,
 ) -> quick_xml::Result<()> {
     let TestCase {
@@ -178,6 +198,7 @@ fn serialize_test_case(
         timestamp,
         time,
         status,
+        properties,
         system_out,
         system_err,
         extra,
@@ -193,10 +214,10 @@ fn serialize_test_case(
     }
 
     if let Some(timestamp) = timestamp {
-        serialize_timestamp(&mut testcase_tag, timestamp);
+        serialize_timestamp(&mut testcase_tag, timestamp, timestamp_format);
     }
     if let Some(time) = time {
-        serialize_time(&mut testcase_tag, time);
+        serialize_time(&mut testcase_tag, time, time_precision);
     }
 
     for (k, v) in extra {
@@ -204,10 +225,24 @@ fn serialize_test_case(
     }
     writer.write_event(Event::Start(testcase_tag))?;
 
+    if !properties.is_empty() {
+        serialize_empty_start_tag(PROPERTIES_TAG, writer)?;
+        for property in properties {
+            serialize_property(property, writer)?;
+        }
+        serialize_end_tag(PROPERTIES_TAG, writer)?;
+    }
+
     match status {
         TestCaseStatus::Success { flaky_runs } => {
             for rerun in flaky_runs {
-                serialize_rerun(rerun, FlakyOrRerun::Flaky, writer)?;
+                serialize_rerun(
+                    rerun,
+                    FlakyOrRerun::Flaky,
+                    timestamp_format,
+                    time_precision,
+                    writer,
+                )?;
             }
         }
         TestCaseStatus::NonSuccess {
@@ -229,7 +264,13 @@ fn serialize_test_case(
                 writer,
             )?;
             for rerun in reruns {
-                serialize_rerun(rerun, FlakyOrRerun::Rerun, writer)?;
+                serialize_rerun(
+                    rerun,
+                    FlakyOrRerun::Rerun,
+                    timestamp_format,
+                    time_precision,
+                    writer,
+                )?;
             }
         }
         TestCaseStatus::Skipped {
@@ -297,6 +338,8 @@ enum FlakyOrRerun {
 fn serialize_rerun(
     rerun: &TestRerun,
     flaky_or_rerun: FlakyOrRerun,
+    timestamp_format: TimestampFormat,
+    time_precision: usize,
     writer: &mut Writer<impl io::Write>,
 ) -> quick_xml::Result<()> {
     let TestRerun {
@@ -320,10 +363,10 @@ fn serialize_rerun(
 
     let mut tag = BytesStart::new(tag_name);
     if let Some(timestamp) = timestamp {
-        serialize_timestamp(&mut tag, timestamp);
+        serialize_timestamp(&mut tag, timestamp, timestamp_format);
     }
     if let Some(time) = time {
-        serialize_time(&mut tag, time);
+        serialize_time(&mut tag, time, time_precision);
     }
     if let Some(message) = message {
         tag.push_attribute(("message", message.as_str()));
@@ -403,17 +446,28 @@ fn serialize_end_tag(
     writer.write_event(Event::End(end_tag))
 }
 
-fn serialize_timestamp(tag: &mut BytesStart<'_>, timestamp: &DateTime<FixedOffset>) {
+fn serialize_timestamp(
+    tag: &mut BytesStart<'_>,
+    timestamp: &DateTime<FixedOffset>,
+    timestamp_format: TimestampFormat,
+) {
     // The format string is obtained from https://docs.rs/chrono/0.4.19/chrono/format/strftime/index.html#fn8.
     // The only change is that this only prints timestamps up to 3 decimal places (to match times).
     static RFC_3339_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
-    tag.push_attribute((
-        "timestamp",
-        format!("{}", timestamp.format(RFC_3339_FORMAT)).as_str(),
-    ));
+    static SIMPLE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+    let timestamp = match timestamp_format {
+        TimestampFormat::Rfc3339 => format!("{}", timestamp.format(RFC_3339_FORMAT)),
+        TimestampFormat::Simple => format!("{}", timestamp.format(SIMPLE_FORMAT)),
+        // Serialize as seconds with 3 decimal points, to match times.
+        TimestampFormat::Epoch => format!("{:.3}", timestamp.timestamp_millis() as f64 / 1000.0),
+    };
+    tag.push_attribute(("timestamp", timestamp.as_str()));
 }
 
-// Serialize time as seconds with 3 decimal points.
-fn serialize_time(tag: &mut BytesStart<'_>, time: &Duration) {
-    tag.push_attribute(("time", format!("{:.3}", time.as_secs_f64()).as_str()));
+// Serialize time as seconds, rounded to `time_precision` decimal points.
+fn serialize_time(tag: &mut BytesStart<'_>, time: &Duration, time_precision: usize) {
+    tag.push_attribute((
+        "time",
+        format!("{:.*}", time_precision, time.as_secs_f64()).as_str(),
+    ));
 }
@@ -6,6 +6,7 @@ use goldenfile::Mint;
 use owo_colors::OwoColorize;
 use quick_junit::{
     NonSuccessKind, Property, Report, TestCase, TestCaseStatus, TestRerun, TestSuite,
+    TimestampFormat,
 };
 use std::time::Duration;
 
@@ -21,6 +22,85 @@ fn fixtures() {
     basic_report
         .serialize(f)
         .expect("serializing basic_report succeeds");
+
+    for (timestamp_format, file_name) in [
+        (
+            TimestampFormat::Simple,
+            "basic_report_simple_timestamps.xml",
+        ),
+        (TimestampFormat::Epoch, "basic_report_epoch_timestamps.xml"),
+    ] {
+        let f = mint
+            .new_goldenfile(file_name)
+            .expect("creating new goldenfile succeeds");
+        let mut report = basic_report.clone();
+        report.set_timestamp_format(timestamp_format);
+        report.serialize(f).expect("serializing report succeeds");
+    }
+
+    let f = mint
+        .new_goldenfile("basic_report_time_precision.xml")
+        .expect("creating new goldenfile succeeds");
+    let mut report = basic_report.clone();
+    report.set_time_precision(1);
+    report.serialize(f).expect("serializing report succeeds");
+
+    let f = mint
+        .new_goldenfile("nested_report.xml")
+        .expect("creating new goldenfile succeeds");
+    nested_report()
+        .serialize(f)
+        .expect("serializing nested_report succeeds");
+
+    let f = mint
+        .new_goldenfile("testcase_properties_report.xml")
+        .expect("creating new goldenfile succeeds");
+    testcase_properties_report()
+        .serialize(f)
+        .expect("serializing testcase_properties_report succeeds");
+}
+
+fn testcase_properties_report() -> Report {
+    let mut report = Report::new("my-test-run");
+
+    let mut test_suite = TestSuite::new("testsuite0");
+    let mut testcase0 = TestCase::new("...testcase0", TestCaseStatus::success());
+    testcase0.add_property(("full-name", "tests::testcase0"));
+    let mut testcase1 = TestCase::new(
+        "testcase1",
+        TestCaseStatus::non_success(NonSuccessKind::Failure),
+    );
+    testcase1
+        .add_property(("owner", "team-a"))
+        .add_property(("flaky", "true"));
+    test_suite
+        .add_test_case(testcase0)
+        .add_test_case(testcase1)
+        .add_test_case(TestCase::new("testcase2", TestCaseStatus::success()));
+    report.add_test_suite(test_suite);
+
+    report
+}
+
+fn nested_report() -> Report {
+    let mut report = Report::new("my-test-run");
+    report.add_properties([("commit", "abc123"), ("branch", "main")]);
+
+    let mut inner0 = TestSuite::new("package::binary0");
+    inner0
+        .add_test_case(TestCase::new("testcase0", TestCaseStatus::success()))
+        .add_test_case(TestCase::new(
+            "testcase1",
+            TestCaseStatus::non_success(NonSuccessKind::Failure),
+        ));
+    let mut inner1 = TestSuite::new("package::binary1");
+    inner1.add_test_case(TestCase::new("testcase2", TestCaseStatus::skipped()));
+
+    let mut outer = TestSuite::new("package");
+    outer.add_test_suites([inner0, inner1]);
+    report.add_test_suite(outer);
+
+    report
 }
 
 fn basic_report() -> Report {